@@ -40,6 +40,18 @@ pub enum ChatType {
     PublicChannel,
 }
 
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type,
+)]
+#[sqlx(type_name = "workspace_role", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceRole {
+    #[default]
+    Member,
+    Admin,
+    Owner,
+}
+
 #[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
 pub struct Workspace {
     pub id: i64,
@@ -48,11 +60,32 @@ pub struct Workspace {
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct WorkspaceInvite {
+    pub id: i64,
+    pub ws_id: i64,
+    pub inviter_id: i64,
+    pub role: WorkspaceRole,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub accepted_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct ChatReadState {
+    pub chat_id: i64,
+    pub user_id: i64,
+    pub last_read_message_id: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
 pub struct ChatUser {
     pub id: i64,
     pub fullname: String,
     pub email: String,
+    pub role: WorkspaceRole,
 }
 
 #[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
@@ -63,6 +96,31 @@ pub struct Message {
     pub content: String,
     pub files: Vec<String>,
     pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted: bool,
+    pub reply_to: Option<i64>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub reply_count: i64,
+    #[sqlx(default, json)]
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reaction {
+    pub emoji: String,
+    pub count: i64,
+    pub user_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct MessageEdit {
+    pub id: i64,
+    pub message_id: i64,
+    pub content: String,
+    pub files: Vec<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[cfg(test)]
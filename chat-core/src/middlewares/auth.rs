@@ -91,8 +91,8 @@ mod tests {
 
     #[tokio::test]
     async fn verify_token_middleware_should_work() -> Result<()> {
-        let priv_pem = include_str!("../../ed25519.priv");
-        let pub_pem = include_str!("../../ed25519.pub");
+        let priv_pem = include_str!("../../fixtures/ed25519.priv");
+        let pub_pem = include_str!("../../fixtures/ed25519.pub");
 
         let ek = ChatEncodingKey::load(&priv_pem)?;
         let dk = ChatDecodingKey::load(&pub_pem)?;
@@ -1,4 +1,4 @@
-use crate::User;
+use crate::{User, WorkspaceInvite, WorkspaceRole};
 use chrono::Utc;
 use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
@@ -7,6 +7,7 @@ use std::collections::HashSet;
 const JWT_DURATION: u64 = 60 * 60 * 24 * 3;
 const JWT_ISS: &str = "chat_server";
 const JWT_AUD: &str = "chat_web";
+const JWT_INVITE_AUD: &str = "chat_invite";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -17,6 +18,17 @@ pub struct Claims {
     aud: String,
 }
 
+/// Invite tokens have their own audience so they can't be used to sign in.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InviteClaims {
+    pub id: i64,
+    pub ws_id: i64,
+    pub role: WorkspaceRole,
+    exp: u64,
+    iss: String,
+    aud: String,
+}
+
 pub struct ChatEncodingKey(EncodingKey);
 
 impl ChatEncodingKey {
@@ -40,6 +52,26 @@ impl ChatEncodingKey {
         )?;
         Ok(token)
     }
+
+    /// The token expires with the invite.
+    pub fn sign_invite(
+        &self,
+        invite: &WorkspaceInvite,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let claims = InviteClaims {
+            id: invite.id,
+            ws_id: invite.ws_id,
+            role: invite.role,
+            exp: invite.expires_at.timestamp() as u64,
+            iss: JWT_ISS.to_string(),
+            aud: JWT_INVITE_AUD.to_string(),
+        };
+        encode(
+            &jsonwebtoken::Header::new(Algorithm::EdDSA),
+            &claims,
+            &self.0,
+        )
+    }
 }
 
 pub struct ChatDecodingKey(DecodingKey);
@@ -58,6 +90,14 @@ impl ChatDecodingKey {
         let claims = decode::<Claims>(token, &self.0, &validation)?;
         Ok(claims.claims.sub)
     }
+
+    pub fn verify_invite(&self, token: &str) -> Result<InviteClaims, jsonwebtoken::errors::Error> {
+        let mut validation = Validation::new(Algorithm::EdDSA);
+        validation.aud = Some(HashSet::from([JWT_INVITE_AUD.to_string()]));
+        validation.iss = Some(HashSet::from([JWT_ISS.to_string()]));
+        let claims = decode::<InviteClaims>(token, &self.0, &validation)?;
+        Ok(claims.claims)
+    }
 }
 
 #[cfg(test)]
@@ -68,8 +108,8 @@ mod tests {
 
     #[tokio::test]
     async fn jwt_sign_verify_should_work() -> Result<()> {
-        let priv_pem = include_str!("../../ed25519.priv");
-        let pub_pem = include_str!("../../ed25519.pub");
+        let priv_pem = include_str!("../../fixtures/ed25519.priv");
+        let pub_pem = include_str!("../../fixtures/ed25519.pub");
 
         let encoding_key = ChatEncodingKey::load(&priv_pem)?;
         let decoding_key = ChatDecodingKey::load(&pub_pem)?;
@@ -82,4 +122,33 @@ mod tests {
         assert_eq!(user, user2);
         Ok(())
     }
+    #[tokio::test]
+    async fn invite_sign_verify_should_work() -> Result<()> {
+        let priv_pem = include_str!("../../fixtures/ed25519.priv");
+        let pub_pem = include_str!("../../fixtures/ed25519.pub");
+
+        let encoding_key = ChatEncodingKey::load(priv_pem)?;
+        let decoding_key = ChatDecodingKey::load(pub_pem)?;
+
+        let invite = WorkspaceInvite {
+            id: 1,
+            ws_id: 2,
+            inviter_id: 1,
+            role: WorkspaceRole::Admin,
+            expires_at: Utc::now() + chrono::Duration::hours(1),
+            revoked_at: None,
+            accepted_by: None,
+            created_at: Utc::now(),
+        };
+        let token = encoding_key.sign_invite(&invite)?;
+        let claims = decoding_key.verify_invite(&token)?;
+        assert_eq!(
+            (claims.id, claims.ws_id, claims.role),
+            (1, 2, WorkspaceRole::Admin)
+        );
+
+        // not a sign in token
+        assert!(decoding_key.verify(&token).is_err());
+        Ok(())
+    }
 }
@@ -1,3 +1,3 @@
 mod jwt;
 
-pub use jwt::{ChatDecodingKey, ChatEncodingKey};
+pub use jwt::{ChatDecodingKey, ChatEncodingKey, InviteClaims};
@@ -1,7 +1,7 @@
 use std::{collections::HashSet, sync::Arc};
 
 use anyhow::Result;
-use chat_core::{Chat, Message};
+use chat_core::{Chat, ChatReadState, Message};
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgListener;
@@ -17,6 +17,9 @@ pub enum AppEvent {
     AddToChat(Chat),
     RemoveFromChat(Chat),
     NewMessage(Message),
+    MessageUpdated(Message),
+    MessageDeleted(Message),
+    ReadUpdated(ChatReadState),
 }
 
 #[derive(Debug)]
@@ -33,7 +36,13 @@ struct ChatUpdated {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ChatMessageCreated {
+struct ChatReadUpdated {
+    members: Vec<u64>,
+    read_state: ChatReadState,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessageChanged {
     members: Vec<u64>,
     message: Message,
 }
@@ -43,6 +52,9 @@ pub async fn setup_pg_listener(state: AppState) -> Result<()> {
         PgListener::connect(state.config.database.connection_string().expose_secret()).await?;
     listener.listen("chat_updated").await?;
     listener.listen("chat_message_created").await?;
+    listener.listen("chat_message_updated").await?;
+    listener.listen("chat_message_deleted").await?;
+    listener.listen("chat_read_updated").await?;
 
     let mut stream = listener.into_stream();
 
@@ -87,12 +99,32 @@ impl Notifucation {
                     event: Arc::new(event),
                 })
             }
-            "chat_message_creatd" => {
-                let payload: ChatMessageCreated = serde_json::from_str(payload)?;
-                let user_ids = payload.members.iter().map(|v| *v as u64).collect();
+            "chat_message_created" | "chat_message_updated" | "chat_message_deleted" => {
+                let payload: ChatMessageChanged = serde_json::from_str(payload)?;
+                let user_ids = payload.members.iter().copied().collect();
+                let event = match r#type {
+                    "chat_message_created" => AppEvent::NewMessage(payload.message),
+                    "chat_message_updated" => AppEvent::MessageUpdated(payload.message),
+                    _ => AppEvent::MessageDeleted(payload.message),
+                };
+                Ok(Self {
+                    user_ids,
+                    event: Arc::new(event),
+                })
+            }
+            "chat_read_updated" => {
+                let payload: ChatReadUpdated = serde_json::from_str(payload)?;
+                // the reader already knows
+                let reader = payload.read_state.user_id as u64;
+                let user_ids = payload
+                    .members
+                    .iter()
+                    .copied()
+                    .filter(|&id| id != reader)
+                    .collect();
                 Ok(Self {
                     user_ids,
-                    event: Arc::new(AppEvent::NewMessage(payload.message)),
+                    event: Arc::new(AppEvent::ReadUpdated(payload.read_state)),
                 })
             }
             _ => Err(anyhow::anyhow!("Invalild notifucation type")),
@@ -36,6 +36,9 @@ pub(crate) async fn sse_handler(
             AppEvent::AddToChat(_) => "AddToChat",
             AppEvent::RemoveFromChat(_) => "RemoveFromChat",
             AppEvent::NewMessage(_) => "NewMessage",
+            AppEvent::MessageUpdated(_) => "MessageUpdated",
+            AppEvent::MessageDeleted(_) => "MessageDeleted",
+            AppEvent::ReadUpdated(_) => "ReadUpdated",
         };
         let v = serde_json::to_string(&v).expect("Failed to serialize event");
         Ok(Event::default().data(v).event(name))
@@ -41,6 +41,11 @@ VALUES
         '$argon2id$v=19$m=19456,t=2,p=1$tuf+xQpdHETIKpdAb078nQ$N8nDgQguI8uEw1IaE7MhmXtjg0p+BMGZx+83pfnjym4'
     );
 
+-- test owns acme and tom is an admin there
+UPDATE workspaces SET owner_id = 1 WHERE id = 1;
+UPDATE users SET role = 'owner' WHERE id = 1;
+UPDATE users SET role = 'admin' WHERE id = 2;
+
 -- insert 4 chat
 -- insert public/private channel
 INSERT INTO
@@ -41,9 +41,21 @@ pub enum AppError {
     #[error("{0}")]
     ChatFileError(String),
 
+    #[error("reaction error: {0}")]
+    ReactionError(String),
+
+    #[error("search error: {0}")]
+    SearchError(String),
+
+    #[error("invite error: {0}")]
+    InviteError(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
     #[error("multipart error: {0}")]
     ChatMultipartError(#[from] axum::extract::multipart::MultipartError),
 
@@ -70,7 +82,11 @@ impl IntoResponse for AppError {
             AppError::CreateChatError(_) => StatusCode::BAD_REQUEST,
             AppError::CreateMessageError(_) => StatusCode::BAD_REQUEST,
             AppError::ChatFileError(_) => StatusCode::BAD_REQUEST,
+            AppError::ReactionError(_) => StatusCode::BAD_REQUEST,
+            AppError::SearchError(_) => StatusCode::BAD_REQUEST,
+            AppError::InviteError(_) => StatusCode::BAD_REQUEST,
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::PermissionDenied(_) => StatusCode::FORBIDDEN,
             AppError::ChatMultipartError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::AnyError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::IoError(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     AppError, AppState, ErrorOutput,
-    models::{CreateUser, SigninUser},
+    models::{AcceptInvite, CreateUser, SigninUser},
 };
 
 // const REDIS_EX_TIME: u64 = 60 * 60 * 24 * 3;
@@ -14,6 +14,9 @@ pub struct AuthOutput {
     token: String,
 }
 
+/// Sign up into the named workspace, creating it if needed. The first user of
+/// a workspace owns it, from then on it's joined by accepting an invite and a
+/// signup into it is refused with 403.
 pub(crate) async fn signup_handler(
     State(state): State<AppState>,
     Json(create_user): Json<CreateUser>,
@@ -32,6 +35,17 @@ pub(crate) async fn signup_handler(
     Ok((StatusCode::CREATED, body))
 }
 
+/// Sign up into the workspace of the invite, with the role it grants.
+pub(crate) async fn accept_invite_handler(
+    State(state): State<AppState>,
+    Json(accept_invite): Json<AcceptInvite>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = state.accept_invite(&accept_invite).await?;
+    let token = state.ek.sign(user)?;
+
+    Ok((StatusCode::CREATED, Json(AuthOutput { token })))
+}
+
 pub(crate) async fn signin_handler(
     State(state): State<AppState>,
     Json(signin_user): Json<SigninUser>,
@@ -67,7 +81,10 @@ pub(crate) async fn signin_handler(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{handlers::create_invite_handler, models::CreateInvite};
     use anyhow::Result;
+    use axum::Extension;
+    use chat_core::WorkspaceRole;
     use http_body_util::BodyExt;
 
     #[tokio::test]
@@ -101,6 +118,61 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn signup_into_owned_workspace_should_need_invite() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+
+        // acme is owned by user 1
+        let create_user = CreateUser::new("acme", "iori", "iori@acme.org", "123456");
+        let ret = signup_handler(State(state.clone()), Json(create_user))
+            .await
+            .into_response();
+        assert_eq!(ret.status(), StatusCode::FORBIDDEN);
+        let body = ret.into_body().collect().await?.to_bytes();
+        let ret: ErrorOutput = serde_json::from_slice(&body)?;
+        assert_eq!(
+            ret.error,
+            "Permission denied: workspace acme can only be joined by invite"
+        );
+
+        let user = state
+            .find_user_by_id(1)
+            .await?
+            .expect("user 1 should exist");
+        let create_invite = CreateInvite {
+            role: WorkspaceRole::Member,
+            expires_in: None,
+        };
+        let ret = create_invite_handler(
+            Extension(user),
+            Extension(WorkspaceRole::Owner),
+            State(state.clone()),
+            Json(create_invite),
+        )
+        .await?
+        .into_response();
+        assert_eq!(ret.status(), StatusCode::CREATED);
+        let body = ret.into_body().collect().await?.to_bytes();
+        let invite: serde_json::Value = serde_json::from_slice(&body)?;
+
+        let accept_invite = AcceptInvite {
+            token: invite["token"].as_str().unwrap_or_default().to_string(),
+            fullname: "iori".to_string(),
+            email: "iori@acme.org".to_string(),
+            password: "123456".to_string(),
+        };
+        let ret = accept_invite_handler(State(state.clone()), Json(accept_invite))
+            .await?
+            .into_response();
+        assert_eq!(ret.status(), StatusCode::CREATED);
+        let body = ret.into_body().collect().await?.to_bytes();
+        let ret: AuthOutput = serde_json::from_slice(&body)?;
+        let user = state.dk.verify(&ret.token)?;
+        assert_eq!(user.ws_id, 1);
+        assert_eq!(user.email, "iori@acme.org");
+        Ok(())
+    }
+
     #[tokio::test]
     async fn signin_should_work() -> Result<()> {
         let (_tdb, state) = AppState::new_for_test().await?;
@@ -7,7 +7,7 @@ use hyper::StatusCode;
 
 use crate::{
     AppError, AppState,
-    models::{CreateChat, UpdateChat},
+    models::{CreateChat, ReadChat, UpdateChat},
 };
 use chat_core::User;
 
@@ -15,9 +15,11 @@ pub(crate) async fn list_chat_handler(
     Extension(user): Extension<User>,
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, AppError> {
-    let chat = state.fetch_chats(user.ws_id as _).await?;
+    let chats = state
+        .fetch_chat_summaries(user.ws_id as _, user.id as _)
+        .await?;
 
-    Ok((StatusCode::OK, Json(chat)))
+    Ok((StatusCode::OK, Json(chats)))
 }
 
 pub(crate) async fn create_chat_handler(
@@ -52,6 +54,16 @@ pub(crate) async fn update_chat_handler(
     Ok((StatusCode::OK, Json(chat)))
 }
 
+pub(crate) async fn read_chat_handler(
+    Extension(user): Extension<User>,
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+    Json(read_chat): Json<ReadChat>,
+) -> Result<impl IntoResponse, AppError> {
+    let read_state = state.read_chat(read_chat, id, user.id as _).await?;
+    Ok((StatusCode::OK, Json(read_state)))
+}
+
 pub(crate) async fn delete_chat_handler(// State(state): State<AppState>,
     // Path(id): Path<u64>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -9,7 +9,7 @@ use tracing::{info, warn};
 
 use crate::{
     AppError, AppState,
-    models::{ChatFile, CreateMessage, ListMessages},
+    models::{ChatFile, CreateMessage, CreateReaction, ListMessages, UpdateMessage},
 };
 use chat_core::User;
 
@@ -36,6 +36,73 @@ pub(crate) async fn list_message_handler(
     Ok(Json(messages))
 }
 
+pub(crate) async fn update_message_handler(
+    Extension(user): Extension<User>,
+    State(state): State<AppState>,
+    Path((id, msg_id)): Path<(u64, u64)>,
+    Json(update_message): Json<UpdateMessage>,
+) -> Result<impl IntoResponse, AppError> {
+    let msg = state
+        .update_message(update_message, id, msg_id, user.id as _)
+        .await?;
+
+    Ok((StatusCode::OK, Json(msg)))
+}
+
+pub(crate) async fn delete_message_handler(
+    Extension(user): Extension<User>,
+    State(state): State<AppState>,
+    Path((id, msg_id)): Path<(u64, u64)>,
+) -> Result<impl IntoResponse, AppError> {
+    let msg = state.delete_message(id, msg_id, user.id as _).await?;
+
+    Ok((StatusCode::OK, Json(msg)))
+}
+
+pub(crate) async fn list_message_edits_handler(
+    State(state): State<AppState>,
+    Path((id, msg_id)): Path<(u64, u64)>,
+) -> Result<impl IntoResponse, AppError> {
+    let edits = state.list_message_edits(id, msg_id).await?;
+
+    Ok(Json(edits))
+}
+
+pub(crate) async fn list_thread_handler(
+    State(state): State<AppState>,
+    Path((id, msg_id)): Path<(u64, u64)>,
+    Query(list_messages): Query<ListMessages>,
+) -> Result<impl IntoResponse, AppError> {
+    let messages = state.list_thread(list_messages, id, msg_id).await?;
+
+    Ok(Json(messages))
+}
+
+pub(crate) async fn add_reaction_handler(
+    Extension(user): Extension<User>,
+    State(state): State<AppState>,
+    Path((id, msg_id)): Path<(u64, u64)>,
+    Json(create_reaction): Json<CreateReaction>,
+) -> Result<impl IntoResponse, AppError> {
+    let msg = state
+        .add_reaction(create_reaction, id, msg_id, user.id as _)
+        .await?;
+
+    Ok((StatusCode::OK, Json(msg)))
+}
+
+pub(crate) async fn remove_reaction_handler(
+    Extension(user): Extension<User>,
+    State(state): State<AppState>,
+    Path((id, msg_id, emoji)): Path<(u64, u64, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let msg = state
+        .remove_reaction(&emoji, id, msg_id, user.id as _)
+        .await?;
+
+    Ok((StatusCode::OK, Json(msg)))
+}
+
 pub(crate) async fn file_handler(
     Extension(user): Extension<User>,
     State(state): State<AppState>,
@@ -1,6 +1,7 @@
 mod auth;
 mod chat;
 mod messages;
+mod search;
 mod workspace;
 
 use axum::response::IntoResponse;
@@ -8,6 +9,7 @@ use axum::response::IntoResponse;
 pub(crate) use auth::*;
 pub(crate) use chat::*;
 pub(crate) use messages::*;
+pub(crate) use search::*;
 pub(crate) use workspace::*;
 
 pub(crate) async fn index_handler() -> impl IntoResponse {
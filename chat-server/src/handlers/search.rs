@@ -0,0 +1,20 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+    response::IntoResponse,
+};
+
+use crate::{AppError, AppState, models::SearchMessages};
+use chat_core::User;
+
+pub(crate) async fn search_handler(
+    Extension(user): Extension<User>,
+    State(state): State<AppState>,
+    Query(search): Query<SearchMessages>,
+) -> Result<impl IntoResponse, AppError> {
+    let hits = state
+        .search_messages(search, user.ws_id as _, user.id as _)
+        .await?;
+
+    Ok(Json(hits))
+}
@@ -1,7 +1,12 @@
-use axum::{Extension, Json, extract::State, response::IntoResponse};
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
 
-use crate::{AppError, AppState};
-use chat_core::User;
+use crate::{AppError, AppState, models::CreateInvite};
+use chat_core::{User, WorkspaceRole};
 
 pub(crate) async fn list_chat_users_handler(
     Extension(user): Extension<User>,
@@ -11,3 +16,48 @@ pub(crate) async fn list_chat_users_handler(
 
     Ok(Json(users))
 }
+
+pub(crate) async fn remove_workspace_user_handler(
+    Extension(user): Extension<User>,
+    Extension(role): Extension<WorkspaceRole>,
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = state
+        .remove_workspace_user(id, user.ws_id as _, role)
+        .await?;
+
+    Ok(Json(user))
+}
+
+pub(crate) async fn list_invites_handler(
+    Extension(user): Extension<User>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let invites = state.list_invites(user.ws_id as _).await?;
+
+    Ok(Json(invites))
+}
+
+pub(crate) async fn create_invite_handler(
+    Extension(user): Extension<User>,
+    Extension(role): Extension<WorkspaceRole>,
+    State(state): State<AppState>,
+    Json(create_invite): Json<CreateInvite>,
+) -> Result<impl IntoResponse, AppError> {
+    let invite = state
+        .create_invite(create_invite, user.ws_id as _, user.id as _, role)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(invite)))
+}
+
+pub(crate) async fn revoke_invite_handler(
+    Extension(user): Extension<User>,
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<impl IntoResponse, AppError> {
+    let invite = state.revoke_invite(id, user.ws_id as _).await?;
+
+    Ok(Json(invite))
+}
@@ -10,12 +10,12 @@ use chat_core::{ChatDecodingKey, ChatEncodingKey, TokenVerify, set_layers, verif
 pub use configuration::{AppConfig, get_configuration};
 pub use error::{AppError, ErrorOutput};
 use handlers::*;
-use middlewares::verify_chat;
+use middlewares::{verify_admin, verify_chat, verify_workspace};
 
 use axum::{
     Router,
-    middleware::from_fn_with_state,
-    routing::{get, post},
+    middleware::{from_fn, from_fn_with_state},
+    routing::{delete, get, patch, post},
 };
 // use r2d2::Pool;
 // use redis::Client;
@@ -160,18 +160,49 @@ pub async fn get_router(state: AppState) -> Result<Router, AppError> {
                 .delete(delete_chat_handler)
                 .post(send_message_handler),
         )
+        .route("/{id}/read", post(read_chat_handler))
         .route("/{id}/messages", get(list_message_handler))
+        .route(
+            "/{id}/messages/{msg_id}",
+            patch(update_message_handler).delete(delete_message_handler),
+        )
+        .route(
+            "/{id}/messages/{msg_id}/edits",
+            get(list_message_edits_handler),
+        )
+        .route("/{id}/messages/{msg_id}/replies", get(list_thread_handler))
+        .route(
+            "/{id}/messages/{msg_id}/reactions",
+            post(add_reaction_handler),
+        )
+        .route(
+            "/{id}/messages/{msg_id}/reactions/{emoji}",
+            delete(remove_reaction_handler),
+        )
         .layer(from_fn_with_state(state.clone(), verify_chat))
         .route("/", get(list_chat_handler).post(create_chat_handler));
 
+    let admin = Router::new()
+        .route("/users/{id}", delete(remove_workspace_user_handler))
+        .route(
+            "/invites",
+            get(list_invites_handler).post(create_invite_handler),
+        )
+        .route("/invites/{id}", delete(revoke_invite_handler))
+        .layer(from_fn(verify_admin));
+
     let api = Router::new()
         .route("/users", get(list_chat_users_handler))
         .nest("/chats", chat)
+        .route("/search", get(search_handler))
         .route("/upload", post(upload_handler))
         .route("/files/{ws_id}/{*path}", get(file_handler))
+        .merge(admin)
+        .layer(from_fn_with_state(state.clone(), verify_workspace))
         .layer(from_fn_with_state(state.clone(), verify_token::<AppState>))
         .route("/signin", post(signin_handler))
-        .route("/signup", post(signup_handler));
+        .route("/signup", post(signup_handler))
+        .route("/invites/accept", post(accept_invite_handler));
 
     let app = Router::new()
         .route("/", get(index_handler))
@@ -6,10 +6,17 @@ use axum::{
 
 use crate::{AppError, AppState};
 use chat_core::User;
+use serde::Deserialize;
+
+/// Message routes carry more path params than the chat id.
+#[derive(Debug, Deserialize)]
+struct ChatPath {
+    id: u64,
+}
 
 pub async fn verify_chat(State(state): State<AppState>, req: Request, next: Next) -> Response {
     let (mut parts, body) = req.into_parts();
-    let Path(chat_id) = Path::<u64>::from_request_parts(&mut parts, &state)
+    let Path(ChatPath { id: chat_id }) = Path::<ChatPath>::from_request_parts(&mut parts, &state)
         .await
         .unwrap();
     let user = parts.extensions.get::<User>().unwrap();
@@ -50,6 +57,7 @@ mod tests {
 
         let app = Router::new()
             .route("/chat/{id}/messages", get(handler))
+            .route("/chat/{id}/messages/{msg_id}", get(handler))
             .layer(from_fn_with_state(state.clone(), verify_chat))
             .layer(from_fn_with_state(state.clone(), verify_token::<AppState>))
             .with_state(state);
@@ -62,6 +70,13 @@ mod tests {
         let res = app.clone().oneshot(req).await?;
         assert_eq!(res.status(), StatusCode::OK);
 
+        let req = Request::builder()
+            .uri("/chat/1/messages/1")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())?;
+        let res = app.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
         // user not in chat
         let req = Request::builder()
             .uri("/chat/100/messages")
@@ -1,3 +1,5 @@
 mod chat;
+mod workspace;
 
 pub use chat::verify_chat;
+pub use workspace::{verify_admin, verify_workspace};
@@ -0,0 +1,135 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{AppError, AppState};
+use chat_core::{User, WorkspaceRole};
+
+/// Tokens outlive membership, so the role is loaded for every request and
+/// added to the extensions for the handlers.
+pub async fn verify_workspace(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let user = req.extensions().get::<User>().unwrap();
+    // removed users are moved to workspace 0, which nobody works in
+    if user.ws_id == 0 {
+        let err = AppError::PermissionDenied(format!(
+            "User {} is not a member of any workspace",
+            user.id
+        ));
+        return err.into_response();
+    }
+    let role = match state
+        .get_workspace_role(user.ws_id as _, user.id as _)
+        .await
+    {
+        Ok(Some(role)) => role,
+        Ok(None) => {
+            let err = AppError::PermissionDenied(format!(
+                "User {} is not a member of workspace {}",
+                user.id, user.ws_id,
+            ));
+            return err.into_response();
+        }
+        Err(e) => return e.into_response(),
+    };
+    req.extensions_mut().insert(role);
+    next.run(req).await
+}
+
+/// Must be used after `verify_workspace`.
+pub async fn verify_admin(req: Request, next: Next) -> Response {
+    match req.extensions().get::<WorkspaceRole>() {
+        Some(role) if *role >= WorkspaceRole::Admin => next.run(req).await,
+        _ => AppError::PermissionDenied("admin role required".to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use axum::{
+        Router,
+        body::Body,
+        middleware::{from_fn, from_fn_with_state},
+        routing::get,
+    };
+    use chat_core::verify_token;
+    use hyper::StatusCode;
+    use tower::ServiceExt;
+
+    async fn handler(_req: Request) -> impl IntoResponse {
+        (StatusCode::OK, "ok")
+    }
+
+    async fn get_status(app: &Router, uri: &str, token: &str) -> Result<StatusCode> {
+        let req = Request::builder()
+            .uri(uri)
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())?;
+        let res = app.clone().oneshot(req).await?;
+        Ok(res.status())
+    }
+
+    #[tokio::test]
+    async fn verify_workspace_middleware_should_work() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+
+        let admin = Router::new()
+            .route("/admin", get(handler))
+            .layer(from_fn(verify_admin));
+        let app = Router::new()
+            .route("/users", get(handler))
+            .merge(admin)
+            .layer(from_fn_with_state(state.clone(), verify_workspace))
+            .layer(from_fn_with_state(state.clone(), verify_token::<AppState>))
+            .with_state(state.clone());
+
+        // user 2 is an admin, user 3 a member
+        let mut tokens = vec![];
+        for id in [2, 3] {
+            let user = state.find_user_by_id(id).await?.expect("user should exist");
+            tokens.push(state.ek.sign(user)?);
+        }
+        assert_eq!(
+            get_status(&app, "/users", &tokens[0]).await?,
+            StatusCode::OK
+        );
+        assert_eq!(
+            get_status(&app, "/admin", &tokens[0]).await?,
+            StatusCode::OK
+        );
+        assert_eq!(
+            get_status(&app, "/users", &tokens[1]).await?,
+            StatusCode::OK
+        );
+        assert_eq!(
+            get_status(&app, "/admin", &tokens[1]).await?,
+            StatusCode::FORBIDDEN
+        );
+
+        // tokens of removed users are no longer accepted
+        state
+            .remove_workspace_user(3, 1, WorkspaceRole::Admin)
+            .await?;
+        assert_eq!(
+            get_status(&app, "/users", &tokens[1]).await?,
+            StatusCode::FORBIDDEN
+        );
+        // nor their new ones
+        let user = state.find_user_by_id(3).await?.expect("user should exist");
+        assert_eq!(user.ws_id, 0);
+        let token = state.ek.sign(user)?;
+        assert_eq!(
+            get_status(&app, "/users", &token).await?,
+            StatusCode::FORBIDDEN
+        );
+
+        Ok(())
+    }
+}
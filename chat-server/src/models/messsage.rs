@@ -4,12 +4,26 @@ use serde::{Deserialize, Serialize};
 
 use super::ChatFile;
 use crate::{AppError, AppState};
-use chat_core::Message;
+use chat_core::{Message, MessageEdit};
+use sqlx::{Postgres, Transaction};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateMessage {
     pub content: String,
     pub files: Vec<String>,
+    #[serde(default)]
+    pub reply_to: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateMessage {
+    pub content: String,
+    pub files: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateReaction {
+    pub emoji: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,34 +39,34 @@ impl AppState {
         chat_id: u64,
         user_id: u64,
     ) -> Result<Message, AppError> {
-        let base_dir = &self.config.base_dir;
-        if create_message.content.is_empty() {
-            return Err(AppError::CreateMessageError(
-                "Content cannot be empty".to_string(),
-            ));
-        }
+        self.verify_message(&create_message.content, &create_message.files)?;
 
-        for s in &create_message.files {
-            let file = ChatFile::from_str(s)?;
-            if !file.path(base_dir).exists() {
-                return Err(AppError::CreateMessageError(format!(
-                    "File {} doesn't exist",
-                    s
-                )));
-            }
-        }
+        // threads are one level deep, replying to a reply joins its thread
+        let reply_to = match create_message.reply_to {
+            Some(id) => match self.get_message(chat_id, id).await? {
+                Some(parent) if !parent.deleted => Some(parent.reply_to.unwrap_or(parent.id)),
+                _ => {
+                    return Err(AppError::CreateMessageError(format!(
+                        "Message {} to reply to doesn't exist",
+                        id
+                    )));
+                }
+            },
+            None => None,
+        };
 
         let message = sqlx::query_as(
             r#"
-            INSERT INTO messages (chat_id, sender_id, content, files)
-            VALUES ($1, $2, $3, $4)
-            RETURNING id, chat_id, sender_id, content, files, created_at
+            INSERT INTO messages (chat_id, sender_id, content, files, reply_to)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, chat_id, sender_id, content, files, created_at, edited_at, deleted, reply_to
             "#,
         )
         .bind(chat_id as i64)
         .bind(user_id as i64)
         .bind(create_message.content)
         .bind(&create_message.files)
+        .bind(reply_to)
         .fetch_one(&self.pool)
         .await?;
 
@@ -67,9 +81,10 @@ impl AppState {
         let last_id = list_messages.last_id.unwrap_or(i64::MAX as _);
         let messages = sqlx::query_as(
             r#"
-            SELECT id, chat_id, sender_id, content, files, created_at
-            FROM messages
+            SELECT *
+            FROM message_details
             WHERE chat_id = $1
+            AND reply_to IS NULL
             AND id < $2
             ORDER BY id DESC
             LIMIT $3
@@ -83,12 +98,293 @@ impl AppState {
 
         Ok(messages)
     }
+
+    /// Replies in the thread of a message, the latest first.
+    pub async fn list_thread(
+        &self,
+        list_messages: ListMessages,
+        chat_id: u64,
+        id: u64,
+    ) -> Result<Vec<Message>, AppError> {
+        if self.get_message(chat_id, id).await?.is_none() {
+            return Err(AppError::NotFound(format!("message id {} not found", id)));
+        }
+
+        let last_id = list_messages.last_id.unwrap_or(i64::MAX as _);
+        let messages = sqlx::query_as(
+            r#"
+            SELECT *
+            FROM message_details
+            WHERE chat_id = $1
+            AND reply_to = $2
+            AND id < $3
+            ORDER BY id DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(chat_id as i64)
+        .bind(id as i64)
+        .bind(last_id as i64)
+        .bind(list_messages.limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    pub async fn get_message(&self, chat_id: u64, id: u64) -> Result<Option<Message>, AppError> {
+        let message = sqlx::query_as(
+            r#"
+            SELECT *
+            FROM message_details
+            WHERE chat_id = $1 AND id = $2
+            "#,
+        )
+        .bind(chat_id as i64)
+        .bind(id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(message)
+    }
+
+    /// A user reacts at most once to a message with each emoji.
+    pub async fn add_reaction(
+        &self,
+        create_reaction: CreateReaction,
+        chat_id: u64,
+        id: u64,
+        user_id: u64,
+    ) -> Result<Message, AppError> {
+        let emoji = create_reaction.emoji;
+        if emoji.is_empty() || emoji.chars().count() > 32 || emoji.contains(char::is_whitespace) {
+            return Err(AppError::ReactionError(format!(
+                "Invalid emoji {:?}",
+                emoji
+            )));
+        }
+        match self.get_message(chat_id, id).await? {
+            Some(message) if !message.deleted => {}
+            _ => return Err(AppError::NotFound(format!("message id {} not found", id))),
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO message_reactions (message_id, user_id, emoji)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(id as i64)
+        .bind(user_id as i64)
+        .bind(emoji)
+        .execute(&self.pool)
+        .await?;
+
+        self.get_message(chat_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("message id {} not found", id)))
+    }
+
+    pub async fn remove_reaction(
+        &self,
+        emoji: &str,
+        chat_id: u64,
+        id: u64,
+        user_id: u64,
+    ) -> Result<Message, AppError> {
+        sqlx::query(
+            r#"
+            DELETE FROM message_reactions r
+            USING messages m
+            WHERE m.id = r.message_id AND m.chat_id = $1
+            AND r.message_id = $2 AND r.user_id = $3 AND r.emoji = $4
+            "#,
+        )
+        .bind(chat_id as i64)
+        .bind(id as i64)
+        .bind(user_id as i64)
+        .bind(emoji)
+        .execute(&self.pool)
+        .await?;
+
+        self.get_message(chat_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("message id {} not found", id)))
+    }
+
+    /// Only the sender can edit a message, the previous version is kept in
+    /// its edit history.
+    pub async fn update_message(
+        &self,
+        update_message: UpdateMessage,
+        chat_id: u64,
+        id: u64,
+        user_id: u64,
+    ) -> Result<Message, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let old = self.get_own_message(&mut tx, chat_id, id, user_id).await?;
+        let files = update_message.files.unwrap_or_else(|| old.files.clone());
+        self.verify_message(&update_message.content, &files)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO message_edits (message_id, content, files, created_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(old.id)
+        .bind(old.content)
+        .bind(old.files)
+        .bind(old.edited_at.unwrap_or(old.created_at))
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE messages SET content = $1, files = $2, edited_at = NOW()
+            WHERE id = $3
+            "#,
+        )
+        .bind(update_message.content)
+        .bind(files)
+        .bind(id as i64)
+        .execute(&mut *tx)
+        .await?;
+        let message = Self::fetch_message(&mut tx, id).await?;
+        tx.commit().await?;
+
+        Ok(message)
+    }
+
+    /// Only the sender can delete a message, its content is cleared and a
+    /// tombstone without reactions is left in the chat.
+    pub async fn delete_message(
+        &self,
+        chat_id: u64,
+        id: u64,
+        user_id: u64,
+    ) -> Result<Message, AppError> {
+        let mut tx = self.pool.begin().await?;
+        self.get_own_message(&mut tx, chat_id, id, user_id).await?;
+
+        sqlx::query("DELETE FROM message_reactions WHERE message_id = $1")
+            .bind(id as i64)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            UPDATE messages SET content = '', files = '{}', deleted = TRUE
+            WHERE id = $1
+            "#,
+        )
+        .bind(id as i64)
+        .execute(&mut *tx)
+        .await?;
+        let message = Self::fetch_message(&mut tx, id).await?;
+        tx.commit().await?;
+
+        Ok(message)
+    }
+
+    /// Previous versions of a message, the latest first.
+    pub async fn list_message_edits(
+        &self,
+        chat_id: u64,
+        id: u64,
+    ) -> Result<Vec<MessageEdit>, AppError> {
+        let edits = sqlx::query_as(
+            r#"
+            SELECT e.id, e.message_id, e.content, e.files, e.created_at
+            FROM message_edits e
+            JOIN messages m ON m.id = e.message_id
+            WHERE m.chat_id = $1 AND m.id = $2 AND NOT m.deleted
+            ORDER BY e.created_at DESC
+            "#,
+        )
+        .bind(chat_id as i64)
+        .bind(id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(edits)
+    }
+
+    async fn fetch_message(
+        tx: &mut Transaction<'_, Postgres>,
+        id: u64,
+    ) -> Result<Message, AppError> {
+        let message = sqlx::query_as("SELECT * FROM message_details WHERE id = $1")
+            .bind(id as i64)
+            .fetch_one(&mut **tx)
+            .await?;
+
+        Ok(message)
+    }
+
+    /// Lock a message of the chat for a change by its sender.
+    async fn get_own_message(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        chat_id: u64,
+        id: u64,
+        user_id: u64,
+    ) -> Result<Message, AppError> {
+        let message: Option<Message> = sqlx::query_as(
+            r#"
+            SELECT id, chat_id, sender_id, content, files, created_at, edited_at, deleted, reply_to
+            FROM messages
+            WHERE id = $1 AND chat_id = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(id as i64)
+        .bind(chat_id as i64)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        match message {
+            Some(message) if message.deleted => Err(AppError::NotFound(format!(
+                "message id {} has been deleted",
+                id
+            ))),
+            Some(message) if message.sender_id != user_id as i64 => {
+                Err(AppError::PermissionDenied(format!(
+                    "user {} is not the sender of message {}",
+                    user_id, id
+                )))
+            }
+            Some(message) => Ok(message),
+            None => Err(AppError::NotFound(format!("message id {} not found", id))),
+        }
+    }
+
+    fn verify_message(&self, content: &str, files: &[String]) -> Result<(), AppError> {
+        let base_dir = &self.config.base_dir;
+        if content.is_empty() {
+            return Err(AppError::CreateMessageError(
+                "Content cannot be empty".to_string(),
+            ));
+        }
+
+        for s in files {
+            let file = ChatFile::from_str(s)?;
+            if !file.path(base_dir).exists() {
+                return Err(AppError::CreateMessageError(format!(
+                    "File {} doesn't exist",
+                    s
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use anyhow::Result;
+    use chat_core::Reaction;
 
     #[tokio::test]
     async fn create_message_should_work() -> Result<()> {
@@ -96,6 +392,7 @@ mod tests {
         let create_message = CreateMessage {
             content: "hello".to_string(),
             files: vec![],
+            reply_to: None,
         };
 
         let message = state
@@ -108,6 +405,7 @@ mod tests {
         let create_message = CreateMessage {
             content: "hello".to_string(),
             files: vec!["1".to_string()],
+            reply_to: None,
         };
 
         let message = state
@@ -120,6 +418,7 @@ mod tests {
         let create_message = CreateMessage {
             content: "hello".to_string(),
             files: vec![url],
+            reply_to: None,
         };
 
         let message = state
@@ -159,6 +458,171 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn update_message_should_keep_edits() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let update_message = UpdateMessage {
+            content: "Hello, edited!".to_string(),
+            files: None,
+        };
+        let message = state.update_message(update_message, 1, 1, 1).await?;
+        assert_eq!(message.content, "Hello, edited!");
+        assert!(message.edited_at.is_some());
+
+        let update_message = UpdateMessage {
+            content: "Hello again!".to_string(),
+            files: Some(vec![]),
+        };
+        state.update_message(update_message, 1, 1, 1).await?;
+        let edits = state.list_message_edits(1, 1).await?;
+        let contents: Vec<_> = edits.iter().map(|e| e.content.as_str()).collect();
+        assert_eq!(contents, ["Hello, edited!", "Hello, world!"]);
+        assert_eq!(edits[1].created_at, message_created_at(&state, 1).await?);
+
+        // message 2 was sent by user 2
+        let update_message = UpdateMessage {
+            content: "hacked".to_string(),
+            files: None,
+        };
+        let err = state
+            .update_message(update_message.clone(), 1, 2, 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::PermissionDenied(_)));
+        let err = state
+            .update_message(update_message, 2, 1, 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_message_should_leave_tombstone() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let err = state.delete_message(1, 2, 1).await.unwrap_err();
+        assert!(matches!(err, AppError::PermissionDenied(_)));
+
+        let message = state.delete_message(1, 10, 1).await?;
+        assert!(message.deleted);
+        assert!(message.content.is_empty());
+
+        let list_messages = ListMessages {
+            last_id: None,
+            limit: 1,
+        };
+        let messages = state.list_messages(list_messages, 1).await?;
+        assert_eq!(messages, [message]);
+
+        let err = state.delete_message(1, 10, 1).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+        let update_message = UpdateMessage {
+            content: "hello".to_string(),
+            files: None,
+        };
+        let err = state
+            .update_message(update_message, 1, 10, 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reply_should_join_thread() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let reply = |reply_to| CreateMessage {
+            content: "reply".to_string(),
+            files: vec![],
+            reply_to: Some(reply_to),
+        };
+        let first = state.create_message(reply(1), 1, 2).await?;
+        assert_eq!(first.reply_to, Some(1));
+        // a reply to a reply goes to the same thread
+        let second = state.create_message(reply(first.id as _), 1, 3).await?;
+        assert_eq!(second.reply_to, Some(1));
+
+        let list_messages = ListMessages {
+            last_id: None,
+            limit: 10,
+        };
+        let thread = state.list_thread(list_messages.clone(), 1, 1).await?;
+        let ids: Vec<_> = thread.iter().map(|m| m.id).collect();
+        assert_eq!(ids, [second.id, first.id]);
+
+        // replies are not listed in the chat
+        let messages = state.list_messages(list_messages.clone(), 1).await?;
+        assert_eq!(messages.len(), 10);
+        let parent = messages.iter().find(|m| m.id == 1).expect("parent");
+        assert_eq!(parent.reply_count, 2);
+
+        state.delete_message(1, second.id as _, 3).await?;
+        let parent = state.get_message(1, 1).await?.expect("parent");
+        assert_eq!(parent.reply_count, 1);
+
+        let err = state.create_message(reply(100), 1, 1).await.unwrap_err();
+        assert!(matches!(err, AppError::CreateMessageError(_)));
+        let err = state.list_thread(list_messages, 2, 1).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reactions_should_be_aggregated() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let react = |emoji: &str| CreateReaction {
+            emoji: emoji.to_string(),
+        };
+        state.add_reaction(react("👍"), 1, 1, 1).await?;
+        state.add_reaction(react("🎉"), 1, 1, 2).await?;
+        state.add_reaction(react("👍"), 1, 1, 3).await?;
+        let message = state.add_reaction(react("👍"), 1, 1, 3).await?;
+        assert_eq!(
+            message.reactions,
+            [
+                Reaction {
+                    emoji: "👍".to_string(),
+                    count: 2,
+                    user_ids: vec![1, 3],
+                },
+                Reaction {
+                    emoji: "🎉".to_string(),
+                    count: 1,
+                    user_ids: vec![2],
+                },
+            ]
+        );
+
+        let message = state.remove_reaction("🎉", 1, 1, 2).await?;
+        assert_eq!(message.reactions.len(), 1);
+        let message = state.remove_reaction("👍", 1, 1, 2).await?;
+        assert_eq!(message.reactions[0].count, 2);
+
+        let err = state.add_reaction(react(""), 1, 1, 1).await.unwrap_err();
+        assert!(matches!(err, AppError::ReactionError(_)));
+        let err = state.add_reaction(react("👍"), 2, 1, 1).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        let message = state.delete_message(1, 1, 1).await?;
+        assert!(message.reactions.is_empty());
+
+        Ok(())
+    }
+
+    async fn message_created_at(
+        state: &AppState,
+        id: i64,
+    ) -> Result<chrono::DateTime<chrono::Utc>> {
+        let (created_at,) = sqlx::query_as("SELECT created_at FROM messages WHERE id = $1")
+            .bind(id)
+            .fetch_one(&state.pool)
+            .await?;
+        Ok(created_at)
+    }
+
     fn upload_dummy_file(state: &AppState) -> Result<String> {
         let file = ChatFile::new(1, "test.txt", b"hello world");
         let path = file.path(&state.config.base_dir);
@@ -1,13 +1,18 @@
 mod chat;
 mod file;
 mod messsage;
+mod read_state;
+mod search;
 mod user;
 mod workspace;
 
 pub use chat::{CreateChat, UpdateChat};
-pub use messsage::{CreateMessage, ListMessages};
+pub use messsage::{CreateMessage, CreateReaction, ListMessages, UpdateMessage};
+pub use read_state::ReadChat;
+pub use search::SearchMessages;
 use serde::{Deserialize, Serialize};
 pub use user::{CreateUser, SigninUser};
+pub use workspace::{AcceptInvite, CreateInvite};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatFile {
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::{AppError, AppState};
+use chat_core::{Chat, ChatReadState, Message};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReadChat {
+    /// The latest message of the chat if not given.
+    pub message_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct ChatSummary {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub chat: Chat,
+    pub unread_count: i64,
+    #[sqlx(json(nullable))]
+    pub last_message: Option<Message>,
+}
+
+impl AppState {
+    /// Move the read cursor of the user in the chat forward, it never goes
+    /// back to an older message.
+    pub async fn read_chat(
+        &self,
+        read_chat: ReadChat,
+        chat_id: u64,
+        user_id: u64,
+    ) -> Result<ChatReadState, AppError> {
+        let message_id: i64 = match read_chat.message_id {
+            Some(id) => {
+                let message: Option<(i64,)> =
+                    sqlx::query_as("SELECT id FROM messages WHERE id = $1 AND chat_id = $2")
+                        .bind(id as i64)
+                        .bind(chat_id as i64)
+                        .fetch_optional(&self.pool)
+                        .await?;
+                message
+                    .ok_or_else(|| AppError::NotFound(format!("message id {} not found", id)))?
+                    .0
+            }
+            None => {
+                let (id,) =
+                    sqlx::query_as("SELECT COALESCE(MAX(id), 0) FROM messages WHERE chat_id = $1")
+                        .bind(chat_id as i64)
+                        .fetch_one(&self.pool)
+                        .await?;
+                id
+            }
+        };
+
+        let read_state = sqlx::query_as(
+            r#"
+            INSERT INTO chat_read_state (chat_id, user_id, last_read_message_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (chat_id, user_id) DO UPDATE
+            SET last_read_message_id = EXCLUDED.last_read_message_id, updated_at = NOW()
+            WHERE chat_read_state.last_read_message_id < EXCLUDED.last_read_message_id
+            RETURNING chat_id, user_id, last_read_message_id, updated_at
+            "#,
+        )
+        .bind(chat_id as i64)
+        .bind(user_id as i64)
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(read_state) = read_state {
+            return Ok(read_state);
+        }
+
+        let read_state = sqlx::query_as(
+            r#"
+            SELECT chat_id, user_id, last_read_message_id, updated_at
+            FROM chat_read_state
+            WHERE chat_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(chat_id as i64)
+        .bind(user_id as i64)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(read_state)
+    }
+
+    /// Chats of the workspace, with the number of messages from others the
+    /// user has not read and the latest message. Both are only given for the
+    /// chats the user is a member of.
+    pub async fn fetch_chat_summaries(
+        &self,
+        ws_id: u64,
+        user_id: u64,
+    ) -> Result<Vec<ChatSummary>, AppError> {
+        let chats = sqlx::query_as(
+            r#"
+            SELECT c.id, c.ws_id, c.name, c.type, c.members, c.created_at,
+            (
+                SELECT COUNT(*)
+                FROM messages m
+                WHERE m.chat_id = c.id
+                AND $2 = ANY(c.members)
+                AND m.id > COALESCE(r.last_read_message_id, 0)
+                AND m.sender_id <> $2
+                AND m.reply_to IS NULL
+                AND NOT m.deleted
+            ) AS unread_count,
+            (
+                SELECT to_jsonb(d)
+                FROM message_details d
+                WHERE d.chat_id = c.id
+                AND $2 = ANY(c.members)
+                AND d.reply_to IS NULL
+                AND NOT d.deleted
+                ORDER BY d.id DESC
+                LIMIT 1
+            ) AS last_message
+            FROM chats c
+            LEFT JOIN chat_read_state r ON r.chat_id = c.id AND r.user_id = $2
+            WHERE c.ws_id = $1
+            ORDER BY c.id
+            "#,
+        )
+        .bind(ws_id as i64)
+        .bind(user_id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(chats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn unread_counts(chats: &[ChatSummary]) -> Vec<(i64, i64)> {
+        chats.iter().map(|c| (c.chat.id, c.unread_count)).collect()
+    }
+
+    #[tokio::test]
+    async fn chat_summaries_should_count_unread() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let chats = state.fetch_chat_summaries(1, 1).await?;
+        // 4 of the 10 messages in chat 1 are from user 1
+        assert_eq!(unread_counts(&chats), [(1, 6), (2, 0), (3, 0), (4, 0)]);
+        let last = chats[0].last_message.as_ref().expect("last message");
+        assert_eq!((last.id, last.content.as_str()), (10, "Hello, world!"));
+        assert_eq!(chats[1].last_message, None);
+
+        let read_chat = ReadChat {
+            message_id: Some(5),
+        };
+        let read_state = state.read_chat(read_chat, 1, 1).await?;
+        assert_eq!(read_state.last_read_message_id, 5);
+        let chats = state.fetch_chat_summaries(1, 1).await?;
+        assert_eq!(chats[0].unread_count, 2);
+
+        // user 2 is not in chat 4
+        let chats = state.fetch_chat_summaries(1, 2).await?;
+        assert_eq!(unread_counts(&chats), [(1, 8), (2, 0), (3, 0), (4, 0)]);
+        assert_eq!(chats[3].last_message, None);
+        assert!(state.fetch_chat_summaries(2, 1).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_chat_should_only_move_forward() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let read_state = state.read_chat(ReadChat::default(), 1, 2).await?;
+        assert_eq!(read_state.last_read_message_id, 10);
+
+        let read_chat = ReadChat {
+            message_id: Some(3),
+        };
+        let read_state = state.read_chat(read_chat, 1, 2).await?;
+        assert_eq!(read_state.last_read_message_id, 10);
+        let chats = state.fetch_chat_summaries(1, 2).await?;
+        assert_eq!(chats[0].unread_count, 0);
+
+        let read_chat = ReadChat {
+            message_id: Some(1),
+        };
+        let err = state.read_chat(read_chat, 2, 2).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+        // nothing to read yet
+        let read_state = state.read_chat(ReadChat::default(), 2, 2).await?;
+        assert_eq!(read_state.last_read_message_id, 0);
+
+        Ok(())
+    }
+}
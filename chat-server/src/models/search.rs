@@ -0,0 +1,185 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::{AppError, AppState};
+use chat_core::Message;
+
+const DEFAULT_SEARCH_LIMIT: u64 = 20;
+const MAX_SEARCH_LIMIT: u64 = 100;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchMessages {
+    pub q: String,
+    pub chat_id: Option<u64>,
+    pub sender_id: Option<u64>,
+    pub before: Option<DateTime<Utc>>,
+    pub after: Option<DateTime<Utc>>,
+    pub last_id: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct MessageHit {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub message: Message,
+    /// Escaped html of the content around the terms found, which are in
+    /// `<mark>`.
+    pub snippet: String,
+}
+
+impl AppState {
+    /// Messages of the workspace matching the query, in the chats the user is
+    /// a member of. The latest come first, the id of the last one is the
+    /// cursor of the next page.
+    pub async fn search_messages(
+        &self,
+        search: SearchMessages,
+        ws_id: u64,
+        user_id: u64,
+    ) -> Result<Vec<MessageHit>, AppError> {
+        if search.q.trim().is_empty() {
+            return Err(AppError::SearchError("Query cannot be empty".to_string()));
+        }
+        if let Some(chat_id) = search.chat_id
+            && !self.is_chat_member(chat_id, user_id).await?
+        {
+            return Err(AppError::PermissionDenied(format!(
+                "user {} is not a member of chat {}",
+                user_id, chat_id
+            )));
+        }
+
+        let last_id = search.last_id.unwrap_or(i64::MAX as _);
+        let limit = search
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .min(MAX_SEARCH_LIMIT);
+        let hits = sqlx::query_as(
+            r#"
+            SELECT d.*, ts_headline(
+                'simple',
+                replace(replace(replace(m.content, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+                q,
+                'StartSel=<mark>, StopSel=</mark>'
+            ) AS snippet
+            FROM messages m
+            JOIN message_details d ON d.id = m.id
+            JOIN chats c ON c.id = m.chat_id,
+            websearch_to_tsquery('simple', $1) q
+            WHERE m.content_tsv @@ q
+            AND c.ws_id = $2 AND $3 = ANY(c.members)
+            AND NOT m.deleted
+            AND ($4::BIGINT IS NULL OR m.chat_id = $4)
+            AND ($5::BIGINT IS NULL OR m.sender_id = $5)
+            AND ($6::TIMESTAMPTZ IS NULL OR m.created_at < $6)
+            AND ($7::TIMESTAMPTZ IS NULL OR m.created_at > $7)
+            AND m.id < $8
+            ORDER BY m.id DESC
+            LIMIT $9
+            "#,
+        )
+        .bind(search.q)
+        .bind(ws_id as i64)
+        .bind(user_id as i64)
+        .bind(search.chat_id.map(|id| id as i64))
+        .bind(search.sender_id.map(|id| id as i64))
+        .bind(search.before)
+        .bind(search.after)
+        .bind(last_id as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CreateMessage;
+    use anyhow::Result;
+
+    fn search(q: &str) -> SearchMessages {
+        SearchMessages {
+            q: q.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn search_messages_should_work() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let hits = state.search_messages(search("hello"), 1, 1).await?;
+        let ids: Vec<_> = hits.iter().map(|h| h.message.id).collect();
+        assert_eq!(ids, [10, 9, 6, 1]);
+        assert_eq!(hits[0].snippet, "<mark>Hello</mark>, world!");
+
+        let page = SearchMessages {
+            last_id: Some(9),
+            limit: Some(1),
+            ..search("hello")
+        };
+        let hits = state.search_messages(page, 1, 1).await?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message.id, 6);
+
+        let by_sender = SearchMessages {
+            sender_id: Some(3),
+            ..search("how OR hello")
+        };
+        let hits = state.search_messages(by_sender, 1, 1).await?;
+        let ids: Vec<_> = hits.iter().map(|h| h.message.id).collect();
+        assert_eq!(ids, [8, 3]);
+
+        let later = SearchMessages {
+            after: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..search("hello")
+        };
+        assert!(state.search_messages(later, 1, 1).await?.is_empty());
+        // another workspace
+        assert!(
+            state
+                .search_messages(search("hello"), 2, 1)
+                .await?
+                .is_empty()
+        );
+
+        let err = state.search_messages(search(" "), 1, 1).await.unwrap_err();
+        assert!(matches!(err, AppError::SearchError(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_messages_should_skip_other_chats() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        // chat 4 is for users 1, 3 and 4
+        let create_message = CreateMessage {
+            content: "the secret plan <3".to_string(),
+            files: vec![],
+            reply_to: None,
+        };
+        let message = state.create_message(create_message, 4, 1).await?;
+
+        let hits = state.search_messages(search("secret"), 1, 3).await?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message, message);
+        assert_eq!(hits[0].snippet, "the <mark>secret</mark> plan &lt;3");
+
+        assert!(
+            state
+                .search_messages(search("secret"), 1, 2)
+                .await?
+                .is_empty()
+        );
+        let in_chat = SearchMessages {
+            chat_id: Some(4),
+            ..search("secret")
+        };
+        let err = state.search_messages(in_chat, 1, 2).await.unwrap_err();
+        assert!(matches!(err, AppError::PermissionDenied(_)));
+        Ok(())
+    }
+}
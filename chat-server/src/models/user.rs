@@ -49,8 +49,15 @@ impl AppState {
             return Err(AppError::EmailAlreadyExists(create_user.email.clone()));
         }
 
-        // check if workspace exists, if not create one
+        // check if workspace exists, if not create one. Workspaces with an
+        // owner can only be joined by invite
         let ws = match self.find_workspace_by_name(&create_user.workspace).await? {
+            Some(ws) if ws.owner_id != 0 => {
+                return Err(AppError::PermissionDenied(format!(
+                    "workspace {} can only be joined by invite",
+                    ws.name
+                )));
+            }
             Some(ws) => ws,
             None => self.create_workspace(&create_user.workspace, 0).await?,
         };
@@ -103,7 +110,7 @@ impl AppState {
     pub async fn fetch_chat_user_by_ids(&self, ids: &[i64]) -> Result<Vec<ChatUser>, AppError> {
         let users = sqlx::query_as(
             r#"
-            SELECT id, fullname, email
+            SELECT id, fullname, email, role
             FROM users
             WHERE id = ANY($1)
             "#,
@@ -118,7 +125,7 @@ impl AppState {
     pub async fn fetch_chat_users(&self, ws_id: u64) -> Result<Vec<ChatUser>, AppError> {
         let users = sqlx::query_as(
             r#"
-            SELECT id, fullname, email
+            SELECT id, fullname, email, role
             FROM users
             WHERE ws_id = $1
             "#,
@@ -131,7 +138,7 @@ impl AppState {
     }
 }
 
-fn hash_password(password: &str) -> Result<String, AppError> {
+pub(super) fn hash_password(password: &str) -> Result<String, AppError> {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
     let password_hash = argon2
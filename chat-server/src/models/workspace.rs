@@ -1,5 +1,35 @@
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::user::hash_password;
 use crate::{AppError, AppState};
-use chat_core::Workspace;
+use chat_core::{ChatUser, User, Workspace, WorkspaceInvite, WorkspaceRole};
+
+const DEFAULT_INVITE_DURATION: u64 = 60 * 60 * 24 * 7;
+const MAX_INVITE_DURATION: u64 = 60 * 60 * 24 * 30;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateInvite {
+    #[serde(default)]
+    pub role: WorkspaceRole,
+    /// Seconds until the invite expires, 7 days if not given.
+    pub expires_in: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InviteOutput {
+    #[serde(flatten)]
+    pub invite: WorkspaceInvite,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptInvite {
+    pub token: String,
+    pub fullname: String,
+    pub email: String,
+    pub password: String,
+}
 
 impl AppState {
     pub async fn create_workspace(&self, name: &str, user_id: u64) -> Result<Workspace, AppError> {
@@ -49,11 +79,13 @@ impl AppState {
         Ok(ws)
     }
 
+    /// The previous owner stays in the workspace as an admin.
     pub async fn update_workspace_owner(
         &self,
         id: u64,
         owner_id: u64,
     ) -> Result<Workspace, AppError> {
+        let mut tx = self.pool.begin().await?;
         let ws = sqlx::query_as(
             r#"
             UPDATE workspaces SET owner_id = $1
@@ -63,11 +95,225 @@ impl AppState {
         )
         .bind(owner_id as i64)
         .bind(id as i64)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET role = CASE WHEN id = $1 THEN 'owner'::workspace_role ELSE 'admin' END
+            WHERE ws_id = $2 AND (id = $1 OR role = 'owner')
+            "#,
+        )
+        .bind(owner_id as i64)
+        .bind(id as i64)
+        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
 
         Ok(ws)
     }
+
+    /// The role of the user, `None` if the user is not in the workspace.
+    pub async fn get_workspace_role(
+        &self,
+        ws_id: u64,
+        user_id: u64,
+    ) -> Result<Option<WorkspaceRole>, AppError> {
+        let role: Option<(WorkspaceRole,)> =
+            sqlx::query_as("SELECT role FROM users WHERE id = $1 AND ws_id = $2")
+                .bind(user_id as i64)
+                .bind(ws_id as i64)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(role.map(|(role,)| role))
+    }
+
+    /// Nobody can be invited as owner, or to a role above the inviter's.
+    pub async fn create_invite(
+        &self,
+        create_invite: CreateInvite,
+        ws_id: u64,
+        inviter_id: u64,
+        inviter_role: WorkspaceRole,
+    ) -> Result<InviteOutput, AppError> {
+        let role = create_invite.role;
+        if role == WorkspaceRole::Owner || role > inviter_role {
+            return Err(AppError::PermissionDenied(format!(
+                "cannot invite users as {:?}",
+                role
+            )));
+        }
+
+        let expires_in = create_invite
+            .expires_in
+            .unwrap_or(DEFAULT_INVITE_DURATION)
+            .min(MAX_INVITE_DURATION);
+        let expires_at = Utc::now() + Duration::seconds(expires_in as i64);
+        let invite = sqlx::query_as(
+            r#"
+            INSERT INTO workspace_invites (ws_id, inviter_id, role, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, ws_id, inviter_id, role, expires_at, revoked_at, accepted_by, created_at
+            "#,
+        )
+        .bind(ws_id as i64)
+        .bind(inviter_id as i64)
+        .bind(role)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+        let token = self.ek.sign_invite(&invite)?;
+
+        Ok(InviteOutput { invite, token })
+    }
+
+    pub async fn list_invites(&self, ws_id: u64) -> Result<Vec<WorkspaceInvite>, AppError> {
+        let invites = sqlx::query_as(
+            r#"
+            SELECT id, ws_id, inviter_id, role, expires_at, revoked_at, accepted_by, created_at
+            FROM workspace_invites
+            WHERE ws_id = $1
+            ORDER BY id DESC
+            "#,
+        )
+        .bind(ws_id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(invites)
+    }
+
+    /// Only invites not accepted yet can be revoked.
+    pub async fn revoke_invite(&self, id: u64, ws_id: u64) -> Result<WorkspaceInvite, AppError> {
+        let invite = sqlx::query_as(
+            r#"
+            UPDATE workspace_invites SET revoked_at = NOW()
+            WHERE id = $1 AND ws_id = $2 AND revoked_at IS NULL AND accepted_by IS NULL
+            RETURNING id, ws_id, inviter_id, role, expires_at, revoked_at, accepted_by, created_at
+            "#,
+        )
+        .bind(id as i64)
+        .bind(ws_id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        invite.ok_or_else(|| AppError::NotFound(format!("pending invite id {} not found", id)))
+    }
+
+    /// Sign up to the workspace of the invite with its role, each invite can
+    /// only be used once.
+    pub async fn accept_invite(&self, accept_invite: &AcceptInvite) -> Result<User, AppError> {
+        let claims = self.dk.verify_invite(&accept_invite.token)?;
+        if self
+            .find_user_by_email(&accept_invite.email)
+            .await?
+            .is_some()
+        {
+            return Err(AppError::EmailAlreadyExists(accept_invite.email.clone()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let invite: Option<WorkspaceInvite> = sqlx::query_as(
+            r#"
+            SELECT id, ws_id, inviter_id, role, expires_at, revoked_at, accepted_by, created_at
+            FROM workspace_invites
+            WHERE id = $1 AND ws_id = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(claims.id)
+        .bind(claims.ws_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let invite = invite
+            .ok_or_else(|| AppError::NotFound(format!("invite id {} not found", claims.id)))?;
+        if invite.revoked_at.is_some() {
+            return Err(AppError::InviteError("invite has been revoked".to_string()));
+        }
+        if invite.accepted_by.is_some() {
+            return Err(AppError::InviteError(
+                "invite has already been used".to_string(),
+            ));
+        }
+        if invite.expires_at <= Utc::now() {
+            return Err(AppError::InviteError("invite has expired".to_string()));
+        }
+
+        let password_hash = hash_password(&accept_invite.password)?;
+        let user: User = sqlx::query_as(
+            r#"
+            INSERT INTO users (ws_id, email, fullname, password_hash, role)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, ws_id, fullname, email, created_at
+            "#,
+        )
+        .bind(invite.ws_id)
+        .bind(&accept_invite.email)
+        .bind(&accept_invite.fullname)
+        .bind(password_hash)
+        .bind(invite.role)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE workspace_invites SET accepted_by = $1 WHERE id = $2")
+            .bind(user.id)
+            .bind(invite.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(user)
+    }
+
+    /// Move the user out to workspace 0 and out of the chats of the
+    /// workspace. Owners can't be removed, admins only remove members.
+    pub async fn remove_workspace_user(
+        &self,
+        id: u64,
+        ws_id: u64,
+        remover_role: WorkspaceRole,
+    ) -> Result<ChatUser, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let user: Option<ChatUser> = sqlx::query_as(
+            r#"
+            SELECT id, fullname, email, role
+            FROM users
+            WHERE id = $1 AND ws_id = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(id as i64)
+        .bind(ws_id as i64)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let user = user.ok_or_else(|| AppError::NotFound(format!("user id {} not found", id)))?;
+        if user.role == WorkspaceRole::Owner || user.role >= remover_role {
+            return Err(AppError::PermissionDenied(format!(
+                "cannot remove user {} with role {:?}",
+                id, user.role
+            )));
+        }
+
+        sqlx::query("UPDATE users SET ws_id = 0, role = 'member' WHERE id = $1")
+            .bind(id as i64)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            UPDATE chats SET members = array_remove(members, $1)
+            WHERE ws_id = $2 AND $1 = ANY(members)
+            "#,
+        )
+        .bind(id as i64)
+        .bind(ws_id as i64)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(user)
+    }
 }
 
 #[cfg(test)]
@@ -94,7 +340,164 @@ mod tests {
             .update_workspace_owner(ws.id as u64, user.id as u64)
             .await?;
         assert_eq!(ws.owner_id, user.id);
+        let role = state.get_workspace_role(ws.id as _, user.id as _).await?;
+        assert_eq!(role, Some(WorkspaceRole::Owner));
+
+        // the workspace has an owner now
+        let create_user = CreateUser::new(&ws.name, "iori", "2@xxx.org", "hunted");
+        let err = state.create_user(&create_user).await.unwrap_err();
+        assert!(matches!(err, AppError::PermissionDenied(_)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn workspace_owner_change_should_keep_old_owner_as_admin() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        state.update_workspace_owner(1, 3).await?;
+
+        assert_eq!(
+            state.get_workspace_role(1, 3).await?,
+            Some(WorkspaceRole::Owner)
+        );
+        assert_eq!(
+            state.get_workspace_role(1, 1).await?,
+            Some(WorkspaceRole::Admin)
+        );
+        assert_eq!(state.get_workspace_role(2, 1).await?, None);
+        Ok(())
+    }
+
+    fn accept(token: &str, email: &str) -> AcceptInvite {
+        AcceptInvite {
+            token: token.to_string(),
+            fullname: "iori".to_string(),
+            email: email.to_string(),
+            password: "hunted".to_string(),
+        }
+    }
 
+    #[tokio::test]
+    async fn invite_should_create_and_accept() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let create_invite = CreateInvite {
+            role: WorkspaceRole::Admin,
+            expires_in: None,
+        };
+        let output = state
+            .create_invite(create_invite, 1, 2, WorkspaceRole::Admin)
+            .await?;
+        assert_eq!(output.invite.inviter_id, 2);
+        assert!(output.invite.expires_at > Utc::now() + Duration::days(6));
+
+        let user = state
+            .accept_invite(&accept(&output.token, "iori@acme.org"))
+            .await?;
+        assert_eq!(user.ws_id, 1);
+        assert_eq!(
+            state.get_workspace_role(1, user.id as _).await?,
+            Some(WorkspaceRole::Admin)
+        );
+        let invites = state.list_invites(1).await?;
+        assert_eq!(invites.len(), 1);
+        assert_eq!(invites[0].accepted_by, Some(user.id));
+
+        let err = state
+            .accept_invite(&accept(&output.token, "shiina@acme.org"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::InviteError(_)));
+        let err = state
+            .accept_invite(&accept("not a token", "shiina@acme.org"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::JsonWebTokenError(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invite_should_not_grant_more_than_inviter_role() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        for (role, inviter_role) in [
+            (WorkspaceRole::Owner, WorkspaceRole::Owner),
+            (WorkspaceRole::Admin, WorkspaceRole::Member),
+        ] {
+            let create_invite = CreateInvite {
+                role,
+                expires_in: None,
+            };
+            let err = state
+                .create_invite(create_invite, 1, 1, inviter_role)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, AppError::PermissionDenied(_)));
+        }
+        assert!(state.list_invites(1).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn revoked_or_expired_invite_should_fail() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        let revoked = state
+            .create_invite(CreateInvite::default(), 1, 1, WorkspaceRole::Owner)
+            .await?;
+        let invite = state.revoke_invite(revoked.invite.id as _, 1).await?;
+        assert!(invite.revoked_at.is_some());
+        let err = state
+            .revoke_invite(revoked.invite.id as _, 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        let create_invite = CreateInvite {
+            expires_in: Some(0),
+            ..Default::default()
+        };
+        let expired = state
+            .create_invite(create_invite, 1, 1, WorkspaceRole::Owner)
+            .await?;
+
+        for output in [revoked, expired] {
+            let err = state
+                .accept_invite(&accept(&output.token, "iori@acme.org"))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, AppError::InviteError(_)));
+        }
+        assert!(state.find_user_by_email("iori@acme.org").await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn remove_workspace_user_should_work() -> Result<()> {
+        let (_tdb, state) = AppState::new_for_test().await?;
+        // admins can't remove other admins or the owner
+        for id in [1, 2] {
+            let err = state
+                .remove_workspace_user(id, 1, WorkspaceRole::Admin)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, AppError::PermissionDenied(_)));
+        }
+
+        let user = state
+            .remove_workspace_user(3, 1, WorkspaceRole::Admin)
+            .await?;
+        assert_eq!(user.id, 3);
+        assert_eq!(state.get_workspace_role(1, 3).await?, None);
+        assert!(!state.is_chat_member(1, 3).await?);
+        assert!(!state.is_chat_member(4, 3).await?);
+        assert_eq!(state.fetch_chat_users(1).await?.len(), 4);
+
+        let err = state
+            .remove_workspace_user(3, 1, WorkspaceRole::Owner)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+        state
+            .remove_workspace_user(2, 1, WorkspaceRole::Owner)
+            .await?;
         Ok(())
     }
 
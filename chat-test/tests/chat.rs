@@ -1,7 +1,7 @@
 use std::{net::SocketAddr, time::Duration};
 
 use anyhow::Result;
-use chat_core::{Chat, ChatType, Message};
+use chat_core::{Chat, ChatReadState, ChatType, Message};
 use futures::StreamExt;
 use reqwest::{
     StatusCode,
@@ -122,6 +122,66 @@ impl ChatServer {
         assert_eq!(message.sender_id, 1);
         Ok(message)
     }
+
+    async fn read_chat(&self, message: &Message) -> Result<ChatReadState> {
+        let res = self
+            .client
+            .post(format!(
+                "http://{}/api/chats/{}/read",
+                self.addr, message.chat_id
+            ))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .header("Content-Type", "application/json")
+            .body(format!(r#"{{"message_id": {}}}"#, message.id))
+            .send()
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        let read_state: ChatReadState = res.json().await?;
+        assert_eq!(read_state.chat_id, message.chat_id);
+        assert_eq!(read_state.user_id, 1);
+        assert_eq!(read_state.last_read_message_id, message.id);
+        Ok(read_state)
+    }
+
+    async fn update_message(&self, message: &Message) -> Result<Message> {
+        let res = self
+            .client
+            .patch(format!(
+                "http://{}/api/chats/{}/messages/{}",
+                self.addr, message.chat_id, message.id
+            ))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .header("Content-Type", "application/json")
+            .body(r#"{"content": "Hello, edited!"}"#)
+            .send()
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        let updated: Message = res.json().await?;
+        assert_eq!(updated.content, "Hello, edited!");
+        assert_eq!(updated.files, message.files);
+        assert!(updated.edited_at.is_some());
+        Ok(updated)
+    }
+
+    async fn delete_message(&self, message: &Message) -> Result<Message> {
+        let res = self
+            .client
+            .delete(format!(
+                "http://{}/api/chats/{}/messages/{}",
+                self.addr, message.chat_id, message.id
+            ))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .send()
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        let deleted: Message = res.json().await?;
+        assert!(deleted.deleted);
+        assert!(deleted.content.is_empty());
+        Ok(deleted)
+    }
 }
 
 struct NotifyServer;
@@ -168,10 +228,21 @@ impl NotifyServer {
 
                         "NewMessage" => {
                             let msg: Message = serde_json::from_str(&message.data).unwrap();
-                            assert_eq!(msg.content, "Hello World!");
+                            assert_eq!(msg.content, "Hello, World!");
                             assert_eq!(msg.files.len(), 1);
                             assert_eq!(msg.sender_id, 1);
                         }
+
+                        "MessageUpdated" => {
+                            let msg: Message = serde_json::from_str(&message.data).unwrap();
+                            assert_eq!(msg.content, "Hello, edited!");
+                            assert!(msg.edited_at.is_some());
+                        }
+
+                        "MessageDeleted" => {
+                            let msg: Message = serde_json::from_str(&message.data).unwrap();
+                            assert!(msg.deleted);
+                        }
                         _ => {
                             panic!("unexpected event: {:?}", message);
                         }
@@ -201,7 +272,10 @@ async fn chat_server_should_work() -> Result<()> {
     )
     .await?;
     let chat = chat_server.create_chat().await?;
-    let _message = chat_server.create_message(chat.id as u64).await?;
+    let message = chat_server.create_message(chat.id as u64).await?;
+    chat_server.read_chat(&message).await?;
+    let message = chat_server.update_message(&message).await?;
+    chat_server.delete_message(&message).await?;
 
     sleep(Duration::from_secs(10)).await;
     Ok(())
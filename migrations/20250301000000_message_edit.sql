@@ -0,0 +1,58 @@
+-- Add migration script here
+
+-- edited and soft deleted messages
+ALTER TABLE messages
+    ADD COLUMN edited_at TIMESTAMPTZ,
+    ADD COLUMN deleted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- previous versions of edited messages
+CREATE TABLE IF NOT EXISTS message_edits (
+    id BIGSERIAL PRIMARY KEY,
+    message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    files TEXT[] DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS message_id_created_at_index ON message_edits(message_id, created_at DESC);
+
+-- if message added, edited or deleted notify with message data
+CREATE OR REPLACE FUNCTION add_to_message()
+    RETURNS TRIGGER
+    AS $$
+    DECLARE USERS bigint[];
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        RAISE NOTICE 'add_to_message: %', NEW;
+        SELECT members INTO USERS
+        FROM chats
+        WHERE id = NEW.chat_id;
+        PERFORM pg_notify('chat_message_created', json_build_object('message', NEW, 'members', USERS)::TEXT);
+    ELSIF TG_OP = 'UPDATE' THEN
+        RAISE NOTICE 'add_to_message: %', NEW;
+        SELECT members INTO USERS
+        FROM chats
+        WHERE id = NEW.chat_id;
+        IF NEW.deleted AND NOT OLD.deleted THEN
+            PERFORM pg_notify('chat_message_deleted', json_build_object('message', NEW, 'members', USERS)::TEXT);
+        ELSIF NEW.edited_at IS DISTINCT FROM OLD.edited_at THEN
+            PERFORM pg_notify('chat_message_updated', json_build_object('message', NEW, 'members', USERS)::TEXT);
+        END IF;
+    ELSIF TG_OP = 'DELETE' THEN
+        RAISE NOTICE 'add_to_message: %', OLD;
+        SELECT members INTO USERS
+        FROM chats
+        WHERE id = OLD.chat_id;
+        PERFORM pg_notify('chat_message_deleted', json_build_object('message', OLD, 'members', USERS)::TEXT);
+    END IF;
+    RETURN NULL;
+END;
+$$
+LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS add_to_message_trigger ON messages;
+
+CREATE TRIGGER add_to_message_trigger
+    AFTER INSERT OR UPDATE OR DELETE ON messages
+    FOR EACH ROW
+    EXECUTE FUNCTION add_to_message();
@@ -0,0 +1,48 @@
+-- Add migration script here
+
+-- replies in the thread of a message
+ALTER TABLE messages
+    ADD COLUMN reply_to BIGINT REFERENCES messages(id);
+
+CREATE INDEX IF NOT EXISTS reply_to_id_index ON messages(reply_to, id DESC);
+
+-- emoji reactions to messages
+CREATE TABLE IF NOT EXISTS message_reactions (
+    message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(id),
+    emoji VARCHAR(32) NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (message_id, emoji, user_id)
+);
+
+-- messages with their reply count and reactions grouped by emoji
+CREATE OR REPLACE VIEW message_details AS
+SELECT
+    m.id,
+    m.chat_id,
+    m.sender_id,
+    m.content,
+    m.files,
+    m.created_at,
+    m.edited_at,
+    m.deleted,
+    m.reply_to,
+    (
+        SELECT COUNT(*)
+        FROM messages r
+        WHERE r.reply_to = m.id AND NOT r.deleted
+    ) AS reply_count,
+    COALESCE((
+        SELECT json_agg(json_build_object(
+            'emoji', r.emoji,
+            'count', r.count,
+            'user_ids', r.user_ids
+        ) ORDER BY r.reacted_at)
+        FROM (
+            SELECT emoji, COUNT(*) AS count, array_agg(user_id ORDER BY created_at) AS user_ids, MIN(created_at) AS reacted_at
+            FROM message_reactions
+            WHERE message_id = m.id
+            GROUP BY emoji
+        ) r
+    ), '[]') AS reactions
+FROM messages m;
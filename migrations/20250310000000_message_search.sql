@@ -0,0 +1,43 @@
+-- Add migration script here
+
+-- full text search of messages, the simple config does not stem words so it
+-- works the same for every language
+ALTER TABLE messages
+    ADD COLUMN content_tsv tsvector
+    GENERATED ALWAYS AS (to_tsvector('simple', content)) STORED;
+
+CREATE INDEX IF NOT EXISTS content_tsv_index ON messages USING GIN(content_tsv);
+
+-- notify with message data, without the search vector as payloads are limited
+CREATE OR REPLACE FUNCTION add_to_message()
+    RETURNS TRIGGER
+    AS $$
+    DECLARE USERS bigint[];
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        RAISE NOTICE 'add_to_message: %', NEW;
+        SELECT members INTO USERS
+        FROM chats
+        WHERE id = NEW.chat_id;
+        PERFORM pg_notify('chat_message_created', json_build_object('message', to_jsonb(NEW) - 'content_tsv', 'members', USERS)::TEXT);
+    ELSIF TG_OP = 'UPDATE' THEN
+        RAISE NOTICE 'add_to_message: %', NEW;
+        SELECT members INTO USERS
+        FROM chats
+        WHERE id = NEW.chat_id;
+        IF NEW.deleted AND NOT OLD.deleted THEN
+            PERFORM pg_notify('chat_message_deleted', json_build_object('message', to_jsonb(NEW) - 'content_tsv', 'members', USERS)::TEXT);
+        ELSIF NEW.edited_at IS DISTINCT FROM OLD.edited_at THEN
+            PERFORM pg_notify('chat_message_updated', json_build_object('message', to_jsonb(NEW) - 'content_tsv', 'members', USERS)::TEXT);
+        END IF;
+    ELSIF TG_OP = 'DELETE' THEN
+        RAISE NOTICE 'add_to_message: %', OLD;
+        SELECT members INTO USERS
+        FROM chats
+        WHERE id = OLD.chat_id;
+        PERFORM pg_notify('chat_message_deleted', json_build_object('message', to_jsonb(OLD) - 'content_tsv', 'members', USERS)::TEXT);
+    END IF;
+    RETURN NULL;
+END;
+$$
+LANGUAGE plpgsql;
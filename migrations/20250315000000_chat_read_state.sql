@@ -0,0 +1,31 @@
+-- Add migration script here
+
+-- the last message each member has read in a chat
+CREATE TABLE IF NOT EXISTS chat_read_state (
+    chat_id BIGINT NOT NULL REFERENCES chats(id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(id),
+    last_read_message_id BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (chat_id, user_id)
+);
+
+-- if read state changed notify with read state data
+CREATE OR REPLACE FUNCTION add_to_chat_read_state()
+    RETURNS TRIGGER
+    AS $$
+    DECLARE USERS bigint[];
+BEGIN
+    RAISE NOTICE 'add_to_chat_read_state: %', NEW;
+    SELECT members INTO USERS
+    FROM chats
+    WHERE id = NEW.chat_id;
+    PERFORM pg_notify('chat_read_updated', json_build_object('read_state', NEW, 'members', USERS)::TEXT);
+    RETURN NULL;
+END;
+$$
+LANGUAGE plpgsql;
+
+CREATE TRIGGER add_to_chat_read_state_trigger
+    AFTER INSERT OR UPDATE ON chat_read_state
+    FOR EACH ROW
+    EXECUTE FUNCTION add_to_chat_read_state();
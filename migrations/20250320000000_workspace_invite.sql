@@ -0,0 +1,27 @@
+-- Add migration script here
+
+-- role of a user in their workspace
+CREATE TYPE workspace_role AS ENUM ('member', 'admin', 'owner');
+
+ALTER TABLE users
+    ADD COLUMN role workspace_role NOT NULL DEFAULT 'member';
+
+UPDATE users u
+    SET role = 'owner'
+    FROM workspaces w
+    WHERE w.owner_id = u.id AND w.id = u.ws_id;
+
+-- invites are signed tokens, the rows are kept to revoke them and to make
+-- sure each one is only used once
+CREATE TABLE IF NOT EXISTS workspace_invites (
+    id bigserial PRIMARY KEY,
+    ws_id bigint NOT NULL REFERENCES workspaces(id),
+    inviter_id bigint NOT NULL REFERENCES users(id),
+    role workspace_role NOT NULL,
+    expires_at timestamptz NOT NULL,
+    revoked_at timestamptz,
+    accepted_by bigint REFERENCES users(id),
+    created_at timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS workspace_invites_ws_id_idx ON workspace_invites(ws_id, id DESC);
@@ -0,0 +1,157 @@
+use crate::backend::glob_match;
+use crate::config::ServerConfig;
+use serde::Deserialize;
+use std::collections::HashMap;
+use thiserror::Error;
+
+pub const DEFAULT_USER: &str = "default";
+
+/// An acl user and the commands and keys it may access.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AclUser {
+    pub name: String,
+    /// any password is accepted when unset
+    #[serde(default)]
+    pub password: Option<String>,
+    /// allowed command names, `*` allows every command and `-name` denies one
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// glob patterns of the keys the user may access
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+impl AclUser {
+    fn can_run(&self, command: &str) -> bool {
+        let denied = self
+            .commands
+            .iter()
+            .any(|rule| rule.strip_prefix('-') == Some(command));
+        !denied
+            && self
+                .commands
+                .iter()
+                .any(|rule| rule == "*" || rule.eq_ignore_ascii_case(command))
+    }
+
+    fn can_access(&self, key: &str) -> bool {
+        self.keys
+            .iter()
+            .any(|pattern| glob_match(pattern.as_bytes(), key.as_bytes()))
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum AclError {
+    #[error("WRONGPASS invalid username-password pair or user is disabled.")]
+    WrongPass,
+
+    #[error("NOPERM User {0} has no permissions to run the '{1}' command")]
+    Command(String, String),
+
+    #[error("NOPERM No permissions to access a key")]
+    Key,
+}
+
+#[derive(Debug, Default)]
+pub struct Acl {
+    users: HashMap<String, AclUser>,
+}
+
+impl Acl {
+    /// The default user may run everything, protected by `requirepass` if
+    /// set. It can be restricted by configuring a user named `default`.
+    pub fn new(config: &ServerConfig) -> Self {
+        let default = AclUser {
+            name: DEFAULT_USER.to_string(),
+            password: config.requirepass.clone(),
+            commands: vec!["*".to_string()],
+            keys: vec!["*".to_string()],
+        };
+        let users = std::iter::once(default)
+            .chain(config.users.iter().cloned())
+            .map(|user| (user.name.clone(), user))
+            .collect();
+        Self { users }
+    }
+
+    /// The user new connections are logged in as, None if they have to
+    /// authenticate first.
+    pub fn default_user(&self) -> Option<&str> {
+        self.users
+            .get(DEFAULT_USER)
+            .filter(|user| user.password.is_none())
+            .map(|user| user.name.as_str())
+    }
+
+    pub fn authenticate(&self, username: &str, password: &str) -> Result<(), AclError> {
+        match self.users.get(username) {
+            Some(user) if user.password.as_deref().is_none_or(|p| p == password) => Ok(()),
+            _ => Err(AclError::WrongPass),
+        }
+    }
+
+    /// Check that the user may run the command on the given keys.
+    pub fn check(&self, username: &str, command: &str, keys: &[&str]) -> Result<(), AclError> {
+        let Some(user) = self.users.get(username) else {
+            return Err(AclError::WrongPass);
+        };
+        if !user.can_run(command) {
+            return Err(AclError::Command(username.to_string(), command.to_string()));
+        }
+        if !keys.iter().all(|key| user.can_access(key)) {
+            return Err(AclError::Key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acl(requirepass: Option<&str>) -> Acl {
+        let config = ServerConfig {
+            requirepass: requirepass.map(String::from),
+            users: vec![AclUser {
+                name: "app".to_string(),
+                password: Some("secret".to_string()),
+                commands: vec!["*".to_string(), "-save".to_string()],
+                keys: vec!["app:*".to_string()],
+            }],
+            ..Default::default()
+        };
+        Acl::new(&config)
+    }
+
+    #[test]
+    fn test_default_user_should_follow_requirepass() {
+        let open = acl(None);
+        assert_eq!(open.default_user(), Some(DEFAULT_USER));
+        assert_eq!(open.authenticate(DEFAULT_USER, "anything"), Ok(()));
+
+        let protected = acl(Some("pwd"));
+        assert_eq!(protected.default_user(), None);
+        assert_eq!(
+            protected.authenticate(DEFAULT_USER, "nope"),
+            Err(AclError::WrongPass)
+        );
+        assert_eq!(protected.authenticate(DEFAULT_USER, "pwd"), Ok(()));
+        assert_eq!(
+            protected.authenticate("bob", "pwd"),
+            Err(AclError::WrongPass)
+        );
+    }
+
+    #[test]
+    fn test_check_should_restrict_commands_and_keys() {
+        let acl = acl(None);
+        assert_eq!(acl.check("app", "get", &["app:1"]), Ok(()));
+        assert_eq!(acl.check("app", "get", &["other"]), Err(AclError::Key));
+        assert_eq!(
+            acl.check("app", "save", &[]),
+            Err(AclError::Command("app".to_string(), "save".to_string()))
+        );
+        assert_eq!(acl.check(DEFAULT_USER, "save", &["other"]), Ok(()));
+    }
+}
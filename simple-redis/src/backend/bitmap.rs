@@ -0,0 +1,253 @@
+use super::string::{index_range, string_bytes};
+use super::{Backend, StringError, WrongType};
+use crate::BulkString;
+use crate::cmd::{BitOperation, BitRange, BitUnit};
+use std::ops::RangeInclusive;
+
+impl Backend {
+    /// Set or clear the bit at the offset, the string is padded with zero
+    /// bytes if it is shorter. Returns the previous bit.
+    pub fn setbit(&self, key: &str, offset: usize, bit: bool) -> Result<bool, StringError> {
+        self.update_string(key, |value| {
+            let mut value = value.map(<[u8]>::to_vec).unwrap_or_default();
+            let byte = offset / 8;
+            if value.len() <= byte {
+                value.resize(byte + 1, 0);
+            }
+            let mask = 0x80 >> (offset % 8);
+            let old = value[byte] & mask != 0;
+            if bit {
+                value[byte] |= mask;
+            } else {
+                value[byte] &= !mask;
+            }
+            Ok((value, old))
+        })
+    }
+
+    /// The bit at the offset, bits past the end of the string are clear.
+    pub fn getbit(&self, key: &str, offset: usize) -> Result<bool, WrongType> {
+        let bytes = self.string_at(key)?.unwrap_or_default();
+        Ok(bit_at(&bytes, offset))
+    }
+
+    /// Number of set bits in the string, or in a range of it.
+    pub fn bitcount(&self, key: &str, range: Option<BitRange>) -> Result<usize, WrongType> {
+        let bytes = self.string_at(key)?.unwrap_or_default();
+        let count = match range {
+            None => count_ones(&bytes),
+            Some(range) => match bit_range(range, bytes.len()) {
+                Some(bits) if range.unit == BitUnit::Byte => {
+                    count_ones(&bytes[bits.start() / 8..=bits.end() / 8])
+                }
+                Some(bits) => bits.filter(|&i| bit_at(&bytes, i)).count(),
+                None => 0,
+            },
+        };
+        Ok(count)
+    }
+
+    /// Offset of the first bit set to `bit` in a range of the string, -1 if
+    /// there is none. Without an explicit end the string counts as padded
+    /// with clear bits, the first one past its end is found then.
+    pub fn bitpos(
+        &self,
+        key: &str,
+        bit: bool,
+        range: BitRange,
+        end_given: bool,
+    ) -> Result<i64, WrongType> {
+        let Some(bytes) = self.string_at(key)? else {
+            return Ok(if bit { -1 } else { 0 });
+        };
+        let Some(mut bits) = bit_range(range, bytes.len()) else {
+            return Ok(-1);
+        };
+        match bits.find(|&i| bit_at(&bytes, i) == bit) {
+            Some(pos) => Ok(pos as i64),
+            None if !bit && !end_given => Ok(bytes.len() as i64 * 8),
+            None => Ok(-1),
+        }
+    }
+
+    /// Store the result of the bitwise operation over the strings at the
+    /// source keys, shorter ones being padded with zero bytes. An empty
+    /// result removes the destination. Returns the length of the result.
+    pub fn bitop(&self, op: BitOperation, dest: &str, keys: &[String]) -> Result<usize, WrongType> {
+        let sources = keys
+            .iter()
+            .map(|key| Ok(self.string_at(key)?.unwrap_or_default()))
+            .collect::<Result<Vec<_>, WrongType>>()?;
+        let len = sources.iter().map(Vec::len).max().unwrap_or_default();
+        let result = (0..len)
+            .map(|i| {
+                let mut bytes = sources.iter().map(|s| s.get(i).copied().unwrap_or(0));
+                match op {
+                    BitOperation::And => bytes.fold(0xff, |a, b| a & b),
+                    BitOperation::Or => bytes.fold(0, |a, b| a | b),
+                    BitOperation::Xor => bytes.fold(0, |a, b| a ^ b),
+                    BitOperation::Not => !bytes.next().unwrap_or(0),
+                }
+            })
+            .collect::<Vec<u8>>();
+
+        if result.is_empty() {
+            self.remove(dest);
+        } else {
+            self.set(dest.to_string(), BulkString::new(result).into());
+        }
+        Ok(len)
+    }
+
+    /// The bytes of the string at the key, None if it does not exist.
+    pub(super) fn string_at(&self, key: &str) -> Result<Option<Vec<u8>>, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(value) => Ok(Some(string_bytes(value.as_string()?))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The offsets of the bits in range of a string of `len` bytes.
+fn bit_range(range: BitRange, len: usize) -> Option<RangeInclusive<usize>> {
+    match range.unit {
+        BitUnit::Byte => index_range(range.start, range.end, len)
+            .map(|bytes| bytes.start() * 8..=bytes.end() * 8 + 7),
+        BitUnit::Bit => index_range(range.start, range.end, len * 8),
+    }
+}
+
+/// Bits are numbered from the most significant one of the first byte.
+fn bit_at(bytes: &[u8], offset: usize) -> bool {
+    bytes
+        .get(offset / 8)
+        .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0)
+}
+
+fn count_ones(bytes: &[u8]) -> usize {
+    bytes.iter().map(|byte| byte.count_ones() as usize).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(backend: &Backend, key: &str, value: &[u8]) {
+        backend.set(key.to_string(), BulkString::new(value.to_vec()).into());
+    }
+
+    fn range(start: i64, end: i64, unit: BitUnit) -> BitRange {
+        BitRange { start, end, unit }
+    }
+
+    #[test]
+    fn test_setbit_and_getbit() {
+        let backend = Backend::new();
+        assert_eq!(backend.setbit("b", 7, true), Ok(false));
+        assert_eq!(backend.setbit("b", 7, true), Ok(true));
+        assert_eq!(backend.get("b"), Ok(Some(BulkString::new(vec![1]).into())));
+        assert_eq!(backend.setbit("b", 17, true), Ok(false));
+        assert_eq!(backend.strlen("b"), Ok(3));
+        assert_eq!(backend.getbit("b", 17), Ok(true));
+        assert_eq!(backend.getbit("b", 16), Ok(false));
+        assert_eq!(backend.getbit("b", 1000), Ok(false));
+        assert_eq!(backend.setbit("b", 7, false), Ok(true));
+        assert_eq!(backend.getbit("missing", 0), Ok(false));
+    }
+
+    #[test]
+    fn test_bitcount() {
+        let backend = Backend::new();
+        set(&backend, "s", b"foobar");
+        assert_eq!(backend.bitcount("s", None), Ok(26));
+        assert_eq!(
+            backend.bitcount("s", Some(range(0, 0, BitUnit::Byte))),
+            Ok(4)
+        );
+        assert_eq!(
+            backend.bitcount("s", Some(range(1, 1, BitUnit::Byte))),
+            Ok(6)
+        );
+        assert_eq!(
+            backend.bitcount("s", Some(range(1, 1, BitUnit::Bit))),
+            Ok(1)
+        );
+        assert_eq!(
+            backend.bitcount("s", Some(range(5, 30, BitUnit::Bit))),
+            Ok(17)
+        );
+        assert_eq!(
+            backend.bitcount("s", Some(range(-2, -1, BitUnit::Byte))),
+            Ok(7)
+        );
+        assert_eq!(
+            backend.bitcount("s", Some(range(3, 1, BitUnit::Byte))),
+            Ok(0)
+        );
+        assert_eq!(backend.bitcount("missing", None), Ok(0));
+    }
+
+    #[test]
+    fn test_bitpos() {
+        let backend = Backend::new();
+        set(&backend, "s", &[0xff, 0xf0, 0x00]);
+        let all = range(0, -1, BitUnit::Byte);
+        assert_eq!(backend.bitpos("s", false, all, false), Ok(12));
+        assert_eq!(
+            backend.bitpos("s", true, range(1, -1, BitUnit::Byte), false),
+            Ok(8)
+        );
+        assert_eq!(
+            backend.bitpos("s", true, range(2, -1, BitUnit::Byte), false),
+            Ok(-1)
+        );
+        assert_eq!(
+            backend.bitpos("s", true, range(7, 15, BitUnit::Bit), true),
+            Ok(7)
+        );
+
+        set(&backend, "ones", &[0xff, 0xff]);
+        assert_eq!(backend.bitpos("ones", false, all, false), Ok(16));
+        assert_eq!(backend.bitpos("ones", false, all, true), Ok(-1));
+        assert_eq!(backend.bitpos("missing", false, all, false), Ok(0));
+        assert_eq!(backend.bitpos("missing", true, all, false), Ok(-1));
+    }
+
+    #[test]
+    fn test_bitop() {
+        let backend = Backend::new();
+        set(&backend, "a", b"foobar");
+        set(&backend, "b", b"abcdef");
+        let keys = ["a".to_string(), "b".to_string()];
+
+        assert_eq!(backend.bitop(BitOperation::And, "and", &keys), Ok(6));
+        assert_eq!(
+            backend.get("and"),
+            Ok(Some(BulkString::from("`bc`ab").into()))
+        );
+        assert_eq!(backend.bitop(BitOperation::Or, "or", &keys), Ok(6));
+        assert_eq!(
+            backend.get("or"),
+            Ok(Some(BulkString::from("goofev").into()))
+        );
+        assert_eq!(backend.bitop(BitOperation::Xor, "a", &keys), Ok(6));
+        assert_eq!(
+            backend.get("a"),
+            Ok(Some(
+                BulkString::new(b"\x07\x0d\x0c\x06\x04\x14".to_vec()).into()
+            ))
+        );
+
+        set(&backend, "short", &[0x0f]);
+        let keys = ["short".to_string(), "missing".to_string()];
+        assert_eq!(backend.bitop(BitOperation::Or, "or", &keys), Ok(1));
+        assert_eq!(backend.bitop(BitOperation::Not, "not", &keys[..1]), Ok(1));
+        assert_eq!(
+            backend.get("not"),
+            Ok(Some(BulkString::new(vec![0xf0]).into()))
+        );
+        assert_eq!(backend.bitop(BitOperation::And, "not", &keys[1..]), Ok(0));
+        assert!(!backend.exists("not"));
+    }
+}
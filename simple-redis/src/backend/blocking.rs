@@ -0,0 +1,174 @@
+use super::Backend;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::Notify;
+
+type Queues = HashMap<(usize, String), VecDeque<Arc<Notify>>>;
+
+/// Clients blocked on keys, by database, in the order they blocked.
+#[derive(Debug, Default)]
+pub(crate) struct BlockedKeys(Mutex<Queues>);
+
+impl BlockedKeys {
+    fn queues(&self) -> MutexGuard<'_, Queues> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn add(&self, db: usize, keys: &[String], waiter: &Arc<Notify>) {
+        let mut queues = self.queues();
+        for key in keys {
+            queues
+                .entry((db, key.clone()))
+                .or_default()
+                .push_back(waiter.clone());
+        }
+    }
+
+    fn remove(&self, db: usize, keys: &[String], waiter: &Arc<Notify>) {
+        let mut queues = self.queues();
+        for key in keys {
+            let id = (db, key.clone());
+            if let Some(queue) = queues.get_mut(&id) {
+                queue.retain(|w| !Arc::ptr_eq(w, waiter));
+                if queue.is_empty() {
+                    queues.remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Wake the client which blocked first on the key, if any.
+    pub(crate) fn wake_first(&self, db: usize, key: &str) {
+        if let Some(waiter) = self
+            .queues()
+            .get(&(db, key.to_string()))
+            .and_then(|queue| queue.front())
+        {
+            waiter.notify_one();
+        }
+    }
+
+    /// Wake every client blocked on the key, in the order they blocked.
+    pub(crate) fn wake_all(&self, db: usize, key: &str) {
+        if let Some(queue) = self.queues().get(&(db, key.to_string())) {
+            queue.iter().for_each(|waiter| waiter.notify_one());
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.queues().values().map(VecDeque::len).sum()
+    }
+}
+
+/// A client blocked on keys until it's dropped. A wake is never lost: it's
+/// kept if the client is not waiting yet, and a client leaving while a list
+/// it blocked on still has values passes the wake on to the next one.
+#[derive(Debug)]
+pub(crate) struct Blocked<'a> {
+    backend: &'a Backend,
+    keys: &'a [String],
+    waiter: Arc<Notify>,
+    list: bool,
+}
+
+impl Backend {
+    /// Queue up for values pushed to one of the lists.
+    pub(crate) fn block_on_lists<'a>(&'a self, keys: &'a [String]) -> Blocked<'a> {
+        self.block_on(keys, true)
+    }
+
+    /// Queue up for entries added to one of the streams.
+    pub(crate) fn block_on_streams<'a>(&'a self, keys: &'a [String]) -> Blocked<'a> {
+        self.block_on(keys, false)
+    }
+
+    fn block_on<'a>(&'a self, keys: &'a [String], list: bool) -> Blocked<'a> {
+        let blocked = Blocked {
+            backend: self,
+            keys,
+            waiter: Arc::new(Notify::new()),
+            list,
+        };
+        blocked.queue().add(self.db_index(), keys, &blocked.waiter);
+        blocked
+    }
+}
+
+impl Blocked<'_> {
+    fn queue(&self) -> &BlockedKeys {
+        match self.list {
+            true => &self.backend.blocked_lists,
+            false => &self.backend.blocked_streams,
+        }
+    }
+
+    pub(crate) async fn woken(&self) {
+        self.waiter.notified().await
+    }
+}
+
+impl Drop for Blocked<'_> {
+    fn drop(&mut self) {
+        let db = self.backend.db_index();
+        self.queue().remove(db, self.keys, &self.waiter);
+        if self.list {
+            for key in self.keys {
+                if self.backend.llen(key).is_ok_and(|len| len > 0) {
+                    self.backend.blocked_lists.wake_first(db, key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+    use std::time::Duration;
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    async fn woken(blocked: &Blocked<'_>) -> bool {
+        tokio::time::timeout(Duration::from_millis(10), blocked.woken())
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_push_should_wake_first_blocked_client_only() {
+        let backend = Backend::new();
+        let (a, b) = (keys(&["list"]), keys(&["list", "other"]));
+        let first = backend.block_on_lists(&a);
+        let second = backend.block_on_lists(&b);
+        assert_eq!(backend.blocked_lists.len(), 3);
+
+        backend
+            .rpush("list".to_string(), vec![BulkString::from("a").into()])
+            .unwrap();
+        assert!(!woken(&second).await);
+        assert!(woken(&first).await);
+
+        // leaving with the value still in the list hands it to the next one
+        drop(first);
+        assert!(woken(&second).await);
+        drop(second);
+        assert_eq!(backend.blocked_lists.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_clients_should_be_per_database() {
+        let backend = Backend::new();
+        let other = backend.select(1).unwrap();
+        let key = keys(&["list"]);
+        let blocked = other.block_on_lists(&key);
+
+        backend
+            .rpush("list".to_string(), vec![BulkString::from("a").into()])
+            .unwrap();
+        assert!(!woken(&blocked).await);
+    }
+}
@@ -0,0 +1,266 @@
+use super::{Backend, Subscriber, now_ms};
+use crate::{BulkString, RespFrame, SimpleString};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// A connection as listed by CLIENT LIST, refreshed after each of its
+/// commands.
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub id: u64,
+    pub addr: SocketAddr,
+    pub name: Option<String>,
+    pub user: Option<String>,
+    pub db: usize,
+    /// unix milliseconds of the connection and of its last command
+    pub created_at: u64,
+    pub last_interaction: u64,
+    /// lowercase name of the last command
+    pub last_command: Option<String>,
+    pub channels: usize,
+    pub patterns: usize,
+    /// number of queued commands while in MULTI
+    pub multi: Option<usize>,
+    pub monitor: bool,
+    pub replica: bool,
+    /// notified to close the connection
+    pub(crate) kill: Arc<Notify>,
+}
+
+impl ClientInfo {
+    pub fn new(id: u64, addr: SocketAddr, kill: Arc<Notify>) -> Self {
+        let now = now_ms();
+        Self {
+            id,
+            addr,
+            name: None,
+            user: None,
+            db: 0,
+            created_at: now,
+            last_interaction: now,
+            last_command: None,
+            channels: 0,
+            patterns: 0,
+            multi: None,
+            monitor: false,
+            replica: false,
+            kill,
+        }
+    }
+
+    /// A line of CLIENT LIST, such as
+    /// `id=3 addr=127.0.0.1:52555 name= age=2 idle=0 flags=N db=0 ...`.
+    pub fn line(&self, now: u64) -> String {
+        format!(
+            "id={} addr={} name={} age={} idle={} flags={} db={} sub={} psub={} multi={} cmd={} user={}",
+            self.id,
+            self.addr,
+            self.name.as_deref().unwrap_or_default(),
+            now.saturating_sub(self.created_at) / 1000,
+            now.saturating_sub(self.last_interaction) / 1000,
+            self.flags(),
+            self.db,
+            self.channels,
+            self.patterns,
+            self.multi.map_or(-1, |queued| queued as i64),
+            self.last_command.as_deref().unwrap_or("NULL"),
+            self.user.as_deref().unwrap_or_default(),
+        )
+    }
+
+    /// S for a replica, O for a monitor, P when subscribed, x in MULTI and
+    /// N for none of them.
+    fn flags(&self) -> String {
+        let mut flags = String::new();
+        if self.replica {
+            flags.push('S');
+        }
+        if self.monitor {
+            flags.push('O');
+        }
+        if self.channels + self.patterns > 0 {
+            flags.push('P');
+        }
+        if self.multi.is_some() {
+            flags.push('x');
+        }
+        if flags.is_empty() {
+            flags.push('N');
+        }
+        flags
+    }
+}
+
+impl Backend {
+    /// Track a connection until it is unregistered.
+    pub(crate) fn register_client(&self, info: ClientInfo) {
+        self.total_connections_received
+            .fetch_add(1, Ordering::Relaxed);
+        self.clients.insert(info.id, info);
+    }
+
+    pub(crate) fn unregister_client(&self, id: u64) {
+        self.clients.remove(&id);
+        self.monitors.remove(&id);
+    }
+
+    pub(crate) fn update_client(&self, id: u64, update: impl FnOnce(&mut ClientInfo)) {
+        if let Some(mut info) = self.clients.get_mut(&id) {
+            update(&mut info);
+        }
+    }
+
+    /// The connections ordered by id.
+    pub fn clients(&self) -> Vec<ClientInfo> {
+        let mut clients = self
+            .clients
+            .iter()
+            .map(|v| v.value().clone())
+            .collect::<Vec<_>>();
+        clients.sort_by_key(|info| info.id);
+        clients
+    }
+
+    /// The reply of CLIENT LIST, a line per connection.
+    pub fn client_list(&self) -> String {
+        let now = now_ms();
+        self.clients()
+            .iter()
+            .map(|info| format!("{}\n", info.line(now)))
+            .collect()
+    }
+
+    /// Close the connections matching the filter, returns their number.
+    pub fn kill_clients(&self, filter: impl Fn(&ClientInfo) -> bool) -> usize {
+        self.clients
+            .iter()
+            .filter(|v| filter(v.value()))
+            .map(|v| v.kill.notify_one())
+            .count()
+    }
+
+    /// Stream every command run from now on to the connection.
+    pub(crate) fn add_monitor(&self, id: u64, sender: Subscriber) {
+        self.monitors.insert(id, sender);
+        self.update_client(id, |info| info.monitor = true);
+    }
+
+    pub fn is_monitored(&self) -> bool {
+        !self.monitors.is_empty()
+    }
+
+    /// Send a command to the monitors as
+    /// `+1339518083.107412 [0 127.0.0.1:60866] "keys" "*"`.
+    pub(crate) fn feed_monitors(&self, addr: &SocketAddr, args: &[BulkString]) {
+        if !self.is_monitored() {
+            return;
+        }
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut line = format!(
+            "{}.{:06} [{} {}]",
+            time.as_secs(),
+            time.subsec_micros(),
+            self.db,
+            addr
+        );
+        for arg in args {
+            line.push(' ');
+            line.push_str(&quote(arg));
+        }
+        let frame: RespFrame = SimpleString::new(line).into();
+        self.monitors
+            .retain(|_, monitor| monitor.send(frame.clone()).is_ok());
+    }
+}
+
+/// An argument in double quotes, with quotes, line breaks and non printable
+/// bytes escaped.
+fn quote(arg: &[u8]) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for &c in arg {
+        match c {
+            b'\\' => quoted.push_str("\\\\"),
+            b'"' => quoted.push_str("\\\""),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            c if c == b' ' || c.is_ascii_graphic() => quoted.push(c as char),
+            c => quoted.push_str(&format!("\\x{:02x}", c)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn test_client_list_and_kill() {
+        let backend = Backend::new();
+        let kill = Arc::new(Notify::new());
+        backend.register_client(ClientInfo::new(2, addr(2000), kill.clone()));
+        backend.register_client(ClientInfo::new(1, addr(1000), Arc::new(Notify::new())));
+        backend.update_client(2, |info| {
+            info.name = Some("worker".to_string());
+            info.db = 3;
+            info.channels = 1;
+            info.multi = Some(2);
+            info.last_command = Some("get".to_string());
+        });
+
+        let list = backend.client_list();
+        let lines = list.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("id=1 addr=127.0.0.1:1000 name= age=0 idle=0 flags=N db=0"));
+        assert!(lines[0].contains(" multi=-1 cmd=NULL "));
+        assert!(lines[1].starts_with("id=2 addr=127.0.0.1:2000 name=worker "));
+        assert!(lines[1].contains(" flags=Px db=3 sub=1 psub=0 multi=2 cmd=get "));
+
+        assert_eq!(backend.kill_clients(|info| info.db == 3), 1);
+        // the permit is kept until the connection waits for it
+        assert!(futures::FutureExt::now_or_never(kill.notified()).is_some());
+
+        backend.unregister_client(2);
+        assert_eq!(backend.clients().len(), 1);
+        assert_eq!(
+            backend.total_connections_received.load(Ordering::Relaxed),
+            2
+        );
+    }
+
+    #[test]
+    fn test_monitors_should_receive_commands() {
+        let backend = Backend::new();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        assert!(!backend.is_monitored());
+        backend.add_monitor(1, sender);
+
+        let args = [
+            BulkString::from("set"),
+            BulkString::from("k"),
+            BulkString::from("a \"b\"\n"),
+        ];
+        backend.select(2).unwrap().feed_monitors(&addr(3000), &args);
+        let Ok(RespFrame::SimpleString(line)) = receiver.try_recv() else {
+            panic!("the monitor should receive a simple string");
+        };
+        assert!(line.ends_with(r#" [2 127.0.0.1:3000] "set" "k" "a \"b\"\n""#));
+
+        drop(receiver);
+        backend.feed_monitors(&addr(3000), &args);
+        assert!(!backend.is_monitored());
+    }
+}
@@ -0,0 +1,338 @@
+use super::{Backend, StringError, Value, WrongType};
+use crate::RespFrame;
+
+/// Bits of the hash selecting a register.
+const P: u32 = 14;
+const REGISTERS: usize = 1 << P;
+/// Bits of the hash whose run of zeros is counted.
+const Q: u32 = 64 - P;
+const REGISTER_BITS: usize = 6;
+const HEADER_SIZE: usize = 16;
+/// Size of a dense sketch, as stored by redis.
+const DENSE_SIZE: usize = HEADER_SIZE + (REGISTERS * REGISTER_BITS).div_ceil(8);
+const MAGIC: &[u8] = b"HYLL";
+const ENCODING_DENSE: u8 = 0;
+/// Set in the last byte of the cached cardinality when it is stale.
+const CACHE_STALE: u8 = 0x80;
+
+/// A HyperLogLog sketch estimating the number of distinct elements added to
+/// it. Kept in the dense redis format it is stored with: a "HYLL" header
+/// with the cached cardinality followed by 16384 6 bit registers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperLogLog {
+    bytes: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        let mut bytes = vec![0; DENSE_SIZE];
+        bytes[..MAGIC.len()].copy_from_slice(MAGIC);
+        bytes[MAGIC.len()] = ENCODING_DENSE;
+        Self { bytes }
+    }
+}
+
+impl HyperLogLog {
+    /// A stored sketch, None if the string is not one.
+    pub fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        Self::is_sketch(&bytes).then_some(Self { bytes })
+    }
+
+    pub fn is_sketch(bytes: &[u8]) -> bool {
+        bytes.len() == DENSE_SIZE
+            && bytes.starts_with(MAGIC)
+            && bytes[MAGIC.len()] == ENCODING_DENSE
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Add an element, returns whether a register changed and so possibly
+    /// the estimated cardinality.
+    pub fn add(&mut self, element: &[u8]) -> bool {
+        let hash = murmur_hash64a(element, 0xadc8_3b19);
+        let index = (hash & (REGISTERS as u64 - 1)) as usize;
+        // the extra bit stops the count of zeros if all the others are clear
+        let count = ((hash >> P) | (1 << Q)).trailing_zeros() as u8 + 1;
+        if count > self.register(index) {
+            self.set_register(index, count);
+            self.invalidate();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Keep the largest register of both sketches, the result estimates the
+    /// cardinality of the union.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for i in 0..REGISTERS {
+            let value = other.register(i);
+            if value > self.register(i) {
+                self.set_register(i, value);
+            }
+        }
+        self.invalidate();
+    }
+
+    /// The estimated cardinality, computed again only if the sketch changed
+    /// since the last time.
+    pub fn count(&mut self) -> u64 {
+        let cache = &self.bytes[8..HEADER_SIZE];
+        if cache[7] & CACHE_STALE == 0 {
+            return u64::from_le_bytes(cache.try_into().expect("the cache is 8 bytes"));
+        }
+        let count = self.estimate();
+        self.bytes[8..HEADER_SIZE].copy_from_slice(&count.to_le_bytes());
+        count
+    }
+
+    /// The estimator of Otmar Ertl, as used by redis.
+    fn estimate(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let mut histogram = [0u32; Q as usize + 2];
+        for i in 0..REGISTERS {
+            histogram[self.register(i) as usize] += 1;
+        }
+        let q = Q as usize;
+        let mut z = m * tau((m - histogram[q + 1] as f64) / m);
+        for &count in histogram[1..=q].iter().rev() {
+            z += count as f64;
+            z *= 0.5;
+        }
+        z += m * sigma(histogram[0] as f64 / m);
+        (0.5 / std::f64::consts::LN_2 * m * m / z).round() as u64
+    }
+
+    fn invalidate(&mut self) {
+        self.bytes[HEADER_SIZE - 1] |= CACHE_STALE;
+    }
+
+    /// Registers are packed from the least significant bits of each byte.
+    fn register(&self, i: usize) -> u8 {
+        let (byte, shift) = (HEADER_SIZE + i * REGISTER_BITS / 8, i * REGISTER_BITS % 8);
+        let low = self.bytes[byte] as u16;
+        let high = self.bytes.get(byte + 1).copied().unwrap_or(0) as u16;
+        (((high << 8 | low) >> shift) & 0x3f) as u8
+    }
+
+    fn set_register(&mut self, i: usize, value: u8) {
+        let (byte, shift) = (HEADER_SIZE + i * REGISTER_BITS / 8, i * REGISTER_BITS % 8);
+        self.bytes[byte] &= !(0x3f << shift);
+        self.bytes[byte] |= value << shift;
+        if shift > 8 - REGISTER_BITS {
+            self.bytes[byte + 1] &= !(0x3f >> (8 - shift));
+            self.bytes[byte + 1] |= value >> (8 - shift);
+        }
+    }
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+/// MurmurHash64A, the hash redis uses for HyperLogLog elements.
+fn murmur_hash64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().expect("chunks are 8 bytes"));
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, &byte) in tail.iter().enumerate() {
+            h ^= (byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+impl Backend {
+    /// Add the elements to the sketch at the key, created if missing.
+    /// Returns whether it was created or its estimate may have changed.
+    pub fn pfadd(&self, key: &str, elements: &[Vec<u8>]) -> Result<bool, StringError> {
+        self.update_string(key, |value| {
+            let (mut hll, mut changed) = match value {
+                Some(value) => (parse(value.to_vec())?, false),
+                None => (HyperLogLog::default(), true),
+            };
+            for element in elements {
+                changed |= hll.add(element);
+            }
+            Ok((hll.into_bytes(), changed))
+        })
+    }
+
+    /// The estimated cardinality of the union of the sketches at the keys,
+    /// missing ones are empty. The cardinality of a single sketch is cached
+    /// in it.
+    pub fn pfcount(&self, keys: &[String]) -> Result<u64, StringError> {
+        let [key] = keys else {
+            return Ok(self.union(keys)?.count());
+        };
+        self.evict_if_expired(key);
+        let Some(mut value) = self.db().keys.get_mut(key) else {
+            return Ok(0);
+        };
+        match value.value_mut() {
+            // refreshing the cached cardinality keeps the size of the string
+            Value::String(RespFrame::BulkString(bytes)) if HyperLogLog::is_sketch(&bytes.0) => {
+                let mut hll = HyperLogLog {
+                    bytes: std::mem::take(&mut bytes.0),
+                };
+                let count = hll.count();
+                bytes.0 = hll.into_bytes();
+                Ok(count)
+            }
+            Value::String(_) => Err(StringError::NotHyperLogLog),
+            _ => Err(WrongType.into()),
+        }
+    }
+
+    /// Merge the sketches at the source keys into the one at the destination,
+    /// created if missing.
+    pub fn pfmerge(&self, dest: &str, sources: &[String]) -> Result<(), StringError> {
+        let union = self.union(sources)?;
+        self.update_string(dest, |value| {
+            let mut hll = match value {
+                Some(value) => parse(value.to_vec())?,
+                None => HyperLogLog::default(),
+            };
+            hll.merge(&union);
+            hll.count();
+            Ok((hll.into_bytes(), ()))
+        })
+    }
+
+    fn union(&self, keys: &[String]) -> Result<HyperLogLog, StringError> {
+        let mut union = HyperLogLog::default();
+        for key in keys {
+            if let Some(bytes) = self.string_at(key)? {
+                union.merge(&parse(bytes)?);
+            }
+        }
+        Ok(union)
+    }
+}
+
+fn parse(bytes: Vec<u8>) -> Result<HyperLogLog, StringError> {
+    HyperLogLog::from_bytes(bytes).ok_or(StringError::NotHyperLogLog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_murmur_hash64a() {
+        assert_eq!(murmur_hash64a(b"", 0), 0);
+        assert_ne!(murmur_hash64a(b"a", 0), murmur_hash64a(b"b", 0));
+        assert_ne!(murmur_hash64a(b"hello", 0), murmur_hash64a(b"hello", 1));
+    }
+
+    #[test]
+    fn test_sketch_should_round_trip() {
+        let mut hll = HyperLogLog::default();
+        assert_eq!(hll.count(), 0);
+        for i in 0..1000 {
+            hll.add(format!("user:{}", i).as_bytes());
+        }
+        let count = hll.count();
+        let bytes = hll.clone().into_bytes();
+        assert_eq!(bytes.len(), DENSE_SIZE);
+        assert_eq!(bytes[HEADER_SIZE - 1] & CACHE_STALE, 0);
+        let mut parsed = HyperLogLog::from_bytes(bytes).expect("a sketch");
+        assert_eq!(parsed, hll);
+        assert_eq!(parsed.count(), count);
+        assert!(hll.add(b"user:1000"));
+        assert_ne!(hll.bytes[HEADER_SIZE - 1] & CACHE_STALE, 0);
+        assert_eq!(HyperLogLog::from_bytes(b"HYLL".to_vec()), None);
+    }
+
+    #[test]
+    fn test_count_should_be_close() {
+        let mut hll = HyperLogLog::default();
+        for i in 0..100_000 {
+            hll.add(format!("user:{}", i).as_bytes());
+        }
+        // the standard error is 0.81%
+        let count = hll.count() as f64;
+        assert!((count - 100_000.0).abs() / 100_000.0 < 0.03, "{}", count);
+        assert!(!hll.add(b"user:42"));
+    }
+
+    #[test]
+    fn test_pfadd_pfcount_and_pfmerge() {
+        let backend = Backend::new();
+        let elements = |range: std::ops::Range<i32>| {
+            range
+                .map(|i| i.to_string().into_bytes())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(backend.pfadd("empty", &[]), Ok(true));
+        assert_eq!(backend.pfcount(&["empty".to_string()]), Ok(0));
+
+        assert_eq!(backend.pfadd("a", &elements(0..10)), Ok(true));
+        assert_eq!(backend.pfadd("a", &elements(0..10)), Ok(false));
+        assert_eq!(backend.pfadd("b", &elements(5..20)), Ok(true));
+        assert_eq!(backend.pfcount(&["a".to_string()]), Ok(10));
+        let both = ["a".to_string(), "b".to_string(), "missing".to_string()];
+        assert_eq!(backend.pfcount(&both), Ok(20));
+
+        assert_eq!(backend.pfmerge("union", &both), Ok(()));
+        assert_eq!(backend.pfcount(&["union".to_string()]), Ok(20));
+
+        backend.set("s".to_string(), BulkString::from("text").into());
+        assert_eq!(backend.pfadd("s", &[]), Err(StringError::NotHyperLogLog));
+        assert_eq!(
+            backend.pfcount(&["s".to_string()]),
+            Err(StringError::NotHyperLogLog)
+        );
+        assert_eq!(backend.get("s"), Ok(Some(BulkString::from("text").into())));
+    }
+}
@@ -0,0 +1,245 @@
+use super::{Backend, DATABASES, WrongType, glob_match};
+use crate::RespFrame;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+impl Backend {
+    /// Remove the keys, returns the number of removed ones.
+    pub fn del(&self, keys: &[String]) -> usize {
+        keys.iter()
+            .filter(|key| {
+                self.evict_if_expired(key);
+                self.remove(key)
+            })
+            .count()
+    }
+
+    /// The type of the value of the key, None if it does not exist.
+    pub fn key_type(&self, key: &str) -> Option<&'static str> {
+        self.evict_if_expired(key);
+        self.db().keys.get(key).map(|value| value.type_name())
+    }
+
+    /// The keys matching the glob pattern.
+    pub fn keys(&self, pattern: &str) -> Vec<String> {
+        self.live_keys()
+            .into_iter()
+            .filter(|key| glob_match(pattern.as_bytes(), key.as_bytes()))
+            .collect()
+    }
+
+    /// One page of the keys, see [`scan_page`]. Returns the cursor to
+    /// continue from, 0 once the iteration is complete.
+    pub fn scan(
+        &self,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&str>,
+        key_type: Option<&str>,
+    ) -> (u64, Vec<String>) {
+        let keys = self.live_keys().into_iter().map(|key| (key, ()));
+        let (cursor, page) = scan_page(keys, cursor, count);
+        let keys = page
+            .into_iter()
+            .map(|(key, _)| key)
+            .filter(|key| pattern.is_none_or(|p| glob_match(p.as_bytes(), key.as_bytes())))
+            .filter(|key| key_type.is_none_or(|t| self.key_type(key) == Some(t)))
+            .collect();
+        (cursor, keys)
+    }
+
+    /// One page of the fields of a hash along with their values.
+    pub fn hscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&str>,
+    ) -> Result<(u64, Vec<(String, RespFrame)>), WrongType> {
+        self.evict_if_expired(key);
+        let fields = match self.db().keys.get(key) {
+            Some(value) => value.as_hash()?.clone(),
+            None => return Ok((0, vec![])),
+        };
+        let (cursor, page) = scan_page(fields.into_iter(), cursor, count);
+        let fields = page
+            .into_iter()
+            .filter(|(field, _)| pattern.is_none_or(|p| glob_match(p.as_bytes(), field.as_bytes())))
+            .collect();
+        Ok((cursor, fields))
+    }
+
+    /// Move the value and ttl of a key to another name, replacing whatever the
+    /// new name held. Returns false if the key does not exist.
+    pub fn rename(&self, key: &str, new_key: String) -> bool {
+        self.evict_if_expired(key);
+        let db = self.db();
+        let Some(value) = db.remove(key) else {
+            return false;
+        };
+        let expire = db.expires.remove(key).map(|(_, at)| at);
+
+        db.expires.remove(&new_key);
+        db.insert(new_key.clone(), value);
+        if let Some(at) = expire {
+            db.expires.insert(new_key, at);
+        }
+        true
+    }
+
+    pub fn dbsize(&self) -> usize {
+        self.db().keys.len()
+    }
+
+    /// Remove every key of the selected database.
+    pub fn flushdb(&self) {
+        self.db().clear();
+        self.touch_all();
+    }
+
+    /// Remove every key of every database.
+    pub fn flushall(&self) {
+        for db in 0..DATABASES {
+            self.with_db(db).flushdb();
+        }
+    }
+
+    /// The names of the keys, evicting the expired ones on the way.
+    fn live_keys(&self) -> Vec<String> {
+        let keys = self
+            .db()
+            .keys
+            .iter()
+            .map(|v| v.key().clone())
+            .collect::<Vec<_>>();
+        keys.into_iter()
+            .filter(|key| !self.evict_if_expired(key))
+            .collect()
+    }
+}
+
+/// Cursor based iteration over named items: they are visited in the order of
+/// the hash of their name and the cursor is the hash to resume from, so that
+/// items added or removed between calls never make the iteration skip the
+/// others. Returns up to `count` items and the next cursor, 0 at the end.
+fn scan_page<T>(
+    items: impl Iterator<Item = (String, T)>,
+    cursor: u64,
+    count: usize,
+) -> (u64, Vec<(String, T)>) {
+    let mut items = items
+        .map(|(name, item)| (scan_hash(&name), name, item))
+        .filter(|(hash, ..)| *hash >= cursor)
+        .collect::<Vec<_>>();
+    // only the page and the item after it need sorting, in linear time
+    let count = count.max(1);
+    if items.len() > count + 1 {
+        items.select_nth_unstable_by_key(count, |(hash, ..)| *hash);
+        items.truncate(count + 1);
+    }
+    items.sort_unstable_by_key(|(hash, ..)| *hash);
+
+    let next = match items.get(count) {
+        Some((hash, ..)) => *hash,
+        None => 0,
+    };
+    let page = items
+        .into_iter()
+        .take_while(|(hash, ..)| next == 0 || *hash < next)
+        .map(|(_, name, item)| (name, item))
+        .collect();
+    (next, page)
+}
+
+/// Never 0, which is the cursor ending an iteration.
+fn scan_hash(name: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    hasher.finish().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+    use std::collections::HashSet;
+
+    fn set(backend: &Backend, key: &str) {
+        backend.set(key.to_string(), BulkString::from(key).into());
+    }
+
+    #[test]
+    fn test_scan_should_visit_every_key() {
+        let backend = Backend::new();
+        for i in 0..25 {
+            set(&backend, &format!("key:{}", i));
+        }
+        backend
+            .hset("hash".to_string(), "f".to_string(), RespFrame::Integer(1))
+            .unwrap();
+
+        let mut seen = HashSet::new();
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = backend.scan(cursor, 10, Some("key:*"), None);
+            assert!(keys.len() <= 10);
+            seen.extend(keys);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(seen.len(), 25);
+
+        let (_, keys) = backend.scan(0, 100, None, Some("hash"));
+        assert_eq!(keys, vec!["hash"]);
+        assert_eq!(backend.hscan("key:1", 0, 10, None), Err(WrongType));
+    }
+
+    #[test]
+    fn test_scan_should_return_every_key_once_from_a_large_keyspace() {
+        let backend = Backend::new();
+        for i in 0..3000 {
+            set(&backend, &format!("key:{}", i));
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = backend.scan(cursor, 10, None, None);
+            assert!(keys.len() <= 10);
+            seen.extend(keys);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        let unique = seen.iter().collect::<HashSet<_>>();
+        assert_eq!(seen.len(), 3000);
+        assert_eq!(unique.len(), 3000);
+    }
+
+    #[test]
+    fn test_keys_del_rename_should_work() {
+        let backend = Backend::new();
+        set(&backend, "a:1");
+        set(&backend, "a:2");
+        set(&backend, "b");
+        backend.expire_at("b", crate::now_ms() + 60_000);
+
+        let mut keys = backend.keys("a:*");
+        keys.sort();
+        assert_eq!(keys, vec!["a:1", "a:2"]);
+
+        assert!(backend.rename("b", "a:1".to_string()));
+        assert_eq!(backend.get("a:1"), Ok(Some(BulkString::from("b").into())));
+        assert!(backend.pttl("a:1") > 0);
+        assert!(!backend.rename("b", "c".to_string()));
+
+        assert_eq!(backend.del(&["a:1".to_string(), "nope".to_string()]), 1);
+        assert_eq!(backend.key_type("a:2"), Some("string"));
+        assert_eq!(backend.dbsize(), 1);
+
+        backend.select(1).unwrap().flushall();
+        assert_eq!(backend.dbsize(), 0);
+    }
+}
@@ -0,0 +1,246 @@
+use super::memory::frame_size;
+use super::{Backend, Value, WrongType, normalize_range};
+use crate::RespFrame;
+use std::collections::VecDeque;
+
+impl Backend {
+    pub fn lpush(&self, key: String, values: Vec<RespFrame>) -> Result<usize, WrongType> {
+        self.push(key, values, true)
+    }
+
+    pub fn rpush(&self, key: String, values: Vec<RespFrame>) -> Result<usize, WrongType> {
+        self.push(key, values, false)
+    }
+
+    fn push(&self, key: String, values: Vec<RespFrame>, left: bool) -> Result<usize, WrongType> {
+        self.evict_if_expired(&key);
+        let db = self.db();
+        let len = {
+            let key_len = key.len();
+            let mut entry = db.keys.entry(key.clone()).or_insert_with(|| {
+                db.created(key_len);
+                Value::List(VecDeque::new())
+            });
+            let list = entry.as_list_mut()?;
+            for value in values {
+                db.grow(frame_size(&value));
+                if left {
+                    list.push_front(value);
+                } else {
+                    list.push_back(value);
+                }
+            }
+            list.len()
+        };
+        self.blocked_lists.wake_first(self.db_index(), &key);
+        Ok(len)
+    }
+
+    /// Pop up to `count` values from the head (or tail) of the list, the key
+    /// is removed once the list becomes empty.
+    pub fn pop(
+        &self,
+        key: &str,
+        count: usize,
+        left: bool,
+    ) -> Result<Option<Vec<RespFrame>>, WrongType> {
+        self.evict_if_expired(key);
+        let db = self.db();
+        let values = {
+            let Some(mut entry) = db.keys.get_mut(key) else {
+                return Ok(None);
+            };
+            let list = entry.as_list_mut()?;
+            let count = count.min(list.len());
+            let values = if left {
+                list.drain(..count).collect::<Vec<_>>()
+            } else {
+                let start = list.len() - count;
+                list.drain(start..).rev().collect::<Vec<_>>()
+            };
+            db.shrink(values.iter().map(frame_size).sum());
+            values
+        };
+        self.remove_if_empty(key);
+        Ok(Some(values))
+    }
+
+    pub fn lrange(&self, key: &str, start: i64, stop: i64) -> Result<Vec<RespFrame>, WrongType> {
+        self.evict_if_expired(key);
+        let Some(entry) = self.db().keys.get(key) else {
+            return Ok(vec![]);
+        };
+        let list = entry.as_list()?;
+        match normalize_range(list.len(), start, stop) {
+            Some((start, stop)) => Ok(list.range(start..=stop).cloned().collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn llen(&self, key: &str) -> Result<usize, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(entry) => Ok(entry.as_list()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    pub fn lindex(&self, key: &str, index: i64) -> Result<Option<RespFrame>, WrongType> {
+        self.evict_if_expired(key);
+        let Some(entry) = self.db().keys.get(key) else {
+            return Ok(None);
+        };
+        let list = entry.as_list()?;
+        let index = if index < 0 {
+            list.len().checked_sub(index.unsigned_abs() as usize)
+        } else {
+            Some(index as usize)
+        };
+        Ok(index.and_then(|index| list.get(index).cloned()))
+    }
+
+    pub fn ltrim(&self, key: &str, start: i64, stop: i64) -> Result<(), WrongType> {
+        self.evict_if_expired(key);
+        let db = self.db();
+        if let Some(mut entry) = db.keys.get_mut(key) {
+            let list = entry.as_list_mut()?;
+            let removed = match normalize_range(list.len(), start, stop) {
+                Some((start, stop)) => {
+                    let tail = list
+                        .drain(stop + 1..)
+                        .map(|v| frame_size(&v))
+                        .sum::<usize>();
+                    tail + list.drain(..start).map(|v| frame_size(&v)).sum::<usize>()
+                }
+                None => list.drain(..).map(|v| frame_size(&v)).sum(),
+            };
+            db.shrink(removed);
+        }
+        self.remove_if_empty(key);
+        Ok(())
+    }
+
+    /// Remove `count` occurrences of `value`: from head to tail if count is
+    /// positive, from tail to head if negative and all of them if zero.
+    pub fn lrem(&self, key: &str, count: i64, value: &RespFrame) -> Result<usize, WrongType> {
+        self.evict_if_expired(key);
+        let db = self.db();
+        let removed = match db.keys.get_mut(key) {
+            Some(mut entry) => {
+                let list = entry.as_list_mut()?;
+                let limit = match count {
+                    0 => usize::MAX,
+                    n => n.unsigned_abs() as usize,
+                };
+                let mut removed = 0;
+                if count >= 0 {
+                    let mut i = 0;
+                    while i < list.len() && removed < limit {
+                        if &list[i] == value {
+                            list.remove(i);
+                            removed += 1;
+                        } else {
+                            i += 1;
+                        }
+                    }
+                } else {
+                    let mut i = list.len();
+                    while i > 0 && removed < limit {
+                        i -= 1;
+                        if &list[i] == value {
+                            list.remove(i);
+                            removed += 1;
+                        }
+                    }
+                }
+                db.shrink(removed * frame_size(value));
+                removed
+            }
+            None => 0,
+        };
+        self.remove_if_empty(key);
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    fn frames(values: &[&str]) -> Vec<RespFrame> {
+        values.iter().map(|v| BulkString::from(*v).into()).collect()
+    }
+
+    #[test]
+    fn test_push_pop_should_work() {
+        let backend = Backend::new();
+        assert_eq!(
+            backend.rpush("list".to_string(), frames(&["a", "b"])),
+            Ok(2)
+        );
+        assert_eq!(
+            backend.lpush("list".to_string(), frames(&["c", "d"])),
+            Ok(4)
+        );
+        assert_eq!(
+            backend.lrange("list", 0, -1),
+            Ok(frames(&["d", "c", "a", "b"]))
+        );
+
+        assert_eq!(backend.pop("list", 1, true), Ok(Some(frames(&["d"]))));
+        assert_eq!(backend.pop("list", 2, false), Ok(Some(frames(&["b", "a"]))));
+        assert_eq!(backend.pop("list", 5, true), Ok(Some(frames(&["c"]))));
+        assert!(!backend.db().keys.contains_key("list"));
+        assert_eq!(backend.pop("list", 1, true), Ok(None));
+
+        backend.set("list".to_string(), BulkString::from("a").into());
+        assert_eq!(
+            backend.rpush("list".to_string(), frames(&["a"])),
+            Err(WrongType)
+        );
+    }
+
+    #[test]
+    fn test_lrange_lindex_ltrim_should_work() {
+        let backend = Backend::new();
+        backend
+            .rpush("list".to_string(), frames(&["a", "b", "c", "d", "e"]))
+            .unwrap();
+
+        assert_eq!(backend.lrange("list", 1, 2), Ok(frames(&["b", "c"])));
+        assert_eq!(backend.lrange("list", -2, 100), Ok(frames(&["d", "e"])));
+        assert_eq!(backend.lrange("list", 3, 1), Ok(vec![]));
+        assert_eq!(
+            backend.lindex("list", -1),
+            Ok(Some(BulkString::from("e").into()))
+        );
+        assert_eq!(backend.lindex("list", 5), Ok(None));
+
+        backend.ltrim("list", 1, -2).unwrap();
+        assert_eq!(backend.lrange("list", 0, -1), Ok(frames(&["b", "c", "d"])));
+
+        backend.ltrim("list", 5, 10).unwrap();
+        assert_eq!(backend.llen("list"), Ok(0));
+        assert!(!backend.db().keys.contains_key("list"));
+    }
+
+    #[test]
+    fn test_lrem_should_work() {
+        let backend = Backend::new();
+        backend
+            .rpush("list".to_string(), frames(&["a", "b", "a", "c", "a"]))
+            .unwrap();
+        let a: RespFrame = BulkString::from("a").into();
+
+        assert_eq!(backend.lrem("list", -1, &a), Ok(1));
+        assert_eq!(
+            backend.lrange("list", 0, -1),
+            Ok(frames(&["a", "b", "a", "c"]))
+        );
+        assert_eq!(backend.lrem("list", 1, &a), Ok(1));
+        assert_eq!(backend.lrange("list", 0, -1), Ok(frames(&["b", "a", "c"])));
+        assert_eq!(backend.lrem("list", 0, &a), Ok(1));
+        assert_eq!(backend.lrange("list", 0, -1), Ok(frames(&["b", "c"])));
+    }
+}
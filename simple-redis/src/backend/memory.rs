@@ -0,0 +1,486 @@
+use super::{Backend, Db, Value, now_ms};
+use crate::{BulkString, RespArray, RespFrame};
+use anyhow::anyhow;
+use rand::Rng;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+
+/// Rough bookkeeping cost of a key besides its name and value.
+const ENTRY_OVERHEAD: usize = 48;
+/// Rough cost of a frame besides its payload.
+const FRAME_OVERHEAD: usize = 16;
+/// Rough cost of a stream entry besides its fields: the id and the index.
+const STREAM_ENTRY_OVERHEAD: usize = 48;
+/// Rough cost of a sorted set member besides its name: the score and both
+/// indexes.
+const ZSET_MEMBER_OVERHEAD: usize = 64;
+/// Keys sampled per database to pick the one to evict.
+const EVICTION_SAMPLES: usize = 5;
+/// Initial LFU counter of a key, so new keys are not evicted right away.
+const LFU_INIT: u8 = 5;
+const LFU_LOG_FACTOR: f64 = 10.0;
+/// The LFU counter of a key decays by one every minute it is not accessed.
+const LFU_DECAY_MS: u64 = 60_000;
+
+/// How keys are picked for eviction once maxmemory is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EvictionPolicy {
+    /// refuse the writes which may use more memory instead
+    #[default]
+    NoEviction,
+    AllKeysLru,
+    AllKeysLfu,
+    /// least recently used among the keys with a ttl
+    VolatileLru,
+    /// the keys with the nearest expire time first
+    VolatileTtl,
+    AllKeysRandom,
+}
+
+impl EvictionPolicy {
+    fn tracks_access(self) -> bool {
+        matches!(
+            self,
+            EvictionPolicy::AllKeysLru | EvictionPolicy::AllKeysLfu | EvictionPolicy::VolatileLru
+        )
+    }
+
+    /// Whether only keys with a ttl may be evicted.
+    fn is_volatile(self) -> bool {
+        matches!(
+            self,
+            EvictionPolicy::VolatileLru | EvictionPolicy::VolatileTtl
+        )
+    }
+}
+
+impl FromStr for EvictionPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "noeviction" => Ok(EvictionPolicy::NoEviction),
+            "allkeys-lru" => Ok(EvictionPolicy::AllKeysLru),
+            "allkeys-lfu" => Ok(EvictionPolicy::AllKeysLfu),
+            "volatile-lru" => Ok(EvictionPolicy::VolatileLru),
+            "volatile-ttl" => Ok(EvictionPolicy::VolatileTtl),
+            "allkeys-random" => Ok(EvictionPolicy::AllKeysRandom),
+            _ => Err(anyhow!("invalid maxmemory policy: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for EvictionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            EvictionPolicy::NoEviction => "noeviction",
+            EvictionPolicy::AllKeysLru => "allkeys-lru",
+            EvictionPolicy::AllKeysLfu => "allkeys-lfu",
+            EvictionPolicy::VolatileLru => "volatile-lru",
+            EvictionPolicy::VolatileTtl => "volatile-ttl",
+            EvictionPolicy::AllKeysRandom => "allkeys-random",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// When a key was last accessed and a logarithmic counter of how often.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Access {
+    at: u64,
+    counter: u8,
+}
+
+impl Access {
+    fn new() -> Self {
+        Self {
+            at: now_ms(),
+            counter: LFU_INIT,
+        }
+    }
+
+    /// The counter once decayed for the time since the last access.
+    fn frequency(&self, now: u64) -> u8 {
+        let decay = now.saturating_sub(self.at) / LFU_DECAY_MS;
+        self.counter.saturating_sub(decay.min(u8::MAX as u64) as u8)
+    }
+
+    /// Count an access: the higher the counter the less likely it grows.
+    fn hit(&mut self) {
+        let now = now_ms();
+        let counter = self.frequency(now);
+        let base = counter.saturating_sub(LFU_INIT) as f64;
+        let grows = rand::rng().random::<f64>() < 1.0 / (base * LFU_LOG_FACTOR + 1.0);
+        self.counter = if grows {
+            counter.saturating_add(1)
+        } else {
+            counter
+        };
+        self.at = now;
+    }
+}
+
+/// Estimated memory used by a frame.
+pub(crate) fn frame_size(frame: &RespFrame) -> usize {
+    let payload = match frame {
+        RespFrame::SimpleString(s) => s.len(),
+        RespFrame::Error(e) => e.len(),
+        RespFrame::BulkString(s) => s.len(),
+        RespFrame::Array(array) => array.iter().map(frame_size).sum(),
+        _ => 0,
+    };
+    FRAME_OVERHEAD + payload
+}
+
+pub(crate) fn zset_member_size(member: &str) -> usize {
+    member.len() + ZSET_MEMBER_OVERHEAD
+}
+
+pub(crate) fn stream_entry_size(fields: &[(String, RespFrame)]) -> usize {
+    STREAM_ENTRY_OVERHEAD
+        + fields
+            .iter()
+            .map(|(field, value)| field.len() + frame_size(value))
+            .sum::<usize>()
+}
+
+fn entry_size(key: &str, value: &Value) -> usize {
+    ENTRY_OVERHEAD + key.len() + value.size()
+}
+
+impl Value {
+    /// Estimated memory used by the value.
+    pub fn size(&self) -> usize {
+        match self {
+            Value::String(value) => frame_size(value),
+            Value::Hash(hash) => hash
+                .iter()
+                .map(|(field, value)| field.len() + frame_size(value))
+                .sum(),
+            Value::List(list) => list.iter().map(frame_size).sum(),
+            Value::ZSet(zset) => zset
+                .iter()
+                .map(|(member, _)| zset_member_size(member))
+                .sum(),
+            Value::Stream(stream) => stream
+                .iter()
+                .map(|(_, fields)| stream_entry_size(fields))
+                .sum(),
+        }
+    }
+}
+
+impl Db {
+    pub(crate) fn grow(&self, size: usize) {
+        self.used.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub(crate) fn shrink(&self, size: usize) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(size))
+            });
+    }
+
+    /// Account for a key created with an empty container.
+    pub(crate) fn created(&self, key_len: usize) {
+        self.grow(ENTRY_OVERHEAD + key_len);
+    }
+
+    /// Insert or replace the value of a key.
+    pub(crate) fn insert(&self, key: String, value: Value) {
+        let overhead = ENTRY_OVERHEAD + key.len();
+        self.grow(overhead + value.size());
+        if let Some(old) = self.keys.insert(key, value) {
+            self.shrink(overhead + old.size());
+        }
+    }
+
+    pub(crate) fn remove(&self, key: &str) -> Option<Value> {
+        let (key, value) = self.keys.remove(key)?;
+        self.shrink(entry_size(&key, &value));
+        self.access.remove(&key);
+        Some(value)
+    }
+
+    /// Remove the key if its value matches, checked while the key is locked.
+    pub(crate) fn remove_if(&self, key: &str, f: impl FnOnce(&Value) -> bool) -> Option<Value> {
+        let (key, value) = self.keys.remove_if(key, |_, v| f(v))?;
+        self.shrink(entry_size(&key, &value));
+        self.access.remove(&key);
+        Some(value)
+    }
+
+    /// Remove the key if its value is an empty container.
+    pub(crate) fn remove_if_empty(&self, key: &str) -> bool {
+        self.remove_if(key, Value::is_empty).is_some()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.keys.clear();
+        self.expires.clear();
+        self.access.clear();
+        self.used.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Backend {
+    /// Estimated memory used by the keys of every database.
+    pub fn used_memory(&self) -> usize {
+        self.dbs
+            .iter()
+            .map(|db| db.used.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Record an access to the key for the LRU and LFU policies.
+    pub fn record_access(&self, key: &str) {
+        let policy = self.config.maxmemory_policy;
+        if self.config.maxmemory == 0 || !policy.tracks_access() {
+            return;
+        }
+        let db = self.db();
+        if db.keys.contains_key(key) {
+            db.access
+                .entry(key.to_string())
+                .or_insert_with(Access::new)
+                .hit();
+        }
+    }
+
+    /// Evict keys until the used memory is back under maxmemory. Returns
+    /// false if that is not possible, writes which may use more memory
+    /// have to be refused then.
+    pub fn reclaim_memory(&self) -> bool {
+        let _guard = self.shared();
+        self.reclaim_memory_locked()
+    }
+
+    /// Same as reclaim_memory for a caller already holding the backend lock.
+    pub(crate) fn reclaim_memory_locked(&self) -> bool {
+        let maxmemory = self.config.maxmemory as usize;
+        if maxmemory == 0 {
+            return true;
+        }
+        while self.used_memory() > maxmemory {
+            if !self.evict_one() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Evict the best candidate among a few keys sampled in every database,
+    /// returns false if there is no candidate.
+    fn evict_one(&self) -> bool {
+        let policy = self.config.maxmemory_policy;
+        if policy == EvictionPolicy::NoEviction {
+            return false;
+        }
+        let now = now_ms();
+        let mut rng = rand::rng();
+        let mut best: Option<(u64, usize, String)> = None;
+        for (index, db) in self.dbs.iter().enumerate() {
+            let keys = if policy.is_volatile() {
+                sample(
+                    || db.expires.iter().map(|v| v.key().clone()),
+                    db.expires.len(),
+                )
+            } else {
+                sample(|| db.keys.iter().map(|v| v.key().clone()), db.keys.len())
+            };
+            for key in keys {
+                // the lower the score the better the candidate
+                let score = match policy {
+                    EvictionPolicy::AllKeysLru | EvictionPolicy::VolatileLru => {
+                        db.access.get(&key).map_or(0, |access| access.at)
+                    }
+                    EvictionPolicy::AllKeysLfu => db
+                        .access
+                        .get(&key)
+                        .map_or(0, |access| access.frequency(now) as u64),
+                    EvictionPolicy::VolatileTtl => db.expires.get(&key).map_or(0, |at| *at),
+                    _ => rng.random(),
+                };
+                if best.as_ref().is_none_or(|(best, ..)| score < *best) {
+                    best = Some((score, index, key));
+                }
+            }
+        }
+
+        let Some((_, index, key)) = best else {
+            return false;
+        };
+        let backend = self.with_db(index);
+        if backend.remove(&key) {
+            backend.touch(&key);
+            self.evicted_keys.fetch_add(1, Ordering::Relaxed);
+            if backend.propagates() {
+                let del = [BulkString::from("del").into(), BulkString::from(key).into()];
+                backend.propagate(vec![RespArray::new(del).into()]);
+            }
+        }
+        true
+    }
+
+    /// The memory section of INFO.
+    pub fn memory_info(&self) -> String {
+        let used = self.used_memory();
+        let maxmemory = self.config.maxmemory;
+        [
+            format!("used_memory:{}", used),
+            format!("used_memory_human:{}", human_size(used as u64)),
+            format!("maxmemory:{}", maxmemory),
+            format!("maxmemory_human:{}", human_size(maxmemory)),
+            format!("maxmemory_policy:{}", self.config.maxmemory_policy),
+        ]
+        .join("\r\n")
+    }
+}
+
+/// Up to EVICTION_SAMPLES consecutive keys from a random position, wrapping
+/// around at the end.
+fn sample<I: Iterator<Item = String>>(keys: impl Fn() -> I, len: usize) -> Vec<String> {
+    if len == 0 {
+        return vec![];
+    }
+    let start = rand::rng().random_range(0..len);
+    let mut sampled = keys()
+        .skip(start)
+        .take(EVICTION_SAMPLES)
+        .collect::<Vec<_>>();
+    let missing = EVICTION_SAMPLES.saturating_sub(sampled.len()).min(start);
+    sampled.extend(keys().take(missing));
+    sampled
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [(&str, u64); 3] = [("G", 1 << 30), ("M", 1 << 20), ("K", 1 << 10)];
+    for (unit, size) in UNITS {
+        if bytes >= size {
+            return format!("{:.2}{}", bytes as f64 / size as f64, unit);
+        }
+    }
+    format!("{}B", bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    /// The used memory computed from scratch.
+    fn recount(backend: &Backend) -> usize {
+        backend
+            .dbs
+            .iter()
+            .flat_map(|db| db.keys.iter())
+            .map(|v| entry_size(v.key(), v.value()))
+            .sum()
+    }
+
+    fn limited(maxmemory: u64, policy: EvictionPolicy) -> Backend {
+        Backend::with_config(ServerConfig {
+            maxmemory,
+            maxmemory_policy: policy,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_used_memory_should_follow_the_dataset() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        backend.set("s".to_string(), bulk("hello"));
+        backend.set("s".to_string(), bulk("hello world"));
+        backend.hset("h".to_string(), "f".to_string(), bulk("1"))?;
+        backend.hset("h".to_string(), "f".to_string(), bulk("22"))?;
+        backend.rpush("l".to_string(), vec![bulk("a"), bulk("b"), bulk("c")])?;
+        backend.pop("l", 1, true)?;
+        backend.lrem("l", 0, &bulk("c"))?;
+        backend.ltrim("l", 1, 0)?;
+        backend.zadd("z".to_string(), vec![(1.0, "m".to_string())], None, false)?;
+        backend.zincrby("z".to_string(), 1.0, "n".to_string())?;
+        backend.zrem("z", &["m".to_string()])?;
+        backend.rename("s", "t".to_string());
+        backend
+            .select(2)
+            .unwrap()
+            .set("s".to_string(), bulk("other"));
+
+        assert!(backend.used_memory() > 0);
+        assert_eq!(backend.used_memory(), recount(&backend));
+
+        backend.flushall();
+        assert_eq!(backend.used_memory(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reclaim_memory_should_evict_by_policy() {
+        let backend = limited(1, EvictionPolicy::NoEviction);
+        backend.set("a".to_string(), bulk("1"));
+        assert!(!backend.reclaim_memory());
+
+        let backend = limited(1, EvictionPolicy::VolatileTtl);
+        backend.set("a".to_string(), bulk("1"));
+        backend.set("b".to_string(), bulk("2"));
+        backend.expire_at("b", now_ms() + 60_000);
+        assert!(!backend.reclaim_memory());
+        assert!(!backend.exists("b"));
+        assert!(backend.exists("a"));
+        assert_eq!(backend.evicted_keys.load(Ordering::Relaxed), 1);
+
+        let backend = limited(1, EvictionPolicy::AllKeysRandom);
+        backend.set("a".to_string(), bulk("1"));
+        backend.select(1).unwrap().set("b".to_string(), bulk("2"));
+        assert!(backend.reclaim_memory());
+        assert_eq!(backend.used_memory(), 0);
+    }
+
+    #[test]
+    fn test_lru_should_evict_least_recently_used() {
+        let backend = limited(u64::MAX, EvictionPolicy::AllKeysLru);
+        backend.set("old".to_string(), bulk("1"));
+        backend.set("new".to_string(), bulk("2"));
+        backend.record_access("new");
+        backend.db().access.get_mut("new").unwrap().at = now_ms() + 1;
+        backend.record_access("old");
+        backend.db().access.get_mut("old").unwrap().at = 1;
+
+        assert!(backend.evict_one());
+        assert!(!backend.exists("old"));
+        assert!(backend.exists("new"));
+    }
+
+    #[test]
+    fn test_lfu_counter_should_grow_and_decay() {
+        let mut access = Access::new();
+        for _ in 0..1000 {
+            access.hit();
+        }
+        assert!(access.counter > LFU_INIT);
+        assert!(access.counter < u8::MAX);
+
+        access.at = now_ms() - 3 * LFU_DECAY_MS;
+        assert_eq!(access.frequency(now_ms()), access.counter - 3);
+    }
+
+    #[test]
+    fn test_eviction_policy_from_str() {
+        assert_eq!(
+            "allkeys-lru".parse::<EvictionPolicy>().unwrap(),
+            EvictionPolicy::AllKeysLru
+        );
+        assert_eq!(EvictionPolicy::VolatileTtl.to_string(), "volatile-ttl");
+        assert!("lru".parse::<EvictionPolicy>().is_err());
+        assert_eq!(human_size(1536), "1.50K");
+    }
+}
@@ -1,63 +1,490 @@
+mod bitmap;
+mod blocking;
+mod clients;
+mod glob;
+mod hyperloglog;
+mod keyspace;
+mod list;
+mod memory;
+mod pubsub;
+mod script;
+mod stats;
+mod stream;
+mod string;
+mod value;
+mod watch;
+mod zset;
+
 use crate::RespFrame;
+use crate::acl::Acl;
+use crate::cluster::Cluster;
+use crate::config::ServerConfig;
+use crate::persistence::{Aof, PersistenceConfig};
+use crate::replication::Replication;
 use dashmap::DashMap;
+use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub use clients::ClientInfo;
+pub(crate) use glob::glob_match;
+pub use hyperloglog::HyperLogLog;
+pub use memory::EvictionPolicy;
+pub use pubsub::{Subscriber, Subscriptions};
+pub use script::{ScriptError, script_sha};
+pub use stats::{CommandStats, SlowLogEntry};
+pub use stream::{
+    ConsumerGroup, Fields, PendingEntry, PendingSummary, Stream, StreamError, StreamId,
+};
+pub use string::StringError;
+pub(crate) use string::parse_int;
+pub use value::{Value, WrongType};
+pub use watch::Watches;
+pub use zset::SortedSet;
+
+/// Number of logical databases, selected with SELECT.
+pub const DATABASES: usize = 16;
 
 #[derive(Debug, Clone)]
-pub struct Backend(Arc<BackendInner>);
+pub struct Backend {
+    inner: Arc<BackendInner>,
+    /// the database commands run against
+    db: usize,
+}
+
 impl Backend {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn get(&self, key: &str) -> Option<RespFrame> {
-        self.map.get(key).map(|v| v.value().clone())
+    /// Panics if the cluster topology is invalid, it is checked when the
+    /// config is loaded.
+    pub fn with_config(config: ServerConfig) -> Self {
+        let inner = BackendInner {
+            acl: Acl::new(&config),
+            cluster: config.cluster().expect("invalid cluster config"),
+            config,
+            ..Default::default()
+        };
+        Self {
+            inner: Arc::new(inner),
+            db: 0,
+        }
+    }
+
+    pub fn with_persistence(persistence: PersistenceConfig) -> Self {
+        Self::with_config(ServerConfig {
+            persistence,
+            ..Default::default()
+        })
+    }
+
+    /// The same backend running commands against another database, None if
+    /// the index is out of range.
+    pub fn select(&self, db: usize) -> Option<Backend> {
+        (db < DATABASES).then(|| self.with_db(db))
+    }
+
+    pub fn db_index(&self) -> usize {
+        self.db
+    }
+
+    fn with_db(&self, db: usize) -> Backend {
+        Self {
+            inner: self.inner.clone(),
+            db,
+        }
+    }
+
+    pub(crate) fn db(&self) -> &Db {
+        &self.dbs[self.db]
+    }
+
+    /// The append only file, only set once the server turned it on.
+    pub fn aof(&self) -> Option<&Aof> {
+        self.aof.get()
+    }
+
+    /// Held while running a single command, so that commands never run in the
+    /// middle of a transaction.
+    pub fn shared(&self) -> RwLockReadGuard<'_, ()> {
+        self.lock.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Held while running a transaction, nothing else runs in the meantime.
+    pub fn exclusive(&self) -> RwLockWriteGuard<'_, ()> {
+        self.lock.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<RespFrame>, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(value) => Ok(Some(value.as_string()?.clone())),
+            None => Ok(None),
+        }
     }
 
+    /// Store a string value, replacing any value and ttl the key had before.
     pub fn set(&self, key: String, value: RespFrame) {
-        self.map.insert(key, value);
+        self.db().expires.remove(&key);
+        self.db().insert(key, Value::String(value));
+    }
+
+    /// Store a string value, keeping the ttl of the key if it has one.
+    pub fn set_keep_ttl(&self, key: String, value: RespFrame) {
+        self.evict_if_expired(&key);
+        self.db().insert(key, Value::String(value));
+    }
+
+    pub fn hget(&self, key: &str, field: &str) -> Result<Option<RespFrame>, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(value) => Ok(value.as_hash()?.get(field).cloned()),
+            None => Ok(None),
+        }
     }
 
-    pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
-        self.hmap
-            .get(key)
-            .and_then(|v| v.get(field).map(|v| v.value().clone()))
+    pub fn hset(&self, key: String, field: String, value: RespFrame) -> Result<(), WrongType> {
+        self.evict_if_expired(&key);
+        let db = self.db();
+        let key_len = key.len();
+        let mut entry = db.keys.entry(key).or_insert_with(|| {
+            db.created(key_len);
+            Value::Hash(HashMap::new())
+        });
+        let hash = entry.as_hash_mut()?;
+        let field_len = field.len();
+        db.grow(memory::frame_size(&value));
+        match hash.insert(field, value) {
+            Some(old) => db.shrink(memory::frame_size(&old)),
+            None => db.grow(field_len),
+        }
+        Ok(())
+    }
+
+    pub fn hgetall(&self, key: &str) -> Result<Option<HashMap<String, RespFrame>>, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(value) => Ok(Some(value.as_hash()?.clone())),
+            None => Ok(None),
+        }
     }
 
-    pub fn hset(&self, key: String, field: String, value: RespFrame) {
-        let hmap = self.hmap.entry(key).or_default();
-        hmap.insert(field, value);
+    pub fn exists(&self, key: &str) -> bool {
+        self.evict_if_expired(key);
+        self.db().keys.contains_key(key)
     }
 
-    pub fn hgetall(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
-        self.hmap.get(key).map(|v| v.clone())
+    pub fn remove(&self, key: &str) -> bool {
+        self.db().expires.remove(key);
+        self.db().remove(key).is_some()
+    }
+
+    /// Set the absolute expire time (unix milliseconds) of an existing key.
+    /// A time in the past removes the key right away.
+    pub fn expire_at(&self, key: &str, at: u64) -> bool {
+        if !self.exists(key) {
+            return false;
+        }
+        if at <= now_ms() {
+            self.remove(key);
+        } else {
+            self.db().expires.insert(key.to_string(), at);
+        }
+        true
+    }
+
+    /// Remaining time to live in milliseconds, -1 if the key has no ttl and
+    /// -2 if the key does not exist.
+    pub fn pttl(&self, key: &str) -> i64 {
+        if !self.exists(key) {
+            return -2;
+        }
+        match self.db().expires.get(key) {
+            Some(at) => i64::try_from(at.saturating_sub(now_ms())).unwrap_or(i64::MAX),
+            None => -1,
+        }
+    }
+
+    pub fn persist(&self, key: &str) -> bool {
+        self.evict_if_expired(key);
+        self.db().expires.remove(key).is_some()
+    }
+
+    /// Remove every key whose ttl has passed in any database, returns the
+    /// number of evicted keys.
+    pub fn remove_expired(&self) -> usize {
+        let _guard = self.shared();
+        let now = now_ms();
+        (0..DATABASES)
+            .map(|db| {
+                let backend = self.with_db(db);
+                let keys = backend
+                    .db()
+                    .expires
+                    .iter()
+                    .filter(|v| *v.value() <= now)
+                    .map(|v| v.key().clone())
+                    .collect::<Vec<_>>();
+                keys.iter()
+                    .filter(|key| backend.evict_if_expired(key))
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Periodically evict expired keys which are never accessed again.
+    pub async fn sweep_expired(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let count = self.remove_expired();
+            if count > 0 {
+                tracing::debug!("Evicted {} expired keys", count);
+            }
+        }
+    }
+
+    fn evict_if_expired(&self, key: &str) -> bool {
+        let now = now_ms();
+        if self
+            .db()
+            .expires
+            .remove_if(key, |_, at| *at <= now)
+            .is_some()
+        {
+            self.db().remove(key);
+            self.touch(key);
+            self.expired_keys.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+
+    /// Remove the key if its value is an empty container.
+    fn remove_if_empty(&self, key: &str) {
+        if self.db().remove_if_empty(key) {
+            self.db().expires.remove(key);
+        }
     }
 }
+
 impl Deref for Backend {
     type Target = BackendInner;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 
 impl Default for Backend {
     fn default() -> Self {
-        Self(Arc::new(BackendInner::default()))
+        Self {
+            inner: Arc::new(BackendInner::default()),
+            db: 0,
+        }
     }
 }
 
+/// A logical database.
+#[derive(Debug, Default)]
+pub struct Db {
+    pub(crate) keys: DashMap<String, Value>,
+    pub(crate) expires: DashMap<String, u64>,
+    pub(crate) versions: DashMap<String, watch::KeyVersion>,
+    /// accesses of the keys, only recorded for the LRU and LFU policies
+    pub(crate) access: DashMap<String, memory::Access>,
+    /// estimated memory used by the keys
+    pub(crate) used: AtomicUsize,
+}
+
 #[derive(Debug)]
 pub struct BackendInner {
-    pub(crate) map: DashMap<String, RespFrame>,
-    pub(crate) hmap: DashMap<String, DashMap<String, RespFrame>>,
+    pub(crate) dbs: Vec<Db>,
+    /// clients blocked popping from lists
+    pub(crate) blocked_lists: blocking::BlockedKeys,
+    /// clients blocked reading streams
+    pub(crate) blocked_streams: blocking::BlockedKeys,
+    pub(crate) config: ServerConfig,
+    pub(crate) acl: Acl,
+    /// set in cluster mode
+    pub(crate) cluster: Option<Cluster>,
+    pub(crate) connected_clients: AtomicUsize,
+    pub(crate) aof: OnceLock<Aof>,
+    pub(crate) bgsave_in_progress: AtomicBool,
+    pub(crate) next_client_id: AtomicU64,
+    pub(crate) channels: pubsub::SubscriberMap,
+    pub(crate) patterns: pubsub::SubscriberMap,
+    pub(crate) replication: Replication,
+    pub(crate) expired_keys: AtomicU64,
+    pub(crate) evicted_keys: AtomicU64,
+    /// compiled scripts by their sha1 digest
+    pub(crate) scripts: DashMap<String, Arc<rhai::AST>>,
+    /// connected clients by id
+    pub(crate) clients: DashMap<u64, ClientInfo>,
+    /// connections which ran MONITOR, by client id
+    pub(crate) monitors: DashMap<u64, Subscriber>,
+    pub(crate) total_connections_received: AtomicU64,
+    pub(crate) total_commands_processed: AtomicU64,
+    /// calls and time spent by command name
+    pub(crate) command_stats: DashMap<String, CommandStats>,
+    pub(crate) slowlog: Mutex<stats::SlowLog>,
+    /// unix milliseconds of the start
+    pub(crate) started_at: u64,
+    lock: RwLock<()>,
 }
 
 impl Default for BackendInner {
     fn default() -> Self {
         Self {
-            map: DashMap::new(),
-            hmap: DashMap::new(),
+            dbs: (0..DATABASES).map(|_| Db::default()).collect(),
+            blocked_lists: Default::default(),
+            blocked_streams: Default::default(),
+            config: ServerConfig::default(),
+            acl: Acl::new(&ServerConfig::default()),
+            cluster: None,
+            connected_clients: AtomicUsize::new(0),
+            aof: OnceLock::new(),
+            bgsave_in_progress: AtomicBool::new(false),
+            next_client_id: AtomicU64::new(0),
+            channels: DashMap::new(),
+            patterns: DashMap::new(),
+            replication: Replication::default(),
+            expired_keys: AtomicU64::new(0),
+            evicted_keys: AtomicU64::new(0),
+            scripts: DashMap::new(),
+            clients: DashMap::new(),
+            monitors: DashMap::new(),
+            total_connections_received: AtomicU64::new(0),
+            total_commands_processed: AtomicU64::new(0),
+            command_stats: DashMap::new(),
+            slowlog: Mutex::new(stats::SlowLog::default()),
+            started_at: now_ms(),
+            lock: RwLock::new(()),
         }
     }
 }
+
+/// Convert redis style inclusive (possibly negative) indexes into a valid
+/// inclusive range of a list or sorted set, None if the range is empty.
+fn normalize_range(len: usize, start: i64, stop: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+    if start > stop || start >= len {
+        return None;
+    }
+    Some((start as usize, stop as usize))
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_expired_key_should_be_evicted_lazily() {
+        let backend = Backend::new();
+        backend.set("hello".to_string(), BulkString::from("world").into());
+        backend
+            .db()
+            .expires
+            .insert("hello".to_string(), now_ms() - 1);
+
+        assert_eq!(backend.get("hello"), Ok(None));
+        assert!(!backend.db().keys.contains_key("hello"));
+        assert!(!backend.db().expires.contains_key("hello"));
+    }
+
+    #[test]
+    fn test_remove_expired_should_sweep_keys() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::from("1").into());
+        backend
+            .hset(
+                "b".to_string(),
+                "f".to_string(),
+                BulkString::from("2").into(),
+            )
+            .unwrap();
+        backend.set("c".to_string(), BulkString::from("3").into());
+        let other = backend.select(3).unwrap();
+        other.set("d".to_string(), BulkString::from("4").into());
+        backend.db().expires.insert("a".to_string(), now_ms() - 1);
+        backend.db().expires.insert("b".to_string(), now_ms() - 1);
+        backend
+            .db()
+            .expires
+            .insert("c".to_string(), now_ms() + 60_000);
+        other.db().expires.insert("d".to_string(), now_ms() - 1);
+
+        assert_eq!(backend.remove_expired(), 3);
+        assert!(!backend.db().keys.contains_key("a"));
+        assert!(!backend.db().keys.contains_key("b"));
+        assert!(backend.db().keys.contains_key("c"));
+        assert!(!other.db().keys.contains_key("d"));
+    }
+
+    #[test]
+    fn test_pttl_and_persist() {
+        let backend = Backend::new();
+        assert_eq!(backend.pttl("hello"), -2);
+
+        backend.set("hello".to_string(), BulkString::from("world").into());
+        assert_eq!(backend.pttl("hello"), -1);
+
+        assert!(backend.expire_at("hello", now_ms() + 10_000));
+        let ttl = backend.pttl("hello");
+        assert!(ttl > 9_000 && ttl <= 10_000);
+
+        assert!(backend.persist("hello"));
+        assert_eq!(backend.pttl("hello"), -1);
+        assert!(!backend.persist("hello"));
+    }
+
+    #[test]
+    fn test_key_should_hold_a_single_type() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::from("1").into());
+        assert_eq!(
+            backend.hset("a".to_string(), "f".to_string(), RespFrame::Integer(1)),
+            Err(WrongType)
+        );
+        assert_eq!(backend.hget("a", "f"), Err(WrongType));
+
+        backend
+            .hset("b".to_string(), "f".to_string(), RespFrame::Integer(1))
+            .unwrap();
+        assert_eq!(backend.get("b"), Err(WrongType));
+        backend.set("b".to_string(), BulkString::from("2").into());
+        assert_eq!(backend.get("b"), Ok(Some(BulkString::from("2").into())));
+    }
+
+    #[test]
+    fn test_databases_should_be_independent() {
+        let backend = Backend::new();
+        let other = backend.select(1).unwrap();
+        backend.set("a".to_string(), BulkString::from("1").into());
+
+        assert_eq!(other.get("a"), Ok(None));
+        assert_eq!(other.db_index(), 1);
+        assert!(backend.select(DATABASES).is_none());
+    }
+}
@@ -0,0 +1,212 @@
+use super::Backend;
+use super::glob::glob_match;
+use crate::{BulkString, RespArray, RespFrame};
+use dashmap::DashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::Ordering;
+use tokio::sync::mpsc;
+
+/// Where the messages published to a subscribed connection are pushed.
+pub type Subscriber = mpsc::UnboundedSender<RespFrame>;
+
+/// Subscribers by channel (or pattern), keyed by client id.
+pub(crate) type SubscriberMap = DashMap<String, HashMap<u64, Subscriber>>;
+
+impl Backend {
+    pub fn next_client_id(&self) -> u64 {
+        self.next_client_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Push the message to the subscribers of the channel and of every pattern
+    /// matching it, returns the number of clients which received it.
+    pub fn publish(&self, channel: &str, message: RespFrame) -> usize {
+        let mut count = 0;
+        if let Some(subscribers) = self.channels.get(channel) {
+            let frame: RespFrame =
+                RespArray::new([bulk("message"), bulk(channel), message.clone()]).into();
+            count += subscribers
+                .values()
+                .filter(|s| s.send(frame.clone()).is_ok())
+                .count();
+        }
+
+        for v in self.patterns.iter() {
+            if !glob_match(v.key().as_bytes(), channel.as_bytes()) {
+                continue;
+            }
+            let frame: RespFrame = RespArray::new([
+                bulk("pmessage"),
+                bulk(v.key()),
+                bulk(channel),
+                message.clone(),
+            ])
+            .into();
+            count += v
+                .value()
+                .values()
+                .filter(|s| s.send(frame.clone()).is_ok())
+                .count();
+        }
+        count
+    }
+
+    fn subscribers(&self, pattern: bool) -> &SubscriberMap {
+        if pattern {
+            &self.patterns
+        } else {
+            &self.channels
+        }
+    }
+}
+
+/// The channels and patterns a client connection is subscribed to, they are
+/// removed from the backend when the connection goes away.
+#[derive(Debug)]
+pub struct Subscriptions {
+    backend: Backend,
+    id: u64,
+    sender: Subscriber,
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+}
+
+impl Subscriptions {
+    pub fn new(backend: &Backend, id: u64, sender: Subscriber) -> Self {
+        Self {
+            backend: backend.clone(),
+            id,
+            sender,
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+        }
+    }
+
+    /// Total number of subscribed channels and patterns.
+    pub fn count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    /// Number of subscribed channels, or patterns.
+    pub fn count_of(&self, pattern: bool) -> usize {
+        self.names(pattern).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Subscribed channels, or patterns, in order.
+    pub fn list(&self, pattern: bool) -> Vec<String> {
+        self.names(pattern).iter().cloned().collect()
+    }
+
+    /// Subscribe to a channel or pattern, returns the subscription count.
+    pub fn subscribe(&mut self, channel: String, pattern: bool) -> usize {
+        self.backend
+            .subscribers(pattern)
+            .entry(channel.clone())
+            .or_default()
+            .insert(self.id, self.sender.clone());
+        self.names_mut(pattern).insert(channel);
+        self.count()
+    }
+
+    /// Unsubscribe from a channel or pattern, returns the subscription count.
+    pub fn unsubscribe(&mut self, channel: &str, pattern: bool) -> usize {
+        if self.names_mut(pattern).remove(channel) {
+            remove_subscriber(self.backend.subscribers(pattern), channel, self.id);
+        }
+        self.count()
+    }
+
+    fn names(&self, pattern: bool) -> &BTreeSet<String> {
+        if pattern {
+            &self.patterns
+        } else {
+            &self.channels
+        }
+    }
+
+    fn names_mut(&mut self, pattern: bool) -> &mut BTreeSet<String> {
+        if pattern {
+            &mut self.patterns
+        } else {
+            &mut self.channels
+        }
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        for channel in &self.channels {
+            remove_subscriber(&self.backend.channels, channel, self.id);
+        }
+        for pattern in &self.patterns {
+            remove_subscriber(&self.backend.patterns, pattern, self.id);
+        }
+    }
+}
+
+fn remove_subscriber(subscribers: &SubscriberMap, channel: &str, id: u64) {
+    if let Some(mut v) = subscribers.get_mut(channel) {
+        v.remove(&id);
+    }
+    subscribers.remove_if(channel, |_, v| v.is_empty());
+}
+
+fn bulk(s: &str) -> RespFrame {
+    BulkString::from(s).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(kind: &str, args: &[&str]) -> RespFrame {
+        let mut frames = vec![bulk(kind)];
+        frames.extend(args.iter().map(|s| bulk(s)));
+        RespArray::new(frames).into()
+    }
+
+    #[test]
+    fn test_publish_should_reach_channel_and_pattern_subscribers() {
+        let backend = Backend::new();
+        let (tx1, mut rx1) = mpsc::unbounded_channel();
+        let (tx2, mut rx2) = mpsc::unbounded_channel();
+        let mut sub1 = Subscriptions::new(&backend, backend.next_client_id(), tx1);
+        let mut sub2 = Subscriptions::new(&backend, backend.next_client_id(), tx2);
+
+        assert_eq!(sub1.subscribe("news.tech".to_string(), false), 1);
+        assert_eq!(sub2.subscribe("news.*".to_string(), true), 1);
+        assert_eq!(sub2.subscribe("sport.*".to_string(), true), 2);
+
+        assert_eq!(backend.publish("news.tech", bulk("hello")), 2);
+        assert_eq!(
+            rx1.try_recv().ok(),
+            Some(message("message", &["news.tech", "hello"]))
+        );
+        assert_eq!(
+            rx2.try_recv().ok(),
+            Some(message("pmessage", &["news.*", "news.tech", "hello"]))
+        );
+        assert_eq!(backend.publish("weather", bulk("sunny")), 0);
+
+        assert_eq!(sub2.unsubscribe("news.*", true), 1);
+        assert_eq!(backend.publish("news.tech", bulk("again")), 1);
+    }
+
+    #[test]
+    fn test_dropped_subscriptions_should_be_removed() {
+        let backend = Backend::new();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut sub = Subscriptions::new(&backend, backend.next_client_id(), tx);
+        sub.subscribe("a".to_string(), false);
+        sub.subscribe("b*".to_string(), true);
+        assert_eq!(sub.list(false), vec!["a".to_string()]);
+
+        drop(sub);
+        assert!(backend.channels.is_empty());
+        assert!(backend.patterns.is_empty());
+        assert_eq!(backend.publish("a", bulk("hello")), 0);
+    }
+}
@@ -0,0 +1,79 @@
+use super::Backend;
+use crate::{RespFrame, SimpleError};
+use rhai::AST;
+use sha1::{Digest, Sha1};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    #[error("ERR Error compiling script: {0}")]
+    Compile(String),
+    #[error("NOSCRIPT No matching script. Please use EVAL.")]
+    NoScript,
+    #[error("ERR Error running script: {0}")]
+    Run(String),
+    /// an error reply raised by a redis.call or thrown by the script itself,
+    /// returned to the client as is
+    #[error("{0}")]
+    Reply(String),
+}
+
+impl From<ScriptError> for RespFrame {
+    fn from(e: ScriptError) -> Self {
+        SimpleError::new(e.to_string()).into()
+    }
+}
+
+/// The lowercase hex SHA1 digest identifying a script.
+pub fn script_sha(source: &str) -> String {
+    hex::encode(Sha1::digest(source.as_bytes()))
+}
+
+impl Backend {
+    /// Keep a compiled script for EVALSHA.
+    pub fn cache_script(&self, sha: String, ast: Arc<AST>) {
+        self.scripts.insert(sha, ast);
+    }
+
+    pub fn script(&self, sha: &str) -> Result<Arc<AST>, ScriptError> {
+        self.scripts
+            .get(&sha.to_ascii_lowercase())
+            .map(|ast| ast.clone())
+            .ok_or(ScriptError::NoScript)
+    }
+
+    pub fn script_exists(&self, sha: &str) -> bool {
+        self.scripts.contains_key(&sha.to_ascii_lowercase())
+    }
+
+    pub fn script_flush(&self) {
+        self.scripts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rhai::Engine;
+
+    #[test]
+    fn test_script_sha() {
+        assert_eq!(script_sha(""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    }
+
+    #[test]
+    fn test_script_cache() {
+        let backend = Backend::new();
+        let sha = script_sha("40 + 2");
+        let ast = Engine::new().compile("40 + 2").unwrap();
+        backend.cache_script(sha.clone(), Arc::new(ast));
+        assert!(backend.script_exists(&sha));
+        assert!(backend.script_exists(&sha.to_ascii_uppercase()));
+        assert!(backend.script(&sha).is_ok());
+
+        backend.script_flush();
+        assert!(!backend.script_exists(&sha));
+        assert!(matches!(backend.script(&sha), Err(ScriptError::NoScript)));
+    }
+}
@@ -0,0 +1,347 @@
+use super::{Backend, DATABASES, now_ms};
+use crate::{BulkString, RespArray, RespFrame};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// Arguments kept by a slowlog entry, the others are summed up in the last
+/// one.
+const SLOWLOG_MAX_ARGS: usize = 32;
+/// Bytes kept of a slowlog argument.
+const SLOWLOG_MAX_ARG_LEN: usize = 128;
+
+/// Calls of a command and the time spent running them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CommandStats {
+    pub calls: u64,
+    pub usec: u64,
+}
+
+/// A command which ran longer than slowlog-log-slower-than.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowLogEntry {
+    pub id: u64,
+    /// unix seconds
+    pub time: u64,
+    pub duration: Duration,
+    pub args: Vec<BulkString>,
+    pub addr: SocketAddr,
+    pub name: Option<String>,
+}
+
+/// The slow commands, newest first.
+#[derive(Debug, Default)]
+pub(crate) struct SlowLog {
+    next_id: u64,
+    entries: VecDeque<SlowLogEntry>,
+}
+
+impl SlowLogEntry {
+    /// The entry as SLOWLOG GET replies it.
+    pub fn to_frame(&self) -> RespFrame {
+        let args = self
+            .args
+            .iter()
+            .cloned()
+            .map(RespFrame::from)
+            .collect::<Vec<_>>();
+        RespArray::new([
+            RespFrame::Integer(self.id as i64),
+            RespFrame::Integer(self.time as i64),
+            RespFrame::Integer(self.duration.as_micros() as i64),
+            RespArray::new(args).into(),
+            BulkString::from(self.addr.to_string()).into(),
+            BulkString::from(self.name.clone().unwrap_or_default()).into(),
+        ])
+        .into()
+    }
+}
+
+impl Backend {
+    /// Count a call of the command.
+    pub(crate) fn record_command(&self, name: &str, elapsed: Duration) {
+        self.total_commands_processed
+            .fetch_add(1, Ordering::Relaxed);
+        let mut stats = self.command_stats.entry(name.to_string()).or_default();
+        stats.calls += 1;
+        stats.usec += elapsed.as_micros() as u64;
+    }
+
+    pub fn command_stats(&self, name: &str) -> Option<CommandStats> {
+        self.command_stats.get(name).map(|stats| *stats)
+    }
+
+    /// Whether commands are logged when slow, a negative threshold turns the
+    /// slowlog off.
+    pub fn slowlog_enabled(&self) -> bool {
+        self.config.slowlog_log_slower_than >= 0
+    }
+
+    /// Log the command if it ran for slowlog-log-slower-than microseconds or
+    /// more, the oldest entries are dropped past slowlog-max-len.
+    pub(crate) fn log_if_slow(
+        &self,
+        args: Vec<BulkString>,
+        elapsed: Duration,
+        addr: SocketAddr,
+        name: Option<String>,
+    ) {
+        if !self.slowlog_enabled()
+            || (elapsed.as_micros() as i64) < self.config.slowlog_log_slower_than
+        {
+            return;
+        }
+        let mut slowlog = self.slowlog.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = SlowLogEntry {
+            id: slowlog.next_id,
+            time: now_ms() / 1000,
+            duration: elapsed,
+            args: truncate_args(args),
+            addr,
+            name,
+        };
+        slowlog.next_id += 1;
+        slowlog.entries.push_front(entry);
+        slowlog.entries.truncate(self.config.slowlog_max_len);
+    }
+
+    /// The newest entries, all of them without a count.
+    pub fn slowlog_get(&self, count: Option<usize>) -> Vec<SlowLogEntry> {
+        let slowlog = self.slowlog.lock().unwrap_or_else(|e| e.into_inner());
+        let count = count.unwrap_or(slowlog.entries.len());
+        slowlog.entries.iter().take(count).cloned().collect()
+    }
+
+    pub fn slowlog_len(&self) -> usize {
+        let slowlog = self.slowlog.lock().unwrap_or_else(|e| e.into_inner());
+        slowlog.entries.len()
+    }
+
+    pub fn slowlog_reset(&self) {
+        let mut slowlog = self.slowlog.lock().unwrap_or_else(|e| e.into_inner());
+        slowlog.entries.clear();
+    }
+
+    /// "cluster" in cluster mode, "standalone" otherwise.
+    pub fn mode(&self) -> &'static str {
+        if self.cluster.is_some() {
+            "cluster"
+        } else {
+            "standalone"
+        }
+    }
+
+    /// The server section of INFO.
+    pub fn server_info(&self) -> String {
+        let uptime = now_ms().saturating_sub(self.started_at) / 1000;
+        [
+            format!("redis_version:{}", env!("CARGO_PKG_VERSION")),
+            format!("redis_mode:{}", self.mode()),
+            format!("process_id:{}", std::process::id()),
+            format!("tcp_port:{}", self.config.port),
+            format!("uptime_in_seconds:{}", uptime),
+            format!("uptime_in_days:{}", uptime / 86400),
+        ]
+        .join("\r\n")
+    }
+
+    /// The clients section of INFO.
+    pub fn clients_info(&self) -> String {
+        [
+            format!(
+                "connected_clients:{}",
+                self.connected_clients.load(Ordering::Relaxed)
+            ),
+            format!("maxclients:{}", self.config.maxclients),
+            format!("monitors:{}", self.monitors.len()),
+        ]
+        .join("\r\n")
+    }
+
+    /// The cluster section of INFO.
+    pub fn cluster_info(&self) -> String {
+        format!("cluster_enabled:{}", self.cluster.is_some() as u8)
+    }
+
+    /// The stats section of INFO.
+    pub fn stats_info(&self) -> String {
+        [
+            format!("expired_keys:{}", self.expired_keys.load(Ordering::Relaxed)),
+            format!("evicted_keys:{}", self.evicted_keys.load(Ordering::Relaxed)),
+            format!(
+                "total_connections_received:{}",
+                self.total_connections_received.load(Ordering::Relaxed)
+            ),
+            format!(
+                "total_commands_processed:{}",
+                self.total_commands_processed.load(Ordering::Relaxed)
+            ),
+        ]
+        .join("\r\n")
+    }
+
+    /// The commandstats section of INFO, a line per command ever called.
+    pub fn commandstats_info(&self) -> String {
+        let mut stats = self
+            .command_stats
+            .iter()
+            .map(|v| (v.key().clone(), *v.value()))
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        stats
+            .into_iter()
+            .map(|(name, stats)| {
+                format!(
+                    "cmdstat_{}:calls={},usec={},usec_per_call={:.2}",
+                    name,
+                    stats.calls,
+                    stats.usec,
+                    stats.usec as f64 / stats.calls as f64
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    }
+
+    /// The keyspace section of INFO, a line per non empty database.
+    pub fn keyspace_info(&self) -> String {
+        let now = now_ms();
+        (0..DATABASES)
+            .filter_map(|index| {
+                let db = &self.dbs[index];
+                let keys = db.keys.len();
+                if keys == 0 {
+                    return None;
+                }
+                let ttls = db
+                    .expires
+                    .iter()
+                    .map(|v| v.value().saturating_sub(now))
+                    .collect::<Vec<_>>();
+                let avg_ttl = match ttls.len() {
+                    0 => 0,
+                    n => ttls.iter().sum::<u64>() / n as u64,
+                };
+                Some(format!(
+                    "db{}:keys={},expires={},avg_ttl={}",
+                    index,
+                    keys,
+                    ttls.len(),
+                    avg_ttl
+                ))
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    }
+}
+
+/// Keep at most SLOWLOG_MAX_ARGS arguments of SLOWLOG_MAX_ARG_LEN bytes.
+fn truncate_args(mut args: Vec<BulkString>) -> Vec<BulkString> {
+    if args.len() > SLOWLOG_MAX_ARGS {
+        let more = args.len() - SLOWLOG_MAX_ARGS + 1;
+        args.truncate(SLOWLOG_MAX_ARGS - 1);
+        args.push(BulkString::from(format!("... ({} more arguments)", more)));
+    }
+    for arg in args.iter_mut() {
+        if arg.len() > SLOWLOG_MAX_ARG_LEN {
+            let more = arg.len() - SLOWLOG_MAX_ARG_LEN;
+            arg.0.truncate(SLOWLOG_MAX_ARG_LEN);
+            arg.0
+                .extend_from_slice(format!("... ({} more bytes)", more).as_bytes());
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+
+    fn addr() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 4000))
+    }
+
+    fn slowlog_backend(slower_than: i64, max_len: usize) -> Backend {
+        Backend::with_config(ServerConfig {
+            slowlog_log_slower_than: slower_than,
+            slowlog_max_len: max_len,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_slowlog_should_keep_newest_slow_commands() {
+        let backend = slowlog_backend(1000, 2);
+        let args = |key: &str| vec![BulkString::from("get"), BulkString::from(key)];
+        backend.log_if_slow(args("fast"), Duration::from_micros(999), addr(), None);
+        for key in ["a", "b", "c"] {
+            backend.log_if_slow(args(key), Duration::from_millis(2), addr(), None);
+        }
+
+        assert_eq!(backend.slowlog_len(), 2);
+        let entries = backend.slowlog_get(None);
+        assert_eq!(entries[0].id, 2);
+        assert_eq!(entries[0].args, args("c"));
+        assert_eq!(entries[1].args, args("b"));
+        assert_eq!(backend.slowlog_get(Some(1)).len(), 1);
+
+        let RespFrame::Array(frame) = entries[0].to_frame() else {
+            panic!("an entry should be an array");
+        };
+        assert_eq!(frame[2], RespFrame::Integer(2000));
+        assert_eq!(frame[4], BulkString::from("127.0.0.1:4000").into());
+
+        backend.slowlog_reset();
+        assert_eq!(backend.slowlog_len(), 0);
+
+        let backend = slowlog_backend(-1, 128);
+        backend.log_if_slow(args("a"), Duration::from_secs(1), addr(), None);
+        assert_eq!(backend.slowlog_len(), 0);
+    }
+
+    #[test]
+    fn test_slowlog_should_truncate_arguments() {
+        let args = (0..40)
+            .map(|i| BulkString::from(i.to_string()))
+            .collect::<Vec<_>>();
+        let args = truncate_args(args);
+        assert_eq!(args.len(), SLOWLOG_MAX_ARGS);
+        assert_eq!(args[31], BulkString::from("... (9 more arguments)"));
+
+        let args = truncate_args(vec![BulkString::from("x".repeat(130))]);
+        assert_eq!(
+            args[0],
+            BulkString::from(format!("{}... (2 more bytes)", "x".repeat(128)))
+        );
+    }
+
+    #[test]
+    fn test_stats_and_keyspace_info() {
+        let backend = Backend::new();
+        backend.record_command("get", Duration::from_micros(10));
+        backend.record_command("get", Duration::from_micros(20));
+        backend.record_command("set", Duration::from_micros(5));
+        assert_eq!(
+            backend.command_stats("get"),
+            Some(CommandStats { calls: 2, usec: 30 })
+        );
+        assert_eq!(
+            backend.commandstats_info(),
+            "cmdstat_get:calls=2,usec=30,usec_per_call=15.00\r\ncmdstat_set:calls=1,usec=5,usec_per_call=5.00"
+        );
+        assert!(backend.stats_info().contains("total_commands_processed:3"));
+
+        assert_eq!(backend.keyspace_info(), "");
+        backend.set("a".to_string(), BulkString::from("1").into());
+        let other = backend.select(2).unwrap();
+        other.set("b".to_string(), BulkString::from("2").into());
+        other.set("c".to_string(), BulkString::from("3").into());
+        other.expire_at("c", now_ms() + 10_500);
+        let info = backend.keyspace_info();
+        let lines = info.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines[0], "db0:keys=1,expires=0,avg_ttl=0");
+        assert!(lines[1].starts_with("db2:keys=2,expires=1,avg_ttl=10"));
+    }
+}
@@ -0,0 +1,732 @@
+use super::memory::stream_entry_size;
+use super::{Backend, Value, WrongType, now_ms};
+use crate::RespFrame;
+use crate::cmd::{ClaimOptions, StreamTrim, XAddId};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Bound;
+use thiserror::Error;
+
+/// The field value pairs of a stream entry.
+pub type Fields = Vec<(String, RespFrame)>;
+
+/// Stream entry id: a millisecond timestamp and a sequence number for the
+/// entries added within the same millisecond.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        Self { ms, seq }
+    }
+
+    /// Parse `<ms>-<seq>`, or `<ms>` alone with the given sequence number.
+    pub fn parse(s: &str, default_seq: u64) -> Option<Self> {
+        match s.split_once('-') {
+            Some((ms, seq)) => Some(Self::new(ms.parse().ok()?, seq.parse().ok()?)),
+            None => Some(Self::new(s.parse().ok()?, default_seq)),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StreamError {
+    #[error(transparent)]
+    WrongType(#[from] WrongType),
+    #[error("ERR The ID specified in XADD is equal or smaller than the target stream top item")]
+    IdTooSmall,
+    #[error("ERR The ID specified in XADD must be greater than 0-0")]
+    IdZero,
+    #[error("ERR The ID specified in XSETID is smaller than the target stream top item")]
+    SetIdTooSmall,
+    #[error("ERR no such key")]
+    NoSuchKey,
+    #[error(
+        "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically."
+    )]
+    NoStream,
+    #[error("BUSYGROUP Consumer Group name already exists")]
+    BusyGroup,
+    #[error("NOGROUP No such key '{0}' or consumer group '{1}'")]
+    NoGroup(String, String),
+}
+
+impl From<StreamError> for RespFrame {
+    fn from(e: StreamError) -> Self {
+        crate::SimpleError::new(e.to_string()).into()
+    }
+}
+
+/// An append only log of entries, read by plain readers or by consumer
+/// groups which track the entries delivered to their consumers.
+#[derive(Debug, Clone, Default)]
+pub struct Stream {
+    entries: BTreeMap<StreamId, Fields>,
+    /// the greatest id ever added, trimmed entries included
+    last_id: StreamId,
+    groups: BTreeMap<String, ConsumerGroup>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConsumerGroup {
+    last_delivered: StreamId,
+    /// the entries delivered to a consumer and not acknowledged yet
+    pending: BTreeMap<StreamId, PendingEntry>,
+    /// the consumers and the last time they were seen
+    consumers: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEntry {
+    pub consumer: String,
+    /// unix milliseconds of the last delivery
+    pub delivered_at: u64,
+    pub deliveries: u64,
+}
+
+/// The summary reply of XPENDING.
+#[derive(Debug, PartialEq)]
+pub struct PendingSummary {
+    pub count: usize,
+    /// the smallest and greatest pending ids
+    pub range: Option<(StreamId, StreamId)>,
+    pub consumers: Vec<(String, usize)>,
+}
+
+impl Stream {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&StreamId, &Fields)> {
+        self.entries.iter()
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = (&String, &ConsumerGroup)> {
+        self.groups.iter()
+    }
+
+    /// The id of a new entry, which has to be greater than any added before.
+    fn next_id(&self, id: XAddId, now: u64) -> Result<StreamId, StreamError> {
+        let last = self.last_id;
+        let id = match id {
+            XAddId::Auto if now > last.ms => StreamId::new(now, 0),
+            XAddId::Auto => StreamId::new(last.ms, last.seq.wrapping_add(1)),
+            XAddId::Partial(ms) if ms == last.ms => StreamId::new(ms, last.seq.wrapping_add(1)),
+            XAddId::Partial(0) => StreamId::new(0, 1),
+            XAddId::Partial(ms) => StreamId::new(ms, 0),
+            XAddId::Explicit(id) => id,
+        };
+        if id == StreamId::default() {
+            return Err(StreamError::IdZero);
+        }
+        if id <= last && last != StreamId::default() {
+            return Err(StreamError::IdTooSmall);
+        }
+        Ok(id)
+    }
+
+    /// Remove the entries the trim strategy drops, returns them.
+    fn trim(&mut self, trim: StreamTrim) -> Vec<Fields> {
+        let ids = match trim {
+            StreamTrim::MaxLen(len) => {
+                let count = self.entries.len().saturating_sub(len);
+                self.entries.keys().take(count).copied().collect::<Vec<_>>()
+            }
+            StreamTrim::MinId(min) => self.entries.range(..min).map(|(id, _)| *id).collect(),
+        };
+        ids.iter()
+            .filter_map(|id| self.entries.remove(id))
+            .collect()
+    }
+
+    fn range(
+        &self,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: usize,
+        rev: bool,
+    ) -> Vec<(StreamId, Fields)> {
+        if range_is_empty(start, end) {
+            return vec![];
+        }
+        let range = self.entries.range((start, end));
+        let entries: Box<dyn Iterator<Item = _>> = if rev {
+            Box::new(range.rev())
+        } else {
+            Box::new(range)
+        };
+        entries
+            .take(count)
+            .map(|(id, fields)| (*id, fields.clone()))
+            .collect()
+    }
+
+    fn group(&mut self, key: &str, group: &str) -> Result<&mut ConsumerGroup, StreamError> {
+        self.groups
+            .get_mut(group)
+            .ok_or_else(|| StreamError::NoGroup(key.to_string(), group.to_string()))
+    }
+}
+
+impl ConsumerGroup {
+    pub fn last_delivered(&self) -> StreamId {
+        self.last_delivered
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = (&StreamId, &PendingEntry)> {
+        self.pending.iter()
+    }
+}
+
+/// BTreeMap::range panics on inverted bounds instead of returning nothing.
+fn range_is_empty(start: Bound<StreamId>, end: Bound<StreamId>) -> bool {
+    match (start, end) {
+        (Bound::Included(s), Bound::Included(e)) => s > e,
+        (Bound::Included(s) | Bound::Excluded(s), Bound::Excluded(e))
+        | (Bound::Excluded(s), Bound::Included(e)) => s >= e,
+        _ => false,
+    }
+}
+
+impl Backend {
+    /// Append an entry, returns its id or None if the stream does not exist
+    /// and must not be created.
+    pub fn xadd(
+        &self,
+        key: String,
+        id: XAddId,
+        fields: Fields,
+        no_mkstream: bool,
+        trim: Option<StreamTrim>,
+    ) -> Result<Option<StreamId>, StreamError> {
+        self.evict_if_expired(&key);
+        let db = self.db();
+        if no_mkstream && !db.keys.contains_key(&key) {
+            return Ok(None);
+        }
+        // checked before the stream gets created
+        if id == XAddId::Explicit(StreamId::default()) {
+            return Err(StreamError::IdZero);
+        }
+        let id = {
+            let key_len = key.len();
+            let mut entry = db.keys.entry(key.clone()).or_insert_with(|| {
+                db.created(key_len);
+                Value::Stream(Stream::default())
+            });
+            let stream = entry.as_stream_mut()?;
+            let id = stream.next_id(id, now_ms())?;
+            db.grow(stream_entry_size(&fields));
+            stream.entries.insert(id, fields);
+            stream.last_id = id;
+            if let Some(trim) = trim {
+                let removed = stream.trim(trim);
+                db.shrink(removed.iter().map(|f| stream_entry_size(f)).sum());
+            }
+            id
+        };
+        self.blocked_streams.wake_all(self.db_index(), &key);
+        Ok(Some(id))
+    }
+
+    pub fn xlen(&self, key: &str) -> Result<usize, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(entry) => Ok(entry.as_stream()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// The greatest id added to the stream, None if it does not exist.
+    pub fn xlast_id(&self, key: &str) -> Result<Option<StreamId>, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(entry) => Ok(Some(entry.as_stream()?.last_id())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn xrange(
+        &self,
+        key: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Result<Vec<(StreamId, Fields)>, WrongType> {
+        self.evict_if_expired(key);
+        let Some(entry) = self.db().keys.get(key) else {
+            return Ok(vec![]);
+        };
+        let count = count.unwrap_or(usize::MAX);
+        Ok(entry.as_stream()?.range(start, end, count, rev))
+    }
+
+    /// The entries after the id, as read by XREAD.
+    pub fn xread(
+        &self,
+        key: &str,
+        after: StreamId,
+        count: Option<usize>,
+    ) -> Result<Vec<(StreamId, Fields)>, WrongType> {
+        self.xrange(key, Bound::Excluded(after), Bound::Unbounded, count, false)
+    }
+
+    /// Returns the number of removed entries.
+    pub fn xtrim(&self, key: &str, trim: StreamTrim) -> Result<usize, WrongType> {
+        self.evict_if_expired(key);
+        let db = self.db();
+        let Some(mut entry) = db.keys.get_mut(key) else {
+            return Ok(0);
+        };
+        let removed = entry.as_stream_mut()?.trim(trim);
+        db.shrink(removed.iter().map(|f| stream_entry_size(f)).sum());
+        Ok(removed.len())
+    }
+
+    /// Set the greatest id of the stream, so that ids are not reused after
+    /// the entries holding them were trimmed.
+    pub fn xsetid(&self, key: &str, id: StreamId) -> Result<(), StreamError> {
+        self.evict_if_expired(key);
+        let Some(mut entry) = self.db().keys.get_mut(key) else {
+            return Err(StreamError::NoSuchKey);
+        };
+        let stream = entry.as_stream_mut()?;
+        if stream
+            .entries
+            .last_key_value()
+            .is_some_and(|(last, _)| id < *last)
+        {
+            return Err(StreamError::SetIdTooSmall);
+        }
+        stream.last_id = id;
+        Ok(())
+    }
+
+    /// Create a consumer group starting after the id, the last id of the
+    /// stream when None.
+    pub fn xgroup_create(
+        &self,
+        key: String,
+        group: String,
+        id: Option<StreamId>,
+        mkstream: bool,
+    ) -> Result<(), StreamError> {
+        self.evict_if_expired(&key);
+        let db = self.db();
+        if !mkstream && !db.keys.contains_key(&key) {
+            return Err(StreamError::NoStream);
+        }
+        let key_len = key.len();
+        let mut entry = db.keys.entry(key).or_insert_with(|| {
+            db.created(key_len);
+            Value::Stream(Stream::default())
+        });
+        let stream = entry.as_stream_mut()?;
+        if stream.groups.contains_key(&group) {
+            return Err(StreamError::BusyGroup);
+        }
+        let group_state = ConsumerGroup {
+            last_delivered: id.unwrap_or(stream.last_id),
+            ..Default::default()
+        };
+        stream.groups.insert(group, group_state);
+        Ok(())
+    }
+
+    /// Returns whether the group existed.
+    pub fn xgroup_destroy(&self, key: &str, group: &str) -> Result<bool, StreamError> {
+        self.evict_if_expired(key);
+        let Some(mut entry) = self.db().keys.get_mut(key) else {
+            return Err(StreamError::NoStream);
+        };
+        Ok(entry.as_stream_mut()?.groups.remove(group).is_some())
+    }
+
+    /// Read the entries never delivered to the group when `after` is None,
+    /// they are added to the pending entries of the consumer unless `no_ack`
+    /// is set. Otherwise read the pending entries of the consumer after the
+    /// id, the fields are None for the entries trimmed in the meantime.
+    pub fn xreadgroup(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        after: Option<StreamId>,
+        count: Option<usize>,
+        no_ack: bool,
+    ) -> Result<Vec<(StreamId, Option<Fields>)>, StreamError> {
+        self.evict_if_expired(key);
+        let Some(mut entry) = self.db().keys.get_mut(key) else {
+            return Err(StreamError::NoGroup(key.to_string(), group.to_string()));
+        };
+        let stream = entry.as_stream_mut()?;
+        let entries = &stream.entries;
+        let state = stream
+            .groups
+            .get_mut(group)
+            .ok_or_else(|| StreamError::NoGroup(key.to_string(), group.to_string()))?;
+        let now = now_ms();
+        state.consumers.insert(consumer.to_string(), now);
+        let count = count.unwrap_or(usize::MAX);
+
+        let read = match after {
+            None => {
+                let read = entries
+                    .range((Bound::Excluded(state.last_delivered), Bound::Unbounded))
+                    .take(count)
+                    .map(|(id, fields)| (*id, Some(fields.clone())))
+                    .collect::<Vec<_>>();
+                if let Some((last, _)) = read.last() {
+                    state.last_delivered = *last;
+                }
+                if !no_ack {
+                    for (id, _) in &read {
+                        let pending = PendingEntry {
+                            consumer: consumer.to_string(),
+                            delivered_at: now,
+                            deliveries: 1,
+                        };
+                        state.pending.insert(*id, pending);
+                    }
+                }
+                read
+            }
+            Some(after) => state
+                .pending
+                .range_mut((Bound::Excluded(after), Bound::Unbounded))
+                .filter(|(_, pending)| pending.consumer == consumer)
+                .take(count)
+                .map(|(id, pending)| {
+                    pending.delivered_at = now;
+                    pending.deliveries += 1;
+                    (*id, entries.get(id).cloned())
+                })
+                .collect(),
+        };
+        Ok(read)
+    }
+
+    /// Acknowledge pending entries, returns the number of acknowledged ones.
+    pub fn xack(&self, key: &str, group: &str, ids: &[StreamId]) -> Result<usize, WrongType> {
+        self.evict_if_expired(key);
+        let Some(mut entry) = self.db().keys.get_mut(key) else {
+            return Ok(0);
+        };
+        let Some(state) = entry.as_stream_mut()?.groups.get_mut(group) else {
+            return Ok(0);
+        };
+        Ok(ids
+            .iter()
+            .filter(|id| state.pending.remove(id).is_some())
+            .count())
+    }
+
+    pub fn xpending_summary(&self, key: &str, group: &str) -> Result<PendingSummary, StreamError> {
+        self.evict_if_expired(key);
+        let Some(mut entry) = self.db().keys.get_mut(key) else {
+            return Err(StreamError::NoGroup(key.to_string(), group.to_string()));
+        };
+        let state = entry.as_stream_mut()?.group(key, group)?;
+        let mut consumers = BTreeMap::<&str, usize>::new();
+        for pending in state.pending.values() {
+            *consumers.entry(&pending.consumer).or_default() += 1;
+        }
+        let first = state.pending.first_key_value().map(|(id, _)| *id);
+        let last = state.pending.last_key_value().map(|(id, _)| *id);
+        Ok(PendingSummary {
+            count: state.pending.len(),
+            range: first.zip(last),
+            consumers: consumers
+                .into_iter()
+                .map(|(consumer, count)| (consumer.to_string(), count))
+                .collect(),
+        })
+    }
+
+    /// The pending entries within the range, optionally only those of a
+    /// consumer and idle for at least `min_idle` milliseconds.
+    #[allow(clippy::too_many_arguments)]
+    pub fn xpending(
+        &self,
+        key: &str,
+        group: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: usize,
+        consumer: Option<&str>,
+        min_idle: Option<u64>,
+    ) -> Result<Vec<(StreamId, PendingEntry)>, StreamError> {
+        self.evict_if_expired(key);
+        let Some(mut entry) = self.db().keys.get_mut(key) else {
+            return Err(StreamError::NoGroup(key.to_string(), group.to_string()));
+        };
+        let state = entry.as_stream_mut()?.group(key, group)?;
+        if range_is_empty(start, end) {
+            return Ok(vec![]);
+        }
+        let now = now_ms();
+        Ok(state
+            .pending
+            .range((start, end))
+            .filter(|(_, pending)| consumer.is_none_or(|c| pending.consumer == c))
+            .filter(|(_, pending)| {
+                min_idle.is_none_or(|idle| now.saturating_sub(pending.delivered_at) >= idle)
+            })
+            .take(count)
+            .map(|(id, pending)| (*id, pending.clone()))
+            .collect())
+    }
+
+    /// Transfer pending entries idle for at least `min_idle` milliseconds to
+    /// the consumer, returns the claimed entries. Entries trimmed from the
+    /// stream are dropped from the pending list instead.
+    pub fn xclaim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: u64,
+        ids: &[StreamId],
+        options: ClaimOptions,
+    ) -> Result<Vec<(StreamId, Fields)>, StreamError> {
+        self.evict_if_expired(key);
+        let Some(mut entry) = self.db().keys.get_mut(key) else {
+            return Err(StreamError::NoGroup(key.to_string(), group.to_string()));
+        };
+        let stream = entry.as_stream_mut()?;
+        let entries = &stream.entries;
+        let state = stream
+            .groups
+            .get_mut(group)
+            .ok_or_else(|| StreamError::NoGroup(key.to_string(), group.to_string()))?;
+        let now = now_ms();
+        state.consumers.insert(consumer.to_string(), now);
+        let delivered_at = match (options.time, options.idle) {
+            (Some(time), _) => time,
+            (None, Some(idle)) => now.saturating_sub(idle),
+            (None, None) => now,
+        };
+
+        let mut claimed = vec![];
+        for id in ids {
+            let Some(fields) = entries.get(id) else {
+                state.pending.remove(id);
+                continue;
+            };
+            let pending = match state.pending.get_mut(id) {
+                Some(pending) if now.saturating_sub(pending.delivered_at) >= min_idle => pending,
+                Some(_) => continue,
+                None if options.force => state.pending.entry(*id).or_insert(PendingEntry {
+                    consumer: consumer.to_string(),
+                    delivered_at,
+                    deliveries: 0,
+                }),
+                None => continue,
+            };
+            pending.consumer = consumer.to_string();
+            pending.delivered_at = delivered_at;
+            if let Some(retry_count) = options.retry_count {
+                pending.deliveries = retry_count;
+            } else if !options.just_id {
+                pending.deliveries += 1;
+            }
+            claimed.push((*id, fields.clone()));
+        }
+        Ok(claimed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn fields(values: &[(&str, &str)]) -> Fields {
+        values
+            .iter()
+            .map(|(f, v)| (f.to_string(), crate::BulkString::from(*v).into()))
+            .collect()
+    }
+
+    fn add(backend: &Backend, key: &str, id: &str) -> Result<Option<StreamId>, StreamError> {
+        let id = XAddId::Explicit(StreamId::parse(id, 0).unwrap());
+        backend.xadd(key.to_string(), id, fields(&[("f", "v")]), false, None)
+    }
+
+    #[test]
+    fn test_xadd_should_generate_increasing_ids() -> Result<()> {
+        let backend = Backend::new();
+        let id = backend.xadd("s".to_string(), XAddId::Auto, fields(&[]), false, None)?;
+        let next = backend.xadd("s".to_string(), XAddId::Auto, fields(&[]), false, None)?;
+        assert!(next > id);
+
+        let id = backend.xadd(
+            "t".to_string(),
+            XAddId::Partial(0),
+            fields(&[]),
+            false,
+            None,
+        )?;
+        assert_eq!(id, Some(StreamId::new(0, 1)));
+        let id = backend.xadd(
+            "t".to_string(),
+            XAddId::Partial(5),
+            fields(&[]),
+            false,
+            None,
+        )?;
+        assert_eq!(id, Some(StreamId::new(5, 0)));
+        let id = backend.xadd(
+            "t".to_string(),
+            XAddId::Partial(5),
+            fields(&[]),
+            false,
+            None,
+        )?;
+        assert_eq!(id, Some(StreamId::new(5, 1)));
+
+        assert_eq!(add(&backend, "t", "5-1"), Err(StreamError::IdTooSmall));
+        assert_eq!(add(&backend, "u", "0-0"), Err(StreamError::IdZero));
+        assert!(!backend.exists("u"));
+        let id = backend.xadd("v".to_string(), XAddId::Auto, fields(&[]), true, None)?;
+        assert_eq!(id, None);
+        assert!(!backend.exists("v"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_xrange_and_xtrim() -> Result<()> {
+        let backend = Backend::new();
+        for id in ["1-1", "1-2", "2-0", "3-0"] {
+            add(&backend, "s", id)?;
+        }
+        let ids = |entries: Vec<(StreamId, Fields)>| {
+            entries
+                .into_iter()
+                .map(|(id, _)| id.to_string())
+                .collect::<Vec<_>>()
+        };
+        let range = backend.xrange(
+            "s",
+            Bound::Included(StreamId::new(1, 2)),
+            Bound::Unbounded,
+            Some(2),
+            false,
+        )?;
+        assert_eq!(ids(range), ["1-2", "2-0"]);
+        let range = backend.xrange("s", Bound::Unbounded, Bound::Unbounded, None, true)?;
+        assert_eq!(ids(range), ["3-0", "2-0", "1-2", "1-1"]);
+        let range = backend.xrange(
+            "s",
+            Bound::Excluded(StreamId::new(3, 0)),
+            Bound::Included(StreamId::new(1, 0)),
+            None,
+            false,
+        )?;
+        assert!(range.is_empty());
+
+        assert_eq!(backend.xtrim("s", StreamTrim::MaxLen(3))?, 1);
+        assert_eq!(
+            backend.xtrim("s", StreamTrim::MinId(StreamId::new(3, 0)))?,
+            2
+        );
+        assert_eq!(backend.xlen("s")?, 1);
+        assert_eq!(add(&backend, "s", "2-5"), Err(StreamError::IdTooSmall));
+        Ok(())
+    }
+
+    #[test]
+    fn test_consumer_group_should_track_pending_entries() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(
+            backend.xgroup_create("s".to_string(), "g".to_string(), None, false),
+            Err(StreamError::NoStream)
+        );
+        backend.xgroup_create("s".to_string(), "g".to_string(), None, true)?;
+        assert_eq!(
+            backend.xgroup_create("s".to_string(), "g".to_string(), None, true),
+            Err(StreamError::BusyGroup)
+        );
+        for id in ["1-0", "2-0", "3-0"] {
+            add(&backend, "s", id)?;
+        }
+
+        let read = backend.xreadgroup("s", "g", "alice", None, Some(2), false)?;
+        assert_eq!(read.len(), 2);
+        let read = backend.xreadgroup("s", "g", "bob", None, None, false)?;
+        assert_eq!(read[0].0, StreamId::new(3, 0));
+        assert!(
+            backend
+                .xreadgroup("s", "g", "bob", None, None, false)?
+                .is_empty()
+        );
+
+        // the history of a consumer is its pending entries
+        let history =
+            backend.xreadgroup("s", "g", "alice", Some(StreamId::default()), None, false)?;
+        assert_eq!(history.len(), 2);
+        assert_eq!(backend.xack("s", "g", &[StreamId::new(1, 0)])?, 1);
+
+        let summary = backend.xpending_summary("s", "g")?;
+        assert_eq!(summary.count, 2);
+        assert_eq!(
+            summary.range,
+            Some((StreamId::new(2, 0), StreamId::new(3, 0)))
+        );
+        assert_eq!(
+            summary.consumers,
+            vec![("alice".to_string(), 1), ("bob".to_string(), 1)]
+        );
+
+        let claimed = backend.xclaim(
+            "s",
+            "g",
+            "bob",
+            0,
+            &[StreamId::new(2, 0)],
+            ClaimOptions::default(),
+        )?;
+        assert_eq!(claimed.len(), 1);
+        let pending = backend.xpending(
+            "s",
+            "g",
+            Bound::Unbounded,
+            Bound::Unbounded,
+            10,
+            Some("bob"),
+            None,
+        )?;
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].1.deliveries, 3);
+
+        assert_eq!(
+            backend.xreadgroup("s", "nope", "bob", None, None, false),
+            Err(StreamError::NoGroup("s".to_string(), "nope".to_string()))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,378 @@
+use super::memory::frame_size;
+use super::{Backend, Value, WrongType};
+use crate::{BulkString, RespFrame};
+use dashmap::mapref::entry::Entry;
+use std::ops::RangeInclusive;
+use thiserror::Error;
+
+/// Largest string SETRANGE may produce, as proto-max-bulk-len.
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StringError {
+    #[error(transparent)]
+    WrongType(#[from] WrongType),
+    #[error("ERR value is not an integer or out of range")]
+    NotInteger,
+    #[error("ERR value is not a valid float")]
+    NotFloat,
+    #[error("ERR increment or decrement would overflow")]
+    Overflow,
+    #[error("ERR increment would produce NaN or Infinity")]
+    NotFinite,
+    #[error("ERR string exceeds maximum allowed size (proto-max-bulk-len)")]
+    TooLarge,
+    #[error("WRONGTYPE Key is not a valid HyperLogLog string value.")]
+    NotHyperLogLog,
+}
+
+impl From<StringError> for RespFrame {
+    fn from(e: StringError) -> Self {
+        crate::SimpleError::new(e.to_string()).into()
+    }
+}
+
+impl Backend {
+    /// Add to the integer stored at the key, a missing key counts as 0.
+    pub fn incr_by(&self, key: &str, delta: i64) -> Result<i64, StringError> {
+        self.update_string(key, |value| {
+            let current = match value {
+                Some(value) => parse_int(value).ok_or(StringError::NotInteger)?,
+                None => 0,
+            };
+            let n = current.checked_add(delta).ok_or(StringError::Overflow)?;
+            Ok((n.to_string().into_bytes(), n))
+        })
+    }
+
+    /// Add to the float stored at the key, a missing key counts as 0.
+    pub fn incr_by_float(&self, key: &str, increment: f64) -> Result<f64, StringError> {
+        self.update_string(key, |value| {
+            let current = match value {
+                Some(value) => std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .filter(|n| n.is_finite())
+                    .ok_or(StringError::NotFloat)?,
+                None => 0.0,
+            };
+            let n = current + increment;
+            if !n.is_finite() {
+                return Err(StringError::NotFinite);
+            }
+            Ok((n.to_string().into_bytes(), n))
+        })
+    }
+
+    /// Append to the string, created if missing, returns its new length.
+    pub fn append(&self, key: &str, suffix: &[u8]) -> Result<usize, StringError> {
+        self.update_string(key, |value| {
+            let mut value = value.map(<[u8]>::to_vec).unwrap_or_default();
+            value.extend_from_slice(suffix);
+            let len = value.len();
+            Ok((value, len))
+        })
+    }
+
+    /// Overwrite the string from the offset, padded with zero bytes if it is
+    /// shorter, returns its new length.
+    pub fn setrange(&self, key: &str, offset: usize, data: &[u8]) -> Result<usize, StringError> {
+        if data.is_empty() {
+            // nothing to write, the key is not created
+            return Ok(self.strlen(key)?);
+        }
+        if offset.saturating_add(data.len()) > MAX_STRING_LEN {
+            return Err(StringError::TooLarge);
+        }
+        self.update_string(key, |value| {
+            let mut value = value.map(<[u8]>::to_vec).unwrap_or_default();
+            let end = offset + data.len();
+            if value.len() < end {
+                value.resize(end, 0);
+            }
+            value[offset..end].copy_from_slice(data);
+            let len = value.len();
+            Ok((value, len))
+        })
+    }
+
+    pub fn strlen(&self, key: &str) -> Result<usize, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(value) => Ok(string_bytes(value.as_string()?).len()),
+            None => Ok(0),
+        }
+    }
+
+    /// The bytes between two inclusive offsets, negative ones counting from
+    /// the end.
+    pub fn getrange(&self, key: &str, start: i64, end: i64) -> Result<Vec<u8>, WrongType> {
+        self.evict_if_expired(key);
+        let Some(value) = self.db().keys.get(key) else {
+            return Ok(vec![]);
+        };
+        let bytes = string_bytes(value.as_string()?);
+        match index_range(start, end, bytes.len()) {
+            Some(range) => Ok(bytes[range].to_vec()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Replace the string and return the old one, the ttl is discarded.
+    pub fn getset(&self, key: String, value: RespFrame) -> Result<Option<RespFrame>, WrongType> {
+        self.evict_if_expired(&key);
+        let db = self.db();
+        let old = match db.keys.entry(key.clone()) {
+            Entry::Occupied(mut entry) => {
+                let old = entry.get().as_string()?.clone();
+                db.grow(frame_size(&value));
+                db.shrink(frame_size(&old));
+                entry.insert(Value::String(value));
+                Some(old)
+            }
+            Entry::Vacant(entry) => {
+                db.created(key.len());
+                db.grow(frame_size(&value));
+                entry.insert(Value::String(value));
+                None
+            }
+        };
+        db.expires.remove(&key);
+        Ok(old)
+    }
+
+    /// Remove the string and return it.
+    pub fn getdel(&self, key: &str) -> Result<Option<RespFrame>, WrongType> {
+        self.evict_if_expired(key);
+        let db = self.db();
+        match db.remove_if(key, |value| matches!(value, Value::String(_))) {
+            Some(Value::String(value)) => {
+                db.expires.remove(key);
+                Ok(Some(value))
+            }
+            _ if db.keys.contains_key(key) => Err(WrongType),
+            _ => Ok(None),
+        }
+    }
+
+    /// Store the string only if the key does not exist, false if it did.
+    pub fn set_nx(&self, key: String, value: RespFrame) -> bool {
+        self.evict_if_expired(&key);
+        let db = self.db();
+        match db.keys.entry(key) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                db.created(entry.key().len());
+                db.grow(frame_size(&value));
+                entry.insert(Value::String(value));
+                true
+            }
+        }
+    }
+
+    /// Replace the string at the key with the one computed from its current
+    /// bytes, the key is locked in the meantime and keeps its ttl.
+    pub(super) fn update_string<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(Option<&[u8]>) -> Result<(Vec<u8>, T), StringError>,
+    ) -> Result<T, StringError> {
+        self.evict_if_expired(key);
+        let db = self.db();
+        match db.keys.entry(key.to_string()) {
+            Entry::Occupied(mut entry) => {
+                let old = entry.get().as_string()?;
+                let (bytes, ret) = f(Some(&string_bytes(old)))?;
+                let value = BulkString::new(bytes).into();
+                db.grow(frame_size(&value));
+                db.shrink(frame_size(old));
+                entry.insert(Value::String(value));
+                Ok(ret)
+            }
+            Entry::Vacant(entry) => {
+                let (bytes, ret) = f(None)?;
+                let value = BulkString::new(bytes).into();
+                db.created(key.len());
+                db.grow(frame_size(&value));
+                entry.insert(Value::String(value));
+                Ok(ret)
+            }
+        }
+    }
+}
+
+/// Inclusive offsets, negative ones counting from the end, clamped to a
+/// sequence of the length. None if nothing is in range.
+pub(super) fn index_range(start: i64, end: i64, len: usize) -> Option<RangeInclusive<usize>> {
+    let len = len as i64;
+    if len == 0 || (start < 0 && end < 0 && start > end) {
+        return None;
+    }
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.clamp(0, len - 1);
+    (start <= end).then_some(start as usize..=end as usize)
+}
+
+/// The bytes of a string value, strings set by clients are bulk strings.
+pub(super) fn string_bytes(value: &RespFrame) -> Vec<u8> {
+    match value {
+        RespFrame::BulkString(s) => s.to_vec(),
+        RespFrame::SimpleString(s) => s.as_bytes().to_vec(),
+        RespFrame::Integer(n) => n.to_string().into_bytes(),
+        RespFrame::Double(n) => n.to_string().into_bytes(),
+        _ => vec![],
+    }
+}
+
+/// Parse an integer the way redis prints it, so "01", "+1" or "-0" which
+/// would not round trip are refused.
+pub(crate) fn parse_int(value: &[u8]) -> Option<i64> {
+    let digits = value.strip_prefix(b"-").unwrap_or(value);
+    let canonical = match digits {
+        [b'0'] => digits.len() == value.len(),
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    };
+    match canonical {
+        true => std::str::from_utf8(value).ok()?.parse().ok(),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::now_ms;
+
+    fn set(backend: &Backend, key: &str, value: &str) {
+        backend.set(key.to_string(), BulkString::from(value).into());
+    }
+
+    #[test]
+    fn test_incr_by_should_keep_ttl() {
+        let backend = Backend::new();
+        assert_eq!(backend.incr_by("n", 5), Ok(5));
+        backend.expire_at("n", now_ms() + 10_000);
+        assert_eq!(backend.incr_by("n", -7), Ok(-2));
+        assert!(backend.pttl("n") > 0);
+        assert_eq!(backend.get("n"), Ok(Some(BulkString::from("-2").into())));
+
+        set(&backend, "s", "12a");
+        assert_eq!(backend.incr_by("s", 1), Err(StringError::NotInteger));
+        set(&backend, "max", &i64::MAX.to_string());
+        assert_eq!(backend.incr_by("max", 1), Err(StringError::Overflow));
+        backend
+            .lpush("list".to_string(), vec![BulkString::from("a").into()])
+            .unwrap();
+        assert_eq!(
+            backend.incr_by("list", 1),
+            Err(StringError::WrongType(WrongType))
+        );
+    }
+
+    #[test]
+    fn test_parse_int_should_only_accept_canonical_integers() {
+        assert_eq!(parse_int(b"0"), Some(0));
+        assert_eq!(parse_int(b"-42"), Some(-42));
+        assert_eq!(parse_int(i64::MIN.to_string().as_bytes()), Some(i64::MIN));
+        for value in [
+            "",
+            "-",
+            "01",
+            "-01",
+            "-0",
+            "+1",
+            " 1",
+            "1 ",
+            "1e3",
+            "9223372036854775808",
+        ] {
+            assert_eq!(parse_int(value.as_bytes()), None, "{:?}", value);
+        }
+
+        let backend = Backend::new();
+        set(&backend, "n", "01");
+        assert_eq!(backend.incr_by("n", 1), Err(StringError::NotInteger));
+    }
+
+    #[test]
+    fn test_incr_by_float() {
+        let backend = Backend::new();
+        set(&backend, "f", "10.5");
+        assert_eq!(backend.incr_by_float("f", 0.1), Ok(10.6));
+        assert_eq!(backend.get("f"), Ok(Some(BulkString::from("10.6").into())));
+        assert_eq!(backend.incr_by_float("f", -0.6), Ok(10.0));
+        assert_eq!(backend.get("f"), Ok(Some(BulkString::from("10").into())));
+        assert_eq!(
+            backend.incr_by_float("f", f64::INFINITY),
+            Err(StringError::NotFinite)
+        );
+        set(&backend, "s", "abc");
+        assert_eq!(backend.incr_by_float("s", 1.0), Err(StringError::NotFloat));
+    }
+
+    #[test]
+    fn test_append_setrange_and_getrange() {
+        let backend = Backend::new();
+        assert_eq!(backend.append("s", b"Hello"), Ok(5));
+        assert_eq!(backend.append("s", b" World"), Ok(11));
+        assert_eq!(backend.strlen("s"), Ok(11));
+        assert_eq!(backend.getrange("s", 0, 4), Ok(b"Hello".to_vec()));
+        assert_eq!(backend.getrange("s", -5, -1), Ok(b"World".to_vec()));
+        assert_eq!(backend.getrange("s", 5, 100), Ok(b" World".to_vec()));
+        assert_eq!(backend.getrange("s", -1, -5), Ok(vec![]));
+        assert_eq!(backend.getrange("missing", 0, -1), Ok(vec![]));
+
+        assert_eq!(backend.setrange("s", 6, b"Redis"), Ok(11));
+        assert_eq!(backend.getrange("s", 0, -1), Ok(b"Hello Redis".to_vec()));
+        assert_eq!(backend.setrange("p", 3, b"x"), Ok(4));
+        assert_eq!(backend.getrange("p", 0, -1), Ok(b"\0\0\0x".to_vec()));
+        assert_eq!(backend.setrange("empty", 3, b""), Ok(0));
+        assert!(!backend.exists("empty"));
+        assert_eq!(
+            backend.setrange("big", MAX_STRING_LEN, b"x"),
+            Err(StringError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn test_getset_getdel_and_set_nx() {
+        let backend = Backend::new();
+        assert!(backend.set_nx("k".to_string(), BulkString::from("1").into()));
+        assert!(!backend.set_nx("k".to_string(), BulkString::from("2").into()));
+        backend.expire_at("k", now_ms() + 10_000);
+
+        let old = backend.getset("k".to_string(), BulkString::from("3").into());
+        assert_eq!(old, Ok(Some(BulkString::from("1").into())));
+        assert_eq!(backend.pttl("k"), -1);
+
+        assert_eq!(backend.getdel("k"), Ok(Some(BulkString::from("3").into())));
+        assert_eq!(backend.getdel("k"), Ok(None));
+        assert_eq!(backend.used_memory(), 0);
+
+        backend
+            .lpush("list".to_string(), vec![BulkString::from("a").into()])
+            .unwrap();
+        assert_eq!(backend.getdel("list"), Err(WrongType));
+        assert!(backend.exists("list"));
+    }
+
+    #[test]
+    fn test_concurrent_incr_should_not_lose_updates() {
+        let backend = Backend::new();
+        let threads = (0..8)
+            .map(|_| {
+                let backend = backend.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        backend.incr_by("n", 1).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(backend.get("n"), Ok(Some(BulkString::from("8000").into())));
+    }
+}
@@ -0,0 +1,111 @@
+use super::{SortedSet, Stream};
+use crate::{RespFrame, SimpleError};
+use std::collections::{HashMap, VecDeque};
+use thiserror::Error;
+
+/// The value of a key, a key holds exactly one type.
+#[derive(Debug, Clone)]
+pub enum Value {
+    String(RespFrame),
+    Hash(HashMap<String, RespFrame>),
+    List(VecDeque<RespFrame>),
+    ZSet(SortedSet),
+    Stream(Stream),
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+pub struct WrongType;
+
+impl From<WrongType> for RespFrame {
+    fn from(e: WrongType) -> Self {
+        SimpleError::new(e.to_string()).into()
+    }
+}
+
+impl Value {
+    /// The type name reported by TYPE.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Hash(_) => "hash",
+            Value::List(_) => "list",
+            Value::ZSet(_) => "zset",
+            Value::Stream(_) => "stream",
+        }
+    }
+
+    /// Containers are removed along with their last element, streams are
+    /// kept even when empty.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Value::String(_) | Value::Stream(_) => false,
+            Value::Hash(hash) => hash.is_empty(),
+            Value::List(list) => list.is_empty(),
+            Value::ZSet(zset) => zset.is_empty(),
+        }
+    }
+
+    pub fn as_string(&self) -> Result<&RespFrame, WrongType> {
+        match self {
+            Value::String(value) => Ok(value),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_hash(&self) -> Result<&HashMap<String, RespFrame>, WrongType> {
+        match self {
+            Value::Hash(hash) => Ok(hash),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_hash_mut(&mut self) -> Result<&mut HashMap<String, RespFrame>, WrongType> {
+        match self {
+            Value::Hash(hash) => Ok(hash),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&VecDeque<RespFrame>, WrongType> {
+        match self {
+            Value::List(list) => Ok(list),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Result<&mut VecDeque<RespFrame>, WrongType> {
+        match self {
+            Value::List(list) => Ok(list),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_zset(&self) -> Result<&SortedSet, WrongType> {
+        match self {
+            Value::ZSet(zset) => Ok(zset),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_zset_mut(&mut self) -> Result<&mut SortedSet, WrongType> {
+        match self {
+            Value::ZSet(zset) => Ok(zset),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_stream(&self) -> Result<&Stream, WrongType> {
+        match self {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_stream_mut(&mut self) -> Result<&mut Stream, WrongType> {
+        match self {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(WrongType),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use super::Backend;
+use std::collections::HashMap;
+
+/// Version of a watched key, only tracked while at least one client watches
+/// the key so that the map does not grow with every key ever written.
+#[derive(Debug, Default)]
+pub(crate) struct KeyVersion {
+    version: u64,
+    watchers: usize,
+}
+
+impl Backend {
+    /// Mark a key as modified, making transactions watching it fail.
+    pub fn touch(&self, key: &str) {
+        if let Some(mut v) = self.db().versions.get_mut(key) {
+            v.version += 1;
+        }
+    }
+
+    /// Mark every watched key of the selected database as modified.
+    pub fn touch_all(&self) {
+        for mut v in self.db().versions.iter_mut() {
+            v.version += 1;
+        }
+    }
+
+    fn watch_key(&self, key: &str) -> u64 {
+        let mut v = self.db().versions.entry(key.to_string()).or_default();
+        v.watchers += 1;
+        v.version
+    }
+
+    fn unwatch_key(&self, key: &str) {
+        if let Some(mut v) = self.db().versions.get_mut(key) {
+            v.watchers -= 1;
+        }
+        self.db().versions.remove_if(key, |_, v| v.watchers == 0);
+    }
+
+    fn key_version(&self, key: &str) -> Option<u64> {
+        self.db().versions.get(key).map(|v| v.version)
+    }
+}
+
+/// The keys a client connection watches, by database, with the version each
+/// had when it was watched. They are released when the connection goes away.
+#[derive(Debug)]
+pub struct Watches {
+    backend: Backend,
+    keys: HashMap<(usize, String), u64>,
+}
+
+impl Watches {
+    pub fn new(backend: &Backend) -> Self {
+        Self {
+            backend: backend.clone(),
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Keys watched from now on belong to the given database.
+    pub fn select(&mut self, db: usize) {
+        self.backend = self.backend.with_db(db);
+    }
+
+    pub fn watch(&mut self, key: String) {
+        let key = (self.backend.db_index(), key);
+        if !self.keys.contains_key(&key) {
+            let version = self.backend.watch_key(&key.1);
+            self.keys.insert(key, version);
+        }
+    }
+
+    /// Whether any watched key was modified since it was watched.
+    pub fn is_dirty(&self) -> bool {
+        self.keys.iter().any(|((db, key), version)| {
+            self.backend.with_db(*db).key_version(key) != Some(*version)
+        })
+    }
+
+    pub fn clear(&mut self) {
+        for ((db, key), _) in self.keys.drain() {
+            self.backend.with_db(db).unwatch_key(&key);
+        }
+    }
+}
+
+impl Drop for Watches {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_touched_key_should_make_watches_dirty() {
+        let backend = Backend::new();
+        let mut watches = Watches::new(&backend);
+        let mut other = Watches::new(&backend);
+        watches.watch("a".to_string());
+        other.watch("a".to_string());
+        assert!(!watches.is_dirty());
+
+        backend.touch("b");
+        assert!(!watches.is_dirty());
+        backend.touch("a");
+        assert!(watches.is_dirty());
+        assert!(other.is_dirty());
+
+        watches.clear();
+        assert!(!watches.is_dirty());
+        assert_eq!(backend.db().versions.get("a").map(|v| v.watchers), Some(1));
+        drop(other);
+        assert!(backend.db().versions.is_empty());
+    }
+
+    #[test]
+    fn test_expired_key_should_be_touched() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::from("1").into());
+        let mut watches = Watches::new(&backend);
+        watches.watch("a".to_string());
+
+        backend
+            .db()
+            .expires
+            .insert("a".to_string(), crate::now_ms() - 1);
+        assert_eq!(backend.get("a"), Ok(None));
+        assert!(watches.is_dirty());
+    }
+
+    #[test]
+    fn test_watches_should_follow_the_selected_database() {
+        let backend = Backend::new();
+        let mut watches = Watches::new(&backend);
+        watches.select(1);
+        watches.watch("a".to_string());
+
+        backend.touch("a");
+        assert!(!watches.is_dirty());
+        backend.select(1).unwrap().touch("a");
+        assert!(watches.is_dirty());
+    }
+}
@@ -0,0 +1,370 @@
+use super::memory::zset_member_size;
+use super::{Backend, Value, WrongType, normalize_range};
+use crate::cmd::SetCondition;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+/// f64 wrapper with a total order so scores can be used as btree keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Score(f64);
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Members indexed by name for score lookups and by (score, member) for
+/// ordered range queries.
+#[derive(Debug, Clone, Default)]
+pub struct SortedSet {
+    members: HashMap<String, f64>,
+    scores: BTreeSet<(Score, String)>,
+}
+
+impl SortedSet {
+    /// Insert or update a member, returns the previous score if any.
+    pub fn insert(&mut self, member: String, score: f64) -> Option<f64> {
+        let old = self.members.insert(member.clone(), score);
+        if let Some(old) = old {
+            self.scores.remove(&(Score(old), member.clone()));
+        }
+        self.scores.insert((Score(score), member));
+        old
+    }
+
+    pub fn remove(&mut self, member: &str) -> bool {
+        match self.members.remove_entry(member) {
+            Some((member, score)) => self.scores.remove(&(Score(score), member)),
+            None => false,
+        }
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.members.get(member).copied()
+    }
+
+    pub fn rank(&self, member: &str) -> Option<usize> {
+        let score = self.score(member)?;
+        Some(
+            self.scores
+                .range(..(Score(score), member.to_string()))
+                .count(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&String, f64)> {
+        self.scores.iter().map(|(score, member)| (member, score.0))
+    }
+
+    pub fn range_by_score(
+        &self,
+        min: Bound<f64>,
+        max: Bound<f64>,
+    ) -> impl Iterator<Item = (&String, f64)> {
+        let start = match min {
+            Bound::Included(min) | Bound::Excluded(min) => {
+                Bound::Included((Score(min), String::new()))
+            }
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        self.scores
+            .range((start, Bound::Unbounded))
+            .skip_while(move |(score, _)| matches!(min, Bound::Excluded(min) if score.0 <= min))
+            .take_while(move |(score, _)| match max {
+                Bound::Included(max) => score.0 <= max,
+                Bound::Excluded(max) => score.0 < max,
+                Bound::Unbounded => true,
+            })
+            .map(|(score, member)| (member, score.0))
+    }
+}
+
+impl Backend {
+    /// Add members to the sorted set, returns the number of added members, or
+    /// the number of added and updated members when `ch` is set.
+    pub fn zadd(
+        &self,
+        key: String,
+        members: Vec<(f64, String)>,
+        condition: Option<SetCondition>,
+        ch: bool,
+    ) -> Result<usize, WrongType> {
+        self.evict_if_expired(&key);
+        let db = self.db();
+        let count = {
+            let mut entry = db.keys.entry(key.clone()).or_insert_with(|| {
+                db.created(key.len());
+                Value::ZSet(SortedSet::default())
+            });
+            let zset = entry.as_zset_mut()?;
+            let mut count = 0;
+            for (score, member) in members {
+                let exists = zset.score(&member);
+                match (condition, exists) {
+                    (Some(SetCondition::Nx), Some(_)) | (Some(SetCondition::Xx), None) => continue,
+                    _ => {}
+                }
+                let size = zset_member_size(&member);
+                match zset.insert(member, score) {
+                    None => {
+                        db.grow(size);
+                        count += 1
+                    }
+                    Some(old) if ch && old != score => count += 1,
+                    _ => {}
+                }
+            }
+            count
+        };
+        self.remove_if_empty(&key);
+        Ok(count)
+    }
+
+    /// Increment the score of a member, returns None if the result is NaN.
+    pub fn zincrby(
+        &self,
+        key: String,
+        increment: f64,
+        member: String,
+    ) -> Result<Option<f64>, WrongType> {
+        self.evict_if_expired(&key);
+        let db = self.db();
+        let score = {
+            let mut entry = db.keys.entry(key.clone()).or_insert_with(|| {
+                db.created(key.len());
+                Value::ZSet(SortedSet::default())
+            });
+            let zset = entry.as_zset_mut()?;
+            let score = zset.score(&member).unwrap_or_default() + increment;
+            if !score.is_nan() {
+                let size = zset_member_size(&member);
+                if zset.insert(member, score).is_none() {
+                    db.grow(size);
+                }
+            }
+            score
+        };
+        self.remove_if_empty(&key);
+        Ok((!score.is_nan()).then_some(score))
+    }
+
+    pub fn zscore(&self, key: &str, member: &str) -> Result<Option<f64>, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(entry) => Ok(entry.as_zset()?.score(member)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn zcard(&self, key: &str) -> Result<usize, WrongType> {
+        self.evict_if_expired(key);
+        match self.db().keys.get(key) {
+            Some(entry) => Ok(entry.as_zset()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    pub fn zrem(&self, key: &str, members: &[String]) -> Result<usize, WrongType> {
+        self.evict_if_expired(key);
+        let db = self.db();
+        let removed = match db.keys.get_mut(key) {
+            Some(mut entry) => {
+                let zset = entry.as_zset_mut()?;
+                let removed = members
+                    .iter()
+                    .filter(|m| zset.remove(m))
+                    .collect::<Vec<_>>();
+                db.shrink(removed.iter().map(|m| zset_member_size(m)).sum());
+                removed.len()
+            }
+            None => 0,
+        };
+        self.remove_if_empty(key);
+        Ok(removed)
+    }
+
+    pub fn zrank(&self, key: &str, member: &str, rev: bool) -> Result<Option<usize>, WrongType> {
+        self.evict_if_expired(key);
+        let Some(entry) = self.db().keys.get(key) else {
+            return Ok(None);
+        };
+        let zset = entry.as_zset()?;
+        Ok(zset
+            .rank(member)
+            .map(|rank| if rev { zset.len() - 1 - rank } else { rank }))
+    }
+
+    pub fn zrange(
+        &self,
+        key: &str,
+        start: i64,
+        stop: i64,
+        rev: bool,
+    ) -> Result<Vec<(String, f64)>, WrongType> {
+        self.evict_if_expired(key);
+        let Some(entry) = self.db().keys.get(key) else {
+            return Ok(vec![]);
+        };
+        let zset = entry.as_zset()?;
+        let Some((start, stop)) = normalize_range(zset.len(), start, stop) else {
+            return Ok(vec![]);
+        };
+        let take = stop - start + 1;
+        let members = zset.iter().map(|(m, s)| (m.clone(), s));
+        if rev {
+            Ok(members.rev().skip(start).take(take).collect())
+        } else {
+            Ok(members.skip(start).take(take).collect())
+        }
+    }
+
+    pub fn zrange_by_score(
+        &self,
+        key: &str,
+        min: Bound<f64>,
+        max: Bound<f64>,
+        limit: Option<(usize, usize)>,
+    ) -> Result<Vec<(String, f64)>, WrongType> {
+        self.evict_if_expired(key);
+        let Some(entry) = self.db().keys.get(key) else {
+            return Ok(vec![]);
+        };
+        let (offset, count) = limit.unwrap_or((0, usize::MAX));
+        Ok(entry
+            .as_zset()?
+            .range_by_score(min, max)
+            .skip(offset)
+            .take(count)
+            .map(|(m, s)| (m.clone(), s))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn members(values: &[(&str, f64)]) -> Vec<(String, f64)> {
+        values.iter().map(|(m, s)| (m.to_string(), *s)).collect()
+    }
+
+    #[test]
+    fn test_sorted_set_should_keep_both_indexes() {
+        let mut zset = SortedSet::default();
+        assert_eq!(zset.insert("a".to_string(), 2.0), None);
+        assert_eq!(zset.insert("b".to_string(), 1.0), None);
+        assert_eq!(zset.insert("c".to_string(), 1.0), None);
+        assert_eq!(zset.insert("a".to_string(), 0.5), Some(2.0));
+
+        let items = zset.iter().map(|(m, s)| (m.clone(), s)).collect::<Vec<_>>();
+        assert_eq!(items, members(&[("a", 0.5), ("b", 1.0), ("c", 1.0)]));
+        assert_eq!(zset.rank("c"), Some(2));
+        assert!(zset.remove("b"));
+        assert_eq!(zset.rank("c"), Some(1));
+        assert_eq!(zset.len(), 2);
+    }
+
+    #[test]
+    fn test_zadd_zrange_should_work() -> Result<()> {
+        let backend = Backend::new();
+        let added = backend.zadd(
+            "z".to_string(),
+            vec![(1.0, "a".to_string()), (2.0, "b".to_string())],
+            None,
+            false,
+        )?;
+        assert_eq!(added, 2);
+
+        let changed = backend.zadd(
+            "z".to_string(),
+            vec![(3.0, "a".to_string()), (4.0, "c".to_string())],
+            Some(SetCondition::Xx),
+            true,
+        )?;
+        assert_eq!(changed, 1);
+        assert_eq!(backend.zcard("z")?, 2);
+
+        assert_eq!(
+            backend.zrange("z", 0, -1, false)?,
+            members(&[("b", 2.0), ("a", 3.0)])
+        );
+        assert_eq!(backend.zrange("z", 0, 0, true)?, members(&[("a", 3.0)]));
+        assert_eq!(backend.zrank("z", "a", false)?, Some(1));
+        assert_eq!(backend.zrank("z", "a", true)?, Some(0));
+        assert_eq!(backend.llen("z"), Err(WrongType));
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrange_by_score_should_respect_bounds() -> Result<()> {
+        let backend = Backend::new();
+        backend.zadd(
+            "z".to_string(),
+            vec![
+                (1.0, "a".to_string()),
+                (2.0, "b".to_string()),
+                (2.0, "c".to_string()),
+                (3.0, "d".to_string()),
+            ],
+            None,
+            false,
+        )?;
+
+        let ret = backend.zrange_by_score("z", Bound::Excluded(1.0), Bound::Included(2.0), None)?;
+        assert_eq!(ret, members(&[("b", 2.0), ("c", 2.0)]));
+
+        let ret =
+            backend.zrange_by_score("z", Bound::Unbounded, Bound::Excluded(3.0), Some((1, 1)))?;
+        assert_eq!(ret, members(&[("b", 2.0)]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_zincrby_zrem_should_work() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(
+            backend.zincrby("z".to_string(), 1.5, "a".to_string())?,
+            Some(1.5)
+        );
+        assert_eq!(
+            backend.zincrby("z".to_string(), 1.0, "a".to_string())?,
+            Some(2.5)
+        );
+        assert_eq!(backend.zscore("z", "a")?, Some(2.5));
+
+        assert_eq!(
+            backend.zincrby("z".to_string(), f64::INFINITY, "b".to_string())?,
+            Some(f64::INFINITY)
+        );
+        assert_eq!(
+            backend.zincrby("z".to_string(), f64::NEG_INFINITY, "b".to_string())?,
+            None
+        );
+
+        assert_eq!(
+            backend.zrem("z", &["a".to_string(), "b".to_string(), "c".to_string()])?,
+            2
+        );
+        assert!(!backend.db().keys.contains_key("z"));
+        Ok(())
+    }
+}
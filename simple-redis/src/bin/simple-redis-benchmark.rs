@@ -0,0 +1,351 @@
+use anyhow::Result;
+use clap::{ArgAction, Parser, ValueEnum};
+use hdrhistogram::Histogram;
+use rand::Rng;
+use simple_redis::RespFrame;
+use simple_redis::client::{Client, Cmd, ConnectionInfo, Pipeline};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// highest latency tracked, in microseconds
+const MAX_LATENCY_US: u64 = 60_000_000;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "simple-redis-benchmark",
+    version,
+    about = "Benchmark a simple-redis server",
+    long_about = None,
+    disable_help_flag = true
+)]
+struct Opts {
+    #[arg(short = 'h', long, default_value = "127.0.0.1")]
+    host: String,
+
+    #[arg(short, long, default_value_t = 6379)]
+    port: u16,
+
+    /// password to AUTH with
+    #[arg(short = 'a', long)]
+    pass: Option<String>,
+
+    /// number of parallel connections
+    #[arg(short, long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+    clients: u32,
+
+    /// total number of requests of each test
+    #[arg(short = 'n', long, default_value_t = 100_000)]
+    requests: usize,
+
+    /// commands sent per round trip
+    #[arg(short = 'P', long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pipeline: u32,
+
+    /// size of the SET, HSET and LPUSH values in bytes
+    #[arg(short, long, default_value_t = 3)]
+    data_size: usize,
+
+    /// keys are picked at random among this many
+    #[arg(short = 'r', long, default_value_t = 10_000, value_parser = clap::value_parser!(u64).range(1..))]
+    keyspace: u64,
+
+    /// tests to run one after the other
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "set,get,hset,lpush"
+    )]
+    tests: Vec<Op>,
+
+    /// run a single test mixing commands by weight instead, e.g. get=80,set=20
+    #[arg(long, conflicts_with = "tests")]
+    mix: Option<Mix>,
+
+    #[arg(long, action = ArgAction::Help)]
+    help: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Op {
+    Get,
+    Set,
+    Hset,
+    Lpush,
+}
+
+/// Commands and their weights.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mix(Vec<(Op, u32)>);
+
+/// A test run, shared by the connections.
+struct Bench {
+    mix: Mix,
+    /// requests not yet claimed by a connection
+    remaining: AtomicUsize,
+    pipeline: usize,
+    keyspace: u64,
+    value: String,
+}
+
+struct Report {
+    requests: usize,
+    errors: usize,
+    elapsed: Duration,
+    /// round trip of each request, in microseconds
+    latency: Histogram<u64>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let tests = match &opts.mix {
+        Some(mix) => vec![mix.clone()],
+        None => opts.tests.iter().map(|op| Mix(vec![(*op, 1)])).collect(),
+    };
+    for mix in tests {
+        let report = run(&opts, mix.clone()).await?;
+        report.print(&mix, &opts);
+    }
+    Ok(())
+}
+
+/// Run a test, every connection is opened before the clock starts.
+async fn run(opts: &Opts, mix: Mix) -> Result<Report> {
+    let info = ConnectionInfo {
+        password: opts.pass.clone(),
+        ..ConnectionInfo::new(format!("{}:{}", opts.host, opts.port))
+    };
+    let mut clients = Vec::with_capacity(opts.clients as usize);
+    for _ in 0..opts.clients {
+        clients.push(Client::connect(info.clone()).await?);
+    }
+
+    let bench = Arc::new(Bench {
+        mix,
+        remaining: AtomicUsize::new(opts.requests),
+        pipeline: opts.pipeline as usize,
+        keyspace: opts.keyspace,
+        value: "x".repeat(opts.data_size),
+    });
+    let start = Instant::now();
+    let handles = clients
+        .into_iter()
+        .map(|client| tokio::spawn(worker(client, bench.clone())))
+        .collect::<Vec<_>>();
+
+    let mut report = Report {
+        requests: opts.requests,
+        errors: 0,
+        elapsed: Duration::ZERO,
+        latency: histogram()?,
+    };
+    for handle in handles {
+        let (errors, latency) = handle.await??;
+        report.errors += errors;
+        report.latency.add(latency)?;
+    }
+    report.elapsed = start.elapsed();
+    Ok(report)
+}
+
+/// Send batches of pipelined requests until none is left, returns the error
+/// replies count and the latencies.
+async fn worker(mut client: Client, bench: Arc<Bench>) -> Result<(usize, Histogram<u64>)> {
+    let mut latency = histogram()?;
+    let mut errors = 0;
+    loop {
+        let n = bench.claim();
+        if n == 0 {
+            return Ok((errors, latency));
+        }
+        let mut pipeline = Pipeline::new();
+        for _ in 0..n {
+            pipeline.add(bench.next_cmd());
+        }
+
+        let start = Instant::now();
+        let replies = pipeline.execute(&mut client).await?;
+        // a request waits for the whole batch
+        latency.saturating_record_n(start.elapsed().as_micros() as u64, n as u64);
+        errors += replies
+            .iter()
+            .filter(|reply| matches!(reply, RespFrame::Error(_)))
+            .count();
+    }
+}
+
+fn histogram() -> Result<Histogram<u64>> {
+    Ok(Histogram::new_with_bounds(1, MAX_LATENCY_US, 3)?)
+}
+
+impl Bench {
+    /// Claim up to a batch of the remaining requests.
+    fn claim(&self) -> usize {
+        let left = self
+            .remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                Some(left - left.min(self.pipeline))
+            })
+            .unwrap_or_else(|left| left);
+        left.min(self.pipeline)
+    }
+
+    fn next_cmd(&self) -> Cmd {
+        let mut rng = rand::rng();
+        let key = rng.random_range(0..self.keyspace);
+        let total = self.mix.0.iter().map(|(_, weight)| weight).sum::<u32>();
+        let mut pick = rng.random_range(0..total);
+        let op = self
+            .mix
+            .0
+            .iter()
+            .find(|(_, weight)| match pick.checked_sub(*weight) {
+                Some(rest) => {
+                    pick = rest;
+                    false
+                }
+                None => true,
+            })
+            .map(|(op, _)| *op)
+            .expect("the pick is below the total weight");
+
+        match op {
+            Op::Get => Cmd::get(&format!("key:{}", key)),
+            Op::Set => Cmd::set(&format!("key:{}", key), &self.value),
+            Op::Hset => Cmd::hset(&format!("hash:{}", key), "field", &self.value),
+            Op::Lpush => Cmd::lpush(&format!("list:{}", key), &self.value),
+        }
+    }
+}
+
+impl Report {
+    fn print(&self, mix: &Mix, opts: &Opts) {
+        let secs = self.elapsed.as_secs_f64();
+        let ms = |us: u64| us as f64 / 1000.0;
+        println!("====== {} ======", mix);
+        println!(
+            "  {} requests completed in {:.2} seconds",
+            self.requests, secs
+        );
+        println!(
+            "  {} parallel clients, pipeline {}, {} bytes payload",
+            opts.clients, opts.pipeline, opts.data_size
+        );
+        if self.errors > 0 {
+            println!("  {} error replies", self.errors);
+        }
+        println!(
+            "  throughput: {:.2} requests per second",
+            self.requests as f64 / secs
+        );
+        println!(
+            "  latency (msec): avg {:.3}, p50 {:.3}, p99 {:.3}, p99.9 {:.3}, max {:.3}",
+            self.latency.mean() / 1000.0,
+            ms(self.latency.value_at_quantile(0.5)),
+            ms(self.latency.value_at_quantile(0.99)),
+            ms(self.latency.value_at_quantile(0.999)),
+            ms(self.latency.max()),
+        );
+        println!();
+    }
+}
+
+impl FromStr for Mix {
+    type Err = String;
+
+    /// `get=80,set=20`, a command without a weight weighs 1.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mix = s
+            .split(',')
+            .map(|part| {
+                let (name, weight) = part.split_once('=').unwrap_or((part, "1"));
+                let op = Op::from_str(name.trim(), true)?;
+                let weight = weight
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid weight of {}: {}", name, weight))?;
+                Ok((op, weight))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if mix.iter().all(|(_, weight)| *weight == 0) {
+            return Err("at least one command must weigh more than 0".to_string());
+        }
+        Ok(Mix(mix))
+    }
+}
+
+impl fmt::Display for Mix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [(op, _)] = self.0.as_slice() else {
+            let parts = self
+                .0
+                .iter()
+                .map(|(op, weight)| format!("{:?}={}", op, weight).to_uppercase())
+                .collect::<Vec<_>>();
+            return write!(f, "MIX {}", parts.join(","));
+        };
+        write!(f, "{}", format!("{:?}", op).to_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simple_redis::{Backend, network};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_mix_from_str() {
+        assert_eq!(
+            "get=80, SET=20,lpush".parse::<Mix>(),
+            Ok(Mix(vec![(Op::Get, 80), (Op::Set, 20), (Op::Lpush, 1)]))
+        );
+        assert!("get=x".parse::<Mix>().is_err());
+        assert!("incr=1".parse::<Mix>().is_err());
+        assert!("get=0".parse::<Mix>().is_err());
+        assert_eq!(
+            "get=80,set=20".parse::<Mix>().unwrap().to_string(),
+            "MIX GET=80,SET=20"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_should_send_every_request() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let backend = Backend::new();
+        let server = backend.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(network::stream_handle(stream, server.clone()));
+            }
+        });
+
+        let opts = Opts::try_parse_from([
+            "simple-redis-benchmark",
+            "-p",
+            &port.to_string(),
+            "-c",
+            "4",
+            "-n",
+            "103",
+            "-P",
+            "5",
+            "-r",
+            "1",
+        ])?;
+        let report = run(&opts, "set=1,lpush=1".parse().unwrap()).await?;
+        assert_eq!(report.latency.len(), 103);
+        assert_eq!(report.errors, 0);
+
+        let mut client = Client::connect(format!("127.0.0.1:{}", port)).await?;
+        let pushed = client.llen("list:0").await?;
+        assert!(pushed > 0 && pushed < 103);
+        assert_eq!(client.get("key:0").await?, Some("xxx".to_string()));
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Result, bail};
+use bytes::BytesMut;
+use simple_redis::{RespDecode, RespError, RespFrame};
+
+/// Split a command line into arguments the way redis-cli does. Double quoted
+/// arguments support escapes such as `\n` or `\x00`, single quoted ones only
+/// `\'`.
+pub fn split_args(line: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut args = vec![];
+    let mut i = 0;
+    loop {
+        while line.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        if i == line.len() {
+            return Ok(args);
+        }
+
+        let mut arg = vec![];
+        while let Some(&c) = line.get(i) {
+            i += 1;
+            match c {
+                b'"' | b'\'' => {
+                    i = read_quoted(line, i, c, &mut arg)?;
+                    if line.get(i).is_some_and(|c| !c.is_ascii_whitespace()) {
+                        bail!("Invalid argument(s): closing quote must be followed by a space");
+                    }
+                    break;
+                }
+                c if c.is_ascii_whitespace() => break,
+                c => arg.push(c),
+            }
+        }
+        args.push(arg);
+    }
+}
+
+/// Read a quoted argument starting after its opening quote, returns the
+/// index after the closing quote.
+fn read_quoted(line: &[u8], mut i: usize, quote: u8, arg: &mut Vec<u8>) -> Result<usize> {
+    while let Some(&c) = line.get(i) {
+        match c {
+            c if c == quote => return Ok(i + 1),
+            b'\\' if quote == b'"' => {
+                if let Some(byte) = hex_escape(&line[i + 1..]) {
+                    arg.push(byte);
+                    i += 4;
+                    continue;
+                }
+                let Some(&escaped) = line.get(i + 1) else {
+                    break;
+                };
+                arg.push(match escaped {
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'b' => 0x08,
+                    b'a' => 0x07,
+                    c => c,
+                });
+                i += 2;
+            }
+            b'\\' if line.get(i + 1) == Some(&b'\'') => {
+                arg.push(b'\'');
+                i += 2;
+            }
+            c => {
+                arg.push(c);
+                i += 1;
+            }
+        }
+    }
+    bail!("Invalid argument(s): unbalanced quotes")
+}
+
+/// The byte of `xHH`.
+fn hex_escape(s: &[u8]) -> Option<u8> {
+    match s {
+        [b'x', h, l, ..] if h.is_ascii_hexdigit() && l.is_ascii_hexdigit() => {
+            u8::from_str_radix(std::str::from_utf8(&[*h, *l]).ok()?, 16).ok()
+        }
+        _ => None,
+    }
+}
+
+/// The commands of a --pipe input, either RESP arrays as redis-cli expects
+/// or a command line per line.
+pub fn split_commands(input: &[u8]) -> Result<Vec<Vec<Vec<u8>>>> {
+    let input = input.trim_ascii_start();
+    if input.first() != Some(&b'*') {
+        return input
+            .split(|c| *c == b'\n')
+            .map(split_args)
+            .filter(|args| !matches!(args, Ok(args) if args.is_empty()))
+            .collect();
+    }
+
+    let mut buf = BytesMut::from(input);
+    let mut cmds = vec![];
+    while !buf.is_empty() {
+        let frame = match RespFrame::decode(&mut buf) {
+            Ok(frame) => frame,
+            Err(RespError::NotComplete) => bail!("Unexpected end of the RESP input"),
+            Err(e) => bail!("Invalid RESP input: {}", e),
+        };
+        let RespFrame::Array(array) = frame else {
+            bail!("Invalid RESP input: a command must be an array");
+        };
+        let args = array
+            .iter()
+            .map(|arg| match arg {
+                RespFrame::BulkString(s) => Ok(s.to_vec()),
+                _ => bail!("Invalid RESP input: arguments must be bulk strings"),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        cmds.push(args);
+        // line breaks between the commands
+        while buf.first().is_some_and(u8::is_ascii_whitespace) {
+            let _ = buf.split_to(1);
+        }
+    }
+    Ok(cmds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: &[&str]) -> Vec<Vec<u8>> {
+        v.iter().map(|s| s.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_split_args() -> Result<()> {
+        assert_eq!(
+            split_args(b"  set  key value ")?,
+            args(&["set", "key", "value"])
+        );
+        assert_eq!(
+            split_args(br#"set "hello world" 'it\'s' """#)?,
+            args(&["set", "hello world", "it's", ""])
+        );
+        assert_eq!(
+            split_args(br#"set k "a\n\"b\"\x41\xzz""#)?,
+            args(&["set", "k", "a\n\"b\"Axzz"])
+        );
+        assert_eq!(split_args(b"   ")?, Vec::<Vec<u8>>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_args_should_reject_unbalanced_quotes() {
+        assert!(split_args(br#"set k "value"#).is_err());
+        assert!(split_args(br#"set k 'value"#).is_err());
+        assert!(split_args(br#"set k "value"x"#).is_err());
+    }
+
+    #[test]
+    fn test_split_commands() -> Result<()> {
+        let cmds = split_commands(b"set a 1\n\nset b \"x y\"\r\n")?;
+        assert_eq!(
+            cmds,
+            vec![args(&["set", "a", "1"]), args(&["set", "b", "x y"])]
+        );
+
+        let cmds = split_commands(
+            b"*3\r\n$3\r\nset\r\n$1\r\na\r\n$1\r\n1\r\n*2\r\n$3\r\nget\r\n$1\r\na\r\n",
+        )?;
+        assert_eq!(cmds, vec![args(&["set", "a", "1"]), args(&["get", "a"])]);
+
+        assert!(split_commands(b"*2\r\n$3\r\nget\r\n").is_err());
+        assert!(split_commands(b"*1\r\n:1\r\n").is_err());
+        Ok(())
+    }
+}
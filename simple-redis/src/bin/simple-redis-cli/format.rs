@@ -0,0 +1,161 @@
+use simple_redis::RespFrame;
+use std::fmt::Write;
+
+/// Render a reply the way redis-cli does on a terminal, e.g. `(integer) 1`,
+/// or a numbered line per item for arrays.
+pub fn format_reply(frame: &RespFrame) -> String {
+    let mut out = String::new();
+    write_reply(&mut out, frame, 0);
+    out
+}
+
+/// Write a reply and a line break, nested lines are indented by `indent`.
+fn write_reply(out: &mut String, frame: &RespFrame, indent: usize) {
+    match frame {
+        RespFrame::SimpleString(s) => out.push_str(s),
+        RespFrame::Error(e) => {
+            let _ = write!(out, "(error) {}", e.as_str());
+        }
+        RespFrame::Integer(i) => {
+            let _ = write!(out, "(integer) {}", i);
+        }
+        RespFrame::Double(f) => {
+            let _ = write!(out, "(double) {}", f);
+        }
+        RespFrame::Boolean(b) => {
+            let _ = write!(out, "({})", b);
+        }
+        RespFrame::BulkString(s) => out.push_str(&quote(s)),
+        RespFrame::NullBulkString(_) | RespFrame::NullArray(_) | RespFrame::Null(_) => {
+            out.push_str("(nil)")
+        }
+        RespFrame::Array(array) => {
+            let items = array.iter().map(|item| (None, item));
+            return write_items(out, items, ')', "(empty array)", indent);
+        }
+        RespFrame::Set(set) => {
+            let items = set.iter().map(|item| (None, item));
+            return write_items(out, items, '~', "(empty set)", indent);
+        }
+        RespFrame::Map(map) => {
+            let items = map.iter().map(|(k, v)| (Some(k.as_str()), v));
+            return write_items(out, items, '#', "(empty hash)", indent);
+        }
+    }
+    out.push('\n');
+}
+
+/// Items numbered as `1) `, the lines of nested items are aligned after the
+/// number.
+fn write_items<'a>(
+    out: &mut String,
+    items: impl ExactSizeIterator<Item = (Option<&'a str>, &'a RespFrame)>,
+    marker: char,
+    empty: &str,
+    indent: usize,
+) {
+    if items.len() == 0 {
+        out.push_str(empty);
+        out.push('\n');
+        return;
+    }
+    let width = items.len().to_string().len();
+    for (i, (key, item)) in items.enumerate() {
+        if i > 0 {
+            out.push_str(&" ".repeat(indent));
+        }
+        let _ = write!(out, "{:>width$}{} ", i + 1, marker);
+        if let Some(key) = key {
+            let _ = write!(out, "{} => ", quote(key.as_bytes()));
+        }
+        write_reply(out, item, indent + width + 2);
+    }
+}
+
+/// A string in double quotes, with quotes, line breaks and non printable
+/// bytes escaped.
+fn quote(s: &[u8]) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for &c in s {
+        match c {
+            b'\\' => out.push_str("\\\\"),
+            b'"' => out.push_str("\\\""),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x07 => out.push_str("\\a"),
+            0x08 => out.push_str("\\b"),
+            c if c == b' ' || c.is_ascii_graphic() => out.push(c as char),
+            c => {
+                let _ = write!(out, "\\x{:02x}", c);
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simple_redis::{
+        BulkString, NullBulkString, RespArray, RespMap, RespSet, SimpleError, SimpleString,
+    };
+
+    fn bulk(s: &[u8]) -> RespFrame {
+        BulkString::new(s.to_vec()).into()
+    }
+
+    #[test]
+    fn test_format_scalars() {
+        assert_eq!(format_reply(&SimpleString::new("OK").into()), "OK\n");
+        assert_eq!(
+            format_reply(&SimpleError::new("ERR boom").into()),
+            "(error) ERR boom\n"
+        );
+        assert_eq!(format_reply(&RespFrame::Integer(-2)), "(integer) -2\n");
+        assert_eq!(format_reply(&RespFrame::Double(1.5)), "(double) 1.5\n");
+        assert_eq!(format_reply(&RespFrame::Boolean(true)), "(true)\n");
+        assert_eq!(format_reply(&NullBulkString.into()), "(nil)\n");
+        assert_eq!(
+            format_reply(&bulk(b"a \"b\"\n\x00\xff")),
+            "\"a \\\"b\\\"\\n\\x00\\xff\"\n"
+        );
+    }
+
+    #[test]
+    fn test_format_aggregates() {
+        let items = (1..=10).map(|i| bulk(i.to_string().as_bytes()));
+        let nested: RespFrame = RespArray::new(vec![
+            RespArray::new(vec![bulk(b"a"), RespFrame::Integer(1)]).into(),
+            RespArray::new(items.collect::<Vec<_>>()).into(),
+            RespArray::new(vec![]).into(),
+        ])
+        .into();
+        let expected = [
+            "1) 1) \"a\"",
+            "   2) (integer) 1",
+            "2)  1) \"1\"",
+            "    2) \"2\"",
+            "    3) \"3\"",
+            "    4) \"4\"",
+            "    5) \"5\"",
+            "    6) \"6\"",
+            "    7) \"7\"",
+            "    8) \"8\"",
+            "    9) \"9\"",
+            "   10) \"10\"",
+            "3) (empty array)",
+            "",
+        ];
+        assert_eq!(format_reply(&nested), expected.join("\n"));
+
+        let mut map = RespMap::new();
+        map.insert("k".to_string(), bulk(b"v"));
+        assert_eq!(format_reply(&map.into()), "1# \"k\" => \"v\"\n");
+        let set = RespSet::new(vec![bulk(b"x")]);
+        assert_eq!(format_reply(&set.into()), "1~ \"x\"\n");
+        assert_eq!(format_reply(&RespSet::new(vec![]).into()), "(empty set)\n");
+    }
+}
@@ -0,0 +1,288 @@
+mod args;
+mod format;
+
+use anyhow::{Context, Result};
+use args::{split_args, split_commands};
+use clap::{ArgAction, Parser};
+use format::format_reply;
+use reedline_repl_rs::reedline::{
+    FileBackedHistory, Prompt, PromptEditMode, PromptHistorySearch, Reedline, Signal,
+};
+use simple_redis::client::{Client, ClientError, Cmd, ConnectionInfo, Pipeline};
+use simple_redis::{BulkString, RespFrame};
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::PathBuf;
+use tokio::runtime::Runtime;
+
+const HISTORY_SIZE: usize = 1024;
+const HISTORY_FILE: &str = ".simple_redis_cli_history";
+/// commands sent per round trip with --pipe
+const PIPE_BATCH: usize = 1000;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "simple-redis-cli",
+    version,
+    about = "Command line interface for simple-redis",
+    long_about = None,
+    disable_help_flag = true
+)]
+struct Opts {
+    #[arg(short = 'h', long, default_value = "127.0.0.1")]
+    host: String,
+
+    #[arg(short, long, default_value_t = 6379)]
+    port: u16,
+
+    /// password to AUTH with
+    #[arg(short = 'a', long)]
+    pass: Option<String>,
+
+    /// user to AUTH with, along with --pass
+    #[arg(long)]
+    user: Option<String>,
+
+    /// database number
+    #[arg(short = 'n', long, default_value_t = 0)]
+    db: usize,
+
+    /// send the commands of a file, or of stdin without a file, as RESP or
+    /// one command line per line
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "-")]
+    pipe: Option<String>,
+
+    /// run a script with EVAL, the arguments are its keys then its ARGV
+    /// after a ",", e.g. `--eval script.rhai key , arg`
+    #[arg(long, value_name = "FILE")]
+    eval: Option<PathBuf>,
+
+    #[arg(long, action = ArgAction::Help)]
+    help: Option<bool>,
+
+    /// a command to run instead of the interactive mode
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+impl Opts {
+    fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            username: self.user.clone(),
+            password: self.pass.clone(),
+            db: self.db,
+            ..ConnectionInfo::new(format!("{}:{}", self.host, self.port))
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let rt = Runtime::new()?;
+    let info = opts.connection_info();
+    let mut client = rt
+        .block_on(Client::connect(info.clone()))
+        .with_context(|| format!("Could not connect to {}", info.addr))?;
+
+    if let Some(path) = &opts.pipe {
+        return rt.block_on(pipe(&mut client, path));
+    }
+    if let Some(path) = &opts.eval {
+        let reply = rt.block_on(eval(&mut client, path, &opts.command))?;
+        print!("{}", format_reply(&reply));
+        return Ok(());
+    }
+    if !opts.command.is_empty() {
+        let args = opts.command.iter().map(|arg| arg.as_bytes().to_vec());
+        let reply = rt.block_on(client.request(to_cmd(args.collect()).into()))?;
+        print!("{}", format_reply(&reply));
+        if is_monitor(&opts.command[0], &reply) {
+            rt.block_on(monitor(&mut client));
+        }
+        return Ok(());
+    }
+
+    let mut session = Session {
+        rt,
+        info,
+        client: Some(client),
+    };
+    session.run()
+}
+
+/// The command of a tokenized line. The server only knows lower case
+/// command names.
+fn to_cmd(args: Vec<Vec<u8>>) -> Cmd {
+    let mut args = args.into_iter();
+    let name = args.next().unwrap_or_default();
+    Cmd::new(&String::from_utf8_lossy(&name).to_ascii_lowercase())
+        .arg(args.map(BulkString::new).collect::<Vec<_>>())
+}
+
+fn is_monitor(name: &str, reply: &RespFrame) -> bool {
+    name.eq_ignore_ascii_case("monitor") && !matches!(reply, RespFrame::Error(_))
+}
+
+/// Print the commands streamed after MONITOR until the connection is closed.
+async fn monitor(client: &mut Client) {
+    while let Ok(frame) = client.receive().await {
+        print!("{}", format_reply(&frame));
+    }
+}
+
+async fn eval(client: &mut Client, path: &PathBuf, args: &[String]) -> Result<RespFrame> {
+    let script = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let (keys, argv) = match args.iter().position(|arg| arg == ",") {
+        Some(i) => (&args[..i], &args[i + 1..]),
+        None => (args, &[][..]),
+    };
+    Ok(client
+        .request(Cmd::eval(&script, keys, argv).into())
+        .await?)
+}
+
+/// Mass insertion, the commands are sent in batches and the error replies
+/// are printed.
+async fn pipe(client: &mut Client, path: &str) -> Result<()> {
+    let input = if path == "-" {
+        let mut input = vec![];
+        std::io::stdin().read_to_end(&mut input)?;
+        input
+    } else {
+        std::fs::read(path).with_context(|| format!("Could not read {}", path))?
+    };
+    let cmds = split_commands(&input)?;
+
+    let (mut replies, mut errors) = (0, 0);
+    for batch in cmds.chunks(PIPE_BATCH) {
+        let mut pipeline = Pipeline::new();
+        for args in batch {
+            pipeline.add(to_cmd(args.clone()));
+        }
+        for reply in pipeline.execute(client).await? {
+            replies += 1;
+            if let RespFrame::Error(e) = reply {
+                errors += 1;
+                eprintln!("{}", e.as_str());
+            }
+        }
+    }
+    println!(
+        "All data transferred. errors: {}, replies: {}",
+        errors, replies
+    );
+    Ok(())
+}
+
+/// The interactive mode, it reconnects on the next command when the
+/// connection is lost.
+struct Session {
+    rt: Runtime,
+    info: ConnectionInfo,
+    client: Option<Client>,
+}
+
+impl Session {
+    fn run(&mut self) -> Result<()> {
+        let mut editor = Reedline::create();
+        if let Some(home) = std::env::var_os("HOME") {
+            let history =
+                FileBackedHistory::with_file(HISTORY_SIZE, PathBuf::from(home).join(HISTORY_FILE))?;
+            editor = editor.with_history(Box::new(history));
+        }
+
+        loop {
+            match editor.read_line(&self.prompt())? {
+                Signal::Success(line) => {
+                    if !self.handle_line(&line) {
+                        return Ok(());
+                    }
+                }
+                Signal::CtrlC => continue,
+                Signal::CtrlD => return Ok(()),
+            }
+        }
+    }
+
+    /// Run a line, false once the session should end.
+    fn handle_line(&mut self, line: &str) -> bool {
+        let args = match split_args(line.as_bytes()) {
+            Ok(args) if args.is_empty() => return true,
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("{}", e);
+                return true;
+            }
+        };
+        let name = String::from_utf8_lossy(&args[0]).to_ascii_lowercase();
+        if name == "quit" || name == "exit" {
+            return false;
+        }
+        let db = match (name.as_str(), args.get(1)) {
+            ("select", Some(db)) => String::from_utf8_lossy(db).parse().ok(),
+            _ => None,
+        };
+
+        match self.request(to_cmd(args)) {
+            Ok(reply) => {
+                if let (Some(db), RespFrame::SimpleString(_)) = (db, &reply) {
+                    self.info.db = db;
+                }
+                print!("{}", format_reply(&reply));
+                if is_monitor(&name, &reply)
+                    && let Some(client) = &mut self.client
+                {
+                    self.rt.block_on(monitor(client));
+                }
+            }
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        true
+    }
+
+    fn request(&mut self, cmd: Cmd) -> Result<RespFrame, ClientError> {
+        let client = match &mut self.client {
+            Some(client) if !client.is_broken() => client,
+            client => client.insert(self.rt.block_on(Client::connect(self.info.clone()))?),
+        };
+        self.rt.block_on(client.request(cmd.into()))
+    }
+
+    fn prompt(&self) -> CliPrompt {
+        let prompt = match (&self.client, self.info.db) {
+            (None, _) => "not connected".to_string(),
+            (Some(_), 0) => self.info.addr.clone(),
+            (Some(_), db) => format!("{}[{}]", self.info.addr, db),
+        };
+        CliPrompt(prompt)
+    }
+}
+
+/// `127.0.0.1:6379> `, with the database when not 0.
+struct CliPrompt(String);
+
+impl Prompt for CliPrompt {
+    fn render_prompt_left(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn render_prompt_right(&self) -> Cow<'_, str> {
+        Cow::Borrowed("")
+    }
+
+    fn render_prompt_indicator(&self, _prompt_mode: PromptEditMode) -> Cow<'_, str> {
+        Cow::Borrowed("> ")
+    }
+
+    fn render_prompt_multiline_indicator(&self) -> Cow<'_, str> {
+        Cow::Borrowed("... ")
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        history_search: PromptHistorySearch,
+    ) -> Cow<'_, str> {
+        Cow::Owned(format!("(reverse-i-search)`{}': ", history_search.term))
+    }
+}
@@ -0,0 +1,414 @@
+use super::{Client, ClientError, FromResp, Pipeline};
+use crate::{BulkString, RespArray, RespFrame};
+use std::collections::HashMap;
+
+/// A stream entry, its id and fields.
+pub type StreamEntry = (String, HashMap<String, String>);
+
+/// A command and its arguments, e.g. `Cmd::new("set").arg("key").arg(1)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cmd {
+    args: Vec<BulkString>,
+}
+
+impl Cmd {
+    pub fn new(name: &str) -> Self {
+        Self {
+            args: vec![BulkString::from(name)],
+        }
+    }
+
+    pub fn arg(mut self, arg: impl ToArgs) -> Self {
+        arg.write_args(&mut self.args);
+        self
+    }
+
+    pub fn eval(script: &str, keys: impl ToArgs, args: impl ToArgs) -> Self {
+        Self::script("eval", script, keys, args)
+    }
+
+    pub fn evalsha(sha: &str, keys: impl ToArgs, args: impl ToArgs) -> Self {
+        Self::script("evalsha", sha, keys, args)
+    }
+
+    /// EVAL or EVALSHA, the keys are preceded by their count.
+    fn script(name: &str, script: &str, keys: impl ToArgs, args: impl ToArgs) -> Self {
+        let mut written = vec![];
+        keys.write_args(&mut written);
+        Cmd::new(name)
+            .arg(script)
+            .arg(written.len())
+            .arg(written)
+            .arg(args)
+    }
+}
+
+impl From<Cmd> for RespFrame {
+    fn from(cmd: Cmd) -> Self {
+        RespArray::new(
+            cmd.args
+                .into_iter()
+                .map(RespFrame::from)
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+}
+
+/// Values usable as command arguments. Sequences write an argument per item
+/// and pairs two arguments, e.g. the field value pairs of XADD.
+pub trait ToArgs {
+    fn write_args(&self, out: &mut Vec<BulkString>);
+}
+
+impl ToArgs for str {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        out.push(BulkString::from(self));
+    }
+}
+
+impl ToArgs for String {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        out.push(BulkString::from(self.as_str()));
+    }
+}
+
+impl ToArgs for BulkString {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        out.push(self.clone());
+    }
+}
+
+macro_rules! integer_to_args {
+    ($($ty:ty),*) => {
+        $(
+            impl ToArgs for $ty {
+                fn write_args(&self, out: &mut Vec<BulkString>) {
+                    out.push(BulkString::from(self.to_string()));
+                }
+            }
+        )*
+    };
+}
+
+integer_to_args!(i32, i64, u16, u32, u64, usize);
+
+/// Written as 1 or 0, e.g. the bit of SETBIT.
+impl ToArgs for bool {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        out.push(BulkString::from(if *self { "1" } else { "0" }));
+    }
+}
+
+impl ToArgs for f64 {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        let s = match *self {
+            f64::INFINITY => "+inf".to_string(),
+            f64::NEG_INFINITY => "-inf".to_string(),
+            f => f.to_string(),
+        };
+        out.push(BulkString::from(s));
+    }
+}
+
+impl<T: ToArgs + ?Sized> ToArgs for &T {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        (**self).write_args(out);
+    }
+}
+
+impl<T: ToArgs> ToArgs for [T] {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        for item in self {
+            item.write_args(out);
+        }
+    }
+}
+
+impl<T: ToArgs, const N: usize> ToArgs for [T; N] {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        self.as_slice().write_args(out);
+    }
+}
+
+impl<T: ToArgs> ToArgs for Vec<T> {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        self.as_slice().write_args(out);
+    }
+}
+
+impl<T: ToArgs> ToArgs for Option<T> {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        if let Some(value) = self {
+            value.write_args(out);
+        }
+    }
+}
+
+impl<A: ToArgs, B: ToArgs> ToArgs for (A, B) {
+    fn write_args(&self, out: &mut Vec<BulkString>) {
+        self.0.write_args(out);
+        self.1.write_args(out);
+    }
+}
+
+/// Define each command as a Cmd constructor, a typed Client method running
+/// it and a Pipeline method queueing it.
+macro_rules! commands {
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty { $($part:expr),+ };
+    )*) => {
+        impl Cmd {
+            $(
+                $(#[$attr])*
+                pub fn $name($($arg: $ty),*) -> Cmd {
+                    Cmd { args: vec![] }$(.arg($part))+
+                }
+            )*
+        }
+
+        impl Client {
+            $(
+                $(#[$attr])*
+                pub async fn $name(&mut self, $($arg: $ty),*) -> Result<$ret, ClientError> {
+                    self.query(Cmd::$name($($arg),*)).await
+                }
+            )*
+
+            pub async fn eval<T: FromResp>(
+                &mut self,
+                script: &str,
+                keys: impl ToArgs,
+                args: impl ToArgs,
+            ) -> Result<T, ClientError> {
+                self.query(Cmd::eval(script, keys, args)).await
+            }
+
+            pub async fn evalsha<T: FromResp>(
+                &mut self,
+                sha: &str,
+                keys: impl ToArgs,
+                args: impl ToArgs,
+            ) -> Result<T, ClientError> {
+                self.query(Cmd::evalsha(sha, keys, args)).await
+            }
+        }
+
+        impl Pipeline {
+            $(
+                $(#[$attr])*
+                pub fn $name(&mut self, $($arg: $ty),*) -> &mut Self {
+                    self.add(Cmd::$name($($arg),*))
+                }
+            )*
+
+            pub fn eval(&mut self, script: &str, keys: impl ToArgs, args: impl ToArgs) -> &mut Self {
+                self.add(Cmd::eval(script, keys, args))
+            }
+
+            pub fn evalsha(&mut self, sha: &str, keys: impl ToArgs, args: impl ToArgs) -> &mut Self {
+                self.add(Cmd::evalsha(sha, keys, args))
+            }
+        }
+    };
+}
+
+commands! {
+    fn get(key: &str) -> Option<String> { "get", key };
+    fn set(key: &str, value: impl ToArgs) -> () { "set", key, value };
+    /// SET with an expire time in seconds.
+    fn set_ex(key: &str, value: impl ToArgs, seconds: u64) -> () { "set", key, value, "ex", seconds };
+    /// SET with an expire time in milliseconds.
+    fn set_px(key: &str, value: impl ToArgs, millis: u64) -> () { "set", key, value, "px", millis };
+    /// SET only if the key does not exist, false if it did.
+    fn set_nx(key: &str, value: impl ToArgs) -> bool { "set", key, value, "nx" };
+    /// SET returning the previous value.
+    fn set_get(key: &str, value: impl ToArgs) -> Option<String> { "set", key, value, "get" };
+    fn incr(key: &str) -> i64 { "incr", key };
+    fn incr_by(key: &str, delta: i64) -> i64 { "incrby", key, delta };
+    fn decr(key: &str) -> i64 { "decr", key };
+    fn decr_by(key: &str, delta: i64) -> i64 { "decrby", key, delta };
+    fn incr_by_float(key: &str, increment: f64) -> f64 { "incrbyfloat", key, increment };
+    /// Append to the string, returns its new length.
+    fn append(key: &str, value: impl ToArgs) -> i64 { "append", key, value };
+    fn strlen(key: &str) -> i64 { "strlen", key };
+    fn getrange(key: &str, start: i64, end: i64) -> String { "getrange", key, start, end };
+    fn setrange(key: &str, offset: usize, value: impl ToArgs) -> i64 { "setrange", key, offset, value };
+    fn getset(key: &str, value: impl ToArgs) -> Option<String> { "getset", key, value };
+    fn getdel(key: &str) -> Option<String> { "getdel", key };
+    fn mget(keys: impl ToArgs) -> Vec<Option<String>> { "mget", keys };
+    /// MSET of several (key, value) pairs.
+    fn mset(pairs: impl ToArgs) -> () { "mset", pairs };
+    /// MSET only if none of the keys exists, false if any did.
+    fn mset_nx(pairs: impl ToArgs) -> bool { "msetnx", pairs };
+    fn setnx(key: &str, value: impl ToArgs) -> bool { "setnx", key, value };
+    /// Returns the previous bit.
+    fn setbit(key: &str, offset: u64, bit: bool) -> bool { "setbit", key, offset, bit };
+    fn getbit(key: &str, offset: u64) -> bool { "getbit", key, offset };
+    fn bitcount(key: &str) -> i64 { "bitcount", key };
+    fn bitop(op: &str, dest: &str, keys: impl ToArgs) -> i64 { "bitop", op, dest, keys };
+    /// Returns whether the estimated cardinality changed.
+    fn pfadd(key: &str, elements: impl ToArgs) -> bool { "pfadd", key, elements };
+    fn pfcount(keys: impl ToArgs) -> i64 { "pfcount", keys };
+    fn pfmerge(dest: &str, sources: impl ToArgs) -> () { "pfmerge", dest, sources };
+    fn hget(key: &str, field: &str) -> Option<String> { "hget", key, field };
+    fn hset(key: &str, field: &str, value: impl ToArgs) -> () { "hset", key, field, value };
+    fn hgetall(key: &str) -> HashMap<String, String> { "hgetall", key };
+    /// The next cursor and a batch of field value pairs.
+    fn hscan(key: &str, cursor: u64) -> (u64, Vec<String>) { "hscan", key, cursor };
+    fn expire(key: &str, seconds: i64) -> bool { "expire", key, seconds };
+    fn pexpire(key: &str, millis: i64) -> bool { "pexpire", key, millis };
+    fn expire_at(key: &str, timestamp: i64) -> bool { "expireat", key, timestamp };
+    fn pexpire_at(key: &str, timestamp: i64) -> bool { "pexpireat", key, timestamp };
+    fn ttl(key: &str) -> i64 { "ttl", key };
+    fn pttl(key: &str) -> i64 { "pttl", key };
+    fn persist(key: &str) -> bool { "persist", key };
+    fn lpush(key: &str, values: impl ToArgs) -> i64 { "lpush", key, values };
+    fn rpush(key: &str, values: impl ToArgs) -> i64 { "rpush", key, values };
+    fn lpop(key: &str) -> Option<String> { "lpop", key };
+    fn rpop(key: &str) -> Option<String> { "rpop", key };
+    fn lpop_count(key: &str, count: usize) -> Vec<String> { "lpop", key, count };
+    fn rpop_count(key: &str, count: usize) -> Vec<String> { "rpop", key, count };
+    /// The key and the value popped, None once the timeout in seconds
+    /// elapsed.
+    fn blpop(keys: impl ToArgs, timeout: f64) -> Option<(String, String)> { "blpop", keys, timeout };
+    fn brpop(keys: impl ToArgs, timeout: f64) -> Option<(String, String)> { "brpop", keys, timeout };
+    fn lrange(key: &str, start: i64, stop: i64) -> Vec<String> { "lrange", key, start, stop };
+    fn llen(key: &str) -> i64 { "llen", key };
+    fn lindex(key: &str, index: i64) -> Option<String> { "lindex", key, index };
+    fn ltrim(key: &str, start: i64, stop: i64) -> () { "ltrim", key, start, stop };
+    fn lrem(key: &str, count: i64, value: impl ToArgs) -> i64 { "lrem", key, count, value };
+    fn zadd(key: &str, score: f64, member: impl ToArgs) -> i64 { "zadd", key, score, member };
+    /// ZADD of several (score, member) pairs.
+    fn zadd_multiple(key: &str, members: impl ToArgs) -> i64 { "zadd", key, members };
+    fn zincrby(key: &str, increment: f64, member: impl ToArgs) -> f64 { "zincrby", key, increment, member };
+    fn zscore(key: &str, member: impl ToArgs) -> Option<f64> { "zscore", key, member };
+    fn zcard(key: &str) -> i64 { "zcard", key };
+    fn zrem(key: &str, members: impl ToArgs) -> i64 { "zrem", key, members };
+    fn zrank(key: &str, member: impl ToArgs) -> Option<i64> { "zrank", key, member };
+    fn zrevrank(key: &str, member: impl ToArgs) -> Option<i64> { "zrevrank", key, member };
+    fn zrange(key: &str, start: i64, stop: i64) -> Vec<String> { "zrange", key, start, stop };
+    fn zrevrange(key: &str, start: i64, stop: i64) -> Vec<String> { "zrevrange", key, start, stop };
+    fn zrange_withscores(key: &str, start: i64, stop: i64) -> Vec<(String, f64)> {
+        "zrange", key, start, stop, "withscores"
+    };
+    fn zrevrange_withscores(key: &str, start: i64, stop: i64) -> Vec<(String, f64)> {
+        "zrevrange", key, start, stop, "withscores"
+    };
+    /// Members with a score between min and max, e.g. `1`, `(1` or `-inf`.
+    fn zrangebyscore(key: &str, min: impl ToArgs, max: impl ToArgs) -> Vec<String> {
+        "zrangebyscore", key, min, max
+    };
+    fn save() -> () { "save" };
+    fn bgsave() -> String { "bgsave" };
+    fn publish(channel: &str, message: impl ToArgs) -> i64 { "publish", channel, message };
+    fn ping() -> String { "ping" };
+    fn config_get(pattern: &str) -> HashMap<String, String> { "config", "get", pattern };
+    fn info(section: &str) -> String { "info", section };
+    /// Switch the database of this connection.
+    fn select(db: i64) -> () { "select", db };
+    fn del(keys: impl ToArgs) -> i64 { "del", keys };
+    fn exists(keys: impl ToArgs) -> i64 { "exists", keys };
+    fn keys(pattern: &str) -> Vec<String> { "keys", pattern };
+    /// The next cursor and a batch of keys, the scan is over once the
+    /// cursor is 0 again.
+    fn scan(cursor: u64) -> (u64, Vec<String>) { "scan", cursor };
+    fn scan_match(cursor: u64, pattern: &str) -> (u64, Vec<String>) { "scan", cursor, "match", pattern };
+    /// TYPE, type being a keyword.
+    fn key_type(key: &str) -> String { "type", key };
+    fn rename(key: &str, new_key: &str) -> () { "rename", key, new_key };
+    fn dbsize() -> i64 { "dbsize" };
+    fn flushdb() -> () { "flushdb" };
+    fn flushall() -> () { "flushall" };
+    /// Add an entry with the field value pairs, returns its id.
+    fn xadd(key: &str, id: &str, fields: impl ToArgs) -> String { "xadd", key, id, fields };
+    fn xadd_maxlen(key: &str, maxlen: usize, id: &str, fields: impl ToArgs) -> String {
+        "xadd", key, "maxlen", maxlen, id, fields
+    };
+    fn xlen(key: &str) -> i64 { "xlen", key };
+    fn xrange(key: &str, start: &str, end: &str) -> Vec<StreamEntry> { "xrange", key, start, end };
+    fn xrevrange(key: &str, end: &str, start: &str) -> Vec<StreamEntry> { "xrevrange", key, end, start };
+    fn xtrim(key: &str, maxlen: usize) -> i64 { "xtrim", key, "maxlen", maxlen };
+    /// The entries of each stream after the given ids, None if there are
+    /// none.
+    fn xread(keys: impl ToArgs, ids: impl ToArgs) -> Option<Vec<(String, Vec<StreamEntry>)>> {
+        "xread", "streams", keys, ids
+    };
+    /// XREAD waiting up to the given milliseconds, 0 waits forever.
+    fn xread_block(block: u64, keys: impl ToArgs, ids: impl ToArgs) -> Option<Vec<(String, Vec<StreamEntry>)>> {
+        "xread", "block", block, "streams", keys, ids
+    };
+    fn xgroup_create(key: &str, group: &str, id: &str) -> () { "xgroup", "create", key, group, id };
+    fn xgroup_create_mkstream(key: &str, group: &str, id: &str) -> () {
+        "xgroup", "create", key, group, id, "mkstream"
+    };
+    fn xgroup_destroy(key: &str, group: &str) -> bool { "xgroup", "destroy", key, group };
+    fn xreadgroup(group: &str, consumer: &str, keys: impl ToArgs, ids: impl ToArgs) -> Option<Vec<(String, Vec<StreamEntry>)>> {
+        "xreadgroup", "group", group, consumer, "streams", keys, ids
+    };
+    fn xack(key: &str, group: &str, ids: impl ToArgs) -> i64 { "xack", key, group, ids };
+    /// The number of pending entries, the smallest and greatest of their
+    /// ids and the number of entries of each consumer.
+    fn xpending(key: &str, group: &str) -> (i64, Option<String>, Option<String>, Vec<(String, i64)>) {
+        "xpending", key, group
+    };
+    fn xclaim(key: &str, group: &str, consumer: &str, min_idle: u64, ids: impl ToArgs) -> Vec<StreamEntry> {
+        "xclaim", key, group, consumer, min_idle, ids
+    };
+    fn script_load(script: &str) -> String { "script", "load", script };
+    fn script_exists(shas: impl ToArgs) -> Vec<bool> { "script", "exists", shas };
+    fn script_flush() -> () { "script", "flush" };
+    fn watch(keys: impl ToArgs) -> () { "watch", keys };
+    fn unwatch() -> () { "unwatch" };
+    fn replicaof(host: &str, port: u16) -> () { "replicaof", host, port };
+    fn replicaof_no_one() -> () { "replicaof", "no", "one" };
+    fn client_id() -> i64 { "client", "id" };
+    /// A line per connection, as `id=3 addr=127.0.0.1:52555 name= ...`.
+    fn client_list() -> String { "client", "list" };
+    fn client_getname() -> Option<String> { "client", "getname" };
+    fn client_setname(name: &str) -> () { "client", "setname", name };
+    /// Close the connection with the id, false if there is none.
+    fn client_kill_id(id: u64) -> bool { "client", "kill", "id", id };
+    fn slowlog_len() -> i64 { "slowlog", "len" };
+    fn slowlog_reset() -> () { "slowlog", "reset" };
+    /// Stream the commands run by every client, read them with receive.
+    fn monitor() -> () { "monitor" };
+    fn cluster_keyslot(key: &str) -> i64 { "cluster", "keyslot", key };
+    /// Let the next command access a slot migrating to the server.
+    fn asking() -> () { "asking" };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmd_args() {
+        let cmd = Cmd::set_ex("k", 1.5, 10);
+        assert_eq!(
+            RespFrame::from(cmd),
+            RespArray::new(vec![
+                BulkString::from("set").into(),
+                BulkString::from("k").into(),
+                BulkString::from("1.5").into(),
+                BulkString::from("ex").into(),
+                BulkString::from("10").into(),
+            ])
+            .into()
+        );
+
+        let cmd = Cmd::xadd("s", "*", [("a", "1"), ("b", "2")]);
+        assert_eq!(cmd, Cmd::new("xadd").arg(["s", "*", "a", "1", "b", "2"]));
+        let cmd = Cmd::zrangebyscore("z", f64::NEG_INFINITY, "(1");
+        assert_eq!(cmd, Cmd::new("zrangebyscore").arg(["z", "-inf", "(1"]));
+    }
+
+    #[test]
+    fn test_eval_cmd_should_count_keys() {
+        let cmd = Cmd::eval("KEYS", ["a", "b"], vec![1]);
+        assert_eq!(cmd, Cmd::new("eval").arg(["KEYS", "2", "a", "b", "1"]));
+        let cmd = Cmd::evalsha("abc", None::<&str>, None::<&str>);
+        assert_eq!(cmd, Cmd::new("evalsha").arg(["abc", "0"]));
+    }
+}
@@ -0,0 +1,313 @@
+mod cmd;
+mod pipeline;
+mod pool;
+mod value;
+
+use crate::RespFrame;
+use crate::network::RespFrameCodec;
+use futures::SinkExt;
+use std::io;
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tokio_stream::StreamExt;
+use tokio_util::codec::Framed;
+
+pub use cmd::{Cmd, StreamEntry, ToArgs};
+pub use pipeline::Pipeline;
+pub use pool::{Pool, PoolBuilder, PoolState, PooledConnection};
+pub use value::FromResp;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+    /// an error reply from the server
+    #[error("{0}")]
+    Server(String),
+    #[error("Unexpected reply: {0}")]
+    UnexpectedReply(String),
+    #[error("Connection closed by the server")]
+    Closed,
+    #[error("Timed out waiting for a connection")]
+    Timeout,
+    #[error("Transaction aborted, a watched key was modified")]
+    TransactionAborted,
+}
+
+impl From<anyhow::Error> for ClientError {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<io::Error>() {
+            Ok(e) => ClientError::Io(e),
+            Err(e) => ClientError::Protocol(e.to_string()),
+        }
+    }
+}
+
+/// Where and as whom to connect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// host:port of the server
+    pub addr: String,
+    /// the default user when not given
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub db: usize,
+}
+
+impl ConnectionInfo {
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            username: None,
+            password: None,
+            db: 0,
+        }
+    }
+}
+
+impl From<&str> for ConnectionInfo {
+    fn from(addr: &str) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl From<String> for ConnectionInfo {
+    fn from(addr: String) -> Self {
+        Self::new(addr)
+    }
+}
+
+/// A connection to a server. Commands are sent one at a time with query or
+/// the typed methods, or batched with a Pipeline.
+#[derive(Debug)]
+pub struct Client {
+    framed: Framed<TcpStream, RespFrameCodec>,
+    /// an I/O or protocol error left the connection unusable
+    broken: bool,
+}
+
+impl Client {
+    /// Connect, authenticate and select the database.
+    pub async fn connect(info: impl Into<ConnectionInfo>) -> Result<Self, ClientError> {
+        let info = info.into();
+        let stream = TcpStream::connect(&info.addr).await?;
+        stream.set_nodelay(true)?;
+        let mut client = Self {
+            framed: Framed::new(stream, RespFrameCodec),
+            broken: false,
+        };
+        if let Some(password) = &info.password {
+            let auth = match &info.username {
+                Some(username) => Cmd::new("auth").arg(username).arg(password),
+                None => Cmd::new("auth").arg(password),
+            };
+            client.query::<()>(auth).await?;
+        }
+        if info.db != 0 {
+            client.select(info.db as i64).await?;
+        }
+        Ok(client)
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    /// Run a command and convert its reply, an error reply is returned as
+    /// ClientError::Server.
+    pub async fn query<T: FromResp>(&mut self, cmd: Cmd) -> Result<T, ClientError> {
+        match self.request(cmd.into()).await? {
+            RespFrame::Error(e) => Err(ClientError::Server(e.0)),
+            frame => T::from_resp(frame),
+        }
+    }
+
+    /// Send a frame and read the reply as is.
+    pub async fn request(&mut self, frame: RespFrame) -> Result<RespFrame, ClientError> {
+        self.send(vec![frame]).await?;
+        self.read().await
+    }
+
+    /// Wait for the next frame pushed by the server, such as the commands
+    /// streamed after MONITOR.
+    pub async fn receive(&mut self) -> Result<RespFrame, ClientError> {
+        self.read().await
+    }
+
+    /// Write all the frames at once.
+    pub(crate) async fn send(&mut self, frames: Vec<RespFrame>) -> Result<(), ClientError> {
+        for frame in frames {
+            if let Err(e) = self.framed.feed(frame).await {
+                self.broken = true;
+                return Err(e.into());
+            }
+        }
+        if let Err(e) = self.framed.flush().await {
+            self.broken = true;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn read(&mut self) -> Result<RespFrame, ClientError> {
+        match self.framed.next().await {
+            Some(Ok(frame)) => Ok(frame),
+            Some(Err(e)) => {
+                self.broken = true;
+                Err(e.into())
+            }
+            None => {
+                self.broken = true;
+                Err(ClientError::Closed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{Backend, network};
+    use anyhow::Result;
+    use std::collections::HashMap;
+    use tokio::net::TcpListener;
+
+    /// Serve a fresh backend on a random port, returns its address.
+    pub(crate) async fn serve() -> Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?.to_string();
+        let backend = Backend::new();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(network::stream_handle(stream, backend.clone()));
+            }
+        });
+        Ok(addr)
+    }
+
+    #[tokio::test]
+    async fn test_client_typed_commands() -> Result<()> {
+        let mut client = Client::connect(serve().await?).await?;
+
+        client.set("hello", "world").await?;
+        assert_eq!(client.get("hello").await?, Some("world".to_string()));
+        assert_eq!(client.get("missing").await?, None);
+        assert!(!client.set_nx("hello", "again").await?);
+
+        assert_eq!(client.incr("n").await?, 1);
+        assert_eq!(client.decr_by("n", 3).await?, -2);
+        assert_eq!(client.incr_by_float("n", 0.5).await?, -1.5);
+        client.mset([("a", "1"), ("b", "2")]).await?;
+        assert!(!client.mset_nx([("b", "3"), ("c", "3")]).await?);
+        assert_eq!(
+            client.mget(["a", "c"]).await?,
+            vec![Some("1".to_string()), None]
+        );
+        assert_eq!(client.append("a", "23").await?, 3);
+        assert_eq!(client.getrange("a", 1, -1).await?, "23");
+        assert_eq!(client.getdel("a").await?, Some("123".to_string()));
+
+        assert!(!client.setbit("bits", 3, true).await?);
+        assert!(client.getbit("bits", 3).await?);
+        assert_eq!(client.bitcount("bits").await?, 1);
+        assert!(client.pfadd("dau", ["alice", "bob"]).await?);
+        assert_eq!(client.pfcount(["dau"]).await?, 2);
+
+        client.hset("map", "a", 1).await?;
+        client.hset("map", "b", 2.5).await?;
+        assert_eq!(client.hget("map", "a").await?, Some("1".to_string()));
+        let all = client.hgetall("map").await?;
+        assert_eq!(
+            all,
+            HashMap::from([
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2.5".to_string())
+            ])
+        );
+
+        assert_eq!(client.rpush("list", ["a", "b", "c"]).await?, 3);
+        assert_eq!(client.lrange("list", 0, -1).await?, vec!["a", "b", "c"]);
+        assert_eq!(client.lpop("list").await?, Some("a".to_string()));
+
+        client.zadd("zset", 1.0, "one").await?;
+        client.zadd("zset", 2.0, "two").await?;
+        assert_eq!(
+            client.zrange_withscores("zset", 0, -1).await?,
+            vec![("one".to_string(), 1.0), ("two".to_string(), 2.0)]
+        );
+        assert_eq!(client.zscore("zset", "two").await?, Some(2.0));
+
+        assert!(client.expire("hello", 100).await?);
+        assert!(client.ttl("hello").await? > 0);
+        assert_eq!(client.key_type("zset").await?, "zset");
+        assert_eq!(client.exists(["hello", "list", "nope"]).await?, 2);
+        assert_eq!(client.del(["hello", "list"]).await?, 2);
+
+        let id = client.xadd("stream", "*", [("f", "v")]).await?;
+        let entries = client.xrange("stream", "-", "+").await?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, id);
+        assert_eq!(entries[0].1["f"], "v");
+
+        let n: i64 = client.eval("ARGV.len()", None::<&str>, [1, 2]).await?;
+        assert_eq!(n, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_should_return_error_replies() -> Result<()> {
+        let mut client = Client::connect(serve().await?).await?;
+        client.set("s", "v").await?;
+        let ret = client.lpush("s", "a").await;
+        assert!(matches!(ret, Err(ClientError::Server(e)) if e.starts_with("WRONGTYPE")));
+        // the connection is still usable
+        assert!(!client.is_broken());
+        assert_eq!(client.ping().await?, "PONG");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_should_receive_monitored_commands() -> Result<()> {
+        let addr = serve().await?;
+        let mut monitor = Client::connect(addr.as_str()).await?;
+        let mut client = Client::connect(addr.as_str()).await?;
+        client.client_setname("app").await?;
+        assert_eq!(client.client_getname().await?, Some("app".to_string()));
+        assert!(client.client_list().await?.contains(" name=app "));
+
+        let id = monitor.client_id().await?;
+        monitor.monitor().await?;
+        client.set("k", "v").await?;
+        let RespFrame::SimpleString(line) = monitor.receive().await? else {
+            panic!("MONITOR should stream simple strings");
+        };
+        assert!(line.ends_with(r#"] "set" "k" "v""#));
+
+        assert!(client.client_kill_id(id as u64).await?);
+        // the kill itself may be streamed before the connection is closed
+        let ret = loop {
+            if let Err(e) = monitor.receive().await {
+                break e;
+            }
+        };
+        assert!(matches!(ret, ClientError::Closed));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_should_select_db() -> Result<()> {
+        let addr = serve().await?;
+        let mut client = Client::connect(addr.as_str()).await?;
+        client.set("k", "db0").await?;
+
+        let info = ConnectionInfo {
+            db: 3,
+            ..ConnectionInfo::new(addr)
+        };
+        let mut client = Client::connect(info).await?;
+        assert_eq!(client.get("k").await?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use super::{Client, ClientError, Cmd, FromResp};
+use crate::{RespArray, RespFrame};
+
+/// Commands sent in a single write, their replies are read once all of them
+/// are written.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    cmds: Vec<Cmd>,
+    /// wrapped in MULTI/EXEC
+    atomic: bool,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run the commands as a transaction.
+    pub fn atomic(&mut self) -> &mut Self {
+        self.atomic = true;
+        self
+    }
+
+    pub fn add(&mut self, cmd: Cmd) -> &mut Self {
+        self.cmds.push(cmd);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.cmds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cmds.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cmds.clear();
+    }
+
+    /// Send the commands and read a reply for each, error replies included.
+    pub async fn execute(&self, client: &mut Client) -> Result<Vec<RespFrame>, ClientError> {
+        let mut frames = self
+            .cmds
+            .iter()
+            .cloned()
+            .map(RespFrame::from)
+            .collect::<Vec<_>>();
+        if self.atomic {
+            frames.insert(0, Cmd::new("multi").into());
+            frames.push(Cmd::new("exec").into());
+        }
+        let n = frames.len();
+        client.send(frames).await?;
+        let mut replies = Vec::with_capacity(n);
+        for _ in 0..n {
+            replies.push(client.read().await?);
+        }
+        if !self.atomic {
+            return Ok(replies);
+        }
+
+        // the replies of MULTI and of the queued commands are only
+        // acknowledgements, or the errors which aborted the transaction
+        match replies.pop() {
+            Some(RespFrame::Array(array)) => Ok(array.0),
+            Some(RespFrame::NullArray(_)) => Err(ClientError::TransactionAborted),
+            Some(RespFrame::Error(e)) => Err(ClientError::Server(e.0)),
+            frame => Err(ClientError::UnexpectedReply(format!("{:?}", frame))),
+        }
+    }
+
+    /// Run the commands and convert their replies as an array, e.g. into a
+    /// tuple. The first error reply is returned as ClientError::Server.
+    pub async fn query<T: FromResp>(&self, client: &mut Client) -> Result<T, ClientError> {
+        let replies = self.execute(client).await?;
+        if let Some(RespFrame::Error(e)) = replies.iter().find(|r| matches!(r, RespFrame::Error(_)))
+        {
+            return Err(ClientError::Server(e.0.clone()));
+        }
+        T::from_resp(RespArray::new(replies).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::tests::serve;
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn test_pipeline_should_batch_commands() -> Result<()> {
+        let mut client = Client::connect(serve().await?).await?;
+        let mut pipe = Pipeline::new();
+        pipe.set("a", 1)
+            .set("b", 2)
+            .get("a")
+            .rpush("list", ["x", "y"]);
+        assert_eq!(pipe.len(), 4);
+
+        let (_, _, a, len): ((), (), String, i64) = pipe.query(&mut client).await?;
+        assert_eq!(a, "1");
+        assert_eq!(len, 2);
+
+        let mut pipe = Pipeline::new();
+        pipe.get("a").lpush("a", "x").get("b");
+        let replies = pipe.execute(&mut client).await?;
+        assert_eq!(replies.len(), 3);
+        assert!(matches!(replies[1], RespFrame::Error(_)));
+        let ret = pipe.query::<Vec<RespFrame>>(&mut client).await;
+        assert!(matches!(ret, Err(ClientError::Server(e)) if e.starts_with("WRONGTYPE")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_atomic_pipeline() -> Result<()> {
+        let addr = serve().await?;
+        let mut client = Client::connect(addr.as_str()).await?;
+        let mut pipe = Pipeline::new();
+        pipe.atomic().set("k", "v").get("k");
+        let (_, v): ((), String) = pipe.query(&mut client).await?;
+        assert_eq!(v, "v");
+
+        // a watched key modified by another client aborts the transaction
+        client.watch("k").await?;
+        Client::connect(addr).await?.set("k", "other").await?;
+        let ret = pipe.execute(&mut client).await;
+        assert!(matches!(ret, Err(ClientError::TransactionAborted)));
+        assert_eq!(client.get("k").await?, Some("other".to_string()));
+        Ok(())
+    }
+}
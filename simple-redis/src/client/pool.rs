@@ -0,0 +1,242 @@
+use super::{Client, ClientError, ConnectionInfo};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A pool of connections to a server, cheap to clone. A connection is taken
+/// with get and goes back to the pool when dropped.
+#[derive(Debug, Clone)]
+pub struct Pool {
+    inner: Arc<PoolInner>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    info: ConnectionInfo,
+    config: PoolBuilder,
+    idle: Mutex<Vec<Client>>,
+    /// a permit per connection in use, at most max_size
+    permits: Arc<Semaphore>,
+}
+
+/// Pool settings, e.g. `Pool::builder().max_size(8).build("127.0.0.1:6379")`.
+#[derive(Debug, Clone)]
+pub struct PoolBuilder {
+    max_size: usize,
+    min_idle: usize,
+    connection_timeout: Duration,
+    test_on_check_out: bool,
+}
+
+impl Default for PoolBuilder {
+    fn default() -> Self {
+        Self {
+            max_size: 10,
+            min_idle: 0,
+            connection_timeout: Duration::from_secs(30),
+            test_on_check_out: true,
+        }
+    }
+}
+
+impl PoolBuilder {
+    /// Most connections open at once.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        assert!(max_size > 0, "max_size must be greater than zero");
+        self.max_size = max_size;
+        self
+    }
+
+    /// Connections opened when the pool is built.
+    pub fn min_idle(mut self, min_idle: usize) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+
+    /// How long get waits for a connection.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = timeout;
+        self
+    }
+
+    /// Check an idle connection with a PING before handing it out.
+    pub fn test_on_check_out(mut self, test: bool) -> Self {
+        self.test_on_check_out = test;
+        self
+    }
+
+    /// Build the pool and open min_idle connections, failing if the server
+    /// can not be reached.
+    pub async fn build(self, info: impl Into<ConnectionInfo>) -> Result<Pool, ClientError> {
+        let pool = self.build_unchecked(info);
+        let mut idle = Vec::with_capacity(pool.inner.config.min_idle);
+        for _ in 0..pool.inner.config.min_idle.min(pool.inner.config.max_size) {
+            idle.push(Client::connect(pool.inner.info.clone()).await?);
+        }
+        *pool.idle() = idle;
+        Ok(pool)
+    }
+
+    /// Build the pool without connecting, connections are opened on demand.
+    pub fn build_unchecked(self, info: impl Into<ConnectionInfo>) -> Pool {
+        Pool {
+            inner: Arc::new(PoolInner {
+                info: info.into(),
+                permits: Arc::new(Semaphore::new(self.max_size)),
+                config: self,
+                idle: Mutex::new(vec![]),
+            }),
+        }
+    }
+}
+
+/// Connections of a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolState {
+    pub connections: usize,
+    pub idle_connections: usize,
+}
+
+impl Pool {
+    pub fn builder() -> PoolBuilder {
+        PoolBuilder::default()
+    }
+
+    /// An idle connection or a new one, waiting for one to be returned if
+    /// max_size connections are in use.
+    pub async fn get(&self) -> Result<PooledConnection, ClientError> {
+        let timeout = self.inner.config.connection_timeout;
+        tokio::time::timeout(timeout, self.checkout())
+            .await
+            .map_err(|_| ClientError::Timeout)?
+    }
+
+    pub fn state(&self) -> PoolState {
+        let in_use = self.inner.config.max_size - self.inner.permits.available_permits();
+        let idle_connections = self.idle().len();
+        PoolState {
+            connections: in_use + idle_connections,
+            idle_connections,
+        }
+    }
+
+    async fn checkout(&self) -> Result<PooledConnection, ClientError> {
+        let permit = self
+            .inner
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        loop {
+            let idle = self.idle().pop();
+            let Some(mut client) = idle else {
+                break;
+            };
+            if !self.inner.config.test_on_check_out || client.ping().await.is_ok() {
+                return Ok(self.wrap(client, permit));
+            }
+        }
+        let client = Client::connect(self.inner.info.clone()).await?;
+        Ok(self.wrap(client, permit))
+    }
+
+    fn wrap(&self, client: Client, permit: OwnedSemaphorePermit) -> PooledConnection {
+        PooledConnection {
+            client: Some(client),
+            pool: self.clone(),
+            _permit: permit,
+        }
+    }
+
+    fn idle(&self) -> std::sync::MutexGuard<'_, Vec<Client>> {
+        self.inner.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A connection taken from a pool, it goes back to the pool when dropped
+/// unless it is broken.
+#[derive(Debug)]
+pub struct PooledConnection {
+    client: Option<Client>,
+    pool: Pool,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for PooledConnection {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().expect("taken on drop only")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().expect("taken on drop only")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take()
+            && !client.is_broken()
+        {
+            self.pool.idle().push(client);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::tests::serve;
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn test_pool_should_reuse_connections() -> Result<()> {
+        let pool = Pool::builder()
+            .max_size(2)
+            .min_idle(1)
+            .build(serve().await?)
+            .await?;
+        assert_eq!(
+            pool.state(),
+            PoolState {
+                connections: 1,
+                idle_connections: 1
+            }
+        );
+
+        {
+            let mut a = pool.get().await?;
+            let mut b = pool.get().await?;
+            a.set("k", "v").await?;
+            assert_eq!(b.get("k").await?, Some("v".to_string()));
+            assert_eq!(pool.state().idle_connections, 0);
+            assert_eq!(pool.state().connections, 2);
+        }
+        assert_eq!(
+            pool.state(),
+            PoolState {
+                connections: 2,
+                idle_connections: 2
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pool_should_time_out_when_exhausted() -> Result<()> {
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_millis(50))
+            .build_unchecked(serve().await?);
+        let conn = pool.get().await?;
+        assert!(matches!(pool.get().await, Err(ClientError::Timeout)));
+        drop(conn);
+        assert!(pool.get().await.is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use super::ClientError;
+use crate::{BulkString, RespFrame};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Conversion of a reply into a rust value.
+pub trait FromResp: Sized {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError>;
+
+    /// The items of an array reply, pairs override it to also accept flat
+    /// arrays such as `[member, score, member, score]`.
+    fn from_resp_items(frames: Vec<RespFrame>) -> Result<Vec<Self>, ClientError> {
+        frames.into_iter().map(Self::from_resp).collect()
+    }
+}
+
+fn unexpected(frame: &RespFrame) -> ClientError {
+    ClientError::UnexpectedReply(format!("{:?}", frame))
+}
+
+fn is_null(frame: &RespFrame) -> bool {
+    matches!(
+        frame,
+        RespFrame::Null(_) | RespFrame::NullArray(_) | RespFrame::NullBulkString(_)
+    )
+}
+
+impl FromResp for RespFrame {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+        Ok(frame)
+    }
+}
+
+/// Any reply, e.g. the OK of SET.
+impl FromResp for () {
+    fn from_resp(_frame: RespFrame) -> Result<Self, ClientError> {
+        Ok(())
+    }
+}
+
+impl FromResp for BulkString {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+        match frame {
+            RespFrame::BulkString(s) => Ok(s),
+            RespFrame::SimpleString(s) => Ok(BulkString::from(s.0)),
+            frame => Err(unexpected(&frame)),
+        }
+    }
+}
+
+impl FromResp for String {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+        match frame {
+            RespFrame::BulkString(s) => {
+                String::from_utf8(s.0).map_err(|e| ClientError::UnexpectedReply(e.to_string()))
+            }
+            RespFrame::SimpleString(s) => Ok(s.0),
+            RespFrame::Integer(i) => Ok(i.to_string()),
+            RespFrame::Double(f) => Ok(f.to_string()),
+            frame => Err(unexpected(&frame)),
+        }
+    }
+}
+
+/// Integers also come as strings, e.g. the cursor of SCAN.
+macro_rules! integer_from_resp {
+    ($($ty:ty),*) => {
+        $(
+            impl FromResp for $ty {
+                fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+                    let n = match &frame {
+                        RespFrame::Integer(i) => (*i).try_into().ok(),
+                        RespFrame::BulkString(s) => std::str::from_utf8(&s.0)
+                            .ok()
+                            .and_then(|s| s.parse().ok()),
+                        RespFrame::Boolean(b) => Some(*b as $ty),
+                        _ => None,
+                    };
+                    n.ok_or_else(|| unexpected(&frame))
+                }
+            }
+        )*
+    };
+}
+
+integer_from_resp!(i32, i64, u32, u64, usize);
+
+impl FromResp for f64 {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+        let n = match &frame {
+            RespFrame::Double(f) => Some(*f),
+            RespFrame::Integer(i) => Some(*i as f64),
+            RespFrame::BulkString(s) => match s.0.as_slice() {
+                b"inf" | b"+inf" => Some(f64::INFINITY),
+                b"-inf" => Some(f64::NEG_INFINITY),
+                s => std::str::from_utf8(s).ok().and_then(|s| s.parse().ok()),
+            },
+            _ => None,
+        };
+        n.ok_or_else(|| unexpected(&frame))
+    }
+}
+
+/// True for a non zero integer or OK, false for a null reply, e.g. SET NX.
+impl FromResp for bool {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+        match frame {
+            RespFrame::Integer(i) => Ok(i != 0),
+            RespFrame::Boolean(b) => Ok(b),
+            RespFrame::SimpleString(_) => Ok(true),
+            frame if is_null(&frame) => Ok(false),
+            frame => Err(unexpected(&frame)),
+        }
+    }
+}
+
+impl<T: FromResp> FromResp for Option<T> {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+        if is_null(&frame) {
+            return Ok(None);
+        }
+        T::from_resp(frame).map(Some)
+    }
+}
+
+/// A null reply is an empty vec.
+impl<T: FromResp> FromResp for Vec<T> {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+        match frame {
+            RespFrame::Array(array) => T::from_resp_items(array.0),
+            RespFrame::Set(set) => T::from_resp_items(set.0),
+            frame if is_null(&frame) => Ok(vec![]),
+            frame => Err(unexpected(&frame)),
+        }
+    }
+}
+
+/// Either a map reply or an array of key value pairs.
+impl<K: FromResp + Eq + Hash, V: FromResp> FromResp for HashMap<K, V> {
+    fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+        match frame {
+            RespFrame::Map(map) => map
+                .0
+                .into_iter()
+                .map(|(k, v)| Ok((K::from_resp(BulkString::from(k).into())?, V::from_resp(v)?)))
+                .collect(),
+            frame if is_null(&frame) => Ok(HashMap::new()),
+            frame => Ok(Vec::<(K, V)>::from_resp(frame)?.into_iter().collect()),
+        }
+    }
+}
+
+macro_rules! tuple_from_resp {
+    ($n:literal; $($name:ident),+) => {
+        impl<$($name: FromResp),+> FromResp for ($($name,)+) {
+            fn from_resp(frame: RespFrame) -> Result<Self, ClientError> {
+                match frame {
+                    RespFrame::Array(array) if array.len() == $n => {
+                        let mut items = array.0.into_iter();
+                        Ok(($($name::from_resp(items.next().expect("length checked"))?,)+))
+                    }
+                    frame => Err(unexpected(&frame)),
+                }
+            }
+
+            fn from_resp_items(frames: Vec<RespFrame>) -> Result<Vec<Self>, ClientError> {
+                // nested arrays, or a flat array to split in chunks
+                if matches!(frames.first(), Some(RespFrame::Array(_)) | None) {
+                    return frames.into_iter().map(Self::from_resp).collect();
+                }
+                if frames.len() % $n != 0 {
+                    return Err(ClientError::UnexpectedReply(format!(
+                        "{} items can not be split in tuples of {}",
+                        frames.len(),
+                        $n
+                    )));
+                }
+                let mut items = frames.into_iter();
+                let mut tuples = vec![];
+                while items.len() > 0 {
+                    tuples.push(($($name::from_resp(items.next().expect("length checked"))?,)+));
+                }
+                Ok(tuples)
+            }
+        }
+    };
+}
+
+tuple_from_resp!(2; A, B);
+tuple_from_resp!(3; A, B, C);
+tuple_from_resp!(4; A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RespArray, RespNull, SimpleString};
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    #[test]
+    fn test_scalar_from_resp() -> Result<(), ClientError> {
+        assert_eq!(String::from_resp(bulk("hello"))?, "hello");
+        assert_eq!(i64::from_resp(RespFrame::Integer(-3))?, -3);
+        assert_eq!(u64::from_resp(bulk("42"))?, 42);
+        assert!(u64::from_resp(RespFrame::Integer(-1)).is_err());
+        assert_eq!(f64::from_resp(bulk("-inf"))?, f64::NEG_INFINITY);
+        assert!(bool::from_resp(SimpleString::new("OK").into())?);
+        assert!(!bool::from_resp(RespFrame::Null(RespNull))?);
+        assert_eq!(
+            Option::<String>::from_resp(RespFrame::Null(RespNull))?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_collections_from_resp() -> Result<(), ClientError> {
+        let flat: RespFrame =
+            RespArray::new(vec![bulk("a"), bulk("1"), bulk("b"), bulk("2")]).into();
+        assert_eq!(
+            Vec::<(String, f64)>::from_resp(flat.clone())?,
+            vec![("a".to_string(), 1.0), ("b".to_string(), 2.0)]
+        );
+        assert_eq!(
+            HashMap::<String, i64>::from_resp(flat)?,
+            HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)])
+        );
+
+        let nested: RespFrame = RespArray::new(vec![
+            RespArray::new(vec![bulk("k"), bulk("v")]).into(),
+            RespArray::new(vec![bulk("x"), bulk("y")]).into(),
+        ])
+        .into();
+        assert_eq!(
+            Vec::<(String, String)>::from_resp(nested)?,
+            vec![
+                ("k".to_string(), "v".to_string()),
+                ("x".to_string(), "y".to_string())
+            ]
+        );
+
+        let odd: RespFrame = RespArray::new(vec![bulk("a"), bulk("1"), bulk("b")]).into();
+        assert!(Vec::<(String, f64)>::from_resp(odd).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Number of hash slots the keys are spread over.
+pub const SLOTS: u16 = 16384;
+
+/// Static topology of the cluster, every node is given the same one.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ClusterConfig {
+    /// id of this node, by default the one listening on the server port
+    #[serde(default)]
+    pub myself: Option<String>,
+    pub nodes: Vec<ClusterNode>,
+    /// slots being moved to another node, served by their owner until the
+    /// keys are gone and by the target after ASKING
+    #[serde(default)]
+    pub migrating: Vec<SlotMigration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ClusterNode {
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub slots: Vec<SlotRange>,
+}
+
+impl ClusterNode {
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SlotMigration {
+    pub slots: SlotRange,
+    /// id of the node the slots move to
+    pub to: String,
+}
+
+/// An inclusive range of slots, written as `0-5460` or a single slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl FromStr for SlotRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        let slot = |n: &str| {
+            n.trim()
+                .parse::<u16>()
+                .ok()
+                .filter(|n| *n < SLOTS)
+                .ok_or_else(|| anyhow!("invalid slot range: {}", s))
+        };
+        let (start, end) = (slot(start)?, slot(end)?);
+        if start > end {
+            return Err(anyhow!("invalid slot range: {}", s));
+        }
+        Ok(SlotRange { start, end })
+    }
+}
+
+impl<'de> Deserialize<'de> for SlotRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Range {
+            Slot(u16),
+            Text(String),
+        }
+
+        match Range::deserialize(deserializer)? {
+            Range::Slot(slot) => slot.to_string(),
+            Range::Text(text) => text,
+        }
+        .parse()
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for SlotRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Why a command is not run by this node.
+#[derive(Error, Debug, PartialEq)]
+pub enum ClusterError {
+    #[error("CROSSSLOT Keys in request don't hash to the same slot")]
+    CrossSlot,
+    #[error("MOVED {0} {1}")]
+    Moved(u16, String),
+    #[error("ASK {0} {1}")]
+    Ask(u16, String),
+    #[error("TRYAGAIN Multiple keys request during rehashing of slot")]
+    TryAgain,
+    #[error("CLUSTERDOWN Hash slot not served")]
+    Down,
+}
+
+/// The cluster as seen by this node, which slots it serves and where the
+/// other ones are.
+#[derive(Debug)]
+pub struct Cluster {
+    nodes: Vec<ClusterNode>,
+    /// index of this node
+    myself: usize,
+    /// index of the node serving each slot
+    owners: Vec<Option<usize>>,
+    /// index of the node each migrating slot moves to
+    migrating: HashMap<u16, usize>,
+}
+
+impl Cluster {
+    /// Check the topology, the port finds this node when its id is not set.
+    pub fn new(config: &ClusterConfig, port: u16) -> Result<Self> {
+        let index = |id: &str| {
+            config
+                .nodes
+                .iter()
+                .position(|node| node.id == id)
+                .ok_or_else(|| anyhow!("unknown cluster node: {}", id))
+        };
+        let myself = match &config.myself {
+            Some(id) => index(id)?,
+            None => config
+                .nodes
+                .iter()
+                .position(|node| node.port == port)
+                .ok_or_else(|| anyhow!("no cluster node listens on port {}", port))?,
+        };
+
+        let mut owners = vec![None; SLOTS as usize];
+        for (i, node) in config.nodes.iter().enumerate() {
+            if config.nodes[..i].iter().any(|other| other.id == node.id) {
+                return Err(anyhow!("duplicate cluster node: {}", node.id));
+            }
+            for range in &node.slots {
+                for slot in range.start..=range.end {
+                    if let Some(owner) = owners[slot as usize].replace(i) {
+                        return Err(anyhow!(
+                            "slot {} is assigned to both {} and {}",
+                            slot,
+                            config.nodes[owner].id,
+                            node.id
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mut migrating = HashMap::new();
+        for migration in &config.migrating {
+            let to = index(&migration.to)?;
+            for slot in migration.slots.start..=migration.slots.end {
+                if owners[slot as usize].is_none_or(|owner| owner == to) {
+                    return Err(anyhow!(
+                        "slot {} can't migrate to {}, it is not served by another node",
+                        slot,
+                        migration.to
+                    ));
+                }
+                migrating.insert(slot, to);
+            }
+        }
+
+        Ok(Self {
+            nodes: config.nodes.clone(),
+            myself,
+            owners,
+            migrating,
+        })
+    }
+
+    pub fn myself(&self) -> &ClusterNode {
+        &self.nodes[self.myself]
+    }
+
+    pub fn nodes(&self) -> &[ClusterNode] {
+        &self.nodes
+    }
+
+    /// The contiguous ranges of assigned slots along with their node.
+    pub fn ranges(&self) -> Vec<(SlotRange, &ClusterNode)> {
+        let mut ranges: Vec<(SlotRange, usize)> = vec![];
+        for (slot, owner) in self.owners.iter().enumerate() {
+            let Some(owner) = *owner else {
+                continue;
+            };
+            let slot = slot as u16;
+            match ranges.last_mut() {
+                Some((range, last)) if *last == owner && range.end + 1 == slot => range.end = slot,
+                _ => ranges.push((
+                    SlotRange {
+                        start: slot,
+                        end: slot,
+                    },
+                    owner,
+                )),
+            }
+        }
+        ranges
+            .into_iter()
+            .map(|(range, owner)| (range, &self.nodes[owner]))
+            .collect()
+    }
+
+    /// Check that this node runs a command on the keys, `asking` is set
+    /// right after ASKING and `exists` tells whether a key is stored here.
+    pub fn route(
+        &self,
+        keys: &[&str],
+        asking: bool,
+        exists: impl Fn(&str) -> bool,
+    ) -> Result<(), ClusterError> {
+        let Some(first) = keys.first() else {
+            return Ok(());
+        };
+        let slot = key_slot(first.as_bytes());
+        if keys.iter().any(|key| key_slot(key.as_bytes()) != slot) {
+            return Err(ClusterError::CrossSlot);
+        }
+        let owner = self.owners[slot as usize].ok_or(ClusterError::Down)?;
+        let migrating = self.migrating.get(&slot).copied();
+
+        if owner != self.myself {
+            return match migrating {
+                Some(to) if to == self.myself && asking => Ok(()),
+                _ => Err(ClusterError::Moved(slot, self.nodes[owner].addr())),
+            };
+        }
+        // keys of a migrating slot which are gone were moved already
+        let Some(to) = migrating else {
+            return Ok(());
+        };
+        match keys.iter().filter(|key| !exists(key)).count() {
+            0 => Ok(()),
+            missing if missing == keys.len() => Err(ClusterError::Ask(slot, self.nodes[to].addr())),
+            _ => Err(ClusterError::TryAgain),
+        }
+    }
+}
+
+/// The slot of a key. Only the part between the first `{` and the next `}`
+/// is hashed if it is not empty, so that related keys share a slot.
+pub fn key_slot(key: &[u8]) -> u16 {
+    let tag = key.iter().position(|&c| c == b'{').and_then(|open| {
+        let rest = &key[open + 1..];
+        rest.iter()
+            .position(|&c| c == b'}')
+            .filter(|&close| close > 0)
+            .map(|close| &rest[..close])
+    });
+    crc16(tag.unwrap_or(key)) % SLOTS
+}
+
+/// CRC16-XMODEM, as used by redis cluster.
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, port: u16, slots: &[&str]) -> ClusterNode {
+        ClusterNode {
+            id: id.to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            slots: slots.iter().map(|s| s.parse().unwrap()).collect(),
+        }
+    }
+
+    fn config() -> ClusterConfig {
+        ClusterConfig {
+            myself: None,
+            nodes: vec![
+                node("a", 7000, &["0-5460"]),
+                node("b", 7001, &["5461-10922"]),
+                node("c", 7002, &["10923-16383"]),
+            ],
+            migrating: vec![],
+        }
+    }
+
+    #[test]
+    fn test_key_slot() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert_eq!(key_slot(b"foo"), 12182);
+        assert_eq!(key_slot(b"bar"), 5061);
+        assert_eq!(key_slot(b"{user1000}.following"), key_slot(b"user1000"));
+        assert_eq!(key_slot(b"foo{bar}{zap}"), key_slot(b"bar"));
+        // empty tags hash the whole key
+        assert_eq!(key_slot(b"foo{}{bar}"), crc16(b"foo{}{bar}") % SLOTS);
+        assert_eq!(key_slot(b"{}"), crc16(b"{}") % SLOTS);
+        assert_eq!(key_slot(b"foo{bar"), crc16(b"foo{bar") % SLOTS);
+    }
+
+    #[test]
+    fn test_slot_range() -> Result<()> {
+        assert_eq!(
+            "0-5460".parse::<SlotRange>()?,
+            SlotRange {
+                start: 0,
+                end: 5460
+            }
+        );
+        assert_eq!("42".parse::<SlotRange>()?, SlotRange { start: 42, end: 42 });
+        assert!("10-5".parse::<SlotRange>().is_err());
+        assert!("0-16384".parse::<SlotRange>().is_err());
+
+        let config: ClusterConfig = serde_yaml::from_str(
+            "myself: b\nnodes:\n  - id: a\n    host: 10.0.0.1\n    port: 7000\n    slots: [0-100, 200]\n  - id: b\n    host: 10.0.0.2\n    port: 7000\n",
+        )?;
+        let cluster = Cluster::new(&config, 6379)?;
+        assert_eq!(cluster.myself().id, "b");
+        let ranges = cluster
+            .ranges()
+            .into_iter()
+            .map(|(range, node)| (range.to_string(), node.id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranges,
+            vec![("0-100".to_string(), "a"), ("200-200".to_string(), "a")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_topology() {
+        let mut config = config();
+        assert!(Cluster::new(&config, 6379).is_err());
+        config.nodes[1].slots.push("5000".parse().unwrap());
+        assert!(Cluster::new(&config, 7000).is_err());
+
+        let mut config = self::config();
+        config.migrating.push(SlotMigration {
+            slots: "0-10".parse().unwrap(),
+            to: "a".to_string(),
+        });
+        assert!(Cluster::new(&config, 7000).is_err());
+    }
+
+    #[test]
+    fn test_route() -> Result<()> {
+        let cluster = Cluster::new(&config(), 7002)?;
+        assert_eq!(cluster.myself().id, "c");
+        assert_eq!(cluster.route(&[], false, |_| true), Ok(()));
+        assert_eq!(cluster.route(&["foo"], false, |_| true), Ok(()));
+        assert_eq!(
+            cluster.route(&["bar"], false, |_| true),
+            Err(ClusterError::Moved(5061, "127.0.0.1:7000".to_string()))
+        );
+        assert_eq!(
+            cluster.route(&["foo", "bar"], false, |_| true),
+            Err(ClusterError::CrossSlot)
+        );
+        assert_eq!(
+            cluster.route(&["{foo}.a", "{foo}.b"], false, |_| true),
+            Ok(())
+        );
+
+        let mut config = config();
+        config.nodes[0].slots.clear();
+        let cluster = Cluster::new(&config, 7002)?;
+        assert_eq!(
+            cluster.route(&["hello"], false, |_| true),
+            Err(ClusterError::Down)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_route_migrating_slot() -> Result<()> {
+        // foo is in slot 12182, moving from c to a
+        let mut config = config();
+        config.migrating.push(SlotMigration {
+            slots: "12000-12999".parse()?,
+            to: "a".to_string(),
+        });
+
+        let owner = Cluster::new(&config, 7002)?;
+        assert_eq!(owner.route(&["foo"], false, |_| true), Ok(()));
+        assert_eq!(
+            owner.route(&["foo"], false, |_| false),
+            Err(ClusterError::Ask(12182, "127.0.0.1:7000".to_string()))
+        );
+        assert_eq!(
+            owner.route(&["{foo}1", "{foo}2"], false, |key| key == "{foo}1"),
+            Err(ClusterError::TryAgain)
+        );
+
+        let target = Cluster::new(&config, 7000)?;
+        assert_eq!(target.route(&["foo"], true, |_| false), Ok(()));
+        assert_eq!(
+            target.route(&["foo"], false, |_| false),
+            Err(ClusterError::Moved(12182, "127.0.0.1:7002".to_string()))
+        );
+        Ok(())
+    }
+}
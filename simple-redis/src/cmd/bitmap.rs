@@ -0,0 +1,293 @@
+use crate::cmd::{
+    BitCountCommand, BitOpCommand, BitOperation, BitPosCommand, BitRange, BitUnit, CommandError,
+    CommandExecutor, GetBitCommand, SetBitCommand, extract_args, extract_int, extract_string,
+    validate_command, validate_variadic_command,
+};
+use crate::{Backend, RespArray, RespFrame};
+
+/// Bitmaps are strings, which are at most 512MB.
+const MAX_BIT_OFFSET: i64 = 512 * 1024 * 1024 * 8;
+
+impl CommandExecutor for SetBitCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.setbit(&self.key, self.offset, self.bit) {
+            Ok(old) => RespFrame::Integer(old as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SetBitCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setbit"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let offset = extract_offset(args.next())?;
+        let bit = match extract_string(args.next(), "bit")?.as_str() {
+            "0" => false,
+            "1" => true,
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "bit is not an integer or out of range".to_string(),
+                ));
+            }
+        };
+        Ok(SetBitCommand { key, offset, bit })
+    }
+}
+
+impl CommandExecutor for GetBitCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.getbit(&self.key, self.offset) {
+            Ok(bit) => RespFrame::Integer(bit as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for GetBitCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getbit"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(GetBitCommand {
+            key: extract_string(args.next(), "key")?,
+            offset: extract_offset(args.next())?,
+        })
+    }
+}
+
+impl CommandExecutor for BitCountCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitcount(&self.key, self.range) {
+            Ok(count) => RespFrame::Integer(count as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BitCountCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["bitcount"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let range = match args.len() {
+            0 => None,
+            2 | 3 => Some(BitRange {
+                start: extract_int(args.next(), "start")?,
+                end: extract_int(args.next(), "end")?,
+                unit: extract_unit(args.next())?,
+            }),
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        Ok(BitCountCommand { key, range })
+    }
+}
+
+impl CommandExecutor for BitPosCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitpos(&self.key, self.bit, self.range, self.end_given) {
+            Ok(pos) => RespFrame::Integer(pos),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BitPosCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["bitpos"], 2)?;
+        if value.len() > 6 {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let bit = match extract_string(args.next(), "bit")?.as_str() {
+            "0" => false,
+            "1" => true,
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "The bit argument must be 1 or 0.".to_string(),
+                ));
+            }
+        };
+        let start = match args.next() {
+            Some(start) => extract_int(Some(start), "start")?,
+            None => 0,
+        };
+        let end_given = args.len() > 0;
+        let end = match args.next() {
+            Some(end) => extract_int(Some(end), "end")?,
+            None => -1,
+        };
+        let range = BitRange {
+            start,
+            end,
+            unit: extract_unit(args.next())?,
+        };
+        Ok(BitPosCommand {
+            key,
+            bit,
+            range,
+            end_given,
+        })
+    }
+}
+
+impl CommandExecutor for BitOpCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitop(self.op, &self.dest, &self.keys) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BitOpCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["bitop"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let op = match extract_string(args.next(), "operation")?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "and" => BitOperation::And,
+            "or" => BitOperation::Or,
+            "xor" => BitOperation::Xor,
+            "not" => BitOperation::Not,
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        let dest = extract_string(args.next(), "destkey")?;
+        let keys = args
+            .map(|key| extract_string(Some(key), "key"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if op == BitOperation::Not && keys.len() != 1 {
+            return Err(CommandError::InvalidArgument(
+                "BITOP NOT must be called with a single source key.".to_string(),
+            ));
+        }
+        Ok(BitOpCommand { op, dest, keys })
+    }
+}
+
+fn extract_offset(frame: Option<RespFrame>) -> Result<usize, CommandError> {
+    extract_int(frame, "offset")
+        .ok()
+        .filter(|offset| (0..MAX_BIT_OFFSET).contains(offset))
+        .map(|offset| offset as usize)
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(
+                "bit offset is not an integer or out of range".to_string(),
+            )
+        })
+}
+
+/// BYTE or BIT, offsets are in bytes by default.
+fn extract_unit(frame: Option<RespFrame>) -> Result<BitUnit, CommandError> {
+    let Some(frame) = frame else {
+        return Ok(BitUnit::Byte);
+    };
+    match extract_string(Some(frame), "unit")?
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "byte" => Ok(BitUnit::Byte),
+        "bit" => Ok(BitUnit::Bit),
+        _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_setbit_getbit_commands() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$6\r\nSETBIT\r\n$1\r\nb\r\n$2\r\n10\r\n$1\r\n1\r\n");
+        let cmd: SetBitCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!((cmd.offset, cmd.bit), (10, true));
+
+        let backend = Backend::new();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd = GetBitCommand {
+            key: "b".to_string(),
+            offset: 10,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        buf.extend_from_slice(b"*4\r\n$6\r\nsetbit\r\n$1\r\nb\r\n$2\r\n-1\r\n$1\r\n1\r\n");
+        let ret: Result<SetBitCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+        buf.extend_from_slice(b"*4\r\n$6\r\nsetbit\r\n$1\r\nb\r\n$1\r\n1\r\n$1\r\n2\r\n");
+        let ret: Result<SetBitCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitcount_bitpos_commands() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$8\r\nbitcount\r\n$1\r\ns\r\n$1\r\n5\r\n$2\r\n30\r\n$3\r\nBIT\r\n",
+        );
+        let cmd: BitCountCommand = RespArray::decode(&mut buf)?.try_into()?;
+        let range = BitRange {
+            start: 5,
+            end: 30,
+            unit: BitUnit::Bit,
+        };
+        assert_eq!(cmd.range, Some(range));
+
+        let backend = Backend::new();
+        backend.set("s".to_string(), BulkString::from("foobar").into());
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(17));
+
+        buf.extend_from_slice(b"*3\r\n$8\r\nbitcount\r\n$1\r\ns\r\n$1\r\n5\r\n");
+        let ret: Result<BitCountCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        buf.extend_from_slice(b"*4\r\n$6\r\nbitpos\r\n$1\r\ns\r\n$1\r\n1\r\n$1\r\n1\r\n");
+        let cmd: BitPosCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert!(!cmd.end_given);
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(9));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitop_command() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\nbitop\r\n$3\r\nNOT\r\n$1\r\nd\r\n$1\r\na\r\n$1\r\nb\r\n",
+        );
+        let ret: Result<BitOpCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\nbitop\r\n$2\r\nor\r\n$1\r\nd\r\n$1\r\na\r\n$1\r\nb\r\n",
+        );
+        let cmd: BitOpCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.op, BitOperation::Or);
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::from("ab").into());
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(backend.get("d"), Ok(Some(BulkString::from("ab").into())));
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use super::{
+    AskingCommand, ClusterAction, ClusterCommand, CommandError, CommandExecutor, extract_args,
+    extract_string, validate_command, validate_variadic_command,
+};
+use crate::cluster::{ClusterNode, key_slot};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespMap, SimpleError};
+
+impl CommandExecutor for ClusterCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let Some(cluster) = &backend.cluster else {
+            return SimpleError::new("ERR This instance has cluster support disabled").into();
+        };
+        match self.action {
+            ClusterAction::Slots => {
+                let slots = cluster
+                    .ranges()
+                    .into_iter()
+                    .map(|(range, node)| {
+                        RespArray::new([
+                            RespFrame::Integer(range.start as i64),
+                            RespFrame::Integer(range.end as i64),
+                            RespArray::new([
+                                BulkString::from(node.host.as_str()).into(),
+                                RespFrame::Integer(node.port as i64),
+                                BulkString::from(node.id.as_str()).into(),
+                            ])
+                            .into(),
+                        ])
+                        .into()
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(slots).into()
+            }
+            // every node is a shard of its own as there are no replicas
+            ClusterAction::Shards => {
+                let ranges = cluster.ranges();
+                let shards = cluster
+                    .nodes()
+                    .iter()
+                    .map(|node| {
+                        let slots = ranges
+                            .iter()
+                            .filter(|(_, owner)| owner.id == node.id)
+                            .flat_map(|(range, _)| [range.start, range.end])
+                            .map(|slot| RespFrame::Integer(slot as i64))
+                            .collect::<Vec<_>>();
+                        let mut shard = RespMap::new();
+                        shard.insert("slots".to_string(), RespArray::new(slots).into());
+                        shard.insert(
+                            "nodes".to_string(),
+                            RespArray::new([shard_node(node)]).into(),
+                        );
+                        shard.into()
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(shards).into()
+            }
+            ClusterAction::KeySlot(key) => RespFrame::Integer(key_slot(key.as_bytes()) as i64),
+        }
+    }
+}
+
+fn shard_node(node: &ClusterNode) -> RespFrame {
+    let mut map = RespMap::new();
+    map.insert("id".to_string(), BulkString::from(node.id.as_str()).into());
+    map.insert("port".to_string(), RespFrame::Integer(node.port as i64));
+    map.insert(
+        "ip".to_string(),
+        BulkString::from(node.host.as_str()).into(),
+    );
+    map.insert(
+        "endpoint".to_string(),
+        BulkString::from(node.host.as_str()).into(),
+    );
+    map.insert("role".to_string(), BulkString::from("master").into());
+    map.insert("replication-offset".to_string(), RespFrame::Integer(0));
+    map.insert("health".to_string(), BulkString::from("online").into());
+    map.into()
+}
+
+impl TryFrom<RespArray> for ClusterCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["cluster"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let subcommand = extract_string(args.next(), "subcommand")?.to_ascii_lowercase();
+        let action = match subcommand.as_str() {
+            "slots" if args.len() == 0 => ClusterAction::Slots,
+            "shards" if args.len() == 0 => ClusterAction::Shards,
+            "keyslot" if args.len() == 1 => {
+                ClusterAction::KeySlot(extract_string(args.next(), "key")?)
+            }
+            "slots" | "shards" | "keyslot" => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "wrong number of arguments for 'cluster|{}' command",
+                    subcommand
+                )));
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "unknown subcommand '{}'",
+                    subcommand
+                )));
+            }
+        };
+
+        Ok(ClusterCommand { action })
+    }
+}
+
+impl CommandExecutor for AskingCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR ASKING is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for AskingCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["asking"], 0)?;
+        Ok(AskingCommand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use crate::cluster::ClusterConfig;
+    use crate::config::ServerConfig;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn cluster_backend() -> Result<Backend> {
+        let cluster: ClusterConfig = serde_yaml::from_str(
+            "nodes:\n  - id: a\n    host: 127.0.0.1\n    port: 7000\n    slots: [0-100, 200-16383]\n  - id: b\n    host: 127.0.0.1\n    port: 7001\n    slots: [101-199]\n",
+        )?;
+        Ok(Backend::with_config(ServerConfig {
+            port: 7000,
+            cluster: Some(cluster),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_cluster_keyslot() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$7\r\ncluster\r\n$7\r\nKEYSLOT\r\n$3\r\nfoo\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: ClusterCommand = frame.try_into()?;
+        assert_eq!(cmd.action, ClusterAction::KeySlot("foo".to_string()));
+
+        let disabled = cmd.execute(&Backend::new());
+        assert!(matches!(disabled, RespFrame::Error(_)));
+        let cmd = ClusterCommand {
+            action: ClusterAction::KeySlot("{foo}bar".to_string()),
+        };
+        assert_eq!(cmd.execute(&cluster_backend()?), RespFrame::Integer(12182));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cluster_slots_and_shards() -> Result<()> {
+        let backend = cluster_backend()?;
+        let cmd = ClusterCommand {
+            action: ClusterAction::Slots,
+        };
+        let RespFrame::Array(slots) = cmd.execute(&backend) else {
+            panic!("CLUSTER SLOTS should reply with an array");
+        };
+        assert_eq!(slots.len(), 3);
+        let expected: RespFrame = RespArray::new([
+            RespFrame::Integer(101),
+            RespFrame::Integer(199),
+            RespArray::new([
+                BulkString::from("127.0.0.1").into(),
+                RespFrame::Integer(7001),
+                BulkString::from("b").into(),
+            ])
+            .into(),
+        ])
+        .into();
+        assert_eq!(slots[1], expected);
+
+        let cmd = ClusterCommand {
+            action: ClusterAction::Shards,
+        };
+        let RespFrame::Array(shards) = cmd.execute(&backend) else {
+            panic!("CLUSTER SHARDS should reply with an array");
+        };
+        let RespFrame::Map(shard) = &shards[0] else {
+            panic!("a shard should be a map");
+        };
+        let slots = [0, 100, 200, 16383].map(RespFrame::Integer);
+        assert_eq!(shard.get("slots"), Some(&RespArray::new(slots).into()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cluster_invalid_subcommand() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$7\r\ncluster\r\n$7\r\nkeyslot\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ClusterCommand::try_from(frame).is_err());
+
+        buf.extend_from_slice(b"*2\r\n$7\r\ncluster\r\n$5\r\nnodes\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let e = ClusterCommand::try_from(frame).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Invalid arguments: unknown subcommand 'nodes'"
+        );
+        Ok(())
+    }
+}
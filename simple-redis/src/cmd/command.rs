@@ -1,6 +1,7 @@
-use crate::{Backend, RespArray, RespError, RespFrame, SimpleString};
+use crate::{Backend, RespArray, RespError, RespFrame, SimpleString, StreamId, parse_int};
 use enum_dispatch::enum_dispatch;
 use lazy_static::lazy_static;
+use std::ops::Bound;
 use thiserror::Error;
 
 lazy_static! {
@@ -17,12 +18,341 @@ pub trait CommandExecutor {
 pub enum Command {
     Get(GetCommand),
     Set(SetCommand),
+    IncrBy(IncrByCommand),
+    IncrByFloat(IncrByFloatCommand),
+    Append(AppendCommand),
+    StrLen(StrLenCommand),
+    GetRange(GetRangeCommand),
+    SetRange(SetRangeCommand),
+    GetSet(GetSetCommand),
+    GetDel(GetDelCommand),
+    MGet(MGetCommand),
+    MSet(MSetCommand),
+    SetNx(SetNxCommand),
+    SetBit(SetBitCommand),
+    GetBit(GetBitCommand),
+    BitCount(BitCountCommand),
+    BitPos(BitPosCommand),
+    BitOp(BitOpCommand),
+    PfAdd(PfAddCommand),
+    PfCount(PfCountCommand),
+    PfMerge(PfMergeCommand),
     HGet(HGetCommand),
     HSet(HSetCommand),
     HGetAll(HGetAllCommand),
+    Expire(ExpireCommand),
+    ExpireAt(ExpireAtCommand),
+    Ttl(TtlCommand),
+    Persist(PersistCommand),
+    Push(PushCommand),
+    Pop(PopCommand),
+    BlockingPop(BlockingPopCommand),
+    LRange(LRangeCommand),
+    LLen(LLenCommand),
+    LIndex(LIndexCommand),
+    LTrim(LTrimCommand),
+    LRem(LRemCommand),
+    ZAdd(ZAddCommand),
+    ZIncrBy(ZIncrByCommand),
+    ZScore(ZScoreCommand),
+    ZCard(ZCardCommand),
+    ZRem(ZRemCommand),
+    ZRank(ZRankCommand),
+    ZRange(ZRangeCommand),
+    ZRangeByScore(ZRangeByScoreCommand),
+    Save(SaveCommand),
+    Publish(PublishCommand),
+    Subscribe(SubscribeCommand),
+    Unsubscribe(UnsubscribeCommand),
+    Ping(PingCommand),
+    Quit(QuitCommand),
+    Hello(HelloCommand),
+    Auth(AuthCommand),
+    ConfigGet(ConfigGetCommand),
+    Multi(MultiCommand),
+    Exec(ExecCommand),
+    Discard(DiscardCommand),
+    Watch(WatchCommand),
+    Unwatch(UnwatchCommand),
+    ReplicaOf(ReplicaOfCommand),
+    ReplConf(ReplConfCommand),
+    PSync(PSyncCommand),
+    Info(InfoCommand),
+    Del(DelCommand),
+    Exists(ExistsCommand),
+    Keys(KeysCommand),
+    Scan(ScanCommand),
+    HScan(HScanCommand),
+    Type(TypeCommand),
+    Rename(RenameCommand),
+    DbSize(DbSizeCommand),
+    Flush(FlushCommand),
+    Select(SelectCommand),
+    XAdd(XAddCommand),
+    XLen(XLenCommand),
+    XRange(XRangeCommand),
+    XTrim(XTrimCommand),
+    XSetId(XSetIdCommand),
+    XRead(XReadCommand),
+    XGroup(XGroupCommand),
+    XReadGroup(XReadGroupCommand),
+    XAck(XAckCommand),
+    XPending(XPendingCommand),
+    XClaim(XClaimCommand),
+    Eval(EvalCommand),
+    Script(ScriptCommand),
+    Client(ClientCommand),
+    Slowlog(SlowlogCommand),
+    Monitor(MonitorCommand),
+    Cluster(ClusterCommand),
+    Asking(AskingCommand),
     Unrecognized(UnrecognizedCommand),
 }
 
+impl Command {
+    /// Whether the command modifies the dataset, such commands are the ones
+    /// written to the append only file.
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Command::Set(_)
+                | Command::IncrBy(_)
+                | Command::IncrByFloat(_)
+                | Command::Append(_)
+                | Command::SetRange(_)
+                | Command::GetSet(_)
+                | Command::GetDel(_)
+                | Command::MSet(_)
+                | Command::SetNx(_)
+                | Command::SetBit(_)
+                | Command::BitOp(_)
+                | Command::PfAdd(_)
+                | Command::PfMerge(_)
+                | Command::HSet(_)
+                | Command::Expire(_)
+                | Command::ExpireAt(_)
+                | Command::Persist(_)
+                | Command::Push(_)
+                | Command::Pop(_)
+                | Command::BlockingPop(_)
+                | Command::LTrim(_)
+                | Command::LRem(_)
+                | Command::ZAdd(_)
+                | Command::ZIncrBy(_)
+                | Command::ZRem(_)
+                | Command::Del(_)
+                | Command::Rename(_)
+                | Command::Flush(_)
+                | Command::XAdd(_)
+                | Command::XTrim(_)
+                | Command::XSetId(_)
+                | Command::XGroup(_)
+                | Command::XReadGroup(_)
+                | Command::XAck(_)
+                | Command::XClaim(_)
+        )
+    }
+
+    /// Whether the command may use more memory, such commands are refused
+    /// once maxmemory is reached and nothing can be evicted.
+    pub fn denies_oom(&self) -> bool {
+        matches!(
+            self,
+            Command::Set(_)
+                | Command::IncrBy(_)
+                | Command::IncrByFloat(_)
+                | Command::Append(_)
+                | Command::SetRange(_)
+                | Command::GetSet(_)
+                | Command::MSet(_)
+                | Command::SetNx(_)
+                | Command::SetBit(_)
+                | Command::BitOp(_)
+                | Command::PfAdd(_)
+                | Command::PfMerge(_)
+                | Command::HSet(_)
+                | Command::Push(_)
+                | Command::ZAdd(_)
+                | Command::ZIncrBy(_)
+                | Command::XAdd(_)
+                | Command::XGroup(_)
+        )
+    }
+
+    /// Whether the command writes several keys which other clients must not
+    /// see half written, or snapshots the whole keyspace, it runs with the
+    /// backend locked exclusively.
+    pub fn writes_atomically(&self) -> bool {
+        matches!(
+            self,
+            Command::MSet(_) | Command::BitOp(_) | Command::PfMerge(_) | Command::Save(_)
+        )
+    }
+
+    /// Whether the command can be run by a connection in pub-sub mode.
+    pub fn is_allowed_when_subscribed(&self) -> bool {
+        matches!(
+            self,
+            Command::Subscribe(_) | Command::Unsubscribe(_) | Command::Ping(_) | Command::Quit(_)
+        )
+    }
+
+    /// Whether a connection in MULTI runs the command right away instead of
+    /// queueing it.
+    pub fn is_transaction_control(&self) -> bool {
+        matches!(
+            self,
+            Command::Multi(_)
+                | Command::Exec(_)
+                | Command::Discard(_)
+                | Command::Watch(_)
+                | Command::Quit(_)
+        )
+    }
+
+    /// Whether the command may wait for data pushed by other clients, such
+    /// commands are not logged as slow.
+    pub fn is_blocking(&self) -> bool {
+        match self {
+            Command::BlockingPop(_) => true,
+            Command::XRead(cmd) => cmd.block.is_some(),
+            Command::XReadGroup(cmd) => cmd.block.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Whether a script can run the command with redis.call, commands tied
+    /// to the connection or the server are not.
+    pub fn is_allowed_in_script(&self) -> bool {
+        !matches!(
+            self,
+            Command::Save(_)
+                | Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::Quit(_)
+                | Command::Hello(_)
+                | Command::Auth(_)
+                | Command::Multi(_)
+                | Command::Exec(_)
+                | Command::Discard(_)
+                | Command::Watch(_)
+                | Command::Unwatch(_)
+                | Command::ReplicaOf(_)
+                | Command::ReplConf(_)
+                | Command::PSync(_)
+                | Command::Select(_)
+                | Command::Eval(_)
+                | Command::Script(_)
+                | Command::Client(_)
+                | Command::Monitor(_)
+                | Command::Asking(_)
+        )
+    }
+
+    /// The keys the command accesses.
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            Command::Get(cmd) => vec![&cmd.key],
+            Command::Set(cmd) => vec![&cmd.key],
+            Command::IncrBy(cmd) => vec![&cmd.key],
+            Command::IncrByFloat(cmd) => vec![&cmd.key],
+            Command::Append(cmd) => vec![&cmd.key],
+            Command::StrLen(cmd) => vec![&cmd.key],
+            Command::GetRange(cmd) => vec![&cmd.key],
+            Command::SetRange(cmd) => vec![&cmd.key],
+            Command::GetSet(cmd) => vec![&cmd.key],
+            Command::GetDel(cmd) => vec![&cmd.key],
+            Command::MGet(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::MSet(cmd) => cmd.pairs.iter().map(|(key, _)| key.as_str()).collect(),
+            Command::SetNx(cmd) => vec![&cmd.key],
+            Command::SetBit(cmd) => vec![&cmd.key],
+            Command::GetBit(cmd) => vec![&cmd.key],
+            Command::BitCount(cmd) => vec![&cmd.key],
+            Command::BitPos(cmd) => vec![&cmd.key],
+            Command::BitOp(cmd) => std::iter::once(&cmd.dest)
+                .chain(&cmd.keys)
+                .map(String::as_str)
+                .collect(),
+            Command::PfAdd(cmd) => vec![&cmd.key],
+            Command::PfCount(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::PfMerge(cmd) => std::iter::once(&cmd.dest)
+                .chain(&cmd.sources)
+                .map(String::as_str)
+                .collect(),
+            Command::HGet(cmd) => vec![&cmd.key],
+            Command::HSet(cmd) => vec![&cmd.key],
+            Command::HGetAll(cmd) => vec![&cmd.key],
+            Command::Expire(cmd) => vec![&cmd.key],
+            Command::ExpireAt(cmd) => vec![&cmd.key],
+            Command::Ttl(cmd) => vec![&cmd.key],
+            Command::Persist(cmd) => vec![&cmd.key],
+            Command::Push(cmd) => vec![&cmd.key],
+            Command::Pop(cmd) => vec![&cmd.key],
+            Command::BlockingPop(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::LRange(cmd) => vec![&cmd.key],
+            Command::LLen(cmd) => vec![&cmd.key],
+            Command::LIndex(cmd) => vec![&cmd.key],
+            Command::LTrim(cmd) => vec![&cmd.key],
+            Command::LRem(cmd) => vec![&cmd.key],
+            Command::ZAdd(cmd) => vec![&cmd.key],
+            Command::ZIncrBy(cmd) => vec![&cmd.key],
+            Command::ZScore(cmd) => vec![&cmd.key],
+            Command::ZCard(cmd) => vec![&cmd.key],
+            Command::ZRem(cmd) => vec![&cmd.key],
+            Command::ZRank(cmd) => vec![&cmd.key],
+            Command::ZRange(cmd) => vec![&cmd.key],
+            Command::ZRangeByScore(cmd) => vec![&cmd.key],
+            Command::Watch(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::Del(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::Exists(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::HScan(cmd) => vec![&cmd.key],
+            Command::Type(cmd) => vec![&cmd.key],
+            Command::Rename(cmd) => vec![&cmd.key, &cmd.new_key],
+            Command::XAdd(cmd) => vec![&cmd.key],
+            Command::XLen(cmd) => vec![&cmd.key],
+            Command::XRange(cmd) => vec![&cmd.key],
+            Command::XTrim(cmd) => vec![&cmd.key],
+            Command::XSetId(cmd) => vec![&cmd.key],
+            Command::XRead(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::XGroup(cmd) => vec![&cmd.key],
+            Command::XReadGroup(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::XAck(cmd) => vec![&cmd.key],
+            Command::XPending(cmd) => vec![&cmd.key],
+            Command::XClaim(cmd) => vec![&cmd.key],
+            Command::Eval(cmd) => cmd.keys.iter().map(String::as_str).collect(),
+            Command::Save(_)
+            | Command::Publish(_)
+            | Command::Subscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::Ping(_)
+            | Command::Quit(_)
+            | Command::Hello(_)
+            | Command::Auth(_)
+            | Command::ConfigGet(_)
+            | Command::Multi(_)
+            | Command::Exec(_)
+            | Command::Discard(_)
+            | Command::Unwatch(_)
+            | Command::ReplicaOf(_)
+            | Command::ReplConf(_)
+            | Command::PSync(_)
+            | Command::Info(_)
+            | Command::Keys(_)
+            | Command::Scan(_)
+            | Command::DbSize(_)
+            | Command::Flush(_)
+            | Command::Select(_)
+            | Command::Script(_)
+            | Command::Client(_)
+            | Command::Slowlog(_)
+            | Command::Monitor(_)
+            | Command::Cluster(_)
+            | Command::Asking(_)
+            | Command::Unrecognized(_) => vec![],
+        }
+    }
+}
+
 impl TryFrom<RespFrame> for Command {
     type Error = CommandError;
 
@@ -43,9 +373,94 @@ impl TryFrom<RespArray> for Command {
             Some(RespFrame::BulkString(cmd)) => match cmd.as_ref() {
                 b"get" => Ok(GetCommand::try_from(v)?.into()),
                 b"set" => Ok(SetCommand::try_from(v)?.into()),
+                b"incr" | b"decr" | b"incrby" | b"decrby" => Ok(IncrByCommand::try_from(v)?.into()),
+                b"incrbyfloat" => Ok(IncrByFloatCommand::try_from(v)?.into()),
+                b"append" => Ok(AppendCommand::try_from(v)?.into()),
+                b"strlen" => Ok(StrLenCommand::try_from(v)?.into()),
+                b"getrange" | b"substr" => Ok(GetRangeCommand::try_from(v)?.into()),
+                b"setrange" => Ok(SetRangeCommand::try_from(v)?.into()),
+                b"getset" => Ok(GetSetCommand::try_from(v)?.into()),
+                b"getdel" => Ok(GetDelCommand::try_from(v)?.into()),
+                b"mget" => Ok(MGetCommand::try_from(v)?.into()),
+                b"mset" | b"msetnx" => Ok(MSetCommand::try_from(v)?.into()),
+                b"setnx" => Ok(SetNxCommand::try_from(v)?.into()),
+                b"setbit" => Ok(SetBitCommand::try_from(v)?.into()),
+                b"getbit" => Ok(GetBitCommand::try_from(v)?.into()),
+                b"bitcount" => Ok(BitCountCommand::try_from(v)?.into()),
+                b"bitpos" => Ok(BitPosCommand::try_from(v)?.into()),
+                b"bitop" => Ok(BitOpCommand::try_from(v)?.into()),
+                b"pfadd" => Ok(PfAddCommand::try_from(v)?.into()),
+                b"pfcount" => Ok(PfCountCommand::try_from(v)?.into()),
+                b"pfmerge" => Ok(PfMergeCommand::try_from(v)?.into()),
                 b"hget" => Ok(HGetCommand::try_from(v)?.into()),
                 b"hset" => Ok(HSetCommand::try_from(v)?.into()),
                 b"hgetall" => Ok(HGetAllCommand::try_from(v)?.into()),
+                b"expire" | b"pexpire" => Ok(ExpireCommand::try_from(v)?.into()),
+                b"expireat" | b"pexpireat" => Ok(ExpireAtCommand::try_from(v)?.into()),
+                b"ttl" | b"pttl" => Ok(TtlCommand::try_from(v)?.into()),
+                b"persist" => Ok(PersistCommand::try_from(v)?.into()),
+                b"lpush" | b"rpush" => Ok(PushCommand::try_from(v)?.into()),
+                b"lpop" | b"rpop" => Ok(PopCommand::try_from(v)?.into()),
+                b"blpop" | b"brpop" => Ok(BlockingPopCommand::try_from(v)?.into()),
+                b"lrange" => Ok(LRangeCommand::try_from(v)?.into()),
+                b"llen" => Ok(LLenCommand::try_from(v)?.into()),
+                b"lindex" => Ok(LIndexCommand::try_from(v)?.into()),
+                b"ltrim" => Ok(LTrimCommand::try_from(v)?.into()),
+                b"lrem" => Ok(LRemCommand::try_from(v)?.into()),
+                b"zadd" => Ok(ZAddCommand::try_from(v)?.into()),
+                b"zincrby" => Ok(ZIncrByCommand::try_from(v)?.into()),
+                b"zscore" => Ok(ZScoreCommand::try_from(v)?.into()),
+                b"zcard" => Ok(ZCardCommand::try_from(v)?.into()),
+                b"zrem" => Ok(ZRemCommand::try_from(v)?.into()),
+                b"zrank" | b"zrevrank" => Ok(ZRankCommand::try_from(v)?.into()),
+                b"zrange" | b"zrevrange" => Ok(ZRangeCommand::try_from(v)?.into()),
+                b"zrangebyscore" => Ok(ZRangeByScoreCommand::try_from(v)?.into()),
+                b"save" | b"bgsave" => Ok(SaveCommand::try_from(v)?.into()),
+                b"publish" => Ok(PublishCommand::try_from(v)?.into()),
+                b"subscribe" | b"psubscribe" => Ok(SubscribeCommand::try_from(v)?.into()),
+                b"unsubscribe" | b"punsubscribe" => Ok(UnsubscribeCommand::try_from(v)?.into()),
+                b"ping" => Ok(PingCommand::try_from(v)?.into()),
+                b"quit" => Ok(QuitCommand::try_from(v)?.into()),
+                b"hello" => Ok(HelloCommand::try_from(v)?.into()),
+                b"auth" => Ok(AuthCommand::try_from(v)?.into()),
+                b"config" => Ok(ConfigGetCommand::try_from(v)?.into()),
+                b"multi" => Ok(MultiCommand::try_from(v)?.into()),
+                b"exec" => Ok(ExecCommand::try_from(v)?.into()),
+                b"discard" => Ok(DiscardCommand::try_from(v)?.into()),
+                b"watch" => Ok(WatchCommand::try_from(v)?.into()),
+                b"unwatch" => Ok(UnwatchCommand::try_from(v)?.into()),
+                b"replicaof" | b"slaveof" => Ok(ReplicaOfCommand::try_from(v)?.into()),
+                b"replconf" => Ok(ReplConfCommand::try_from(v)?.into()),
+                b"psync" => Ok(PSyncCommand::try_from(v)?.into()),
+                b"info" => Ok(InfoCommand::try_from(v)?.into()),
+                b"del" => Ok(DelCommand::try_from(v)?.into()),
+                b"exists" => Ok(ExistsCommand::try_from(v)?.into()),
+                b"keys" => Ok(KeysCommand::try_from(v)?.into()),
+                b"scan" => Ok(ScanCommand::try_from(v)?.into()),
+                b"hscan" => Ok(HScanCommand::try_from(v)?.into()),
+                b"type" => Ok(TypeCommand::try_from(v)?.into()),
+                b"rename" => Ok(RenameCommand::try_from(v)?.into()),
+                b"dbsize" => Ok(DbSizeCommand::try_from(v)?.into()),
+                b"flushdb" | b"flushall" => Ok(FlushCommand::try_from(v)?.into()),
+                b"select" => Ok(SelectCommand::try_from(v)?.into()),
+                b"xadd" => Ok(XAddCommand::try_from(v)?.into()),
+                b"xlen" => Ok(XLenCommand::try_from(v)?.into()),
+                b"xrange" | b"xrevrange" => Ok(XRangeCommand::try_from(v)?.into()),
+                b"xtrim" => Ok(XTrimCommand::try_from(v)?.into()),
+                b"xsetid" => Ok(XSetIdCommand::try_from(v)?.into()),
+                b"xread" => Ok(XReadCommand::try_from(v)?.into()),
+                b"xgroup" => Ok(XGroupCommand::try_from(v)?.into()),
+                b"xreadgroup" => Ok(XReadGroupCommand::try_from(v)?.into()),
+                b"xack" => Ok(XAckCommand::try_from(v)?.into()),
+                b"xpending" => Ok(XPendingCommand::try_from(v)?.into()),
+                b"xclaim" => Ok(XClaimCommand::try_from(v)?.into()),
+                b"eval" | b"evalsha" => Ok(EvalCommand::try_from(v)?.into()),
+                b"script" => Ok(ScriptCommand::try_from(v)?.into()),
+                b"client" => Ok(ClientCommand::try_from(v)?.into()),
+                b"slowlog" => Ok(SlowlogCommand::try_from(v)?.into()),
+                b"monitor" => Ok(MonitorCommand::try_from(v)?.into()),
+                b"cluster" => Ok(ClusterCommand::try_from(v)?.into()),
+                b"asking" => Ok(AskingCommand::try_from(v)?.into()),
                 _ => Ok(UnrecognizedCommand.into()),
             },
             _ => Err(CommandError::InvalidCommand(
@@ -63,6 +478,15 @@ pub enum CommandError {
     #[error("Invalid arguments: {0}")]
     InvalidArgument(String),
 
+    #[error("value is not an integer or out of range")]
+    NotInteger,
+
+    #[error("invalid expire time in '{0}' command")]
+    InvalidExpireTime(&'static str),
+
+    #[error("{0}")]
+    OutOfRange(&'static str),
+
     #[error("{0}")]
     RespError(#[from] RespError),
 
@@ -79,6 +503,169 @@ pub struct GetCommand {
 pub struct SetCommand {
     pub(crate) key: String,
     pub(crate) value: RespFrame,
+    pub(crate) expire: Option<SetExpire>,
+    pub(crate) condition: Option<SetCondition>,
+    pub(crate) get: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetExpire {
+    /// expire after the given milliseconds
+    After(u64),
+    KeepTtl,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetCondition {
+    /// only set the key if it does not already exist
+    Nx,
+    /// only set the key if it already exists
+    Xx,
+}
+
+#[derive(Debug)]
+pub struct IncrByCommand {
+    pub(crate) key: String,
+    /// negative for DECR and DECRBY
+    pub(crate) delta: i64,
+}
+
+#[derive(Debug)]
+pub struct IncrByFloatCommand {
+    pub(crate) key: String,
+    pub(crate) increment: f64,
+}
+
+#[derive(Debug)]
+pub struct AppendCommand {
+    pub(crate) key: String,
+    pub(crate) value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct StrLenCommand {
+    pub(crate) key: String,
+}
+
+#[derive(Debug)]
+pub struct GetRangeCommand {
+    pub(crate) key: String,
+    /// inclusive offsets, negative ones count from the end
+    pub(crate) start: i64,
+    pub(crate) end: i64,
+}
+
+#[derive(Debug)]
+pub struct SetRangeCommand {
+    pub(crate) key: String,
+    pub(crate) offset: usize,
+    pub(crate) value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct GetSetCommand {
+    pub(crate) key: String,
+    pub(crate) value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct GetDelCommand {
+    pub(crate) key: String,
+}
+
+#[derive(Debug)]
+pub struct MGetCommand {
+    pub(crate) keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct MSetCommand {
+    pub(crate) pairs: Vec<(String, RespFrame)>,
+    /// MSETNX, nothing is set if any of the keys exists
+    pub(crate) nx: bool,
+}
+
+#[derive(Debug)]
+pub struct SetNxCommand {
+    pub(crate) key: String,
+    pub(crate) value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct SetBitCommand {
+    pub(crate) key: String,
+    pub(crate) offset: usize,
+    pub(crate) bit: bool,
+}
+
+#[derive(Debug)]
+pub struct GetBitCommand {
+    pub(crate) key: String,
+    pub(crate) offset: usize,
+}
+
+#[derive(Debug)]
+pub struct BitCountCommand {
+    pub(crate) key: String,
+    /// the whole string when not given
+    pub(crate) range: Option<BitRange>,
+}
+
+#[derive(Debug)]
+pub struct BitPosCommand {
+    pub(crate) key: String,
+    pub(crate) bit: bool,
+    /// the whole string by default, `end_given` tells whether the end was
+    /// explicit
+    pub(crate) range: BitRange,
+    pub(crate) end_given: bool,
+}
+
+/// Inclusive offsets of bytes or bits, negative ones count from the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitRange {
+    pub(crate) start: i64,
+    pub(crate) end: i64,
+    pub(crate) unit: BitUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitUnit {
+    Byte,
+    Bit,
+}
+
+#[derive(Debug)]
+pub struct BitOpCommand {
+    pub(crate) op: BitOperation,
+    pub(crate) dest: String,
+    pub(crate) keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOperation {
+    And,
+    Or,
+    Xor,
+    /// takes a single source key
+    Not,
+}
+
+#[derive(Debug)]
+pub struct PfAddCommand {
+    pub(crate) key: String,
+    pub(crate) elements: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct PfCountCommand {
+    pub(crate) keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PfMergeCommand {
+    pub(crate) dest: String,
+    pub(crate) sources: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -97,7 +684,526 @@ pub struct HSetCommand {
 #[derive(Debug)]
 pub struct HGetAllCommand {
     pub(crate) key: String,
-    pub(crate) sort: bool,
+}
+
+#[derive(Debug)]
+pub struct ExpireCommand {
+    pub(crate) key: String,
+    pub(crate) millis: i64,
+}
+
+#[derive(Debug)]
+pub struct ExpireAtCommand {
+    pub(crate) key: String,
+    /// unix time in milliseconds
+    pub(crate) at: i64,
+}
+
+#[derive(Debug)]
+pub struct TtlCommand {
+    pub(crate) key: String,
+    pub(crate) millis: bool,
+}
+
+#[derive(Debug)]
+pub struct PersistCommand {
+    pub(crate) key: String,
+}
+
+#[derive(Debug)]
+pub struct PushCommand {
+    pub(crate) key: String,
+    pub(crate) values: Vec<RespFrame>,
+    pub(crate) left: bool,
+}
+
+#[derive(Debug)]
+pub struct PopCommand {
+    pub(crate) key: String,
+    pub(crate) count: Option<usize>,
+    pub(crate) left: bool,
+}
+
+#[derive(Debug)]
+pub struct BlockingPopCommand {
+    pub(crate) keys: Vec<String>,
+    /// seconds to block, 0 blocks forever
+    pub(crate) timeout: f64,
+    pub(crate) left: bool,
+}
+
+#[derive(Debug)]
+pub struct LRangeCommand {
+    pub(crate) key: String,
+    pub(crate) start: i64,
+    pub(crate) stop: i64,
+}
+
+#[derive(Debug)]
+pub struct LLenCommand {
+    pub(crate) key: String,
+}
+
+#[derive(Debug)]
+pub struct LIndexCommand {
+    pub(crate) key: String,
+    pub(crate) index: i64,
+}
+
+#[derive(Debug)]
+pub struct LTrimCommand {
+    pub(crate) key: String,
+    pub(crate) start: i64,
+    pub(crate) stop: i64,
+}
+
+#[derive(Debug)]
+pub struct LRemCommand {
+    pub(crate) key: String,
+    pub(crate) count: i64,
+    pub(crate) value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct ZAddCommand {
+    pub(crate) key: String,
+    pub(crate) members: Vec<(f64, String)>,
+    pub(crate) condition: Option<SetCondition>,
+    /// count changed members as well as added ones
+    pub(crate) ch: bool,
+}
+
+#[derive(Debug)]
+pub struct ZIncrByCommand {
+    pub(crate) key: String,
+    pub(crate) increment: f64,
+    pub(crate) member: String,
+}
+
+#[derive(Debug)]
+pub struct ZScoreCommand {
+    pub(crate) key: String,
+    pub(crate) member: String,
+}
+
+#[derive(Debug)]
+pub struct ZCardCommand {
+    pub(crate) key: String,
+}
+
+#[derive(Debug)]
+pub struct ZRemCommand {
+    pub(crate) key: String,
+    pub(crate) members: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ZRankCommand {
+    pub(crate) key: String,
+    pub(crate) member: String,
+    pub(crate) rev: bool,
+}
+
+#[derive(Debug)]
+pub struct ZRangeCommand {
+    pub(crate) key: String,
+    pub(crate) start: i64,
+    pub(crate) stop: i64,
+    pub(crate) rev: bool,
+    pub(crate) with_scores: bool,
+}
+
+#[derive(Debug)]
+pub struct ZRangeByScoreCommand {
+    pub(crate) key: String,
+    pub(crate) min: Bound<f64>,
+    pub(crate) max: Bound<f64>,
+    pub(crate) with_scores: bool,
+    /// offset and count
+    pub(crate) limit: Option<(usize, usize)>,
+}
+
+#[derive(Debug)]
+pub struct SaveCommand {
+    pub(crate) background: bool,
+}
+
+#[derive(Debug)]
+pub struct PublishCommand {
+    pub(crate) channel: String,
+    pub(crate) message: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct SubscribeCommand {
+    pub(crate) channels: Vec<String>,
+    /// PSUBSCRIBE, the channels are glob patterns
+    pub(crate) pattern: bool,
+}
+
+#[derive(Debug)]
+pub struct UnsubscribeCommand {
+    /// unsubscribe from everything when empty
+    pub(crate) channels: Vec<String>,
+    pub(crate) pattern: bool,
+}
+
+#[derive(Debug)]
+pub struct PingCommand {
+    pub(crate) message: Option<RespFrame>,
+}
+
+#[derive(Debug)]
+pub struct QuitCommand;
+
+#[derive(Debug)]
+pub struct HelloCommand {
+    pub(crate) protocol: Option<i64>,
+    /// username and password
+    pub(crate) auth: Option<(String, String)>,
+    pub(crate) name: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct AuthCommand {
+    /// the default user when not given
+    pub(crate) username: Option<String>,
+    pub(crate) password: String,
+}
+
+#[derive(Debug)]
+pub struct ConfigGetCommand {
+    pub(crate) patterns: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct MultiCommand;
+
+#[derive(Debug)]
+pub struct ExecCommand;
+
+#[derive(Debug)]
+pub struct DiscardCommand;
+
+#[derive(Debug)]
+pub struct WatchCommand {
+    pub(crate) keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct UnwatchCommand;
+
+#[derive(Debug)]
+pub struct ReplicaOfCommand {
+    /// host and port, None for REPLICAOF NO ONE
+    pub(crate) master: Option<(String, u16)>,
+}
+
+#[derive(Debug)]
+pub struct ReplConfCommand {
+    /// lowercase option names and their values
+    pub(crate) options: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct PSyncCommand {
+    pub(crate) replid: String,
+    pub(crate) offset: i64,
+}
+
+#[derive(Debug)]
+pub struct InfoCommand {
+    pub(crate) section: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ClientCommand {
+    pub(crate) action: ClientAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientAction {
+    List,
+    Id,
+    GetName,
+    SetName(String),
+    Kill {
+        filters: Vec<ClientKillFilter>,
+        /// CLIENT KILL addr, replies OK instead of the number of clients
+        legacy: bool,
+    },
+}
+
+/// A connection is killed when it matches all the filters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientKillFilter {
+    Id(u64),
+    Addr(String),
+    User(String),
+    /// whether the connection running the command is spared, the default
+    SkipMe(bool),
+}
+
+#[derive(Debug)]
+pub struct SlowlogCommand {
+    pub(crate) action: SlowlogAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowlogAction {
+    /// the number of entries, 10 by default and all of them when negative
+    Get(Option<i64>),
+    Len,
+    Reset,
+}
+
+#[derive(Debug)]
+pub struct MonitorCommand;
+
+#[derive(Debug)]
+pub struct ClusterCommand {
+    pub(crate) action: ClusterAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClusterAction {
+    Slots,
+    Shards,
+    KeySlot(String),
+}
+
+#[derive(Debug)]
+pub struct AskingCommand;
+
+#[derive(Debug)]
+pub struct DelCommand {
+    pub(crate) keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ExistsCommand {
+    /// a key given several times is counted several times
+    pub(crate) keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct KeysCommand {
+    pub(crate) pattern: String,
+}
+
+#[derive(Debug)]
+pub struct ScanCommand {
+    pub(crate) cursor: u64,
+    pub(crate) pattern: Option<String>,
+    pub(crate) count: usize,
+    pub(crate) key_type: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct HScanCommand {
+    pub(crate) key: String,
+    pub(crate) cursor: u64,
+    pub(crate) pattern: Option<String>,
+    pub(crate) count: usize,
+    /// reply with the field names only
+    pub(crate) no_values: bool,
+}
+
+#[derive(Debug)]
+pub struct TypeCommand {
+    pub(crate) key: String,
+}
+
+#[derive(Debug)]
+pub struct RenameCommand {
+    pub(crate) key: String,
+    pub(crate) new_key: String,
+}
+
+#[derive(Debug)]
+pub struct DbSizeCommand;
+
+#[derive(Debug)]
+pub struct FlushCommand {
+    /// FLUSHALL, every database instead of the selected one
+    pub(crate) all: bool,
+}
+
+#[derive(Debug)]
+pub struct SelectCommand {
+    pub(crate) db: i64,
+}
+
+#[derive(Debug)]
+pub struct XAddCommand {
+    pub(crate) key: String,
+    pub(crate) id: XAddId,
+    pub(crate) fields: Vec<(String, RespFrame)>,
+    /// do not create the stream if it does not exist
+    pub(crate) no_mkstream: bool,
+    pub(crate) trim: Option<StreamTrim>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XAddId {
+    /// `*`, generated from the current time
+    Auto,
+    /// `<ms>-*`, the sequence number is generated
+    Partial(u64),
+    Explicit(StreamId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamTrim {
+    /// keep the newest entries only
+    MaxLen(usize),
+    /// remove the entries with a smaller id
+    MinId(StreamId),
+}
+
+#[derive(Debug)]
+pub struct XLenCommand {
+    pub(crate) key: String,
+}
+
+#[derive(Debug)]
+pub struct XRangeCommand {
+    pub(crate) key: String,
+    pub(crate) start: Bound<StreamId>,
+    pub(crate) end: Bound<StreamId>,
+    pub(crate) count: Option<usize>,
+    /// XREVRANGE, from end to start
+    pub(crate) rev: bool,
+}
+
+#[derive(Debug)]
+pub struct XTrimCommand {
+    pub(crate) key: String,
+    pub(crate) trim: StreamTrim,
+}
+
+#[derive(Debug)]
+pub struct XSetIdCommand {
+    pub(crate) key: String,
+    pub(crate) id: StreamId,
+}
+
+#[derive(Debug)]
+pub struct XReadCommand {
+    pub(crate) keys: Vec<String>,
+    /// read the entries after these ids, None for `$`
+    pub(crate) ids: Vec<Option<StreamId>>,
+    pub(crate) count: Option<usize>,
+    /// milliseconds to block, 0 blocks forever
+    pub(crate) block: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct XGroupCommand {
+    pub(crate) key: String,
+    pub(crate) group: String,
+    pub(crate) action: XGroupAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XGroupAction {
+    /// the group starts after the id, None for `$`
+    Create {
+        id: Option<StreamId>,
+        mkstream: bool,
+    },
+    Destroy,
+}
+
+#[derive(Debug)]
+pub struct XReadGroupCommand {
+    pub(crate) group: String,
+    pub(crate) consumer: String,
+    pub(crate) keys: Vec<String>,
+    /// the pending entries of the consumer after these ids, None for `>`
+    /// which reads entries never delivered to the group
+    pub(crate) ids: Vec<Option<StreamId>>,
+    pub(crate) count: Option<usize>,
+    pub(crate) block: Option<u64>,
+    /// do not add the entries to the pending entries list
+    pub(crate) no_ack: bool,
+}
+
+#[derive(Debug)]
+pub struct XAckCommand {
+    pub(crate) key: String,
+    pub(crate) group: String,
+    pub(crate) ids: Vec<StreamId>,
+}
+
+#[derive(Debug)]
+pub struct XPendingCommand {
+    pub(crate) key: String,
+    pub(crate) group: String,
+    /// the summary of the pending entries when None
+    pub(crate) range: Option<XPendingRange>,
+}
+
+#[derive(Debug)]
+pub struct XPendingRange {
+    /// only the entries idle for at least these milliseconds
+    pub(crate) idle: Option<u64>,
+    pub(crate) start: Bound<StreamId>,
+    pub(crate) end: Bound<StreamId>,
+    pub(crate) count: usize,
+    pub(crate) consumer: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct XClaimCommand {
+    pub(crate) key: String,
+    pub(crate) group: String,
+    pub(crate) consumer: String,
+    /// only claim the entries idle for at least these milliseconds
+    pub(crate) min_idle: u64,
+    pub(crate) ids: Vec<StreamId>,
+    pub(crate) options: ClaimOptions,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClaimOptions {
+    /// set the idle time of the claimed entries
+    pub idle: Option<u64>,
+    /// set the delivery time (unix milliseconds) of the claimed entries
+    pub time: Option<u64>,
+    /// set the delivery count of the claimed entries
+    pub retry_count: Option<u64>,
+    /// create the pending entries missing from the list
+    pub force: bool,
+    /// reply with the ids only, the delivery count is left as is
+    pub just_id: bool,
+}
+
+#[derive(Debug)]
+pub struct EvalCommand {
+    pub(crate) script: ScriptSource,
+    pub(crate) keys: Vec<String>,
+    pub(crate) args: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptSource {
+    /// EVAL, the script itself
+    Body(String),
+    /// EVALSHA, the digest of a cached script
+    Sha(String),
+}
+
+#[derive(Debug)]
+pub struct ScriptCommand {
+    pub(crate) action: ScriptAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptAction {
+    Load(String),
+    Exists(Vec<String>),
+    Flush,
 }
 
 #[derive(Debug)]
@@ -122,6 +1228,26 @@ pub fn validate_command(
         )));
     }
 
+    validate_names(value, names)
+}
+
+pub fn validate_variadic_command(
+    value: &RespArray,
+    names: &[&'static str],
+    min_args: usize,
+) -> Result<(), CommandError> {
+    if value.len() < min_args + names.len() {
+        return Err(CommandError::InvalidArgument(format!(
+            "{} command must have at least {} argument",
+            names.join(" "),
+            min_args
+        )));
+    }
+
+    validate_names(value, names)
+}
+
+fn validate_names(value: &RespArray, names: &[&'static str]) -> Result<(), CommandError> {
     for (i, name) in names.iter().enumerate() {
         match value[i] {
             RespFrame::BulkString(ref cmd) => {
@@ -143,10 +1269,41 @@ pub fn validate_command(
     Ok(())
 }
 
+/// Check whether the command name (case insensitive) is `name`, used by
+/// commands sharing one struct for several variants such as LPUSH/RPUSH.
+pub fn is_command(value: &RespArray, name: &str) -> bool {
+    matches!(value.first(), Some(RespFrame::BulkString(cmd)) if cmd.eq_ignore_ascii_case(name.as_bytes()))
+}
+
 pub fn extract_args(value: RespArray, start: usize) -> Result<Vec<RespFrame>, CommandError> {
     Ok(value.0.into_iter().skip(start).collect::<Vec<RespFrame>>())
 }
 
+pub fn extract_string(frame: Option<RespFrame>, name: &str) -> Result<String, CommandError> {
+    match frame {
+        Some(RespFrame::BulkString(s)) => Ok(String::from_utf8(s.0)?),
+        _ => Err(CommandError::InvalidArgument(format!("Invalid {}", name))),
+    }
+}
+
+pub fn extract_bytes(frame: Option<RespFrame>, name: &str) -> Result<Vec<u8>, CommandError> {
+    match frame {
+        Some(RespFrame::BulkString(s)) => Ok(s.0),
+        _ => Err(CommandError::InvalidArgument(format!("Invalid {}", name))),
+    }
+}
+
+pub fn extract_int(frame: Option<RespFrame>, name: &str) -> Result<i64, CommandError> {
+    parse_int(extract_string(frame, name)?.as_bytes())
+        .ok_or_else(|| CommandError::InvalidArgument(format!("{} is not an integer", name)))
+}
+
+pub fn extract_float(frame: Option<RespFrame>, name: &str) -> Result<f64, CommandError> {
+    extract_string(frame, name)?
+        .parse()
+        .map_err(|_| CommandError::InvalidArgument(format!("{} is not a valid float", name)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
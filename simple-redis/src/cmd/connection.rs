@@ -0,0 +1,380 @@
+use crate::cmd::{
+    AuthCommand, ClientAction, ClientCommand, ClientKillFilter, CommandError, CommandExecutor,
+    HelloCommand, MonitorCommand, PingCommand, QuitCommand, RESP_OK, SelectCommand, extract_args,
+    extract_int, extract_string, validate_command, validate_variadic_command,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespMap, SimpleError, SimpleString};
+
+impl PingCommand {
+    /// A subscribed connection gets the pong as a pushed `["pong", message]`.
+    pub fn execute_subscribed(self) -> RespFrame {
+        let message = self.message.unwrap_or_else(|| BulkString::from("").into());
+        RespArray::new([BulkString::from("pong").into(), message]).into()
+    }
+}
+
+impl CommandExecutor for PingCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self.message {
+            Some(message) => message,
+            None => SimpleString::new("PONG").into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PingCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["ping"], 0)?;
+        if value.len() > 2 {
+            return Err(CommandError::InvalidArgument(
+                "ping command must have at most 1 argument".to_string(),
+            ));
+        }
+        let message = extract_args(value, 1)?.into_iter().next();
+        Ok(PingCommand { message })
+    }
+}
+
+impl CommandExecutor for QuitCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for QuitCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["quit"], 0)?;
+        Ok(QuitCommand)
+    }
+}
+
+impl SelectCommand {
+    /// The backend running commands against the selected database.
+    pub fn select(&self, backend: &Backend) -> Result<Backend, SimpleError> {
+        usize::try_from(self.db)
+            .ok()
+            .and_then(|db| backend.select(db))
+            .ok_or_else(|| SimpleError::new("ERR DB index is out of range"))
+    }
+}
+
+impl CommandExecutor for SelectCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR SELECT is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for SelectCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["select"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(SelectCommand {
+            db: extract_int(args.next(), "index")?,
+        })
+    }
+}
+
+impl HelloCommand {
+    /// The server properties sent back by HELLO.
+    pub fn reply(id: u64, protocol: u8, mode: &str, role: &str) -> RespFrame {
+        let mut map = RespMap::new();
+        map.insert("server".to_string(), BulkString::from("redis").into());
+        map.insert(
+            "version".to_string(),
+            BulkString::from(env!("CARGO_PKG_VERSION")).into(),
+        );
+        map.insert("proto".to_string(), RespFrame::Integer(protocol as i64));
+        map.insert("id".to_string(), RespFrame::Integer(id as i64));
+        map.insert("mode".to_string(), BulkString::from(mode).into());
+        map.insert("role".to_string(), BulkString::from(role).into());
+        map.insert("modules".to_string(), RespArray::new([]).into());
+        map.into()
+    }
+}
+
+impl CommandExecutor for HelloCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR HELLO is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for HelloCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["hello"], 0)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let mut cmd = HelloCommand {
+            protocol: None,
+            auth: None,
+            name: None,
+        };
+        if let Some(protocol) = args.next() {
+            cmd.protocol = Some(extract_int(Some(protocol), "protocol version")?);
+        }
+        while let Some(option) = args.next() {
+            let option = extract_string(Some(option), "option")?;
+            match option.to_ascii_lowercase().as_str() {
+                "auth" => {
+                    let username = extract_string(args.next(), "username")?;
+                    let password = extract_string(args.next(), "password")?;
+                    cmd.auth = Some((username, password));
+                }
+                "setname" => cmd.name = Some(extract_string(args.next(), "name")?),
+                _ => {
+                    return Err(CommandError::InvalidArgument(format!(
+                        "Syntax error in HELLO option '{}'",
+                        option
+                    )));
+                }
+            }
+        }
+        Ok(cmd)
+    }
+}
+
+impl CommandExecutor for AuthCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR AUTH is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for AuthCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["auth"], 1)?;
+        if value.len() > 3 {
+            return Err(CommandError::InvalidArgument(
+                "auth command must have at most 2 argument".to_string(),
+            ));
+        }
+        let mut args = extract_args(value, 1)?;
+
+        let password = extract_string(args.pop(), "password")?;
+        let username = match args.pop() {
+            Some(username) => Some(extract_string(Some(username), "username")?),
+            None => None,
+        };
+        Ok(AuthCommand { username, password })
+    }
+}
+
+impl CommandExecutor for ClientCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR CLIENT is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for ClientCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["client"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let subcommand = extract_string(args.next(), "subcommand")?.to_ascii_lowercase();
+        let action = match subcommand.as_str() {
+            "list" if args.len() == 0 => ClientAction::List,
+            "id" if args.len() == 0 => ClientAction::Id,
+            "getname" if args.len() == 0 => ClientAction::GetName,
+            "setname" if args.len() == 1 => {
+                let name = extract_string(args.next(), "name")?;
+                if name.contains(|c: char| c == ' ' || c.is_ascii_control()) {
+                    return Err(CommandError::InvalidArgument(
+                        "Client names cannot contain spaces, newlines or special characters"
+                            .to_string(),
+                    ));
+                }
+                ClientAction::SetName(name)
+            }
+            "kill" if args.len() == 1 => ClientAction::Kill {
+                filters: vec![ClientKillFilter::Addr(extract_string(
+                    args.next(),
+                    "address",
+                )?)],
+                legacy: true,
+            },
+            "kill" if args.len() > 0 && args.len().is_multiple_of(2) => {
+                let mut filters = vec![];
+                while let Some(filter) = args.next() {
+                    let filter = extract_string(Some(filter), "filter")?;
+                    let filter = match filter.to_ascii_lowercase().as_str() {
+                        "id" => {
+                            let id = extract_int(args.next(), "client id")?;
+                            ClientKillFilter::Id(u64::try_from(id).map_err(|_| {
+                                CommandError::InvalidArgument("client id is invalid".to_string())
+                            })?)
+                        }
+                        "addr" => ClientKillFilter::Addr(extract_string(args.next(), "address")?),
+                        "user" => ClientKillFilter::User(extract_string(args.next(), "user")?),
+                        "skipme" => {
+                            let skip = extract_string(args.next(), "skipme")?;
+                            match skip.to_ascii_lowercase().as_str() {
+                                "yes" => ClientKillFilter::SkipMe(true),
+                                "no" => ClientKillFilter::SkipMe(false),
+                                _ => {
+                                    return Err(CommandError::InvalidArgument(
+                                        "skipme must be yes or no".to_string(),
+                                    ));
+                                }
+                            }
+                        }
+                        _ => {
+                            return Err(CommandError::InvalidArgument(format!(
+                                "unknown client kill filter '{}'",
+                                filter
+                            )));
+                        }
+                    };
+                    filters.push(filter);
+                }
+                ClientAction::Kill {
+                    filters,
+                    legacy: false,
+                }
+            }
+            "list" | "id" | "getname" | "setname" | "kill" => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "wrong number of arguments for 'client|{}' command",
+                    subcommand
+                )));
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "unknown subcommand '{}'",
+                    subcommand
+                )));
+            }
+        };
+
+        Ok(ClientCommand { action })
+    }
+}
+
+impl CommandExecutor for MonitorCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR MONITOR is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for MonitorCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["monitor"], 0)?;
+        Ok(MonitorCommand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_ping_command() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$4\r\nping\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: PingCommand = frame.try_into()?;
+        let backend = Backend::new();
+        assert_eq!(cmd.execute(&backend), SimpleString::new("PONG").into());
+
+        let cmd = PingCommand {
+            message: Some(BulkString::from("hi").into()),
+        };
+        assert_eq!(
+            cmd.execute_subscribed(),
+            RespArray::new([
+                BulkString::from("pong").into(),
+                BulkString::from("hi").into()
+            ])
+            .into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hello_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$5\r\nhello\r\n$1\r\n3\r\n$4\r\nAUTH\r\n$7\r\ndefault\r\n$3\r\npwd\r\n$7\r\nsetname\r\n$3\r\ncli\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: HelloCommand = frame.try_into()?;
+        assert_eq!(result.protocol, Some(3));
+        assert_eq!(
+            result.auth,
+            Some(("default".to_string(), "pwd".to_string()))
+        );
+        assert_eq!(result.name.as_deref(), Some("cli"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_auth_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\nauth\r\n$3\r\npwd\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: AuthCommand = frame.try_into()?;
+        assert_eq!(result.username, None);
+        assert_eq!(result.password, "pwd");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nclient\r\n$7\r\nSETNAME\r\n$6\r\nworker\r\n");
+        let cmd: ClientCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.action, ClientAction::SetName("worker".to_string()));
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nclient\r\n$7\r\nsetname\r\n$3\r\na b\r\n");
+        let ret: Result<ClientCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        buf.extend_from_slice(
+            b"*6\r\n$6\r\nclient\r\n$4\r\nkill\r\n$2\r\nID\r\n$1\r\n7\r\n$6\r\nskipme\r\n$2\r\nno\r\n",
+        );
+        let cmd: ClientCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(
+            cmd.action,
+            ClientAction::Kill {
+                filters: vec![ClientKillFilter::Id(7), ClientKillFilter::SkipMe(false)],
+                legacy: false
+            }
+        );
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nclient\r\n$4\r\nkill\r\n$14\r\n127.0.0.1:6000\r\n");
+        let cmd: ClientCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(
+            cmd.action,
+            ClientAction::Kill {
+                filters: vec![ClientKillFilter::Addr("127.0.0.1:6000".to_string())],
+                legacy: true
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use crate::cmd::{
+    CommandError, CommandExecutor, ExpireAtCommand, ExpireCommand, PersistCommand, TtlCommand,
+    extract_args, extract_int, extract_string, is_command, validate_command,
+};
+use crate::{Backend, RespArray, RespFrame, now_ms};
+
+impl CommandExecutor for ExpireCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let at = now_ms().saturating_add_signed(self.millis);
+        RespFrame::Integer(backend.expire_at(&self.key, at) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for ExpireCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let millis = is_command(&value, "pexpire");
+        let name = if millis { "pexpire" } else { "expire" };
+        validate_command(&value, &[name], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let ttl = extract_int(args.next(), "expire time")?;
+        Ok(ExpireCommand {
+            key,
+            millis: expire_millis(ttl, !millis, now_ms())
+                .ok_or(CommandError::InvalidExpireTime(name))?,
+        })
+    }
+}
+
+impl CommandExecutor for ExpireAtCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let at = u64::try_from(self.at).unwrap_or_default();
+        RespFrame::Integer(backend.expire_at(&self.key, at) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for ExpireAtCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let millis = is_command(&value, "pexpireat");
+        let name = if millis { "pexpireat" } else { "expireat" };
+        validate_command(&value, &[name], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let at = extract_int(args.next(), "expire time")?;
+        Ok(ExpireAtCommand {
+            key,
+            at: expire_millis(at, !millis, 0).ok_or(CommandError::InvalidExpireTime(name))?,
+        })
+    }
+}
+
+impl CommandExecutor for TtlCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let ttl = backend.pttl(&self.key);
+        if self.millis || ttl < 0 {
+            return RespFrame::Integer(ttl);
+        }
+        // rounded to the nearest second
+        RespFrame::Integer(ttl / 1000 + (ttl % 1000 >= 500) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for TtlCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let millis = is_command(&value, "pttl");
+        let name = if millis { "pttl" } else { "ttl" };
+        validate_command(&value, &[name], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(TtlCommand {
+            key: extract_string(args.next(), "key")?,
+            millis,
+        })
+    }
+}
+
+impl CommandExecutor for PersistCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.persist(&self.key) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for PersistCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["persist"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(PersistCommand {
+            key: extract_string(args.next(), "key")?,
+        })
+    }
+}
+
+/// The expire time in milliseconds, None if it overflows once converted or
+/// added to `base`, redis refuses such times.
+pub(crate) fn expire_millis(time: i64, seconds: bool, base: u64) -> Option<i64> {
+    let millis = if seconds {
+        time.checked_mul(1000)?
+    } else {
+        time
+    };
+    millis.checked_add(base as i64)?;
+    Some(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::SetCommand;
+    use crate::{BulkString, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_expire_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nexpire\r\n$5\r\nhello\r\n$2\r\n10\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ExpireCommand = frame.try_into()?;
+        assert_eq!(result.key, "hello");
+        assert_eq!(result.millis, 10_000);
+
+        buf.extend_from_slice(b"*3\r\n$7\r\npexpire\r\n$5\r\nhello\r\n$2\r\n10\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ExpireCommand = frame.try_into()?;
+        assert_eq!(result.millis, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_ttl_persist_commands() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = ExpireCommand {
+            key: "hello".to_string(),
+            millis: 10_000,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        backend.set("hello".to_string(), BulkString::from("world").into());
+        let cmd = TtlCommand {
+            key: "hello".to_string(),
+            millis: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(-1));
+
+        let cmd = ExpireCommand {
+            key: "hello".to_string(),
+            millis: 10_000,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = TtlCommand {
+            key: "hello".to_string(),
+            millis: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(10));
+
+        let cmd = PersistCommand {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = ExpireCommand {
+            key: "hello".to_string(),
+            millis: -1,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = TtlCommand {
+            key: "hello".to_string(),
+            millis: true,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(-2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expireat_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("hello".to_string(), BulkString::from("world").into());
+
+        let cmd = ExpireAtCommand {
+            key: "hello".to_string(),
+            at: (now_ms() + 5_000) as i64,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(backend.pttl("hello") > 4_000);
+
+        let cmd = ExpireAtCommand {
+            key: "hello".to_string(),
+            at: 1,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.get("hello"), Ok(None));
+
+        Ok(())
+    }
+
+    fn args(args: &[&str]) -> RespArray {
+        RespArray::new(
+            args.iter()
+                .map(|arg| BulkString::from(*arg).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+    }
+
+    fn ttl(backend: &Backend, millis: bool) -> RespFrame {
+        let cmd = TtlCommand {
+            key: "hello".to_string(),
+            millis,
+        };
+        cmd.execute(backend)
+    }
+
+    #[test]
+    fn test_huge_expire_times_should_be_refused() -> Result<()> {
+        let ret = SetCommand::try_from(args(&["set", "hello", "world", "ex", "99999999999999999"]));
+        assert_eq!(
+            ret.unwrap_err().to_string(),
+            "invalid expire time in 'set' command"
+        );
+        let ret = ExpireCommand::try_from(args(&["expire", "hello", "9223372036854775807"]));
+        assert_eq!(
+            ret.unwrap_err().to_string(),
+            "invalid expire time in 'expire' command"
+        );
+        let ret = ExpireCommand::try_from(args(&["pexpire", "hello", "9223372036854775807"]));
+        assert!(matches!(
+            ret,
+            Err(CommandError::InvalidExpireTime("pexpire"))
+        ));
+        let ret = ExpireAtCommand::try_from(args(&["expireat", "hello", "9223372036854775807"]));
+        assert!(matches!(
+            ret,
+            Err(CommandError::InvalidExpireTime("expireat"))
+        ));
+
+        // the largest times accepted still give a ttl
+        let backend = Backend::new();
+        let secs = (i64::MAX - now_ms() as i64) / 1000 - 1;
+        let cmd = SetCommand::try_from(args(&["set", "hello", "world", "ex", &secs.to_string()]))?;
+        assert_eq!(cmd.execute(&backend), crate::cmd::RESP_OK.clone());
+        let RespFrame::Integer(pttl) = ttl(&backend, true) else {
+            panic!("pttl should be an integer");
+        };
+        assert!(pttl > (secs - 1) * 1000);
+        let RespFrame::Integer(secs_left) = ttl(&backend, false) else {
+            panic!("ttl should be an integer");
+        };
+        assert!(secs_left >= secs - 1);
+
+        let cmd = ExpireAtCommand::try_from(args(&["pexpireat", "hello", "9223372036854775807"]))?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let RespFrame::Integer(secs_left) = ttl(&backend, false) else {
+            panic!("ttl should be an integer");
+        };
+        assert!(secs_left >= (i64::MAX - now_ms() as i64) / 1000);
+        Ok(())
+    }
+}
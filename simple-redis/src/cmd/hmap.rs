@@ -2,13 +2,14 @@ use crate::cmd::{
     CommandError, CommandExecutor, HGetAllCommand, HGetCommand, HSetCommand, RESP_OK, extract_args,
     validate_command,
 };
-use crate::{Backend, BulkString, RespArray, RespFrame, RespNull};
+use crate::{Backend, RespArray, RespFrame, RespMap, RespNull};
 
 impl CommandExecutor for HGetCommand {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend
-            .hget(&self.key, &self.field)
-            .unwrap_or(RespFrame::Null(RespNull))
+        match backend.hget(&self.key, &self.field) {
+            Ok(value) => value.unwrap_or(RespFrame::Null(RespNull)),
+            Err(e) => e.into(),
+        }
     }
 }
 
@@ -34,27 +35,13 @@ impl TryFrom<RespArray> for HGetCommand {
 
 impl CommandExecutor for HGetAllCommand {
     fn execute(self, backend: &Backend) -> RespFrame {
-        let hmap = backend.hmap.get(&self.key);
-
-        match hmap {
-            Some(hmap) => {
-                let mut data = Vec::with_capacity(hmap.len() * 2);
-                for v in hmap.iter() {
-                    let key = v.key().to_owned();
-                    data.push((key, v.value().clone()));
-                }
-                if self.sort {
-                    data.sort_by(|a, b| a.0.cmp(&b.0));
-                }
-                let ret = data
-                    .into_iter()
-                    .flat_map(|(k, v)| vec![BulkString::from(k).into(), v])
-                    .collect::<Vec<RespFrame>>();
-
-                RespArray::new(ret).into()
-            }
-            None => RespArray::new([]).into(),
+        let mut map = RespMap::new();
+        match backend.hgetall(&self.key) {
+            Ok(Some(hmap)) => map.extend(hmap),
+            Ok(None) => {}
+            Err(e) => return e.into(),
         }
+        map.into()
     }
 }
 
@@ -68,7 +55,6 @@ impl TryFrom<RespArray> for HGetAllCommand {
         match args.next() {
             Some(RespFrame::BulkString(key)) => Ok(HGetAllCommand {
                 key: String::from_utf8(key.0)?,
-                sort: false,
             }),
             _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
         }
@@ -77,8 +63,10 @@ impl TryFrom<RespArray> for HGetAllCommand {
 
 impl CommandExecutor for HSetCommand {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.hset(self.key, self.field, self.value);
-        RESP_OK.clone()
+        match backend.hset(self.key, self.field, self.value) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
     }
 }
 
@@ -106,7 +94,7 @@ impl TryFrom<RespArray> for HSetCommand {
 
 #[cfg(test)]
 mod tests {
-    use crate::RespDecode;
+    use crate::{BulkString, RespDecode};
 
     use super::*;
     use anyhow::Result;
@@ -181,17 +169,23 @@ mod tests {
 
         let cmd = HGetAllCommand {
             key: "map".to_string(),
-            sort: true,
         };
         let result = cmd.execute(&backend);
 
-        let expected = RespArray::new([
-            BulkString::from("hello").into(),
-            BulkString::from("world").into(),
-            BulkString::from("hello1").into(),
-            BulkString::from("world1").into(),
-        ]);
-        assert_eq!(result, expected.into());
+        let mut expected = RespMap::new();
+        expected.insert("hello".to_string(), BulkString::from("world").into());
+        expected.insert("hello1".to_string(), BulkString::from("world1").into());
+        assert_eq!(result, expected.clone().into());
+        assert_eq!(
+            result.into_resp2(),
+            RespArray::new([
+                BulkString::from("hello").into(),
+                BulkString::from("world").into(),
+                BulkString::from("hello1").into(),
+                BulkString::from("world1").into(),
+            ])
+            .into()
+        );
         Ok(())
     }
 }
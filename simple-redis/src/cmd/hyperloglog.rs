@@ -0,0 +1,113 @@
+use crate::cmd::{
+    CommandError, CommandExecutor, PfAddCommand, PfCountCommand, PfMergeCommand, RESP_OK,
+    extract_args, extract_bytes, extract_string, validate_variadic_command,
+};
+use crate::{Backend, RespArray, RespFrame};
+
+impl CommandExecutor for PfAddCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfadd(&self.key, &self.elements) {
+            Ok(changed) => RespFrame::Integer(changed as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PfAddCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["pfadd"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let elements = args
+            .map(|element| extract_bytes(Some(element), "element"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PfAddCommand { key, elements })
+    }
+}
+
+impl CommandExecutor for PfCountCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfcount(&self.keys) {
+            Ok(count) => RespFrame::Integer(count as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PfCountCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["pfcount"], 1)?;
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|key| extract_string(Some(key), "key"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PfCountCommand { keys })
+    }
+}
+
+impl CommandExecutor for PfMergeCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfmerge(&self.dest, &self.sources) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PfMergeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["pfmerge"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let dest = extract_string(args.next(), "destkey")?;
+        let sources = args
+            .map(|key| extract_string(Some(key), "key"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PfMergeCommand { dest, sources })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespDecode, SimpleError};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_pf_commands() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nPFADD\r\n$3\r\ndau\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let cmd: PfAddCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.elements, vec![b"a".to_vec(), b"b".to_vec()]);
+
+        let backend = Backend::new();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd = PfMergeCommand {
+            dest: "week".to_string(),
+            sources: vec!["dau".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        buf.extend_from_slice(b"*3\r\n$7\r\npfcount\r\n$3\r\ndau\r\n$4\r\nweek\r\n");
+        let cmd: PfCountCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+
+        backend.set("s".to_string(), BulkString::from("x").into());
+        let cmd = PfCountCommand {
+            keys: vec!["s".to_string()],
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("WRONGTYPE Key is not a valid HyperLogLog string value.").into()
+        );
+        Ok(())
+    }
+}
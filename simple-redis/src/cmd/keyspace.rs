@@ -0,0 +1,378 @@
+use crate::cmd::{
+    CommandError, CommandExecutor, DbSizeCommand, DelCommand, ExistsCommand, FlushCommand,
+    HScanCommand, KeysCommand, RESP_OK, RenameCommand, ScanCommand, TypeCommand, extract_args,
+    extract_int, extract_string, is_command, validate_command, validate_variadic_command,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, SimpleError, SimpleString};
+
+/// Page size of SCAN and HSCAN when COUNT is not given.
+const DEFAULT_SCAN_COUNT: usize = 10;
+
+impl CommandExecutor for DelCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.del(&self.keys) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for DelCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["del"], 1)?;
+        Ok(DelCommand {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for ExistsCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let count = self.keys.iter().filter(|key| backend.exists(key)).count();
+        RespFrame::Integer(count as i64)
+    }
+}
+
+impl TryFrom<RespArray> for ExistsCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["exists"], 1)?;
+        Ok(ExistsCommand {
+            keys: extract_keys(value)?,
+        })
+    }
+}
+
+impl CommandExecutor for KeysCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        keys_to_frame(backend.keys(&self.pattern))
+    }
+}
+
+impl TryFrom<RespArray> for KeysCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["keys"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(KeysCommand {
+            pattern: extract_string(args.next(), "pattern")?,
+        })
+    }
+}
+
+impl CommandExecutor for ScanCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let (cursor, keys) = backend.scan(
+            self.cursor,
+            self.count,
+            self.pattern.as_deref(),
+            self.key_type.as_deref(),
+        );
+        scan_reply(cursor, keys_to_frame(keys))
+    }
+}
+
+impl TryFrom<RespArray> for ScanCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["scan"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let mut cmd = ScanCommand {
+            cursor: extract_cursor(args.next())?,
+            pattern: None,
+            count: DEFAULT_SCAN_COUNT,
+            key_type: None,
+        };
+        while let Some(option) = args.next() {
+            match extract_string(Some(option), "option")?
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "match" => cmd.pattern = Some(extract_string(args.next(), "pattern")?),
+                "count" => cmd.count = extract_count(args.next())?,
+                "type" => {
+                    cmd.key_type = Some(extract_string(args.next(), "type")?.to_ascii_lowercase())
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
+        }
+        Ok(cmd)
+    }
+}
+
+impl CommandExecutor for HScanCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let page = backend.hscan(&self.key, self.cursor, self.count, self.pattern.as_deref());
+        match page {
+            Ok((cursor, fields)) => {
+                let frames = fields
+                    .into_iter()
+                    .flat_map(|(field, value)| {
+                        let field = BulkString::from(field).into();
+                        if self.no_values {
+                            vec![field]
+                        } else {
+                            vec![field, value]
+                        }
+                    })
+                    .collect::<Vec<RespFrame>>();
+                scan_reply(cursor, RespArray::new(frames).into())
+            }
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for HScanCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["hscan"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let mut cmd = HScanCommand {
+            key: extract_string(args.next(), "key")?,
+            cursor: extract_cursor(args.next())?,
+            pattern: None,
+            count: DEFAULT_SCAN_COUNT,
+            no_values: false,
+        };
+        while let Some(option) = args.next() {
+            match extract_string(Some(option), "option")?
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "match" => cmd.pattern = Some(extract_string(args.next(), "pattern")?),
+                "count" => cmd.count = extract_count(args.next())?,
+                "novalues" => cmd.no_values = true,
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
+        }
+        Ok(cmd)
+    }
+}
+
+impl CommandExecutor for TypeCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        SimpleString::new(backend.key_type(&self.key).unwrap_or("none")).into()
+    }
+}
+
+impl TryFrom<RespArray> for TypeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["type"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(TypeCommand {
+            key: extract_string(args.next(), "key")?,
+        })
+    }
+}
+
+impl CommandExecutor for RenameCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.rename(&self.key, self.new_key) {
+            RESP_OK.clone()
+        } else {
+            SimpleError::new("ERR no such key").into()
+        }
+    }
+}
+
+impl TryFrom<RespArray> for RenameCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["rename"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(RenameCommand {
+            key: extract_string(args.next(), "key")?,
+            new_key: extract_string(args.next(), "new key")?,
+        })
+    }
+}
+
+impl CommandExecutor for DbSizeCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.dbsize() as i64)
+    }
+}
+
+impl TryFrom<RespArray> for DbSizeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["dbsize"], 0)?;
+        Ok(DbSizeCommand)
+    }
+}
+
+impl CommandExecutor for FlushCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.all {
+            backend.flushall();
+        } else {
+            backend.flushdb();
+        }
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for FlushCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let all = is_command(&value, "flushall");
+        let name = if all { "flushall" } else { "flushdb" };
+        validate_variadic_command(&value, &[name], 0)?;
+        if value.len() > 2 {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        // everything is freed right away, ASYNC behaves as SYNC
+        if let Some(mode) = args.next() {
+            let mode = extract_string(Some(mode), "mode")?.to_ascii_lowercase();
+            if mode != "async" && mode != "sync" {
+                return Err(CommandError::InvalidArgument("syntax error".to_string()));
+            }
+        }
+        Ok(FlushCommand { all })
+    }
+}
+
+fn extract_keys(value: RespArray) -> Result<Vec<String>, CommandError> {
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|key| extract_string(Some(key), "key"))
+        .collect()
+}
+
+fn extract_cursor(frame: Option<RespFrame>) -> Result<u64, CommandError> {
+    extract_string(frame, "cursor")?
+        .parse()
+        .map_err(|_| CommandError::InvalidArgument("invalid cursor".to_string()))
+}
+
+fn extract_count(frame: Option<RespFrame>) -> Result<usize, CommandError> {
+    match extract_int(frame, "count")? {
+        count if count > 0 => Ok(count as usize),
+        _ => Err(CommandError::InvalidArgument("syntax error".to_string())),
+    }
+}
+
+fn keys_to_frame(keys: Vec<String>) -> RespFrame {
+    RespArray::new(
+        keys.into_iter()
+            .map(|key| BulkString::from(key).into())
+            .collect::<Vec<RespFrame>>(),
+    )
+    .into()
+}
+
+/// The cursor to continue from, as a string, and the items of the page.
+fn scan_reply(cursor: u64, items: RespFrame) -> RespFrame {
+    RespArray::new([BulkString::from(cursor.to_string()).into(), items]).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_scan_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*8\r\n$4\r\nscan\r\n$2\r\n42\r\n$5\r\nMATCH\r\n$5\r\nuser*\r\n$5\r\nCOUNT\r\n$3\r\n100\r\n$4\r\nTYPE\r\n$4\r\nHASH\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ScanCommand = frame.try_into()?;
+        assert_eq!(result.cursor, 42);
+        assert_eq!(result.pattern.as_deref(), Some("user*"));
+        assert_eq!(result.count, 100);
+        assert_eq!(result.key_type.as_deref(), Some("hash"));
+
+        buf.extend_from_slice(b"*2\r\n$4\r\nscan\r\n$2\r\n-1\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        assert!(ScanCommand::try_from(frame).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hscan_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.hset("map".to_string(), "f".to_string(), RespFrame::Integer(1))?;
+        backend.set("str".to_string(), BulkString::from("v").into());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nhscan\r\n$3\r\nmap\r\n$1\r\n0\r\n$8\r\nnovalues\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: HScanCommand = frame.try_into()?;
+        let ret = cmd.execute(&backend);
+        let expected = RespArray::new([
+            BulkString::from("0").into(),
+            RespArray::new([BulkString::from("f").into()]).into(),
+        ]);
+        assert_eq!(ret, expected.into());
+
+        buf.extend_from_slice(b"*3\r\n$5\r\nhscan\r\n$3\r\nstr\r\n$1\r\n0\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: HScanCommand = frame.try_into()?;
+        assert!(matches!(cmd.execute(&backend), RespFrame::Error(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_del_exists_rename_commands() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::from("1").into());
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$6\r\nexists\r\n$1\r\na\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: ExistsCommand = frame.try_into()?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nrename\r\n$1\r\nb\r\n$1\r\nc\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: RenameCommand = frame.try_into()?;
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR no such key").into()
+        );
+
+        buf.extend_from_slice(b"*3\r\n$3\r\ndel\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: DelCommand = frame.try_into()?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.dbsize(), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,430 @@
+use crate::cmd::{
+    BlockingPopCommand, CommandError, CommandExecutor, LIndexCommand, LLenCommand, LRangeCommand,
+    LRemCommand, LTrimCommand, PopCommand, PushCommand, RESP_OK, extract_args, extract_float,
+    extract_int, extract_string, is_command, validate_command, validate_variadic_command,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, RespNullArray};
+use std::time::Duration;
+use tokio::time::Instant;
+
+impl CommandExecutor for PushCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let len = if self.left {
+            backend.lpush(self.key, self.values)
+        } else {
+            backend.rpush(self.key, self.values)
+        };
+        match len {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PushCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let left = is_command(&value, "lpush");
+        validate_variadic_command(&value, &[if left { "lpush" } else { "rpush" }], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(PushCommand {
+            key: extract_string(args.next(), "key")?,
+            values: args.collect(),
+            left,
+        })
+    }
+}
+
+impl CommandExecutor for PopCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let values = backend.pop(&self.key, self.count.unwrap_or(1), self.left);
+        match (values, self.count) {
+            (Err(e), _) => e.into(),
+            (Ok(Some(values)), Some(_)) => RespArray::new(values).into(),
+            (Ok(Some(mut values)), None) => values.pop().unwrap_or(RespFrame::Null(RespNull)),
+            (Ok(None), Some(_)) => RespFrame::NullArray(RespNullArray),
+            (Ok(None), None) => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PopCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let left = is_command(&value, "lpop");
+        let name = if left { "lpop" } else { "rpop" };
+        validate_variadic_command(&value, &[name], 1)?;
+        if value.len() > 3 {
+            return Err(CommandError::InvalidArgument(format!(
+                "{} command must have at most 2 argument",
+                name
+            )));
+        }
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let count = match args.next() {
+            Some(count) => {
+                let count = extract_int(Some(count), "count")?;
+                if count < 0 {
+                    return Err(CommandError::InvalidArgument(
+                        "value is out of range, must be positive".to_string(),
+                    ));
+                }
+                Some(count as usize)
+            }
+            None => None,
+        };
+        Ok(PopCommand { key, count, left })
+    }
+}
+
+impl BlockingPopCommand {
+    /// Pop from the first non-empty list, returns `[key, value]` if any, or
+    /// the error for a key holding another type.
+    fn try_pop(&self, backend: &Backend) -> Option<RespFrame> {
+        self.keys
+            .iter()
+            .find_map(|key| match backend.pop(key, 1, self.left) {
+                Ok(values) => {
+                    let value = values?.pop()?;
+                    Some(RespArray::new([BulkString::from(key.as_str()).into(), value]).into())
+                }
+                Err(e) => Some(e.into()),
+            })
+    }
+
+    /// Pop outside of the request handler, which otherwise takes the backend
+    /// lock and marks the keys as modified.
+    fn pop_locked(&self, backend: &Backend) -> Option<RespFrame> {
+        let _guard = backend.shared();
+        let frame = self.try_pop(backend)?;
+        for key in &self.keys {
+            backend.touch(key);
+            backend.record_access(key);
+        }
+        Some(frame)
+    }
+
+    /// Park the caller until one of the lists gets a value or the timeout
+    /// passes, in which case a null array is returned. Blocked clients are
+    /// served in the order they blocked, one per pushed value.
+    pub async fn execute_blocking(self, backend: &Backend) -> RespFrame {
+        let deadline =
+            (self.timeout > 0.0).then(|| Instant::now() + Duration::from_secs_f64(self.timeout));
+        // queued before checking the lists so a push in between can not be
+        // missed
+        let blocked = backend.block_on_lists(&self.keys);
+        loop {
+            if let Some(frame) = self.pop_locked(backend) {
+                return frame;
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, blocked.woken())
+                        .await
+                        .is_err()
+                    {
+                        return RespFrame::NullArray(RespNullArray);
+                    }
+                }
+                None => blocked.woken().await,
+            }
+        }
+    }
+}
+
+impl CommandExecutor for BlockingPopCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.try_pop(backend)
+            .unwrap_or(RespFrame::NullArray(RespNullArray))
+    }
+}
+
+impl TryFrom<RespArray> for BlockingPopCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let left = is_command(&value, "blpop");
+        validate_variadic_command(&value, &[if left { "blpop" } else { "brpop" }], 2)?;
+        let mut args = extract_args(value, 1)?;
+
+        let timeout = extract_float(args.pop(), "timeout")?;
+        if timeout < 0.0 || !timeout.is_finite() {
+            return Err(CommandError::InvalidArgument(
+                "timeout is negative".to_string(),
+            ));
+        }
+        let keys = args
+            .into_iter()
+            .map(|key| extract_string(Some(key), "key"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(BlockingPopCommand {
+            keys,
+            timeout,
+            left,
+        })
+    }
+}
+
+impl CommandExecutor for LRangeCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lrange(&self.key, self.start, self.stop) {
+            Ok(values) => RespArray::new(values).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for LRangeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lrange"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(LRangeCommand {
+            key: extract_string(args.next(), "key")?,
+            start: extract_int(args.next(), "start")?,
+            stop: extract_int(args.next(), "stop")?,
+        })
+    }
+}
+
+impl CommandExecutor for LLenCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.llen(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for LLenCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["llen"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(LLenCommand {
+            key: extract_string(args.next(), "key")?,
+        })
+    }
+}
+
+impl CommandExecutor for LIndexCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lindex(&self.key, self.index) {
+            Ok(value) => value.unwrap_or(RespFrame::Null(RespNull)),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for LIndexCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lindex"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(LIndexCommand {
+            key: extract_string(args.next(), "key")?,
+            index: extract_int(args.next(), "index")?,
+        })
+    }
+}
+
+impl CommandExecutor for LTrimCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.ltrim(&self.key, self.start, self.stop) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for LTrimCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ltrim"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(LTrimCommand {
+            key: extract_string(args.next(), "key")?,
+            start: extract_int(args.next(), "start")?,
+            stop: extract_int(args.next(), "stop")?,
+        })
+    }
+}
+
+impl CommandExecutor for LRemCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lrem(&self.key, self.count, &self.value) {
+            Ok(removed) => RespFrame::Integer(removed as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for LRemCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lrem"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let count = extract_int(args.next(), "count")?;
+        match args.next() {
+            Some(value) => Ok(LRemCommand { key, count, value }),
+            None => Err(CommandError::InvalidArgument("Invalid value".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_push_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nrpush\r\n$4\r\nlist\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: PushCommand = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert_eq!(result.values, vec![b"a".into(), b"b".into()]);
+        assert!(!result.left);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blpop_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nblpop\r\n$1\r\na\r\n$1\r\nb\r\n$3\r\n0.5\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: BlockingPopCommand = frame.try_into()?;
+        assert_eq!(result.keys, vec!["a", "b"]);
+        assert_eq!(result.timeout, 0.5);
+        assert!(result.left);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_pop_commands() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = PushCommand {
+            key: "list".to_string(),
+            values: vec![b"a".into(), b"b".into(), b"c".into()],
+            left: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(3));
+
+        let cmd = PopCommand {
+            key: "list".to_string(),
+            count: None,
+            left: true,
+        };
+        assert_eq!(cmd.execute(&backend), b"a".into());
+
+        let cmd = PopCommand {
+            key: "list".to_string(),
+            count: Some(5),
+            left: false,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([b"c".into(), b"b".into()]).into()
+        );
+
+        let cmd = PopCommand {
+            key: "list".to_string(),
+            count: None,
+            left: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blocking_pop_should_wait_for_push() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = BlockingPopCommand {
+            keys: vec!["a".to_string(), "b".to_string()],
+            timeout: 0.0,
+            left: true,
+        };
+        let cloned = backend.clone();
+        let handle = tokio::spawn(async move { cmd.execute_blocking(&cloned).await });
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        backend.rpush("b".to_string(), vec![b"hello".into()])?;
+
+        let ret = handle.await?;
+        assert_eq!(ret, RespArray::new([b"b".into(), b"hello".into()]).into());
+        assert_eq!(backend.llen("b"), Ok(0));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blocked_pops_should_be_served_in_order() -> Result<()> {
+        let backend = Backend::new();
+        let mut handles = vec![];
+        for _ in 0..3 {
+            let cmd = BlockingPopCommand {
+                keys: vec!["list".to_string()],
+                timeout: 0.0,
+                left: true,
+            };
+            let cloned = backend.clone();
+            handles.push(tokio::spawn(
+                async move { cmd.execute_blocking(&cloned).await },
+            ));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        backend.rpush("list".to_string(), vec![b"a".into(), b"b".into()])?;
+        let first = handles.remove(0).await?;
+        assert_eq!(first, RespArray::new([b"list".into(), b"a".into()]).into());
+        let second = handles.remove(0).await?;
+        assert_eq!(second, RespArray::new([b"list".into(), b"b".into()]).into());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!handles[0].is_finished());
+        assert_eq!(backend.blocked_lists.len(), 1);
+        handles[0].abort();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blocking_pop_should_time_out() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = BlockingPopCommand {
+            keys: vec!["a".to_string()],
+            timeout: 0.01,
+            left: false,
+        };
+        let ret = cmd.execute_blocking(&backend).await;
+        assert_eq!(ret, RespFrame::NullArray(RespNullArray));
+
+        Ok(())
+    }
+}
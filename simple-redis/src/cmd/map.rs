@@ -1,11 +1,15 @@
 use crate::cmd::{
-    CommandError, CommandExecutor, GetCommand, RESP_OK, SetCommand, extract_args, validate_command,
+    CommandError, CommandExecutor, GetCommand, RESP_OK, SetCommand, SetCondition, SetExpire,
+    expire::expire_millis, extract_args, extract_int, validate_command, validate_variadic_command,
 };
-use crate::{Backend, RespArray, RespFrame, RespNull};
+use crate::{Backend, RespArray, RespFrame, RespNull, now_ms};
 
 impl CommandExecutor for GetCommand {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.get(&self.key).unwrap_or(RespFrame::Null(RespNull))
+        match backend.get(&self.key) {
+            Ok(value) => value.unwrap_or(RespFrame::Null(RespNull)),
+            Err(e) => e.into(),
+        }
     }
 }
 
@@ -27,8 +31,37 @@ impl TryFrom<RespArray> for GetCommand {
 
 impl CommandExecutor for SetCommand {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.set(self.key, self.value);
-        RESP_OK.clone()
+        let old = if self.get {
+            match backend.get(&self.key) {
+                Ok(old) => old,
+                Err(e) => return e.into(),
+            }
+        } else {
+            None
+        };
+        let exists = backend.exists(&self.key);
+        let skip = match self.condition {
+            Some(SetCondition::Nx) => exists,
+            Some(SetCondition::Xx) => !exists,
+            None => false,
+        };
+
+        if !skip {
+            match self.expire {
+                Some(SetExpire::After(millis)) => {
+                    backend.set(self.key.clone(), self.value);
+                    backend.expire_at(&self.key, now_ms() + millis);
+                }
+                Some(SetExpire::KeepTtl) => backend.set_keep_ttl(self.key, self.value),
+                None => backend.set(self.key, self.value),
+            }
+        }
+
+        match (self.get, skip) {
+            (true, _) => old.unwrap_or(RespFrame::Null(RespNull)),
+            (false, true) => RespFrame::Null(RespNull),
+            (false, false) => RESP_OK.clone(),
+        }
     }
 }
 
@@ -36,16 +69,42 @@ impl TryFrom<RespArray> for SetCommand {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["set"], 2)?;
+        validate_variadic_command(&value, &["set"], 2)?;
         let mut args = extract_args(value, 1)?.into_iter();
 
-        match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(value)) => Ok(SetCommand {
+        let mut cmd = match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(value)) => SetCommand {
                 key: String::from_utf8(key.0)?,
                 value,
-            }),
-            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+                expire: None,
+                condition: None,
+                get: false,
+            },
+            _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        };
+
+        while let Some(arg) = args.next() {
+            let option = match arg {
+                RespFrame::BulkString(option) => option.to_ascii_lowercase(),
+                _ => return Err(CommandError::InvalidArgument("Invalid option".to_string())),
+            };
+            match option.as_slice() {
+                b"ex" | b"px" if cmd.expire.is_none() => {
+                    let ttl = extract_int(args.next(), "expire time")?;
+                    let millis = expire_millis(ttl, option == b"ex", now_ms())
+                        .filter(|millis| *millis > 0)
+                        .ok_or(CommandError::InvalidExpireTime("set"))?;
+                    cmd.expire = Some(SetExpire::After(millis as u64));
+                }
+                b"keepttl" if cmd.expire.is_none() => cmd.expire = Some(SetExpire::KeepTtl),
+                b"nx" if cmd.condition.is_none() => cmd.condition = Some(SetCondition::Nx),
+                b"xx" if cmd.condition.is_none() => cmd.condition = Some(SetCondition::Xx),
+                b"get" => cmd.get = true,
+                _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+            }
         }
+
+        Ok(cmd)
     }
 }
 
@@ -84,12 +143,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_set_with_options_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*6\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n$2\r\nEX\r\n$2\r\n10\r\n$2\r\nnx\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SetCommand = frame.try_into()?;
+        assert_eq!(result.expire, Some(SetExpire::After(10_000)));
+        assert_eq!(result.condition, Some(SetCondition::Nx));
+        assert!(!result.get);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n$2\r\nnx\r\n$2\r\nxx\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let result: Result<SetCommand, _> = frame.try_into();
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_set_get_command() -> Result<()> {
         let backend = Backend::new();
         let cmd = SetCommand {
             key: "hello".to_string(),
             value: RespFrame::BulkString(b"world".into()),
+            expire: None,
+            condition: None,
+            get: false,
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
@@ -102,4 +189,57 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_nx_xx_get_command() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = SetCommand {
+            key: "hello".to_string(),
+            value: RespFrame::BulkString(b"world".into()),
+            expire: None,
+            condition: Some(SetCondition::Xx),
+            get: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+        assert_eq!(backend.get("hello"), Ok(None));
+
+        let cmd = SetCommand {
+            key: "hello".to_string(),
+            value: RespFrame::BulkString(b"world".into()),
+            expire: Some(SetExpire::After(10_000)),
+            condition: Some(SetCondition::Nx),
+            get: false,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert!(backend.pttl("hello") > 0);
+
+        let cmd = SetCommand {
+            key: "hello".to_string(),
+            value: RespFrame::BulkString(b"world1".into()),
+            expire: Some(SetExpire::KeepTtl),
+            condition: Some(SetCondition::Xx),
+            get: true,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::BulkString(b"world".into())
+        );
+        assert_eq!(
+            backend.get("hello"),
+            Ok(Some(RespFrame::BulkString(b"world1".into())))
+        );
+        assert!(backend.pttl("hello") > 0);
+
+        let cmd = SetCommand {
+            key: "hello".to_string(),
+            value: RespFrame::BulkString(b"world2".into()),
+            expire: None,
+            condition: None,
+            get: false,
+        };
+        cmd.execute(&backend);
+        assert_eq!(backend.pttl("hello"), -1);
+
+        Ok(())
+    }
 }
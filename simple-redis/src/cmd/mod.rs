@@ -1,5 +1,20 @@
+mod bitmap;
+mod cluster;
 mod command;
+mod connection;
+mod expire;
 mod hmap;
+mod hyperloglog;
+mod keyspace;
+mod list;
 mod map;
+mod pubsub;
+mod replication;
+mod scripting;
+mod server;
+mod stream;
+mod string;
+mod transaction;
+mod zset;
 
 pub use command::*;
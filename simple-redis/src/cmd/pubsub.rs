@@ -0,0 +1,213 @@
+use crate::cmd::{
+    CommandError, CommandExecutor, PublishCommand, SubscribeCommand, UnsubscribeCommand,
+    extract_args, extract_string, is_command, validate_command, validate_variadic_command,
+};
+use crate::{
+    Backend, BulkString, NullBulkString, RespArray, RespFrame, SimpleError, Subscriptions,
+};
+
+impl CommandExecutor for PublishCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.publish(&self.channel, self.message) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for PublishCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["publish"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        match (args.next(), args.next()) {
+            (Some(channel), Some(message)) => Ok(PublishCommand {
+                channel: extract_string(Some(channel), "channel")?,
+                message,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid channel or message".to_string(),
+            )),
+        }
+    }
+}
+
+impl SubscribeCommand {
+    /// Subscribe the connection, replies with one confirmation per channel.
+    pub fn execute_subscribe(self, subscriptions: &mut Subscriptions) -> Vec<RespFrame> {
+        let kind = if self.pattern {
+            "psubscribe"
+        } else {
+            "subscribe"
+        };
+        self.channels
+            .into_iter()
+            .map(|channel| {
+                let count = subscriptions.subscribe(channel.clone(), self.pattern);
+                confirmation(kind, BulkString::from(channel).into(), count)
+            })
+            .collect()
+    }
+}
+
+impl CommandExecutor for SubscribeCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR SUBSCRIBE is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for SubscribeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let pattern = is_command(&value, "psubscribe");
+        let name = if pattern { "psubscribe" } else { "subscribe" };
+        validate_variadic_command(&value, &[name], 1)?;
+
+        let channels = extract_args(value, 1)?
+            .into_iter()
+            .map(|channel| extract_string(Some(channel), "channel"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SubscribeCommand { channels, pattern })
+    }
+}
+
+impl UnsubscribeCommand {
+    /// Unsubscribe the connection from the given channels, or all of them
+    /// when none is given, replies with one confirmation per channel.
+    pub fn execute_unsubscribe(self, subscriptions: &mut Subscriptions) -> Vec<RespFrame> {
+        let kind = if self.pattern {
+            "punsubscribe"
+        } else {
+            "unsubscribe"
+        };
+        let channels = if self.channels.is_empty() {
+            subscriptions.list(self.pattern)
+        } else {
+            self.channels
+        };
+        if channels.is_empty() {
+            return vec![confirmation(
+                kind,
+                RespFrame::NullBulkString(NullBulkString),
+                subscriptions.count(),
+            )];
+        }
+
+        channels
+            .into_iter()
+            .map(|channel| {
+                let count = subscriptions.unsubscribe(&channel, self.pattern);
+                confirmation(kind, BulkString::from(channel).into(), count)
+            })
+            .collect()
+    }
+}
+
+impl CommandExecutor for UnsubscribeCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR UNSUBSCRIBE is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for UnsubscribeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let pattern = is_command(&value, "punsubscribe");
+        let name = if pattern {
+            "punsubscribe"
+        } else {
+            "unsubscribe"
+        };
+        validate_variadic_command(&value, &[name], 0)?;
+
+        let channels = extract_args(value, 1)?
+            .into_iter()
+            .map(|channel| extract_string(Some(channel), "channel"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(UnsubscribeCommand { channels, pattern })
+    }
+}
+
+fn confirmation(kind: &str, channel: RespFrame, count: usize) -> RespFrame {
+    RespArray::new([
+        BulkString::from(kind).into(),
+        channel,
+        RespFrame::Integer(count as i64),
+    ])
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_psubscribe_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n$4\r\nchat\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SubscribeCommand = frame.try_into()?;
+        assert!(result.pattern);
+        assert_eq!(result.channels, vec!["news.*", "chat"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscribe_publish_unsubscribe() -> Result<()> {
+        let backend = Backend::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut subscriptions = Subscriptions::new(&backend, backend.next_client_id(), tx);
+
+        let cmd = SubscribeCommand {
+            channels: vec!["a".to_string(), "b".to_string()],
+            pattern: false,
+        };
+        let replies = cmd.execute_subscribe(&mut subscriptions);
+        assert_eq!(
+            replies[1],
+            confirmation("subscribe", BulkString::from("b").into(), 2)
+        );
+
+        let cmd = PublishCommand {
+            channel: "a".to_string(),
+            message: BulkString::from("hello").into(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(rx.try_recv().is_ok());
+
+        let cmd = UnsubscribeCommand {
+            channels: vec![],
+            pattern: false,
+        };
+        let replies = cmd.execute_unsubscribe(&mut subscriptions);
+        assert_eq!(replies.len(), 2);
+        assert_eq!(
+            replies[1],
+            confirmation("unsubscribe", BulkString::from("b").into(), 0)
+        );
+
+        let cmd = UnsubscribeCommand {
+            channels: vec![],
+            pattern: true,
+        };
+        let replies = cmd.execute_unsubscribe(&mut subscriptions);
+        assert_eq!(
+            replies,
+            vec![confirmation(
+                "punsubscribe",
+                RespFrame::NullBulkString(NullBulkString),
+                0
+            )]
+        );
+
+        Ok(())
+    }
+}
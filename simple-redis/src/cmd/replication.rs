@@ -0,0 +1,144 @@
+use crate::cmd::{
+    CommandError, CommandExecutor, PSyncCommand, RESP_OK, ReplConfCommand, ReplicaOfCommand,
+    extract_args, extract_int, extract_string, is_command, validate_command,
+    validate_variadic_command,
+};
+use crate::{Backend, RespArray, RespFrame, SimpleError, SimpleString};
+
+impl CommandExecutor for ReplicaOfCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.replicaof(self.master) {
+            RESP_OK.clone()
+        } else {
+            SimpleString::new("OK Already connected to specified master").into()
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ReplicaOfCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let name = if is_command(&value, "slaveof") {
+            "slaveof"
+        } else {
+            "replicaof"
+        };
+        validate_command(&value, &[name], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let host = extract_string(args.next(), "host")?;
+        let port = extract_string(args.next(), "port")?;
+        if host.eq_ignore_ascii_case("no") && port.eq_ignore_ascii_case("one") {
+            return Ok(ReplicaOfCommand { master: None });
+        }
+        let port = port
+            .parse()
+            .map_err(|_| CommandError::InvalidArgument("Invalid master port".to_string()))?;
+        Ok(ReplicaOfCommand {
+            master: Some((host, port)),
+        })
+    }
+}
+
+impl CommandExecutor for ReplConfCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR REPLCONF is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for ReplConfCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["replconf"], 2)?;
+        if value.len().is_multiple_of(2) {
+            return Err(CommandError::InvalidArgument(
+                "replconf options must come in pairs".to_string(),
+            ));
+        }
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let mut options = vec![];
+        while let Some(option) = args.next() {
+            let option = extract_string(Some(option), "option")?.to_ascii_lowercase();
+            options.push((option, extract_string(args.next(), "value")?));
+        }
+        Ok(ReplConfCommand { options })
+    }
+}
+
+impl CommandExecutor for PSyncCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR PSYNC is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for PSyncCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["psync"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(PSyncCommand {
+            replid: extract_string(args.next(), "replication id")?,
+            offset: extract_int(args.next(), "offset")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_replicaof_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$9\r\nreplicaof\r\n$9\r\nlocalhost\r\n$4\r\n6380\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ReplicaOfCommand = frame.try_into()?;
+        assert_eq!(result.master, Some(("localhost".to_string(), 6380)));
+
+        buf.extend_from_slice(b"*3\r\n$7\r\nslaveof\r\n$2\r\nNO\r\n$3\r\nONE\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ReplicaOfCommand = frame.try_into()?;
+        assert_eq!(result.master, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replconf_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$8\r\nreplconf\r\n$3\r\nACK\r\n$2\r\n42\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ReplConfCommand = frame.try_into()?;
+        assert_eq!(result.options, vec![("ack".to_string(), "42".to_string())]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_psync_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\npsync\r\n$1\r\n?\r\n$2\r\n-1\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: PSyncCommand = frame.try_into()?;
+        assert_eq!(result.replid, "?");
+        assert_eq!(result.offset, -1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,569 @@
+use crate::cmd::{
+    Command, CommandError, CommandExecutor, EvalCommand, RESP_OK, ScriptAction, ScriptCommand,
+    ScriptSource, extract_args, extract_int, extract_string, is_command, validate_variadic_command,
+};
+use crate::network;
+use crate::{
+    Backend, BulkString, RespArray, RespFrame, RespMap, RespNull, ScriptError, SimpleError,
+    SimpleString, script_sha,
+};
+use rhai::{
+    AST, Array, Dynamic, Engine, EvalAltResult, ImmutableString, LexError, Map, ParseError,
+    Position, Scope,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How long a script may run, nothing else runs in the meantime.
+const SCRIPT_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+/// What `redis.call` runs commands with, on behalf of the user.
+#[derive(Debug, Clone)]
+struct Redis {
+    backend: Backend,
+    user: Option<String>,
+}
+
+impl CommandExecutor for EvalCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_as(backend, None)
+    }
+}
+
+impl EvalCommand {
+    /// Run the script, the caller holds the backend lock exclusively so it
+    /// runs atomically. The commands it calls are checked against the acl
+    /// rules of the user if any, and propagated one by one.
+    pub(crate) fn execute_as(self, backend: &Backend, user: Option<&str>) -> RespFrame {
+        let ast = match self.script {
+            ScriptSource::Body(source) => load(backend, &source).map(|(_, ast)| ast),
+            ScriptSource::Sha(sha) => backend.script(&sha),
+        };
+        let redis = Redis {
+            backend: backend.clone(),
+            user: user.map(String::from),
+        };
+        match ast.and_then(|ast| run(redis, &ast, self.keys, self.args)) {
+            Ok(frame) => frame,
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for EvalCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let sha = is_command(&value, "evalsha");
+        let name = if sha { "evalsha" } else { "eval" };
+        validate_variadic_command(&value, &[name], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let script = extract_string(args.next(), "script")?;
+        let script = if sha {
+            ScriptSource::Sha(script)
+        } else {
+            ScriptSource::Body(script)
+        };
+        let numkeys = usize::try_from(extract_int(args.next(), "numkeys")?)
+            .map_err(|_| CommandError::InvalidArgument("numkeys can't be negative".to_string()))?;
+        if numkeys > args.len() {
+            return Err(CommandError::InvalidArgument(
+                "numkeys can't be greater than the number of arguments".to_string(),
+            ));
+        }
+        let keys = args
+            .by_ref()
+            .take(numkeys)
+            .map(|arg| extract_string(Some(arg), "key"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let args = args
+            .map(|arg| extract_string(Some(arg), "arg"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(EvalCommand { script, keys, args })
+    }
+}
+
+impl CommandExecutor for ScriptCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self.action {
+            ScriptAction::Load(source) => match load(backend, &source) {
+                Ok((sha, _)) => BulkString::from(sha).into(),
+                Err(e) => e.into(),
+            },
+            ScriptAction::Exists(shas) => RespArray::new(
+                shas.iter()
+                    .map(|sha| RespFrame::Integer(backend.script_exists(sha) as i64))
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            ScriptAction::Flush => {
+                backend.script_flush();
+                RESP_OK.clone()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ScriptCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["script"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let subcommand = extract_string(args.next(), "subcommand")?.to_ascii_lowercase();
+        let action = match subcommand.as_str() {
+            "load" if args.len() == 1 => ScriptAction::Load(extract_string(args.next(), "script")?),
+            "exists" if args.len() > 0 => ScriptAction::Exists(
+                args.map(|arg| extract_string(Some(arg), "sha1"))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            // scripts are always flushed synchronously
+            "flush" if args.len() <= 1 => ScriptAction::Flush,
+            "load" | "exists" | "flush" => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "wrong number of arguments for 'script|{}' command",
+                    subcommand
+                )));
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "unknown subcommand '{}'",
+                    subcommand
+                )));
+            }
+        };
+
+        Ok(ScriptCommand { action })
+    }
+}
+
+/// Compile a script unless it is cached already, returns its digest.
+fn load(backend: &Backend, source: &str) -> Result<(String, Arc<AST>), ScriptError> {
+    let sha = script_sha(source);
+    if let Ok(ast) = backend.script(&sha) {
+        return Ok((sha, ast));
+    }
+    let redis = Redis {
+        backend: backend.clone(),
+        user: None,
+    };
+    let ast = engine(redis)
+        .compile(source)
+        .map_err(|e| ScriptError::Compile(e.to_string()))?;
+    let ast = Arc::new(ast);
+    backend.cache_script(sha.clone(), ast.clone());
+    Ok((sha, ast))
+}
+
+/// An engine where `redis.call(...)`, `redis.pcall(...)`,
+/// `redis.error_reply(...)` and `redis.status_reply(...)` are available. They
+/// are custom syntax as rhai treats any `.call(...)` as a function pointer
+/// call.
+fn engine(redis: Redis) -> Engine {
+    let mut engine = Engine::new();
+    let started = Instant::now();
+    engine.on_progress(move |_| {
+        (started.elapsed() > SCRIPT_TIME_LIMIT).then(|| "script timed out".into())
+    });
+    engine.on_print(|s| debug!("Script printed: {}", s));
+    engine.register_custom_syntax_with_state_raw(
+        "redis",
+        parse_redis_syntax,
+        false,
+        move |context, inputs, _| {
+            let name = inputs[0].get_string_value().unwrap_or_default();
+            let mut args = vec![];
+            for input in &inputs[1..] {
+                push_arg(&mut args, context.eval_expression_tree(input)?).map_err(raise)?;
+            }
+            match name {
+                "call" | "pcall" if args.is_empty() => Err(raise(
+                    "ERR Please specify at least one argument for this redis lib call",
+                )),
+                "call" => match redis.call(args) {
+                    RespFrame::Error(e) => Err(raise(e.0)),
+                    frame => Ok(to_dynamic(frame)),
+                },
+                // like call but an error is returned as an #{err: ...} map
+                "pcall" => Ok(to_dynamic(redis.call(args))),
+                _ if args.len() != 1 => Err(raise(format!(
+                    "ERR wrong number of arguments for redis.{}",
+                    name
+                ))),
+                "error_reply" => Ok(to_dynamic(SimpleError::new(args.remove(0)).into())),
+                _ => Ok(to_dynamic(SimpleString::new(args.remove(0)).into())),
+            }
+        },
+    );
+    engine
+}
+
+/// The symbol expected after the ones of a `redis.<function>(<args>)`
+/// parsed so far.
+fn parse_redis_syntax(
+    symbols: &[ImmutableString],
+    look_ahead: &str,
+    _: &mut Dynamic,
+) -> Result<Option<ImmutableString>, ParseError> {
+    let next = match symbols.len() {
+        1 => ".",
+        2 => "$token$",
+        3 => match symbols[2].as_str() {
+            "call" | "pcall" | "error_reply" | "status_reply" => "(",
+            name => {
+                return Err(LexError::ImproperSymbol(
+                    name.to_string(),
+                    format!("unknown redis function '{}'", name),
+                )
+                .into_err(Position::NONE));
+            }
+        },
+        _ => match (symbols[symbols.len() - 1].as_str(), look_ahead) {
+            (")", _) => return Ok(None),
+            ("(" | "$expr$", ")") => ")",
+            ("(" | ",", _) => "$expr$",
+            _ => ",",
+        },
+    };
+    Ok(Some(next.into()))
+}
+
+/// Evaluate a compiled script with its KEYS and ARGV, the reply is converted
+/// from the value it evaluates to.
+fn run(
+    redis: Redis,
+    ast: &AST,
+    keys: Vec<String>,
+    args: Vec<String>,
+) -> Result<RespFrame, ScriptError> {
+    let mut scope = Scope::new();
+    scope.push_constant(
+        "KEYS",
+        keys.into_iter().map(Dynamic::from).collect::<Array>(),
+    );
+    scope.push_constant(
+        "ARGV",
+        args.into_iter().map(Dynamic::from).collect::<Array>(),
+    );
+    match engine(redis).eval_ast_with_scope::<Dynamic>(&mut scope, ast) {
+        Ok(value) => Ok(to_frame(value)),
+        Err(e) => Err(script_error(*e)),
+    }
+}
+
+impl Redis {
+    /// Run a command from a script as if a client sent it.
+    fn call(&self, args: Vec<String>) -> RespFrame {
+        let backend = &self.backend;
+        let name = args[0].to_ascii_lowercase();
+        let frame: RespFrame = RespArray::new(
+            std::iter::once(name.clone())
+                .chain(args.into_iter().skip(1))
+                .map(|arg| BulkString::from(arg).into())
+                .collect::<Vec<_>>(),
+        )
+        .into();
+        let logged = backend.propagates().then(|| frame.clone());
+        let cmd = match Command::try_from(frame) {
+            Ok(Command::Unrecognized(_)) => {
+                return SimpleError::new("ERR Unknown Redis command called from script").into();
+            }
+            Ok(cmd) => cmd,
+            Err(e) => return SimpleError::new(format!("ERR {}", e)).into(),
+        };
+        if !cmd.is_allowed_in_script() {
+            return SimpleError::new("ERR This Redis command is not allowed from script").into();
+        }
+        if let Some(user) = &self.user
+            && let Err(e) = backend.acl.check(user, &name, &cmd.keys())
+        {
+            return SimpleError::new(e.to_string()).into();
+        }
+        if cmd.is_write() && backend.is_replica() {
+            return SimpleError::new("READONLY You can't write against a read only replica.")
+                .into();
+        }
+        if cmd.denies_oom() && !backend.is_replica() && !backend.reclaim_memory_locked() {
+            return SimpleError::new("OOM command not allowed when used memory > 'maxmemory'.")
+                .into();
+        }
+        network::execute(cmd, logged, backend)
+    }
+}
+
+/// Add an argument of a redis.call, arrays are expanded and numbers
+/// formatted.
+fn push_arg(strings: &mut Vec<String>, arg: Dynamic) -> Result<(), String> {
+    if arg.is_array() {
+        for item in arg.cast::<Array>() {
+            push_arg(strings, item)?;
+        }
+        return Ok(());
+    }
+    if arg.is_string() || arg.is_char() || arg.is_int() || arg.is_float() || arg.is_bool() {
+        strings.push(arg.to_string());
+        return Ok(());
+    }
+    Err("ERR redis lib command arguments must be strings or numbers".to_string())
+}
+
+fn error_reply(msg: impl Into<String>) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("err".into(), msg.into().into());
+    Dynamic::from_map(map)
+}
+
+/// Throw an error reply out of the script, see script_error.
+fn raise(msg: impl Into<String>) -> Box<EvalAltResult> {
+    EvalAltResult::ErrorRuntime(error_reply(msg), Position::NONE).into()
+}
+
+/// A command reply as a script value: integers, strings and arrays as is,
+/// nulls as `()`, status and error replies as `#{ok: ...}` and
+/// `#{err: ...}` maps.
+fn to_dynamic(frame: RespFrame) -> Dynamic {
+    match frame {
+        RespFrame::Integer(i) => i.into(),
+        RespFrame::SimpleString(s) => {
+            let mut map = Map::new();
+            map.insert("ok".into(), s.0.into());
+            Dynamic::from_map(map)
+        }
+        RespFrame::Error(e) => error_reply(e.0),
+        RespFrame::BulkString(s) => String::from_utf8_lossy(&s.0).into_owned().into(),
+        RespFrame::NullBulkString(_) | RespFrame::NullArray(_) | RespFrame::Null(_) => {
+            Dynamic::UNIT
+        }
+        RespFrame::Array(array) => array
+            .0
+            .into_iter()
+            .map(to_dynamic)
+            .collect::<Array>()
+            .into(),
+        RespFrame::Set(set) => set.0.into_iter().map(to_dynamic).collect::<Array>().into(),
+        RespFrame::Boolean(b) => b.into(),
+        RespFrame::Double(d) => d.into(),
+        RespFrame::Map(map) => Dynamic::from_map(
+            map.0
+                .into_iter()
+                .map(|(k, v)| (k.into(), to_dynamic(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// The reply of a script from the value it evaluates to, the reverse of
+/// to_dynamic. Numbers are truncated to integers, false is a null reply
+/// and true is 1.
+fn to_frame(value: Dynamic) -> RespFrame {
+    if value.is_unit() {
+        return RespFrame::Null(RespNull);
+    }
+    if let Ok(i) = value.as_int() {
+        return RespFrame::Integer(i);
+    }
+    if let Ok(f) = value.as_float() {
+        return RespFrame::Integer(f as i64);
+    }
+    if let Ok(b) = value.as_bool() {
+        return if b {
+            RespFrame::Integer(1)
+        } else {
+            RespFrame::Null(RespNull)
+        };
+    }
+    if value.is_array() {
+        let array = value.cast::<Array>().into_iter().map(to_frame);
+        return RespArray::new(array.collect::<Vec<_>>()).into();
+    }
+    if value.is_map() {
+        let mut map = value.cast::<Map>();
+        if let Some(err) = map.remove("err") {
+            return SimpleError::new(err.to_string()).into();
+        }
+        if let Some(ok) = map.remove("ok") {
+            return SimpleString::new(ok.to_string()).into();
+        }
+        let mut frame = RespMap::new();
+        for (k, v) in map {
+            frame.insert(k.to_string(), to_frame(v));
+        }
+        return frame.into();
+    }
+    BulkString::from(value.to_string()).into()
+}
+
+fn script_error(e: EvalAltResult) -> ScriptError {
+    match e {
+        // an error raised within a function defined by the script
+        EvalAltResult::ErrorInFunctionCall(_, _, e, _) => script_error(*e),
+        EvalAltResult::ErrorRuntime(value, _) if value.is_map() => {
+            match value.cast::<Map>().remove("err") {
+                Some(err) => ScriptError::Reply(err.to_string()),
+                None => ScriptError::Run("script threw a map without err".to_string()),
+            }
+        }
+        e => ScriptError::Run(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn decode(buf: &[u8]) -> Result<RespArray> {
+        Ok(RespArray::decode(&mut BytesMut::from(buf))?)
+    }
+
+    fn eval(backend: &Backend, script: &str, keys: &[&str], args: &[&str]) -> RespFrame {
+        EvalCommand {
+            script: ScriptSource::Body(script.to_string()),
+            keys: keys.iter().map(|s| s.to_string()).collect(),
+            args: args.iter().map(|s| s.to_string()).collect(),
+        }
+        .execute(backend)
+    }
+
+    #[test]
+    fn test_eval_from_resp_array() -> Result<()> {
+        let frame =
+            decode(b"*5\r\n$4\r\nEVAL\r\n$6\r\nreturn\r\n$1\r\n1\r\n$1\r\nk\r\n$1\r\na\r\n")?;
+        let cmd = EvalCommand::try_from(frame)?;
+        assert_eq!(cmd.script, ScriptSource::Body("return".to_string()));
+        assert_eq!(cmd.keys, vec!["k"]);
+        assert_eq!(cmd.args, vec!["a"]);
+
+        let frame = decode(b"*4\r\n$7\r\nevalsha\r\n$3\r\nabc\r\n$1\r\n3\r\n$1\r\nk\r\n")?;
+        assert!(EvalCommand::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_script_from_resp_array() -> Result<()> {
+        let frame = decode(b"*4\r\n$6\r\nscript\r\n$6\r\nEXISTS\r\n$1\r\na\r\n$1\r\nb\r\n")?;
+        let cmd = ScriptCommand::try_from(frame)?;
+        assert_eq!(
+            cmd.action,
+            ScriptAction::Exists(vec!["a".to_string(), "b".to_string()])
+        );
+
+        let frame = decode(b"*2\r\n$6\r\nscript\r\n$4\r\nload\r\n")?;
+        assert!(ScriptCommand::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_should_call_commands() {
+        let backend = Backend::new();
+        let script = r#"
+            let current = redis.call("get", KEYS[0]);
+            if current == () {
+                redis.call("SET", KEYS[0], ARGV[0]);
+                return true;
+            }
+            false
+        "#;
+        assert_eq!(
+            eval(&backend, script, &["lock"], &["me"]),
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            eval(&backend, script, &["lock"], &["you"]),
+            RespFrame::Null(RespNull)
+        );
+        assert_eq!(backend.get("lock"), Ok(Some(BulkString::from("me").into())));
+
+        let ret = eval(
+            &backend,
+            r#"redis.call("rpush", "list", [1, 2], "3")"#,
+            &[],
+            &[],
+        );
+        assert_eq!(ret, RespFrame::Integer(3));
+        let ret = eval(&backend, r#"redis.call("lrange", "list", 0, -1)"#, &[], &[]);
+        assert_eq!(
+            ret,
+            RespArray::new(vec![
+                BulkString::from("1").into(),
+                BulkString::from("2").into(),
+                BulkString::from("3").into(),
+            ])
+            .into()
+        );
+        let ret = eval(&backend, r#"redis.call("set", "k", "v")"#, &[], &[]);
+        assert_eq!(ret, SimpleString::new("OK").into());
+    }
+
+    #[test]
+    fn test_eval_should_return_errors() {
+        let backend = Backend::new();
+        backend.set("s".to_string(), BulkString::from("v").into());
+
+        let ret = eval(&backend, r#"redis.call("lpush", "s", "a"); 1"#, &[], &[]);
+        assert_eq!(
+            ret,
+            SimpleError::new("WRONGTYPE Operation against a key holding the wrong kind of value")
+                .into()
+        );
+        let ret = eval(&backend, r#"redis.pcall("lpush", "s", "a").err"#, &[], &[]);
+        assert!(matches!(ret, RespFrame::BulkString(_)));
+        let ret = eval(&backend, r#"redis.call("nosuchcommand")"#, &[], &[]);
+        assert_eq!(
+            ret,
+            SimpleError::new("ERR Unknown Redis command called from script").into()
+        );
+        let ret = eval(&backend, r#"redis.call("multi")"#, &[], &[]);
+        assert_eq!(
+            ret,
+            SimpleError::new("ERR This Redis command is not allowed from script").into()
+        );
+        let ret = eval(&backend, r#"redis.error_reply("MY error")"#, &[], &[]);
+        assert_eq!(ret, SimpleError::new("MY error").into());
+        let ret = eval(&backend, "let x = ;", &[], &[]);
+        assert!(
+            matches!(ret, RespFrame::Error(e) if e.0.starts_with("ERR Error compiling script"))
+        );
+        let ret = eval(&backend, r#"throw "oops""#, &[], &[]);
+        assert!(matches!(ret, RespFrame::Error(e) if e.0.starts_with("ERR Error running script")));
+    }
+
+    #[test]
+    fn test_evalsha_should_run_loaded_script() {
+        let backend = Backend::new();
+        let load = ScriptCommand {
+            action: ScriptAction::Load("ARGV[0] + ARGV[1]".to_string()),
+        };
+        let RespFrame::BulkString(sha) = load.execute(&backend) else {
+            panic!("SCRIPT LOAD should reply with the sha1");
+        };
+        let sha = String::from_utf8(sha.0).unwrap();
+        let exists = ScriptCommand {
+            action: ScriptAction::Exists(vec![sha.clone(), "nope".to_string()]),
+        };
+        assert_eq!(
+            exists.execute(&backend),
+            RespArray::new(vec![RespFrame::Integer(1), RespFrame::Integer(0)]).into()
+        );
+
+        let evalsha = |sha: &str| EvalCommand {
+            script: ScriptSource::Sha(sha.to_string()),
+            keys: vec![],
+            args: vec!["a".to_string(), "b".to_string()],
+        };
+        assert_eq!(
+            evalsha(&sha).execute(&backend),
+            BulkString::from("ab").into()
+        );
+        assert_eq!(
+            evalsha("nope").execute(&backend),
+            ScriptError::NoScript.into()
+        );
+    }
+}
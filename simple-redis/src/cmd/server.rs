@@ -0,0 +1,365 @@
+use crate::cmd::{
+    CommandError, CommandExecutor, ConfigGetCommand, InfoCommand, RESP_OK, SaveCommand,
+    SlowlogAction, SlowlogCommand, extract_args, extract_int, extract_string, is_command,
+    validate_command, validate_variadic_command,
+};
+use crate::config::PARAMETERS;
+use crate::{Backend, BulkString, RespArray, RespFrame, RespMap, SimpleError, SimpleString};
+use crate::{glob_match, persistence};
+use std::sync::atomic::Ordering;
+use tracing::{info, warn};
+
+impl CommandExecutor for SaveCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if !self.background {
+            return match persistence::save(backend) {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => SimpleError::new(format!("ERR {}", e)).into(),
+            };
+        }
+
+        if backend.bgsave_in_progress.swap(true, Ordering::SeqCst) {
+            return SimpleError::new("ERR Background save already in progress").into();
+        }
+        // taken while writes are locked out, only writing it goes off the lock
+        let snapshot = persistence::snapshot(backend);
+        let backend = backend.clone();
+        std::thread::spawn(move || {
+            match persistence::save_snapshot(&backend, &snapshot) {
+                Ok(()) => info!("Background saving terminated with success"),
+                Err(e) => warn!("Background saving failed: {:?}", e),
+            }
+            backend.bgsave_in_progress.store(false, Ordering::SeqCst);
+        });
+        SimpleString::new("Background saving started").into()
+    }
+}
+
+impl TryFrom<RespArray> for SaveCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let background = is_command(&value, "bgsave");
+        validate_command(&value, &[if background { "bgsave" } else { "save" }], 0)?;
+        Ok(SaveCommand { background })
+    }
+}
+
+impl CommandExecutor for ConfigGetCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut map = RespMap::new();
+        for name in PARAMETERS {
+            let matched = self
+                .patterns
+                .iter()
+                .any(|pattern| glob_match(pattern.as_bytes(), name.as_bytes()));
+            if matched && let Some(value) = backend.config.get(name) {
+                map.insert(name.to_string(), BulkString::from(value).into());
+            }
+        }
+        map.into()
+    }
+}
+
+impl TryFrom<RespArray> for ConfigGetCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["config", "get"], 1)?;
+        let patterns = extract_args(value, 2)?
+            .into_iter()
+            .map(|pattern| Ok(extract_string(Some(pattern), "pattern")?.to_ascii_lowercase()))
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        Ok(ConfigGetCommand { patterns })
+    }
+}
+
+impl CommandExecutor for InfoCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let section = self.section.as_deref().unwrap_or("default");
+        let sections = [
+            ("Server", backend.server_info()),
+            ("Clients", backend.clients_info()),
+            ("Memory", backend.memory_info()),
+            ("Stats", backend.stats_info()),
+            ("Replication", backend.replication_info()),
+            ("Cluster", backend.cluster_info()),
+            ("Commandstats", backend.commandstats_info()),
+            ("Keyspace", backend.keyspace_info()),
+        ];
+        let info = sections
+            .into_iter()
+            .filter(|(name, _)| match section {
+                // commandstats is only listed when asked for
+                "default" => *name != "Commandstats",
+                "all" | "everything" => true,
+                _ => name.eq_ignore_ascii_case(section),
+            })
+            .map(|(name, info)| format!("# {}\r\n{}\r\n", name, info))
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        BulkString::from(info).into()
+    }
+}
+
+impl TryFrom<RespArray> for InfoCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["info"], 0)?;
+        if value.len() > 2 {
+            return Err(CommandError::InvalidArgument(
+                "info command must have at most 1 argument".to_string(),
+            ));
+        }
+        let section = match extract_args(value, 1)?.into_iter().next() {
+            Some(section) => Some(extract_string(Some(section), "section")?.to_ascii_lowercase()),
+            None => None,
+        };
+        Ok(InfoCommand { section })
+    }
+}
+
+impl CommandExecutor for SlowlogCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self.action {
+            SlowlogAction::Get(count) => {
+                let count = match count {
+                    None => Some(10),
+                    Some(count) if count < 0 => None,
+                    Some(count) => Some(count as usize),
+                };
+                let entries = backend
+                    .slowlog_get(count)
+                    .iter()
+                    .map(|entry| entry.to_frame())
+                    .collect::<Vec<_>>();
+                RespArray::new(entries).into()
+            }
+            SlowlogAction::Len => RespFrame::Integer(backend.slowlog_len() as i64),
+            SlowlogAction::Reset => {
+                backend.slowlog_reset();
+                RESP_OK.clone()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SlowlogCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["slowlog"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let subcommand = extract_string(args.next(), "subcommand")?.to_ascii_lowercase();
+        let action = match subcommand.as_str() {
+            "get" if args.len() == 0 => SlowlogAction::Get(None),
+            "get" if args.len() == 1 => {
+                SlowlogAction::Get(Some(extract_int(args.next(), "count")?))
+            }
+            "len" if args.len() == 0 => SlowlogAction::Len,
+            "reset" if args.len() == 0 => SlowlogAction::Reset,
+            "get" | "len" | "reset" => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "wrong number of arguments for 'slowlog|{}' command",
+                    subcommand
+                )));
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "unknown subcommand '{}'",
+                    subcommand
+                )));
+            }
+        };
+
+        Ok(SlowlogCommand { action })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use crate::persistence::PersistenceConfig;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_bgsave_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$6\r\nBGSAVE\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SaveCommand = frame.try_into()?;
+        assert!(result.background);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_command() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("simple-redis-save-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let backend = Backend::with_persistence(PersistenceConfig {
+            dir: dir.clone(),
+            ..Default::default()
+        });
+
+        let cmd = SaveCommand { background: false };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert!(dir.join("dump.rdb").exists());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_bgsave_should_snapshot_the_time_it_is_called() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("simple-redis-bgsave-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let backend = Backend::with_persistence(PersistenceConfig {
+            dir: dir.clone(),
+            ..Default::default()
+        });
+        backend.set("key".to_string(), BulkString::from("before").into());
+
+        let cmd = SaveCommand { background: true };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleString::new("Background saving started").into()
+        );
+        backend.set("key".to_string(), BulkString::from("after").into());
+        while backend.bgsave_in_progress.load(Ordering::SeqCst) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let restored = Backend::new();
+        let mut buf = BytesMut::from(std::fs::read(dir.join("dump.rdb"))?.as_slice());
+        persistence::restore(&restored, &mut buf)?;
+        assert_eq!(
+            restored.get("key")?,
+            Some(BulkString::from("before").into())
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_get_command() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nconfig\r\n$3\r\nGET\r\n$7\r\nAPPEND*\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: ConfigGetCommand = frame.try_into()?;
+        let backend = Backend::new();
+        let mut expected = RespMap::new();
+        expected.insert(
+            "appendfilename".to_string(),
+            BulkString::from("appendonly.aof").into(),
+        );
+        expected.insert(
+            "appendfsync".to_string(),
+            BulkString::from("everysec").into(),
+        );
+        expected.insert("appendonly".to_string(), BulkString::from("no").into());
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_info_replication_command() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\ninfo\r\n$11\r\nReplication\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: InfoCommand = frame.try_into()?;
+        assert_eq!(cmd.section.as_deref(), Some("replication"));
+        let backend = Backend::new();
+        let RespFrame::BulkString(info) = cmd.execute(&backend) else {
+            panic!("INFO should reply with a bulk string");
+        };
+        let info = String::from_utf8(info.0)?;
+        assert!(info.starts_with("# Replication\r\nrole:master\r\nconnected_slaves:0\r\n"));
+        assert!(info.contains("master_repl_offset:0\r\n"));
+
+        let cmd = InfoCommand {
+            section: Some("nope".to_string()),
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::from("").into());
+
+        let cmd = InfoCommand { section: None };
+        let RespFrame::BulkString(info) = cmd.execute(&backend) else {
+            panic!("INFO should reply with a bulk string");
+        };
+        let info = String::from_utf8(info.0)?;
+        assert!(info.starts_with("# Server\r\nredis_version:"));
+        assert!(info.contains("# Clients\r\nconnected_clients:0\r\n"));
+        assert!(info.contains("# Memory\r\nused_memory:0\r\n"));
+        assert!(info.contains("maxmemory_policy:noeviction\r\n"));
+        assert!(info.contains("# Stats\r\nexpired_keys:0\r\nevicted_keys:0\r\n"));
+        assert!(info.contains("# Replication\r\n"));
+        assert!(!info.contains("# Commandstats"));
+        assert!(info.ends_with("# Keyspace\r\n\r\n"));
+
+        let cmd = InfoCommand {
+            section: Some("all".to_string()),
+        };
+        let RespFrame::BulkString(info) = cmd.execute(&backend) else {
+            panic!("INFO should reply with a bulk string");
+        };
+        assert!(String::from_utf8(info.0)?.contains("# Commandstats\r\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_slowlog_command() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$7\r\nslowlog\r\n$3\r\nGET\r\n$2\r\n-1\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let cmd: SlowlogCommand = frame.try_into()?;
+        assert_eq!(cmd.action, SlowlogAction::Get(Some(-1)));
+
+        let backend = Backend::new();
+        let addr = "127.0.0.1:4000".parse()?;
+        for _ in 0..12 {
+            backend.log_if_slow(
+                vec![BulkString::from("get")],
+                std::time::Duration::from_secs(1),
+                addr,
+                None,
+            );
+        }
+        let RespFrame::Array(entries) = cmd.execute(&backend) else {
+            panic!("SLOWLOG GET should reply with an array");
+        };
+        assert_eq!(entries.len(), 12);
+        let cmd = SlowlogCommand {
+            action: SlowlogAction::Get(None),
+        };
+        let RespFrame::Array(entries) = cmd.execute(&backend) else {
+            panic!("SLOWLOG GET should reply with an array");
+        };
+        assert_eq!(entries.len(), 10);
+
+        let cmd = SlowlogCommand {
+            action: SlowlogAction::Reset,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd = SlowlogCommand {
+            action: SlowlogAction::Len,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1023 @@
+use crate::cmd::{
+    ClaimOptions, CommandError, CommandExecutor, RESP_OK, StreamTrim, XAckCommand, XAddCommand,
+    XAddId, XClaimCommand, XGroupAction, XGroupCommand, XLenCommand, XPendingCommand,
+    XPendingRange, XRangeCommand, XReadCommand, XReadGroupCommand, XSetIdCommand, XTrimCommand,
+    extract_args, extract_int, extract_string, is_command, validate_command,
+    validate_variadic_command,
+};
+use crate::{
+    Backend, BulkString, Fields, RespArray, RespFrame, RespNull, RespNullArray, StreamId, now_ms,
+};
+use std::iter::Peekable;
+use std::ops::Bound;
+use std::time::Duration;
+use std::vec::IntoIter;
+use tokio::time::Instant;
+
+type Args = Peekable<IntoIter<RespFrame>>;
+
+impl CommandExecutor for XAddCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xadd(self.key, self.id, self.fields, self.no_mkstream, self.trim) {
+            Ok(Some(id)) => BulkString::from(id.to_string()).into(),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XAddCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["xadd"], 4)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        let key = extract_string(args.next(), "key")?;
+        let mut no_mkstream = false;
+        let mut trim = None;
+        while let Some(option) = peek_option(&mut args) {
+            match option.as_str() {
+                "nomkstream" => {
+                    args.next();
+                    no_mkstream = true;
+                }
+                "maxlen" | "minid" => trim = Some(extract_trim(&mut args)?),
+                _ => break,
+            }
+        }
+
+        let id = match extract_string(args.next(), "id")?.as_str() {
+            "*" => XAddId::Auto,
+            id => match id.strip_suffix("-*") {
+                Some(ms) => XAddId::Partial(ms.parse().map_err(|_| invalid_id())?),
+                None => XAddId::Explicit(StreamId::parse(id, 0).ok_or_else(invalid_id)?),
+            },
+        };
+        let args = args.collect::<Vec<_>>();
+        if args.is_empty() || !args.len().is_multiple_of(2) {
+            return Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'xadd' command".to_string(),
+            ));
+        }
+        let mut fields = Vec::with_capacity(args.len() / 2);
+        let mut args = args.into_iter();
+        while let (Some(field), Some(value)) = (args.next(), args.next()) {
+            fields.push((extract_string(Some(field), "field")?, value));
+        }
+
+        Ok(XAddCommand {
+            key,
+            id,
+            fields,
+            no_mkstream,
+            trim,
+        })
+    }
+}
+
+impl CommandExecutor for XLenCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xlen(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XLenCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["xlen"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(XLenCommand {
+            key: extract_string(args.next(), "key")?,
+        })
+    }
+}
+
+impl CommandExecutor for XRangeCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xrange(&self.key, self.start, self.end, self.count, self.rev) {
+            Ok(entries) => entries_frame(entries),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XRangeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let rev = is_command(&value, "xrevrange");
+        let name = if rev { "xrevrange" } else { "xrange" };
+        validate_variadic_command(&value, &[name], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        let key = extract_string(args.next(), "key")?;
+        // XREVRANGE takes the end first
+        let (first, second) = (args.next(), args.next());
+        let (start, end) = if rev {
+            (second, first)
+        } else {
+            (first, second)
+        };
+        let start = extract_bound(start, 0)?;
+        let end = extract_bound(end, u64::MAX)?;
+        let count = match peek_option(&mut args).as_deref() {
+            Some("count") => {
+                args.next();
+                Some(extract_count(args.next())?)
+            }
+            None => None,
+            Some(_) => return Err(syntax_error()),
+        };
+        if args.next().is_some() {
+            return Err(syntax_error());
+        }
+
+        Ok(XRangeCommand {
+            key,
+            start,
+            end,
+            count,
+            rev,
+        })
+    }
+}
+
+impl CommandExecutor for XTrimCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xtrim(&self.key, self.trim) {
+            Ok(removed) => RespFrame::Integer(removed as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XTrimCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["xtrim"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        let key = extract_string(args.next(), "key")?;
+        let trim = extract_trim(&mut args)?;
+        if args.next().is_some() {
+            return Err(syntax_error());
+        }
+        Ok(XTrimCommand { key, trim })
+    }
+}
+
+impl CommandExecutor for XSetIdCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xsetid(&self.key, self.id) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XSetIdCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["xsetid"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(XSetIdCommand {
+            key: extract_string(args.next(), "key")?,
+            id: extract_id(args.next(), 0)?,
+        })
+    }
+}
+
+impl XReadCommand {
+    /// The ids to read after, with `$` turned into the last id of the stream
+    /// so that only the entries added from now on are read.
+    fn resolve_ids(&self, backend: &Backend) -> Result<Vec<StreamId>, RespFrame> {
+        self.keys
+            .iter()
+            .zip(&self.ids)
+            .map(|(key, id)| match id {
+                Some(id) => Ok(*id),
+                None => Ok(backend.xlast_id(key)?.unwrap_or_default()),
+            })
+            .collect()
+    }
+
+    /// The streams with entries after the ids, None if there is none.
+    fn read(&self, backend: &Backend, ids: &[StreamId]) -> Option<RespFrame> {
+        let mut streams = vec![];
+        for (key, id) in self.keys.iter().zip(ids) {
+            match backend.xread(key, *id, self.count) {
+                Ok(entries) if entries.is_empty() => {}
+                Ok(entries) => streams.push(stream_frame(key, entries_frame(entries))),
+                Err(e) => return Some(e.into()),
+            }
+        }
+        (!streams.is_empty()).then(|| RespArray::new(streams).into())
+    }
+
+    /// Wait for entries until the BLOCK timeout passes, in which case a null
+    /// array is returned.
+    pub async fn execute_blocking(self, backend: &Backend) -> RespFrame {
+        let ids = {
+            let _guard = backend.shared();
+            match self.resolve_ids(backend) {
+                Ok(ids) => ids,
+                Err(e) => return e,
+            }
+        };
+        block_on(backend, &self.keys, self.block.unwrap_or_default(), || {
+            let _guard = backend.shared();
+            let frame = self.read(backend, &ids)?;
+            for key in &self.keys {
+                backend.record_access(key);
+            }
+            Some(frame)
+        })
+        .await
+    }
+}
+
+impl CommandExecutor for XReadCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self.resolve_ids(backend) {
+            Ok(ids) => self
+                .read(backend, &ids)
+                .unwrap_or(RespFrame::NullArray(RespNullArray)),
+            Err(e) => e,
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XReadCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["xread"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        let mut count = None;
+        let mut block = None;
+        loop {
+            match peek_option(&mut args).as_deref() {
+                Some("count") => {
+                    args.next();
+                    count = Some(extract_count(args.next())?);
+                }
+                Some("block") => {
+                    args.next();
+                    block = Some(extract_timeout(args.next())?);
+                }
+                Some("streams") => {
+                    args.next();
+                    break;
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        let (keys, ids) = extract_streams(args, "$")?;
+
+        Ok(XReadCommand {
+            keys,
+            ids,
+            count,
+            block,
+        })
+    }
+}
+
+impl CommandExecutor for XGroupCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self.action {
+            XGroupAction::Create { id, mkstream } => {
+                match backend.xgroup_create(self.key, self.group, id, mkstream) {
+                    Ok(()) => RESP_OK.clone(),
+                    Err(e) => e.into(),
+                }
+            }
+            XGroupAction::Destroy => match backend.xgroup_destroy(&self.key, &self.group) {
+                Ok(destroyed) => RespFrame::Integer(destroyed as i64),
+                Err(e) => e.into(),
+            },
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XGroupCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["xgroup"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        let subcommand = extract_string(args.next(), "subcommand")?.to_ascii_lowercase();
+        let key = extract_string(args.next(), "key")?;
+        let group = extract_string(args.next(), "group")?;
+        let action = match subcommand.as_str() {
+            "create" => {
+                let id = match extract_string(args.next(), "id")?.as_str() {
+                    "$" => None,
+                    id => Some(StreamId::parse(id, 0).ok_or_else(invalid_id)?),
+                };
+                let mut mkstream = false;
+                while let Some(option) = peek_option(&mut args) {
+                    args.next();
+                    match option.as_str() {
+                        "mkstream" => mkstream = true,
+                        // the lag of the group is not tracked
+                        "entriesread" => {
+                            extract_int(args.next(), "entries read")?;
+                        }
+                        _ => return Err(syntax_error()),
+                    }
+                }
+                XGroupAction::Create { id, mkstream }
+            }
+            "destroy" => XGroupAction::Destroy,
+            _ => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "unknown subcommand '{}'",
+                    subcommand
+                )));
+            }
+        };
+        if args.next().is_some() {
+            return Err(syntax_error());
+        }
+
+        Ok(XGroupCommand { key, group, action })
+    }
+}
+
+impl XReadGroupCommand {
+    /// The entries read for each stream, None if there is nothing to reply
+    /// yet.
+    fn read(&self, backend: &Backend) -> Option<RespFrame> {
+        let mut streams = vec![];
+        for (key, id) in self.keys.iter().zip(&self.ids) {
+            let entries = backend.xreadgroup(
+                key,
+                &self.group,
+                &self.consumer,
+                *id,
+                self.count,
+                self.no_ack,
+            );
+            match entries {
+                // the history of a consumer is replied even when empty
+                Ok(entries) if entries.is_empty() && id.is_none() => {}
+                Ok(entries) => {
+                    let entries = entries
+                        .into_iter()
+                        .map(|(id, fields)| match fields {
+                            Some(fields) => entry_frame(id, fields),
+                            None => {
+                                RespArray::new([id_frame(id), RespFrame::Null(RespNull)]).into()
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    streams.push(stream_frame(key, RespArray::new(entries).into()));
+                }
+                Err(e) => return Some(e.into()),
+            }
+        }
+        (!streams.is_empty()).then(|| RespArray::new(streams).into())
+    }
+
+    /// Wait for new entries until the BLOCK timeout passes. Reading the
+    /// history of the consumer never blocks.
+    pub async fn execute_blocking(self, backend: &Backend) -> RespFrame {
+        if self.ids.iter().any(Option::is_some) {
+            let _guard = backend.shared();
+            return self.execute(backend);
+        }
+        block_on(backend, &self.keys, self.block.unwrap_or_default(), || {
+            let _guard = backend.shared();
+            let frame = self.read(backend)?;
+            for key in &self.keys {
+                backend.touch(key);
+                backend.record_access(key);
+            }
+            Some(frame)
+        })
+        .await
+    }
+}
+
+impl CommandExecutor for XReadGroupCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.read(backend)
+            .unwrap_or(RespFrame::NullArray(RespNullArray))
+    }
+}
+
+impl TryFrom<RespArray> for XReadGroupCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["xreadgroup"], 6)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        if peek_option(&mut args).as_deref() != Some("group") {
+            return Err(syntax_error());
+        }
+        args.next();
+        let group = extract_string(args.next(), "group")?;
+        let consumer = extract_string(args.next(), "consumer")?;
+        let mut count = None;
+        let mut block = None;
+        let mut no_ack = false;
+        loop {
+            match peek_option(&mut args).as_deref() {
+                Some("count") => {
+                    args.next();
+                    count = Some(extract_count(args.next())?);
+                }
+                Some("block") => {
+                    args.next();
+                    block = Some(extract_timeout(args.next())?);
+                }
+                Some("noack") => {
+                    args.next();
+                    no_ack = true;
+                }
+                Some("streams") => {
+                    args.next();
+                    break;
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        let (keys, ids) = extract_streams(args, ">")?;
+
+        Ok(XReadGroupCommand {
+            group,
+            consumer,
+            keys,
+            ids,
+            count,
+            block,
+            no_ack,
+        })
+    }
+}
+
+impl CommandExecutor for XAckCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xack(&self.key, &self.group, &self.ids) {
+            Ok(acked) => RespFrame::Integer(acked as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XAckCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["xack"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(XAckCommand {
+            key: extract_string(args.next(), "key")?,
+            group: extract_string(args.next(), "group")?,
+            ids: args
+                .map(|id| extract_id(Some(id), 0))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl CommandExecutor for XPendingCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let Some(range) = self.range else {
+            return match backend.xpending_summary(&self.key, &self.group) {
+                Ok(summary) => {
+                    let (first, last) = match summary.range {
+                        Some((first, last)) => (id_frame(first), id_frame(last)),
+                        None => (RespFrame::Null(RespNull), RespFrame::Null(RespNull)),
+                    };
+                    let consumers = if summary.consumers.is_empty() {
+                        RespFrame::NullArray(RespNullArray)
+                    } else {
+                        let consumers = summary.consumers.into_iter().map(|(consumer, count)| {
+                            RespArray::new([
+                                BulkString::from(consumer).into(),
+                                BulkString::from(count.to_string()).into(),
+                            ])
+                            .into()
+                        });
+                        RespArray::new(consumers.collect::<Vec<_>>()).into()
+                    };
+                    RespArray::new([
+                        RespFrame::Integer(summary.count as i64),
+                        first,
+                        last,
+                        consumers,
+                    ])
+                    .into()
+                }
+                Err(e) => e.into(),
+            };
+        };
+
+        let pending = backend.xpending(
+            &self.key,
+            &self.group,
+            range.start,
+            range.end,
+            range.count,
+            range.consumer.as_deref(),
+            range.idle,
+        );
+        match pending {
+            Ok(pending) => {
+                let now = now_ms();
+                let entries = pending.into_iter().map(|(id, pending)| {
+                    RespArray::new([
+                        id_frame(id),
+                        BulkString::from(pending.consumer).into(),
+                        RespFrame::Integer(now.saturating_sub(pending.delivered_at) as i64),
+                        RespFrame::Integer(pending.deliveries as i64),
+                    ])
+                    .into()
+                });
+                RespArray::new(entries.collect::<Vec<_>>()).into()
+            }
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XPendingCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["xpending"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        let key = extract_string(args.next(), "key")?;
+        let group = extract_string(args.next(), "group")?;
+        let range = if args.peek().is_some() {
+            let idle = if peek_option(&mut args).as_deref() == Some("idle") {
+                args.next();
+                Some(extract_timeout(args.next())?)
+            } else {
+                None
+            };
+            let range = XPendingRange {
+                idle,
+                start: extract_bound(args.next(), 0)?,
+                end: extract_bound(args.next(), u64::MAX)?,
+                count: extract_count(args.next())?,
+                consumer: args
+                    .next()
+                    .map(|consumer| extract_string(Some(consumer), "consumer"))
+                    .transpose()?,
+            };
+            if args.next().is_some() {
+                return Err(syntax_error());
+            }
+            Some(range)
+        } else {
+            None
+        };
+
+        Ok(XPendingCommand { key, group, range })
+    }
+}
+
+impl CommandExecutor for XClaimCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let claimed = backend.xclaim(
+            &self.key,
+            &self.group,
+            &self.consumer,
+            self.min_idle,
+            &self.ids,
+            self.options,
+        );
+        match claimed {
+            Ok(claimed) if self.options.just_id => {
+                let ids = claimed.into_iter().map(|(id, _)| id_frame(id));
+                RespArray::new(ids.collect::<Vec<_>>()).into()
+            }
+            Ok(claimed) => entries_frame(claimed),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for XClaimCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["xclaim"], 5)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        let key = extract_string(args.next(), "key")?;
+        let group = extract_string(args.next(), "group")?;
+        let consumer = extract_string(args.next(), "consumer")?;
+        let min_idle = extract_timeout(args.next())?;
+        // the ids go on until the first option
+        let mut ids = vec![];
+        while let Some(RespFrame::BulkString(id)) = args.peek() {
+            let Some(id) = std::str::from_utf8(id)
+                .ok()
+                .and_then(|id| StreamId::parse(id, 0))
+            else {
+                break;
+            };
+            ids.push(id);
+            args.next();
+        }
+        if ids.is_empty() {
+            return Err(invalid_id());
+        }
+
+        let mut options = ClaimOptions::default();
+        while let Some(option) = peek_option(&mut args) {
+            args.next();
+            match option.as_str() {
+                "idle" => options.idle = Some(extract_timeout(args.next())?),
+                "time" => options.time = Some(extract_timeout(args.next())?),
+                "retrycount" => options.retry_count = Some(extract_timeout(args.next())?),
+                "force" => options.force = true,
+                "justid" => options.just_id = true,
+                "lastid" => {
+                    extract_id(args.next(), 0)?;
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        if args.next().is_some() {
+            return Err(syntax_error());
+        }
+
+        Ok(XClaimCommand {
+            key,
+            group,
+            consumer,
+            min_idle,
+            ids,
+            options,
+        })
+    }
+}
+
+/// Retry `read` every time an entry is added to one of the streams until it
+/// returns a reply or `block` milliseconds pass, 0 blocks forever.
+async fn block_on(
+    backend: &Backend,
+    keys: &[String],
+    block: u64,
+    read: impl Fn() -> Option<RespFrame>,
+) -> RespFrame {
+    let deadline = (block > 0).then(|| Instant::now() + Duration::from_millis(block));
+    // queued before reading so an entry added in between can not be missed
+    let blocked = backend.block_on_streams(keys);
+    loop {
+        if let Some(frame) = read() {
+            return frame;
+        }
+
+        match deadline {
+            Some(deadline) => {
+                if tokio::time::timeout_at(deadline, blocked.woken())
+                    .await
+                    .is_err()
+                {
+                    return RespFrame::NullArray(RespNullArray);
+                }
+            }
+            None => blocked.woken().await,
+        }
+    }
+}
+
+fn id_frame(id: StreamId) -> RespFrame {
+    BulkString::from(id.to_string()).into()
+}
+
+/// `[id, [field, value, ...]]`
+fn entry_frame(id: StreamId, fields: Fields) -> RespFrame {
+    let fields = fields
+        .into_iter()
+        .flat_map(|(field, value)| [BulkString::from(field).into(), value])
+        .collect::<Vec<_>>();
+    RespArray::new([id_frame(id), RespArray::new(fields).into()]).into()
+}
+
+fn entries_frame(entries: Vec<(StreamId, Fields)>) -> RespFrame {
+    let entries = entries
+        .into_iter()
+        .map(|(id, fields)| entry_frame(id, fields))
+        .collect::<Vec<_>>();
+    RespArray::new(entries).into()
+}
+
+fn stream_frame(key: &str, entries: RespFrame) -> RespFrame {
+    RespArray::new([BulkString::from(key).into(), entries]).into()
+}
+
+/// The lowercase name of the next argument if it is a bulk string.
+fn peek_option(args: &mut Args) -> Option<String> {
+    match args.peek() {
+        Some(RespFrame::BulkString(option)) => {
+            Some(String::from_utf8_lossy(option).to_ascii_lowercase())
+        }
+        _ => None,
+    }
+}
+
+fn invalid_id() -> CommandError {
+    CommandError::InvalidArgument(
+        "Invalid stream ID specified as stream command argument".to_string(),
+    )
+}
+
+fn syntax_error() -> CommandError {
+    CommandError::InvalidArgument("syntax error".to_string())
+}
+
+fn extract_id(frame: Option<RespFrame>, default_seq: u64) -> Result<StreamId, CommandError> {
+    StreamId::parse(&extract_string(frame, "id")?, default_seq).ok_or_else(invalid_id)
+}
+
+/// A range bound: `-` and `+` for the smallest and greatest ids, a leading
+/// `(` for an exclusive bound. The sequence number of an id given as
+/// milliseconds only defaults to `default_seq`.
+fn extract_bound(
+    frame: Option<RespFrame>,
+    default_seq: u64,
+) -> Result<Bound<StreamId>, CommandError> {
+    let id = extract_string(frame, "id")?;
+    match id.as_str() {
+        "-" | "+" => Ok(Bound::Unbounded),
+        id => match id.strip_prefix('(') {
+            Some(id) => Ok(Bound::Excluded(
+                StreamId::parse(id, default_seq).ok_or_else(invalid_id)?,
+            )),
+            None => Ok(Bound::Included(
+                StreamId::parse(id, default_seq).ok_or_else(invalid_id)?,
+            )),
+        },
+    }
+}
+
+fn extract_count(frame: Option<RespFrame>) -> Result<usize, CommandError> {
+    // a negative count means no limit
+    Ok(usize::try_from(extract_int(frame, "count")?).unwrap_or(usize::MAX))
+}
+
+/// Milliseconds, e.g. the BLOCK timeout.
+fn extract_timeout(frame: Option<RespFrame>) -> Result<u64, CommandError> {
+    u64::try_from(extract_int(frame, "timeout")?)
+        .map_err(|_| CommandError::InvalidArgument("timeout is negative".to_string()))
+}
+
+/// `MAXLEN [=|~] <count>` or `MINID [=|~] <id>`, approximate trimming is
+/// exact here and the LIMIT it allows is ignored.
+fn extract_trim(args: &mut Args) -> Result<StreamTrim, CommandError> {
+    let strategy = peek_option(args).ok_or_else(syntax_error)?;
+    args.next();
+    if matches!(peek_option(args).as_deref(), Some("=" | "~")) {
+        args.next();
+    }
+    let trim = match strategy.as_str() {
+        "maxlen" => {
+            let len = extract_int(args.next(), "maxlen")?;
+            StreamTrim::MaxLen(usize::try_from(len).map_err(|_| {
+                CommandError::InvalidArgument("The MAXLEN argument must be >= 0.".to_string())
+            })?)
+        }
+        "minid" => StreamTrim::MinId(extract_id(args.next(), 0)?),
+        _ => return Err(syntax_error()),
+    };
+    if peek_option(args).as_deref() == Some("limit") {
+        args.next();
+        extract_int(args.next(), "limit")?;
+    }
+    Ok(trim)
+}
+
+/// The keys and ids following STREAMS, `last` (`$` or `>`) is parsed as
+/// None.
+#[allow(clippy::type_complexity)]
+fn extract_streams(
+    args: Args,
+    last: &str,
+) -> Result<(Vec<String>, Vec<Option<StreamId>>), CommandError> {
+    let args = args.collect::<Vec<_>>();
+    if args.is_empty() || !args.len().is_multiple_of(2) {
+        return Err(CommandError::InvalidArgument(
+            "Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified."
+                .to_string(),
+        ));
+    }
+    let half = args.len() / 2;
+    let mut args = args.into_iter();
+    let keys = args
+        .by_ref()
+        .take(half)
+        .map(|key| extract_string(Some(key), "key"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let ids = args
+        .map(|id| match extract_string(Some(id), "id")? {
+            id if id == last => Ok(None),
+            id => Ok(Some(StreamId::parse(&id, 0).ok_or_else(invalid_id)?)),
+        })
+        .collect::<Result<Vec<_>, CommandError>>()?;
+    Ok((keys, ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn decode(buf: &[u8]) -> Result<RespArray> {
+        Ok(RespArray::decode(&mut BytesMut::from(buf))?)
+    }
+
+    fn add(backend: &Backend, key: &str, id: &str) -> RespFrame {
+        XAddCommand {
+            key: key.to_string(),
+            id: XAddId::Explicit(StreamId::parse(id, 0).unwrap()),
+            fields: vec![("f".to_string(), b"v".into())],
+            no_mkstream: false,
+            trim: None,
+        }
+        .execute(backend)
+    }
+
+    #[test]
+    fn test_xadd_from_resp_array() -> Result<()> {
+        let frame = decode(
+            b"*9\r\n$4\r\nxadd\r\n$1\r\ns\r\n$6\r\nMAXLEN\r\n$1\r\n~\r\n$2\r\n10\r\n$3\r\n5-*\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n",
+        )?;
+        assert!(XAddCommand::try_from(frame).is_err());
+
+        let frame = decode(
+            b"*8\r\n$4\r\nxadd\r\n$1\r\ns\r\n$6\r\nMAXLEN\r\n$1\r\n~\r\n$2\r\n10\r\n$3\r\n5-*\r\n$1\r\na\r\n$1\r\nb\r\n",
+        )?;
+        let cmd = XAddCommand::try_from(frame)?;
+        assert_eq!(cmd.key, "s");
+        assert_eq!(cmd.id, XAddId::Partial(5));
+        assert_eq!(cmd.trim, Some(StreamTrim::MaxLen(10)));
+        assert_eq!(cmd.fields, vec![("a".to_string(), b"b".into())]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_xread_from_resp_array() -> Result<()> {
+        let frame = decode(
+            b"*8\r\n$5\r\nxread\r\n$5\r\nCOUNT\r\n$1\r\n2\r\n$5\r\nBLOCK\r\n$1\r\n0\r\n$7\r\nSTREAMS\r\n$1\r\ns\r\n$1\r\n$\r\n",
+        )?;
+        let cmd = XReadCommand::try_from(frame)?;
+        assert_eq!(cmd.keys, vec!["s"]);
+        assert_eq!(cmd.ids, vec![None]);
+        assert_eq!(cmd.count, Some(2));
+        assert_eq!(cmd.block, Some(0));
+
+        let frame =
+            decode(b"*5\r\n$5\r\nxread\r\n$7\r\nSTREAMS\r\n$1\r\ns\r\n$1\r\nt\r\n$3\r\n0-1\r\n")?;
+        assert!(XReadCommand::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_xrange_commands() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(add(&backend, "s", "1-1"), BulkString::from("1-1").into());
+        add(&backend, "s", "2-0");
+        add(&backend, "s", "3-0");
+
+        let frame = decode(
+            b"*6\r\n$9\r\nxrevrange\r\n$1\r\ns\r\n$1\r\n+\r\n$1\r\n2\r\n$5\r\ncount\r\n$1\r\n1\r\n",
+        )?;
+        let cmd = XRangeCommand::try_from(frame)?;
+        assert_eq!(cmd.start, Bound::Included(StreamId::new(2, 0)));
+        let entry = entry_frame(StreamId::new(3, 0), vec![("f".to_string(), b"v".into())]);
+        assert_eq!(cmd.execute(&backend), RespArray::new([entry]).into());
+
+        let cmd = XLenCommand {
+            key: "s".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(3));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_xread_should_block_for_new_entries() -> Result<()> {
+        let backend = Backend::new();
+        add(&backend, "s", "1-0");
+        let cmd = XReadCommand {
+            keys: vec!["s".to_string()],
+            ids: vec![None],
+            count: None,
+            block: Some(0),
+        };
+        let cloned = backend.clone();
+        let handle = tokio::spawn(async move { cmd.execute_blocking(&cloned).await });
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        add(&backend, "s", "2-0");
+
+        let entry = entry_frame(StreamId::new(2, 0), vec![("f".to_string(), b"v".into())]);
+        let expected = RespArray::new([stream_frame("s", RespArray::new([entry]).into())]);
+        assert_eq!(handle.await?, expected.into());
+
+        let cmd = XReadCommand {
+            keys: vec!["s".to_string()],
+            ids: vec![None],
+            count: None,
+            block: Some(10),
+        };
+        let ret = cmd.execute_blocking(&backend).await;
+        assert_eq!(ret, RespFrame::NullArray(RespNullArray));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_xreadgroup_commands() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = XGroupCommand {
+            key: "s".to_string(),
+            group: "g".to_string(),
+            action: XGroupAction::Create {
+                id: None,
+                mkstream: true,
+            },
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        let read = |block| XReadGroupCommand {
+            group: "g".to_string(),
+            consumer: "c".to_string(),
+            keys: vec!["s".to_string()],
+            ids: vec![None],
+            count: None,
+            block,
+            no_ack: false,
+        };
+        let cloned = backend.clone();
+        let cmd = read(Some(0));
+        let handle = tokio::spawn(async move { cmd.execute_blocking(&cloned).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        add(&backend, "s", "1-0");
+        let entry = entry_frame(StreamId::new(1, 0), vec![("f".to_string(), b"v".into())]);
+        let expected = RespArray::new([stream_frame("s", RespArray::new([entry]).into())]);
+        assert_eq!(handle.await?, expected.into());
+        assert_eq!(
+            read(None).execute(&backend),
+            RespFrame::NullArray(RespNullArray)
+        );
+
+        let cmd = XPendingCommand {
+            key: "s".to_string(),
+            group: "g".to_string(),
+            range: None,
+        };
+        let consumers = RespArray::new([RespArray::new([
+            BulkString::from("c").into(),
+            BulkString::from("1").into(),
+        ])
+        .into()]);
+        let expected = RespArray::new([
+            RespFrame::Integer(1),
+            BulkString::from("1-0").into(),
+            BulkString::from("1-0").into(),
+            consumers.into(),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = XAckCommand {
+            key: "s".to_string(),
+            group: "g".to_string(),
+            ids: vec![StreamId::new(1, 0), StreamId::new(9, 0)],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let frame = decode(
+            b"*8\r\n$6\r\nxclaim\r\n$1\r\ns\r\n$1\r\ng\r\n$1\r\nc\r\n$1\r\n0\r\n$3\r\n1-0\r\n$5\r\nFORCE\r\n$6\r\nJUSTID\r\n",
+        )?;
+        let cmd = XClaimCommand::try_from(frame)?;
+        assert_eq!(cmd.ids, vec![StreamId::new(1, 0)]);
+        assert!(cmd.options.force && cmd.options.just_id);
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([BulkString::from("1-0").into()]).into()
+        );
+        Ok(())
+    }
+}
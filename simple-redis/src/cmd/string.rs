@@ -0,0 +1,439 @@
+use crate::cmd::{
+    AppendCommand, CommandError, CommandExecutor, GetDelCommand, GetRangeCommand, GetSetCommand,
+    IncrByCommand, IncrByFloatCommand, MGetCommand, MSetCommand, RESP_OK, SetNxCommand,
+    SetRangeCommand, StrLenCommand, extract_args, extract_bytes, extract_float, extract_int,
+    extract_string, is_command, validate_command, validate_variadic_command,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull};
+
+impl CommandExecutor for IncrByCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.incr_by(&self.key, self.delta) {
+            Ok(n) => RespFrame::Integer(n),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for IncrByCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let decr = is_command(&value, "decr") || is_command(&value, "decrby");
+        let by = is_command(&value, "incrby") || is_command(&value, "decrby");
+        let name = match (decr, by) {
+            (false, false) => "incr",
+            (true, false) => "decr",
+            (false, true) => "incrby",
+            (true, true) => "decrby",
+        };
+        validate_command(&value, &[name], if by { 2 } else { 1 })?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let delta = match by {
+            true => extract_int(args.next(), "increment").map_err(|_| CommandError::NotInteger)?,
+            false => 1,
+        };
+        let delta = match decr {
+            true => delta
+                .checked_neg()
+                .ok_or(CommandError::OutOfRange("decrement would overflow"))?,
+            false => delta,
+        };
+        Ok(IncrByCommand { key, delta })
+    }
+}
+
+impl CommandExecutor for IncrByFloatCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.incr_by_float(&self.key, self.increment) {
+            Ok(n) => BulkString::from(n.to_string()).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for IncrByFloatCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["incrbyfloat"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(IncrByFloatCommand {
+            key: extract_string(args.next(), "key")?,
+            increment: extract_float(args.next(), "increment")?,
+        })
+    }
+}
+
+impl CommandExecutor for AppendCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.append(&self.key, &self.value) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for AppendCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["append"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(AppendCommand {
+            key: extract_string(args.next(), "key")?,
+            value: extract_bytes(args.next(), "value")?,
+        })
+    }
+}
+
+impl CommandExecutor for StrLenCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.strlen(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for StrLenCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["strlen"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(StrLenCommand {
+            key: extract_string(args.next(), "key")?,
+        })
+    }
+}
+
+impl CommandExecutor for GetRangeCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.getrange(&self.key, self.start, self.end) {
+            Ok(bytes) => BulkString::new(bytes).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for GetRangeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let name = if is_command(&value, "substr") {
+            "substr"
+        } else {
+            "getrange"
+        };
+        validate_command(&value, &[name], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(GetRangeCommand {
+            key: extract_string(args.next(), "key")?,
+            start: extract_int(args.next(), "start").map_err(|_| CommandError::NotInteger)?,
+            end: extract_int(args.next(), "end").map_err(|_| CommandError::NotInteger)?,
+        })
+    }
+}
+
+impl CommandExecutor for SetRangeCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.setrange(&self.key, self.offset, &self.value) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SetRangeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setrange"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let offset = extract_int(args.next(), "offset").map_err(|_| CommandError::NotInteger)?;
+        let offset = usize::try_from(offset)
+            .map_err(|_| CommandError::OutOfRange("offset is out of range"))?;
+        Ok(SetRangeCommand {
+            key,
+            offset,
+            value: extract_bytes(args.next(), "value")?,
+        })
+    }
+}
+
+impl CommandExecutor for GetSetCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.getset(self.key, self.value) {
+            Ok(old) => old.unwrap_or(RespFrame::Null(RespNull)),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for GetSetCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getset"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        match args.next() {
+            Some(value) => Ok(GetSetCommand { key, value }),
+            None => Err(CommandError::InvalidArgument("Invalid value".to_string())),
+        }
+    }
+}
+
+impl CommandExecutor for GetDelCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.getdel(&self.key) {
+            Ok(value) => value.unwrap_or(RespFrame::Null(RespNull)),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for GetDelCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getdel"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(GetDelCommand {
+            key: extract_string(args.next(), "key")?,
+        })
+    }
+}
+
+impl CommandExecutor for MGetCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        // keys holding other types are nil rather than errors
+        let values = self
+            .keys
+            .iter()
+            .map(|key| match backend.get(key) {
+                Ok(Some(value)) => value,
+                _ => RespFrame::Null(RespNull),
+            })
+            .collect::<Vec<_>>();
+        RespArray::new(values).into()
+    }
+}
+
+impl TryFrom<RespArray> for MGetCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["mget"], 1)?;
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|key| extract_string(Some(key), "key"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(MGetCommand { keys })
+    }
+}
+
+impl CommandExecutor for MSetCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.nx && self.pairs.iter().any(|(key, _)| backend.exists(key)) {
+            return RespFrame::Integer(0);
+        }
+        for (key, value) in self.pairs {
+            backend.set(key, value);
+        }
+        match self.nx {
+            true => RespFrame::Integer(1),
+            false => RESP_OK.clone(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for MSetCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let nx = is_command(&value, "msetnx");
+        let name = if nx { "msetnx" } else { "mset" };
+        validate_variadic_command(&value, &[name], 2)?;
+        if value.len().is_multiple_of(2) {
+            return Err(CommandError::InvalidArgument(format!(
+                "wrong number of arguments for '{}' command",
+                name
+            )));
+        }
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let mut pairs = vec![];
+        while let (Some(key), Some(value)) = (args.next(), args.next()) {
+            pairs.push((extract_string(Some(key), "key")?, value));
+        }
+        Ok(MSetCommand { pairs, nx })
+    }
+}
+
+impl CommandExecutor for SetNxCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.set_nx(self.key, self.value) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for SetNxCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setnx"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        match args.next() {
+            Some(value) => Ok(SetNxCommand { key, value }),
+            None => Err(CommandError::InvalidArgument("Invalid value".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RespDecode, SimpleError};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_incr_decr_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nDECRBY\r\n$1\r\nn\r\n$1\r\n5\r\n");
+        let cmd: IncrByCommand = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.key, "n");
+        assert_eq!(cmd.delta, -5);
+
+        buf.extend_from_slice(b"*2\r\n$4\r\nincr\r\n$1\r\nn\r\n");
+        let cmd: IncrByCommand = RespArray::decode(&mut buf)?.try_into()?;
+        let backend = Backend::new();
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        buf.extend_from_slice(b"*3\r\n$6\r\nincrby\r\n$1\r\nn\r\n$3\r\none\r\n");
+        let ret: Result<IncrByCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert_eq!(
+            ret.unwrap_err().to_string(),
+            "value is not an integer or out of range"
+        );
+        for arg in ["01", "+5", "-0"] {
+            let mut buf = BytesMut::from(
+                format!(
+                    "*3\r\n$6\r\nincrby\r\n$1\r\nn\r\n${}\r\n{}\r\n",
+                    arg.len(),
+                    arg
+                )
+                .as_bytes(),
+            );
+            let ret: Result<IncrByCommand, _> = RespArray::decode(&mut buf)?.try_into();
+            assert!(matches!(ret, Err(CommandError::NotInteger)), "{}", arg);
+        }
+
+        buf.extend_from_slice(b"*3\r\n$6\r\ndecrby\r\n$1\r\nn\r\n$20\r\n-9223372036854775808\r\n");
+        let ret: Result<IncrByCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert_eq!(ret.unwrap_err().to_string(), "decrement would overflow");
+
+        backend.set("s".to_string(), BulkString::from("x").into());
+        let cmd = IncrByCommand {
+            key: "s".to_string(),
+            delta: 1,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR value is not an integer or out of range").into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incrbyfloat_command() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = IncrByFloatCommand {
+            key: "f".to_string(),
+            increment: 2.5,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::from("2.5").into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mset_mget_commands() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*5\r\n$4\r\nmset\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n");
+        let cmd: MSetCommand = RespArray::decode(&mut buf)?.try_into()?;
+        let backend = Backend::new();
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        buf.extend_from_slice(b"*4\r\n$4\r\nmset\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n");
+        let ret: Result<MSetCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(ret.is_err());
+
+        let cmd = MSetCommand {
+            pairs: vec![
+                ("c".to_string(), BulkString::from("3").into()),
+                ("a".to_string(), BulkString::from("x").into()),
+            ],
+            nx: true,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert!(!backend.exists("c"));
+
+        backend
+            .lpush("list".to_string(), vec![BulkString::from("v").into()])
+            .map_err(|e| anyhow::anyhow!(e))?;
+        let cmd = MGetCommand {
+            keys: vec!["a".into(), "b".into(), "c".into(), "list".into()],
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([
+                BulkString::from("1").into(),
+                BulkString::from("2").into(),
+                RespFrame::Null(RespNull),
+                RespFrame::Null(RespNull),
+            ])
+            .into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_getrange_setrange_commands() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$8\r\nsetrange\r\n$1\r\nk\r\n$2\r\n-1\r\n$1\r\nx\r\n");
+        let ret: Result<SetRangeCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert_eq!(ret.unwrap_err().to_string(), "offset is out of range");
+        buf.extend_from_slice(b"*4\r\n$8\r\nsetrange\r\n$1\r\nk\r\n$2\r\n01\r\n$1\r\nx\r\n");
+        let ret: Result<SetRangeCommand, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(matches!(ret, Err(CommandError::NotInteger)));
+
+        let backend = Backend::new();
+        let cmd = SetRangeCommand {
+            key: "k".to_string(),
+            offset: 2,
+            value: b"llo".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(5));
+        let cmd = GetRangeCommand {
+            key: "k".to_string(),
+            start: 2,
+            end: -1,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::from("llo").into());
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use crate::cmd::{
+    CommandError, CommandExecutor, DiscardCommand, ExecCommand, MultiCommand, RESP_OK,
+    UnwatchCommand, WatchCommand, extract_args, extract_string, validate_command,
+    validate_variadic_command,
+};
+use crate::{Backend, RespArray, RespFrame, SimpleError, Watches};
+
+impl CommandExecutor for MultiCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR MULTI is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for MultiCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["multi"], 0)?;
+        Ok(MultiCommand)
+    }
+}
+
+impl CommandExecutor for ExecCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR EXEC without MULTI").into()
+    }
+}
+
+impl TryFrom<RespArray> for ExecCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["exec"], 0)?;
+        Ok(ExecCommand)
+    }
+}
+
+impl CommandExecutor for DiscardCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR DISCARD without MULTI").into()
+    }
+}
+
+impl TryFrom<RespArray> for DiscardCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["discard"], 0)?;
+        Ok(DiscardCommand)
+    }
+}
+
+impl WatchCommand {
+    pub fn execute_watch(self, watches: &mut Watches) -> RespFrame {
+        for key in self.keys {
+            watches.watch(key);
+        }
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for WatchCommand {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR WATCH is only allowed on a client connection").into()
+    }
+}
+
+impl TryFrom<RespArray> for WatchCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["watch"], 1)?;
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|key| extract_string(Some(key), "key"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(WatchCommand { keys })
+    }
+}
+
+impl UnwatchCommand {
+    pub fn execute_unwatch(self, watches: &mut Watches) -> RespFrame {
+        watches.clear();
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for UnwatchCommand {
+    /// Only reached when queued in a transaction, the watches are released
+    /// by EXEC anyway.
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for UnwatchCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["unwatch"], 0)?;
+        Ok(UnwatchCommand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_watch_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nwatch\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: WatchCommand = frame.try_into()?;
+        assert_eq!(result.keys, vec!["a", "b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_watch_unwatch_command() -> Result<()> {
+        let backend = Backend::new();
+        let mut watches = Watches::new(&backend);
+
+        let cmd = WatchCommand {
+            keys: vec!["a".to_string()],
+        };
+        assert_eq!(cmd.execute_watch(&mut watches), RESP_OK.clone());
+        backend.touch("a");
+        assert!(watches.is_dirty());
+
+        assert_eq!(
+            UnwatchCommand.execute_unwatch(&mut watches),
+            RESP_OK.clone()
+        );
+        assert!(!watches.is_dirty());
+
+        Ok(())
+    }
+}
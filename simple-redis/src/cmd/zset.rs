@@ -0,0 +1,402 @@
+use crate::cmd::{
+    CommandError, CommandExecutor, SetCondition, ZAddCommand, ZCardCommand, ZIncrByCommand,
+    ZRangeByScoreCommand, ZRangeCommand, ZRankCommand, ZRemCommand, ZScoreCommand, extract_args,
+    extract_int, extract_string, is_command, validate_command, validate_variadic_command,
+};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError};
+use std::ops::Bound;
+
+impl CommandExecutor for ZAddCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zadd(self.key, self.members, self.condition, self.ch) {
+            Ok(count) => RespFrame::Integer(count as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZAddCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["zadd"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+
+        let key = extract_string(args.next(), "key")?;
+        let mut condition = None;
+        let mut ch = false;
+        while let Some(RespFrame::BulkString(option)) = args.peek() {
+            match option.to_ascii_lowercase().as_slice() {
+                b"nx" if condition.is_none() => condition = Some(SetCondition::Nx),
+                b"xx" if condition.is_none() => condition = Some(SetCondition::Xx),
+                b"ch" => ch = true,
+                b"nx" | b"xx" => {
+                    return Err(CommandError::InvalidArgument(
+                        "XX and NX options at the same time are not compatible".to_string(),
+                    ));
+                }
+                _ => break,
+            }
+            args.next();
+        }
+
+        let args = args.collect::<Vec<_>>();
+        if args.is_empty() || args.len() % 2 != 0 {
+            return Err(CommandError::InvalidArgument("syntax error".to_string()));
+        }
+        let mut members = Vec::with_capacity(args.len() / 2);
+        let mut args = args.into_iter();
+        while let (Some(score), Some(member)) = (args.next(), args.next()) {
+            let score = extract_score(Some(score))?;
+            members.push((score, extract_string(Some(member), "member")?));
+        }
+
+        Ok(ZAddCommand {
+            key,
+            members,
+            condition,
+            ch,
+        })
+    }
+}
+
+impl CommandExecutor for ZIncrByCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zincrby(self.key, self.increment, self.member) {
+            Ok(Some(score)) => RespFrame::Double(score),
+            Ok(None) => SimpleError::new("ERR resulting score is not a number (NaN)").into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZIncrByCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zincrby"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(ZIncrByCommand {
+            key: extract_string(args.next(), "key")?,
+            increment: extract_score(args.next())?,
+            member: extract_string(args.next(), "member")?,
+        })
+    }
+}
+
+impl CommandExecutor for ZScoreCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zscore(&self.key, &self.member) {
+            Ok(score) => score
+                .map(RespFrame::Double)
+                .unwrap_or(RespFrame::Null(RespNull)),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZScoreCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zscore"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(ZScoreCommand {
+            key: extract_string(args.next(), "key")?,
+            member: extract_string(args.next(), "member")?,
+        })
+    }
+}
+
+impl CommandExecutor for ZCardCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zcard(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZCardCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zcard"], 1)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(ZCardCommand {
+            key: extract_string(args.next(), "key")?,
+        })
+    }
+}
+
+impl CommandExecutor for ZRemCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zrem(&self.key, &self.members) {
+            Ok(removed) => RespFrame::Integer(removed as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZRemCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["zrem"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let members = args
+            .map(|member| extract_string(Some(member), "member"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ZRemCommand { key, members })
+    }
+}
+
+impl CommandExecutor for ZRankCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zrank(&self.key, &self.member, self.rev) {
+            Ok(rank) => rank
+                .map(|rank| RespFrame::Integer(rank as i64))
+                .unwrap_or(RespFrame::Null(RespNull)),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZRankCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let rev = is_command(&value, "zrevrank");
+        validate_command(&value, &[if rev { "zrevrank" } else { "zrank" }], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        Ok(ZRankCommand {
+            key: extract_string(args.next(), "key")?,
+            member: extract_string(args.next(), "member")?,
+            rev,
+        })
+    }
+}
+
+impl CommandExecutor for ZRangeCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zrange(&self.key, self.start, self.stop, self.rev) {
+            Ok(members) => members_to_frame(members, self.with_scores),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZRangeCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let rev = is_command(&value, "zrevrange");
+        validate_variadic_command(&value, &[if rev { "zrevrange" } else { "zrange" }], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let key = extract_string(args.next(), "key")?;
+        let start = extract_int(args.next(), "start")?;
+        let stop = extract_int(args.next(), "stop")?;
+        let with_scores = match args.next() {
+            None => false,
+            Some(option) if is_option(&option, "withscores") && args.len() == 0 => true,
+            _ => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        Ok(ZRangeCommand {
+            key,
+            start,
+            stop,
+            rev,
+            with_scores,
+        })
+    }
+}
+
+impl CommandExecutor for ZRangeByScoreCommand {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zrange_by_score(&self.key, self.min, self.max, self.limit) {
+            Ok(members) => members_to_frame(members, self.with_scores),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZRangeByScoreCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_variadic_command(&value, &["zrangebyscore"], 3)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+
+        let mut cmd = ZRangeByScoreCommand {
+            key: extract_string(args.next(), "key")?,
+            min: extract_score_bound(args.next())?,
+            max: extract_score_bound(args.next())?,
+            with_scores: false,
+            limit: None,
+        };
+        while let Some(option) = args.next() {
+            if is_option(&option, "withscores") {
+                cmd.with_scores = true;
+            } else if is_option(&option, "limit") {
+                let offset = extract_int(args.next(), "offset")?;
+                let count = extract_int(args.next(), "count")?;
+                // a negative offset returns nothing and a negative count returns everything
+                let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+                let count = usize::try_from(count).unwrap_or(usize::MAX);
+                cmd.limit = Some((offset, count));
+            } else {
+                return Err(CommandError::InvalidArgument("syntax error".to_string()));
+            }
+        }
+        Ok(cmd)
+    }
+}
+
+fn members_to_frame(members: Vec<(String, f64)>, with_scores: bool) -> RespFrame {
+    let frames = members
+        .into_iter()
+        .flat_map(|(member, score)| {
+            let member = BulkString::from(member).into();
+            if with_scores {
+                vec![member, RespFrame::Double(score)]
+            } else {
+                vec![member]
+            }
+        })
+        .collect::<Vec<RespFrame>>();
+    RespArray::new(frames).into()
+}
+
+fn is_option(frame: &RespFrame, name: &str) -> bool {
+    matches!(frame, RespFrame::BulkString(option) if option.eq_ignore_ascii_case(name.as_bytes()))
+}
+
+fn extract_score(frame: Option<RespFrame>) -> Result<f64, CommandError> {
+    match extract_string(frame, "score")?.parse::<f64>() {
+        Ok(score) if !score.is_nan() => Ok(score),
+        _ => Err(CommandError::InvalidArgument(
+            "value is not a valid float".to_string(),
+        )),
+    }
+}
+
+/// Parse a score range boundary, `(` makes it exclusive and `-inf`/`+inf`
+/// leave it unbounded.
+fn extract_score_bound(frame: Option<RespFrame>) -> Result<Bound<f64>, CommandError> {
+    let s = extract_string(frame, "score")?;
+    let invalid = || CommandError::InvalidArgument("min or max is not a float".to_string());
+    let (exclusive, s) = match s.strip_prefix('(') {
+        Some(s) => (true, s),
+        None => (false, s.as_str()),
+    };
+    let score = s.parse::<f64>().map_err(|_| invalid())?;
+    match score {
+        score if score.is_nan() => Err(invalid()),
+        score if score.is_infinite() => Ok(Bound::Unbounded),
+        score if exclusive => Ok(Bound::Excluded(score)),
+        score => Ok(Bound::Included(score)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_zadd_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$4\r\nzadd\r\n$1\r\nz\r\n$2\r\nCH\r\n$1\r\n1\r\n$1\r\na\r\n$4\r\n-inf\r\n$1\r\nb\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ZAddCommand = frame.try_into()?;
+        assert_eq!(result.key, "z");
+        assert!(result.ch);
+        assert_eq!(result.condition, None);
+        assert_eq!(
+            result.members,
+            vec![(1.0, "a".to_string()), (f64::NEG_INFINITY, "b".to_string())]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrangebyscore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*8\r\n$13\r\nzrangebyscore\r\n$1\r\nz\r\n$2\r\n(1\r\n$4\r\n+inf\r\n$10\r\nWITHSCORES\r\n$5\r\nLIMIT\r\n$1\r\n0\r\n$1\r\n5\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: ZRangeByScoreCommand = frame.try_into()?;
+        assert_eq!(result.min, Bound::Excluded(1.0));
+        assert_eq!(result.max, Bound::Unbounded);
+        assert!(result.with_scores);
+        assert_eq!(result.limit, Some((0, 5)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zset_commands() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = ZAddCommand {
+            key: "board".to_string(),
+            members: vec![(10.0, "alice".to_string()), (20.0, "bob".to_string())],
+            condition: None,
+            ch: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+
+        let cmd = ZIncrByCommand {
+            key: "board".to_string(),
+            increment: 15.5,
+            member: "alice".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Double(25.5));
+
+        let cmd = ZRangeCommand {
+            key: "board".to_string(),
+            start: 0,
+            stop: -1,
+            rev: true,
+            with_scores: true,
+        };
+        let expected = RespArray::new([
+            BulkString::from("alice").into(),
+            RespFrame::Double(25.5),
+            BulkString::from("bob").into(),
+            RespFrame::Double(20.0),
+        ]);
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = ZRankCommand {
+            key: "board".to_string(),
+            member: "bob".to_string(),
+            rev: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        let cmd = ZScoreCommand {
+            key: "board".to_string(),
+            member: "carol".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+use crate::acl::AclUser;
+use crate::backend::EvictionPolicy;
+use crate::cluster::{Cluster, ClusterConfig};
+#[cfg(feature = "server")]
+use crate::persistence::FsyncPolicy;
+use crate::persistence::PersistenceConfig;
+use anyhow::{Result, anyhow};
+#[cfg(feature = "server")]
+use clap::Parser;
+use serde::{Deserialize, Deserializer};
+#[cfg(feature = "server")]
+use std::{fs, path::PathBuf};
+
+/// Parameters reported by CONFIG GET.
+pub const PARAMETERS: &[&str] = &[
+    "bind",
+    "port",
+    "maxclients",
+    "loglevel",
+    "requirepass",
+    "dir",
+    "dbfilename",
+    "appendonly",
+    "appendfilename",
+    "appendfsync",
+    "replicaof",
+    "masterauth",
+    "maxmemory",
+    "maxmemory-policy",
+    "slowlog-log-slower-than",
+    "slowlog-max-len",
+    "cluster-enabled",
+];
+
+#[cfg(feature = "server")]
+#[derive(Debug, Parser)]
+#[command(name = "simple-redis", version, about, long_about = None)]
+pub struct Opts {
+    /// yaml config file, the options below take precedence over it
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    #[arg(long)]
+    pub bind: Option<String>,
+
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    #[arg(long)]
+    pub maxclients: Option<usize>,
+
+    /// debug, verbose, notice or warning
+    #[arg(long)]
+    pub loglevel: Option<String>,
+
+    /// password of the default user
+    #[arg(long)]
+    pub requirepass: Option<String>,
+
+    /// directory of the snapshot and the append only file
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    #[arg(long)]
+    pub appendonly: Option<bool>,
+
+    #[arg(long)]
+    pub appendfsync: Option<FsyncPolicy>,
+
+    /// "<host> <port>" of the master to follow
+    #[arg(long)]
+    pub replicaof: Option<String>,
+
+    /// password to authenticate with the master
+    #[arg(long)]
+    pub masterauth: Option<String>,
+
+    /// memory limit of the dataset such as 100mb, 0 for no limit
+    #[arg(long, value_parser = parse_memory)]
+    pub maxmemory: Option<u64>,
+
+    /// noeviction, allkeys-lru, allkeys-lfu, volatile-lru, volatile-ttl or
+    /// allkeys-random
+    #[arg(long)]
+    pub maxmemory_policy: Option<EvictionPolicy>,
+
+    /// microseconds from which a command is logged as slow, negative to turn
+    /// the slowlog off
+    #[arg(long, allow_hyphen_values = true)]
+    pub slowlog_log_slower_than: Option<i64>,
+
+    /// number of slow commands kept
+    #[arg(long)]
+    pub slowlog_max_len: Option<usize>,
+
+    /// yaml file of the cluster topology, turns cluster mode on
+    #[arg(long)]
+    pub cluster_config_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub port: u16,
+    pub maxclients: usize,
+    pub loglevel: String,
+    pub requirepass: Option<String>,
+    #[serde(flatten)]
+    pub persistence: PersistenceConfig,
+    /// "<host> <port>" of the master to follow at startup
+    pub replicaof: Option<String>,
+    pub masterauth: Option<String>,
+    /// memory limit of the dataset in bytes, 0 for no limit
+    #[serde(deserialize_with = "deserialize_memory")]
+    pub maxmemory: u64,
+    #[serde(rename = "maxmemory-policy")]
+    pub maxmemory_policy: EvictionPolicy,
+    /// microseconds from which a command is logged as slow, negative to turn
+    /// the slowlog off
+    #[serde(rename = "slowlog-log-slower-than")]
+    pub slowlog_log_slower_than: i64,
+    #[serde(rename = "slowlog-max-len")]
+    pub slowlog_max_len: usize,
+    /// acl users besides the default one
+    pub users: Vec<AclUser>,
+    /// cluster mode is on when the topology is set
+    pub cluster: Option<ClusterConfig>,
+}
+
+impl ServerConfig {
+    /// Read the config file if any, then apply the command line options.
+    #[cfg(feature = "server")]
+    pub fn load(opts: Opts) -> Result<Self> {
+        let mut config = match &opts.config {
+            Some(path) => serde_yaml::from_str(&fs::read_to_string(path)?)?,
+            None => ServerConfig::default(),
+        };
+
+        if let Some(bind) = opts.bind {
+            config.bind = bind;
+        }
+        if let Some(port) = opts.port {
+            config.port = port;
+        }
+        if let Some(maxclients) = opts.maxclients {
+            config.maxclients = maxclients;
+        }
+        if let Some(loglevel) = opts.loglevel {
+            config.loglevel = loglevel;
+        }
+        if let Some(requirepass) = opts.requirepass {
+            config.requirepass = Some(requirepass);
+        }
+        if let Some(dir) = opts.dir {
+            config.persistence.dir = dir;
+        }
+        if let Some(appendonly) = opts.appendonly {
+            config.persistence.appendonly = appendonly;
+        }
+        if let Some(appendfsync) = opts.appendfsync {
+            config.persistence.appendfsync = appendfsync;
+        }
+        if let Some(replicaof) = opts.replicaof {
+            config.replicaof = Some(replicaof);
+        }
+        if let Some(masterauth) = opts.masterauth {
+            config.masterauth = Some(masterauth);
+        }
+        if let Some(maxmemory) = opts.maxmemory {
+            config.maxmemory = maxmemory;
+        }
+        if let Some(policy) = opts.maxmemory_policy {
+            config.maxmemory_policy = policy;
+        }
+        if let Some(slower_than) = opts.slowlog_log_slower_than {
+            config.slowlog_log_slower_than = slower_than;
+        }
+        if let Some(max_len) = opts.slowlog_max_len {
+            config.slowlog_max_len = max_len;
+        }
+        if let Some(path) = opts.cluster_config_file {
+            config.cluster = Some(serde_yaml::from_str(&fs::read_to_string(path)?)?);
+        }
+
+        config.log_filter()?;
+        config.master()?;
+        config.cluster()?;
+        Ok(config)
+    }
+
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.bind, self.port)
+    }
+
+    /// Tracing filter for the log level, redis level names are accepted as
+    /// well as the tracing ones.
+    pub fn log_filter(&self) -> Result<&'static str> {
+        match self.loglevel.to_ascii_lowercase().as_str() {
+            "trace" => Ok("trace"),
+            "debug" | "verbose" => Ok("debug"),
+            "info" | "notice" => Ok("info"),
+            "warn" | "warning" => Ok("warn"),
+            "error" => Ok("error"),
+            _ => Err(anyhow!("invalid log level: {}", self.loglevel)),
+        }
+    }
+
+    /// Host and port of the master to follow at startup.
+    pub fn master(&self) -> Result<Option<(String, u16)>> {
+        let Some(replicaof) = &self.replicaof else {
+            return Ok(None);
+        };
+        match replicaof.split_whitespace().collect::<Vec<_>>()[..] {
+            [host, port] => Ok(Some((host.to_string(), port.parse()?))),
+            _ => Err(anyhow!("invalid replicaof: {}", replicaof)),
+        }
+    }
+
+    /// The topology seen by this node if cluster mode is on.
+    pub fn cluster(&self) -> Result<Option<Cluster>> {
+        self.cluster
+            .as_ref()
+            .map(|cluster| Cluster::new(cluster, self.port))
+            .transpose()
+    }
+
+    /// The value of a CONFIG GET parameter.
+    pub fn get(&self, name: &str) -> Option<String> {
+        let persistence = &self.persistence;
+        let value = match name {
+            "bind" => self.bind.clone(),
+            "port" => self.port.to_string(),
+            "maxclients" => self.maxclients.to_string(),
+            "loglevel" => self.loglevel.clone(),
+            "requirepass" => self.requirepass.clone().unwrap_or_default(),
+            "dir" => persistence.dir.display().to_string(),
+            "dbfilename" => persistence.dbfilename.clone(),
+            "appendonly" => if persistence.appendonly { "yes" } else { "no" }.to_string(),
+            "appendfilename" => persistence.appendfilename.clone(),
+            "appendfsync" => persistence.appendfsync.to_string(),
+            "replicaof" => self.replicaof.clone().unwrap_or_default(),
+            "masterauth" => self.masterauth.clone().unwrap_or_default(),
+            "maxmemory" => self.maxmemory.to_string(),
+            "maxmemory-policy" => self.maxmemory_policy.to_string(),
+            "slowlog-log-slower-than" => self.slowlog_log_slower_than.to_string(),
+            "slowlog-max-len" => self.slowlog_max_len.to_string(),
+            "cluster-enabled" => if self.cluster.is_some() { "yes" } else { "no" }.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Parse a memory size in bytes, or with a kb, mb or gb unit.
+pub fn parse_memory(s: &str) -> Result<u64> {
+    let lower = s.trim().to_ascii_lowercase();
+    let (number, unit) = match lower.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => lower.split_at(i),
+        None => (lower.as_str(), ""),
+    };
+    let unit = match unit {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        _ => return Err(anyhow!("invalid memory size: {}", s)),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| anyhow!("invalid memory size: {}", s))
+}
+
+/// Memory sizes may be written as a number of bytes or with a unit.
+fn deserialize_memory<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match Size::deserialize(deserializer)? {
+        Size::Bytes(bytes) => Ok(bytes),
+        Size::Text(text) => parse_memory(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0".to_string(),
+            port: 6379,
+            maxclients: 10000,
+            loglevel: "notice".to_string(),
+            requirepass: None,
+            persistence: PersistenceConfig::default(),
+            replicaof: None,
+            masterauth: None,
+            maxmemory: 0,
+            maxmemory_policy: EvictionPolicy::default(),
+            slowlog_log_slower_than: 10000,
+            slowlog_max_len: 128,
+            users: vec![],
+            cluster: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "server")]
+    fn test_config_file_and_options() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("simple-redis-{}.yaml", std::process::id()));
+        fs::write(
+            &path,
+            "port: 7000\nloglevel: warning\nappendonly: true\nappendfsync: always\nmaxmemory: 64mb\nusers:\n  - name: app\n    password: secret\n    commands: [get, set]\n    keys: [\"app:*\"]\n",
+        )?;
+
+        let opts = Opts::parse_from([
+            "simple-redis",
+            "-c",
+            path.to_str().unwrap(),
+            "-p",
+            "7001",
+            "--replicaof",
+            "127.0.0.1 7000",
+            "--maxmemory-policy",
+            "allkeys-lfu",
+            "--slowlog-log-slower-than",
+            "-1",
+        ]);
+        let config = ServerConfig::load(opts)?;
+        assert_eq!(config.port, 7001);
+        assert_eq!(config.master()?, Some(("127.0.0.1".to_string(), 7000)));
+        assert_eq!(config.bind, "0.0.0.0");
+        assert_eq!(config.log_filter()?, "warn");
+        assert!(config.persistence.appendonly);
+        assert_eq!(config.persistence.appendfsync, FsyncPolicy::Always);
+        assert_eq!(config.users[0].keys, vec!["app:*"]);
+        assert_eq!(config.get("appendonly").as_deref(), Some("yes"));
+        assert_eq!(config.maxmemory, 64 << 20);
+        assert_eq!(config.maxmemory_policy, EvictionPolicy::AllKeysLfu);
+        assert_eq!(
+            config.get("maxmemory-policy").as_deref(),
+            Some("allkeys-lfu")
+        );
+        assert_eq!(config.slowlog_log_slower_than, -1);
+        assert_eq!(config.get("slowlog-max-len").as_deref(), Some("128"));
+        assert_eq!(config.get("cluster-enabled").as_deref(), Some("no"));
+        assert_eq!(config.get("nope"), None);
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "server")]
+    fn test_cluster_config_file() -> Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("simple-redis-cluster-{}.yaml", std::process::id()));
+        fs::write(
+            &path,
+            "nodes:\n  - id: a\n    host: 127.0.0.1\n    port: 7000\n    slots: [0-8191]\n  - id: b\n    host: 127.0.0.1\n    port: 7001\n    slots: [8192-16383]\n",
+        )?;
+        let file = path.to_str().unwrap();
+
+        let opts = Opts::parse_from(["simple-redis", "-p", "7001", "--cluster-config-file", file]);
+        let config = ServerConfig::load(opts)?;
+        assert_eq!(config.get("cluster-enabled").as_deref(), Some("yes"));
+        let cluster = config.cluster()?.expect("cluster mode should be on");
+        assert_eq!(cluster.myself().id, "b");
+
+        // no node listens on the default port
+        let opts = Opts::parse_from(["simple-redis", "--cluster-config-file", file]);
+        assert!(ServerConfig::load(opts).is_err());
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_memory() -> Result<()> {
+        assert_eq!(parse_memory("1024")?, 1024);
+        assert_eq!(parse_memory("100kb")?, 100 << 10);
+        assert_eq!(parse_memory("2GB")?, 2 << 30);
+        assert!(parse_memory("10tb").is_err());
+        assert!(parse_memory("mb").is_err());
+        Ok(())
+    }
+}
@@ -2,8 +2,14 @@ mod backend;
 mod resp;
 mod respv2;
 
+pub mod acl;
+pub mod client;
+pub mod cluster;
 pub mod cmd;
+pub mod config;
 pub mod network;
+pub mod persistence;
+pub mod replication;
 
 pub use backend::*;
 pub use resp::*;
@@ -1,18 +1,41 @@
 use anyhow::Result;
+use clap::Parser;
+use simple_redis::config::{Opts, ServerConfig};
+use simple_redis::persistence::{self, FsyncPolicy};
 use simple_redis::{Backend, network};
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tracing::{info, warn};
 
-const ADDR: &str = "0.0.0.0:6379";
+const EXPIRE_SWEEP_PERIOD: Duration = Duration::from_millis(100);
+const AOF_FSYNC_PERIOD: Duration = Duration::from_secs(1);
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    let config = ServerConfig::load(Opts::parse())?;
+    tracing_subscriber::fmt()
+        .with_env_filter(config.log_filter()?)
+        .init();
 
-    info!("Simple-Redis-Server is listening on {}", ADDR);
-    let listener = TcpListener::bind(ADDR).await?;
+    let addr = config.addr();
+    info!("Simple-Redis-Server is listening on {}", addr);
+    let listener = TcpListener::bind(&addr).await?;
 
-    let backend = Backend::new();
+    let master = config.master()?;
+    let backend = Backend::with_config(config);
+    let loaded = persistence::load(&backend)?;
+    info!("Loaded {} commands from disk", loaded);
+    if master.is_some() {
+        backend.replicaof(master);
+    }
+
+    tokio::spawn(backend.clone().sweep_expired(EXPIRE_SWEEP_PERIOD));
+    if backend
+        .aof()
+        .is_some_and(|aof| aof.fsync() == FsyncPolicy::EverySec)
+    {
+        tokio::spawn(backend.clone().fsync_aof(AOF_FSYNC_PERIOD));
+    }
     loop {
         let (stream, raddr) = listener.accept().await?;
         info!("Accept connection from: {}", raddr);
@@ -1,16 +1,31 @@
 use crate::{
-    cmd::{Command, CommandExecutor}, Backend, RespDecodeV2, RespEncode, RespError, RespFrame
+    Backend, BulkString, ClientInfo, RespArray, RespDecodeV2, RespEncode, RespError, RespFrame,
+    RespNull, RespNullArray, SimpleError, SimpleString, Subscriber, Subscriptions, Watches,
+    acl::DEFAULT_USER,
+    cmd::{
+        AuthCommand, ClientAction, ClientCommand, ClientKillFilter, Command, CommandExecutor,
+        HelloCommand, PSyncCommand, RESP_OK, ReplConfCommand, SelectCommand,
+    },
+    now_ms,
+    persistence::AofRecord,
+    replication::ReplicaLink,
 };
 use anyhow::Result;
 use bytes::BytesMut;
 use futures::SinkExt;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
 use tokio::net::TcpStream;
+use tokio::sync::{Notify, mpsc};
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
-use tracing::info;
+use tracing::{debug, info};
 
 #[derive(Debug)]
-struct RespFrameCodec;
+pub(crate) struct RespFrameCodec;
 
 impl Encoder<RespFrame> for RespFrameCodec {
     type Error = anyhow::Error;
@@ -46,34 +61,1019 @@ struct RedisRequest {
 
 #[derive(Debug)]
 struct RedisResponse {
-    frame: RespFrame,
+    frames: Vec<RespFrame>,
+    /// close the connection once the frames are sent
+    close: bool,
+}
+
+/// Per connection state.
+#[derive(Debug)]
+struct Session {
+    backend: Backend,
+    id: u64,
+    addr: SocketAddr,
+    /// RESP version negotiated with HELLO
+    protocol: u8,
+    name: Option<String>,
+    /// the authenticated acl user
+    user: Option<String>,
+    subscriptions: Subscriptions,
+    /// messages published to the subscribed channels, or the write commands
+    /// streamed to a replica
+    messages: mpsc::UnboundedReceiver<RespFrame>,
+    sender: Subscriber,
+    /// port a replica listens on, announced with REPLCONF
+    listening_port: Option<u16>,
+    watches: Watches,
+    /// set between MULTI and EXEC/DISCARD
+    transaction: Option<Transaction>,
+    /// notified by CLIENT KILL
+    kill: Arc<Notify>,
+    /// set by ASKING for the next command only
+    asking: bool,
+}
+
+#[derive(Debug, Default)]
+struct Transaction {
+    /// queued commands along with their request frame to propagate
+    queued: Vec<(Command, Option<RespFrame>)>,
+    /// a command could not be queued, EXEC discards the transaction
+    aborted: bool,
+}
+
+impl Session {
+    fn new(backend: &Backend, addr: SocketAddr) -> Self {
+        let (sender, messages) = mpsc::unbounded_channel();
+        let id = backend.next_client_id();
+        backend.connected_clients.fetch_add(1, Ordering::Relaxed);
+        let kill = Arc::new(Notify::new());
+        let user = backend.acl.default_user().map(String::from);
+        backend.register_client(ClientInfo {
+            user: user.clone(),
+            ..ClientInfo::new(id, addr, kill.clone())
+        });
+        Self {
+            backend: backend.clone(),
+            id,
+            addr,
+            protocol: 2,
+            name: None,
+            user,
+            subscriptions: Subscriptions::new(backend, id, sender.clone()),
+            messages,
+            sender,
+            listening_port: None,
+            watches: Watches::new(backend),
+            transaction: None,
+            kill,
+            asking: false,
+        }
+    }
+
+    /// Switch the protocol version and optionally authenticate and name the
+    /// connection, replies with the server properties.
+    fn hello(&mut self, cmd: HelloCommand) -> RespFrame {
+        let protocol = match cmd.protocol {
+            Some(protocol @ 2..=3) => protocol as u8,
+            Some(_) => return SimpleError::new("NOPROTO unsupported protocol version").into(),
+            None => self.protocol,
+        };
+        if let Some((username, password)) = cmd.auth {
+            if let Err(e) = self.backend.acl.authenticate(&username, &password) {
+                return SimpleError::new(e.to_string()).into();
+            }
+            self.user = Some(username);
+        }
+        if self.user.is_none() {
+            return SimpleError::new(
+                "NOAUTH HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the client and select the RESP protocol version at the same time",
+            )
+            .into();
+        }
+
+        self.protocol = protocol;
+        if let Some(name) = cmd.name {
+            self.name = Some(name);
+            info!("Client {} is named {:?}", self.id, self.name);
+        }
+        let role = if self.backend.is_replica() {
+            "replica"
+        } else {
+            "master"
+        };
+        HelloCommand::reply(self.id, self.protocol, self.backend.mode(), role)
+    }
+
+    fn auth(&mut self, cmd: AuthCommand) -> RespFrame {
+        let username = match cmd.username {
+            Some(username) => username,
+            None if self.backend.acl.default_user().is_some() => {
+                return SimpleError::new(
+                    "ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?",
+                )
+                .into();
+            }
+            None => DEFAULT_USER.to_string(),
+        };
+        match self.backend.acl.authenticate(&username, &cmd.password) {
+            Ok(()) => {
+                self.user = Some(username);
+                RESP_OK.clone()
+            }
+            Err(e) => SimpleError::new(e.to_string()).into(),
+        }
+    }
+
+    /// Check that the connection is authenticated and its user may run the
+    /// command on its keys.
+    fn authorize(&self, name: &str, cmd: &Command) -> Result<(), String> {
+        if matches!(cmd, Command::Auth(_) | Command::Hello(_) | Command::Quit(_)) {
+            return Ok(());
+        }
+        match &self.user {
+            Some(user) => self
+                .backend
+                .acl
+                .check(user, name, &cmd.keys())
+                .map_err(|e| e.to_string()),
+            None => Err("NOAUTH Authentication required.".to_string()),
+        }
+    }
+
+    /// Check that this node serves the keys of the command in cluster mode.
+    fn route(&self, cmd: &Command, asking: bool) -> Result<(), String> {
+        let Some(cluster) = &self.backend.cluster else {
+            return Ok(());
+        };
+        if let Command::Select(select) = cmd
+            && select.db != 0
+        {
+            return Err("ERR SELECT is not allowed in cluster mode".to_string());
+        }
+        cluster
+            .route(&cmd.keys(), asking, |key| self.backend.exists(key))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Let the next command access a slot migrating to this node.
+    fn asking(&mut self) -> RespFrame {
+        if self.backend.cluster.is_none() {
+            return SimpleError::new("ERR This instance has cluster support disabled").into();
+        }
+        self.asking = true;
+        RESP_OK.clone()
+    }
+
+    /// Handle the options a replica sends, an ACK gets no reply.
+    fn replconf(&mut self, cmd: ReplConfCommand) -> Option<RespFrame> {
+        for (option, value) in cmd.options {
+            match option.as_str() {
+                "listening-port" => match value.parse() {
+                    Ok(port) => self.listening_port = Some(port),
+                    Err(_) => return Some(SimpleError::new("ERR invalid listening port").into()),
+                },
+                "ack" => {
+                    if let Ok(offset) = value.parse() {
+                        self.backend.replica_ack(self.id, offset);
+                    }
+                    return None;
+                }
+                _ => {}
+            }
+        }
+        Some(RESP_OK.clone())
+    }
+
+    /// Turn the connection into a replica link, the write commands are
+    /// streamed to it through the messages channel.
+    fn psync(&mut self, cmd: PSyncCommand) -> Vec<RespFrame> {
+        let port = self.listening_port.unwrap_or(self.addr.port());
+        let link = ReplicaLink::new(self.addr.ip().to_string(), port, self.sender.clone());
+        self.backend
+            .update_client(self.id, |info| info.replica = true);
+        self.backend.psync(self.id, link, &cmd.replid, cmd.offset)
+    }
+
+    /// Encode the frame for the protocol version of the connection.
+    fn reply(&self, frame: RespFrame) -> RespFrame {
+        match self.protocol {
+            3 => frame,
+            _ => frame.into_resp2(),
+        }
+    }
+
+    fn multi(&mut self) -> RespFrame {
+        if self.transaction.is_some() {
+            return SimpleError::new("ERR MULTI calls can not be nested").into();
+        }
+        self.transaction = Some(Transaction::default());
+        RESP_OK.clone()
+    }
+
+    fn discard(&mut self) -> RespFrame {
+        match self.transaction.take() {
+            Some(_) => {
+                self.watches.clear();
+                RESP_OK.clone()
+            }
+            None => SimpleError::new("ERR DISCARD without MULTI").into(),
+        }
+    }
+
+    /// Run the queued commands with the backend locked exclusively. Nothing
+    /// runs if the transaction was aborted or a watched key was modified.
+    fn exec(&mut self) -> RespFrame {
+        let Some(transaction) = self.transaction.take() else {
+            return SimpleError::new("ERR EXEC without MULTI").into();
+        };
+        if transaction.aborted {
+            self.watches.clear();
+            return SimpleError::new("EXECABORT Transaction discarded because of previous errors.")
+                .into();
+        }
+
+        // held on to as a queued SELECT switches the session backend
+        let backend = self.backend.clone();
+        let _guard = backend.exclusive();
+        let dirty = self.watches.is_dirty();
+        self.watches.clear();
+        if dirty {
+            return RespFrame::NullArray(RespNullArray);
+        }
+        let frames = transaction
+            .queued
+            .into_iter()
+            .map(|(cmd, logged)| match cmd {
+                Command::Select(cmd) => self.select(cmd),
+                Command::Eval(cmd) => cmd.execute_as(&self.backend, self.user.as_deref()),
+                cmd => execute(cmd, logged, &self.backend),
+            })
+            .collect::<Vec<_>>();
+        RespArray::new(frames).into()
+    }
+
+    /// Run the following commands, and watch the following keys, in another
+    /// database.
+    fn select(&mut self, cmd: SelectCommand) -> RespFrame {
+        match cmd.select(&self.backend) {
+            Ok(backend) => {
+                self.watches.select(backend.db_index());
+                self.backend = backend;
+                RESP_OK.clone()
+            }
+            Err(e) => e.into(),
+        }
+    }
+
+    fn client(&mut self, cmd: ClientCommand) -> RespFrame {
+        match cmd.action {
+            ClientAction::List => BulkString::from(self.backend.client_list()).into(),
+            ClientAction::Id => RespFrame::Integer(self.id as i64),
+            ClientAction::GetName => match &self.name {
+                Some(name) => BulkString::from(name.as_str()).into(),
+                None => RespFrame::Null(RespNull),
+            },
+            // an empty name removes the name
+            ClientAction::SetName(name) => {
+                self.name = (!name.is_empty()).then_some(name);
+                RESP_OK.clone()
+            }
+            ClientAction::Kill { filters, legacy } => {
+                // the legacy form may close the connection running it
+                let skip_me = filters
+                    .iter()
+                    .rev()
+                    .find_map(|filter| match filter {
+                        ClientKillFilter::SkipMe(skip) => Some(*skip),
+                        _ => None,
+                    })
+                    .unwrap_or(!legacy);
+                let killed = self.backend.kill_clients(|info| {
+                    (!skip_me || info.id != self.id)
+                        && filters.iter().all(|filter| match filter {
+                            ClientKillFilter::Id(id) => info.id == *id,
+                            ClientKillFilter::Addr(addr) => info.addr.to_string() == *addr,
+                            ClientKillFilter::User(user) => {
+                                info.user.as_deref() == Some(user.as_str())
+                            }
+                            ClientKillFilter::SkipMe(_) => true,
+                        })
+                });
+                match (legacy, killed) {
+                    (true, 0) => SimpleError::new("ERR No such client").into(),
+                    (true, _) => RESP_OK.clone(),
+                    (false, killed) => RespFrame::Integer(killed as i64),
+                }
+            }
+        }
+    }
+
+    /// Stream every command run from now on to the connection.
+    fn monitor(&mut self) -> RespFrame {
+        self.backend.add_monitor(self.id, self.sender.clone());
+        RESP_OK.clone()
+    }
+
+    /// Refresh the connection as listed by CLIENT LIST after a command.
+    fn sync_info(&self) {
+        self.backend.update_client(self.id, |info| {
+            info.name.clone_from(&self.name);
+            info.user.clone_from(&self.user);
+            info.db = self.backend.db_index();
+            info.channels = self.subscriptions.count_of(false);
+            info.patterns = self.subscriptions.count_of(true);
+            info.multi = self.transaction.as_ref().map(|t| t.queued.len());
+        });
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.backend.unregister_client(self.id);
+        self.backend.remove_replica(self.id);
+        self.backend
+            .connected_clients
+            .fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 pub async fn stream_handle(stream: TcpStream, backend: Backend) -> Result<()> {
+    let addr = stream.peer_addr()?;
+    // replies of pipelined commands must not wait for the ack of the first
+    stream.set_nodelay(true)?;
     let mut framed = Framed::new(stream, RespFrameCodec);
+    let mut session = Session::new(&backend, addr);
+    if backend.connected_clients.load(Ordering::Relaxed) > backend.config.maxclients {
+        let error = SimpleError::new("ERR max number of clients reached");
+        framed.send(error.into()).await?;
+        return Ok(());
+    }
+    // commands pipelined behind a blocking one
+    let mut pending = VecDeque::new();
     loop {
-        match framed.next().await {
-            Some(Ok(frame)) => {
-                info!("Received frame: {:?}", frame);
-                let request = RedisRequest {
-                    frame,
-                    backend: backend.clone(),
-                };
-                let response = request_handler(request).await?;
-                info!("Sending response: {:?}", response.frame);
-                framed.send(response.frame).await?;
+        let frame = match pending.pop_front() {
+            Some(frame) => frame,
+            None => tokio::select! {
+                frame = framed.next() => match frame {
+                    Some(Ok(frame)) => frame,
+                    Some(Err(e)) => return Err(e),
+                    None => return Ok(()),
+                },
+                // the session holds a sender so the channel is never closed
+                Some(message) = session.messages.recv() => {
+                    framed.send(session.reply(message)).await?;
+                    continue;
+                }
+                _ = session.kill.notified() => return Ok(()),
+            },
+        };
+        debug!("Received frame: {:?}", frame);
+        let request = RedisRequest {
+            frame,
+            backend: session.backend.clone(),
+        };
+        let kill = session.kill.clone();
+        let response = {
+            let handled = request_handler(request, &mut session);
+            tokio::pin!(handled);
+            // a blocked command is dropped, before it takes anything, once
+            // the connection is closed or killed
+            loop {
+                tokio::select! {
+                    biased;
+                    response = &mut handled => break response?,
+                    frame = framed.next() => match frame {
+                        Some(Ok(frame)) => pending.push_back(frame),
+                        Some(Err(e)) => return Err(e),
+                        None => return Ok(()),
+                    },
+                    _ = kill.notified() => return Ok(()),
+                }
             }
-
-            Some(Err(e)) => return Err(e.into()),
-            None => return Ok(()),
+        };
+        debug!("Sending response: {:?}", response.frames);
+        for frame in response.frames {
+            framed.feed(session.reply(frame)).await?;
+        }
+        framed.flush().await?;
+        if response.close {
+            return Ok(());
         }
     }
 }
 
-async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
+async fn request_handler(request: RedisRequest, session: &mut Session) -> Result<RedisResponse> {
+    let name = command_name(&request.frame);
+    session.backend.update_client(session.id, |info| {
+        info.last_interaction = now_ms();
+        if !name.is_empty() {
+            info.last_command = Some(name.clone());
+        }
+    });
+    let response = handle_command(request, session, &name).await?;
+    session.sync_info();
+    Ok(response)
+}
+
+async fn handle_command(
+    request: RedisRequest,
+    session: &mut Session,
+    name: &str,
+) -> Result<RedisResponse> {
     let (frame, backend) = (request.frame, request.backend);
-    let cmd: Command = Command::try_from(frame)?;
-    info!("Executing command: {:?}", cmd);
-    let frame = cmd.execute(&backend);
-    Ok(RedisResponse { frame })
+    let asking = std::mem::take(&mut session.asking);
+    let logged = backend.propagates().then(|| frame.clone());
+    // only copied when a monitor or the slowlog may need them
+    let args =
+        (backend.is_monitored() || backend.slowlog_enabled()).then(|| command_args(name, &frame));
+    let cmd = Command::try_from(frame)
+        .map_err(|e| format!("ERR {}", e))
+        .and_then(|cmd| {
+            session.authorize(name, &cmd)?;
+            session.route(&cmd, asking)?;
+            if cmd.is_write() && backend.is_replica() {
+                return Err("READONLY You can't write against a read only replica.".to_string());
+            }
+            // replicas leave eviction to their master
+            if !backend.is_replica() && !backend.reclaim_memory() && cmd.denies_oom() {
+                return Err("OOM command not allowed when used memory > 'maxmemory'.".to_string());
+            }
+            Ok(cmd)
+        });
+    let cmd = match cmd {
+        Ok(cmd) => cmd,
+        // a rejected command inside MULTI fails the whole transaction
+        Err(e) => {
+            if let Some(transaction) = session.transaction.as_mut() {
+                transaction.aborted = true;
+            }
+            return Ok(RedisResponse::new(SimpleError::new(e).into()));
+        }
+    };
+    debug!("Executing command: {:?}", cmd);
+    let recognized = !matches!(cmd, Command::Unrecognized(_));
+    if recognized && let Some(args) = &args {
+        backend.feed_monitors(&session.addr, args);
+    }
+
+    let subscribed = !session.subscriptions.is_empty();
+    if subscribed && !cmd.is_allowed_when_subscribed() {
+        let error = SimpleError::new(
+            "ERR only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING / QUIT are allowed in this context",
+        );
+        return Ok(RedisResponse::new(error.into()));
+    }
+
+    if let Some(transaction) = session.transaction.as_mut()
+        && !cmd.is_transaction_control()
+    {
+        transaction.queued.push((cmd, logged));
+        return Ok(RedisResponse::new(SimpleString::new("QUEUED").into()));
+    }
+
+    let blocking = cmd.is_blocking();
+    let start = Instant::now();
+    let response = match cmd {
+        Command::Subscribe(cmd) => {
+            RedisResponse::many(cmd.execute_subscribe(&mut session.subscriptions))
+        }
+        Command::Unsubscribe(cmd) => {
+            RedisResponse::many(cmd.execute_unsubscribe(&mut session.subscriptions))
+        }
+        Command::Ping(cmd) if subscribed => RedisResponse::new(cmd.execute_subscribed()),
+        Command::Quit(cmd) => RedisResponse {
+            frames: vec![cmd.execute(&backend)],
+            close: true,
+        },
+        Command::Hello(cmd) => RedisResponse::new(session.hello(cmd)),
+        Command::Auth(cmd) => RedisResponse::new(session.auth(cmd)),
+        Command::Multi(_) => RedisResponse::new(session.multi()),
+        Command::Exec(_) => RedisResponse::new(session.exec()),
+        Command::Discard(_) => RedisResponse::new(session.discard()),
+        Command::Select(cmd) => RedisResponse::new(session.select(cmd)),
+        Command::Client(cmd) => RedisResponse::new(session.client(cmd)),
+        Command::Monitor(_) => RedisResponse::new(session.monitor()),
+        Command::Asking(_) => RedisResponse::new(session.asking()),
+        Command::Watch(_) if session.transaction.is_some() => {
+            RedisResponse::new(SimpleError::new("ERR WATCH inside MULTI is not allowed").into())
+        }
+        Command::Watch(cmd) => RedisResponse::new(cmd.execute_watch(&mut session.watches)),
+        Command::Unwatch(cmd) => RedisResponse::new(cmd.execute_unwatch(&mut session.watches)),
+        Command::ReplConf(cmd) => match session.replconf(cmd) {
+            Some(frame) => RedisResponse::new(frame),
+            None => RedisResponse::many(vec![]),
+        },
+        Command::PSync(cmd) => RedisResponse::many(session.psync(cmd)),
+        Command::BlockingPop(cmd) => {
+            let record = AofRecord::Pop { left: cmd.left };
+            let frame = cmd.execute_blocking(&backend).await;
+            if let Some(logged) = logged {
+                backend.propagate(record.frames(logged, &frame, &backend));
+            }
+            RedisResponse::new(frame)
+        }
+        Command::XRead(cmd) if cmd.block.is_some() => {
+            RedisResponse::new(cmd.execute_blocking(&backend).await)
+        }
+        Command::XReadGroup(cmd) if cmd.block.is_some() => {
+            let frame = cmd.execute_blocking(&backend).await;
+            if let Some(logged) = logged {
+                backend.propagate(AofRecord::Served.frames(logged, &frame, &backend));
+            }
+            RedisResponse::new(frame)
+        }
+        // scripts run atomically, their commands are propagated instead
+        Command::Eval(cmd) => {
+            let _guard = backend.exclusive();
+            RedisResponse::new(cmd.execute_as(&backend, session.user.as_deref()))
+        }
+        cmd if cmd.writes_atomically() => {
+            let _guard = backend.exclusive();
+            RedisResponse::new(execute(cmd, logged, &backend))
+        }
+        cmd => {
+            let _guard = backend.shared();
+            RedisResponse::new(execute(cmd, logged, &backend))
+        }
+    };
+
+    if recognized {
+        let elapsed = start.elapsed();
+        backend.record_command(name, elapsed);
+        // blocked clients wait for other ones, they are not slow
+        if !blocking && let Some(args) = args {
+            backend.log_if_slow(args, elapsed, session.addr, session.name.clone());
+        }
+    }
+    Ok(response)
+}
+
+/// Lowercase name of the command in the request, used for acl checks.
+fn command_name(frame: &RespFrame) -> String {
+    match frame {
+        RespFrame::Array(array) => match array.first() {
+            Some(RespFrame::BulkString(name)) => String::from_utf8_lossy(name).to_ascii_lowercase(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// Arguments of the request as shown to monitors and kept by the slowlog,
+/// with the passwords redacted.
+fn command_args(name: &str, frame: &RespFrame) -> Vec<BulkString> {
+    let RespFrame::Array(array) = frame else {
+        return vec![];
+    };
+    let mut args = array
+        .iter()
+        .filter_map(|arg| match arg {
+            RespFrame::BulkString(arg) => Some(arg.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let redacted = match name {
+        "auth" => 1..args.len(),
+        "hello" => match args
+            .iter()
+            .position(|arg| arg.eq_ignore_ascii_case(b"auth"))
+        {
+            Some(i) => i + 1..(i + 3).min(args.len()),
+            None => 0..0,
+        },
+        _ => 0..0,
+    };
+    for arg in &mut args[redacted] {
+        *arg = BulkString::from("(redacted)");
+    }
+    args
+}
+
+/// Run a command, the caller holds the backend lock. The keys it writes are
+/// marked as modified, accesses are recorded for eviction and the command is
+/// propagated to the aof and the replicas if its request frame is given and
+/// it did not fail.
+pub(crate) fn execute(cmd: Command, logged: Option<RespFrame>, backend: &Backend) -> RespFrame {
+    let record = AofRecord::from(&cmd);
+    let write = cmd.is_write();
+    let keys = cmd.keys().into_iter().map(String::from).collect::<Vec<_>>();
+    let frame = cmd.execute(backend);
+    for key in &keys {
+        if write {
+            backend.touch(key);
+        }
+        backend.record_access(key);
+    }
+    if let Some(logged) = logged
+        && !matches!(frame, RespFrame::Error(_))
+    {
+        backend.propagate(record.frames(logged, &frame, backend));
+    }
+    frame
+}
+
+impl RedisResponse {
+    fn new(frame: RespFrame) -> Self {
+        Self::many(vec![frame])
+    }
+
+    fn many(frames: Vec<RespFrame>) -> Self {
+        Self {
+            frames,
+            close: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acl::AclUser;
+    use crate::config::ServerConfig;
+    use crate::{BulkString, NullBulkString, RespNull};
+
+    fn test_addr() -> SocketAddr {
+        "127.0.0.1:50000".parse().unwrap()
+    }
+
+    async fn run(session: &mut Session, args: &[&str]) -> Result<RespFrame> {
+        let frame = RespArray::new(
+            args.iter()
+                .map(|arg| BulkString::from(*arg).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+        .into();
+        let request = RedisRequest {
+            frame,
+            backend: session.backend.clone(),
+        };
+        let mut response = request_handler(request, session).await?;
+        Ok(response.frames.remove(0))
+    }
+
+    #[tokio::test]
+    async fn test_multi_exec_should_run_queued_commands() -> Result<()> {
+        let backend = Backend::new();
+        let mut session = Session::new(&backend, test_addr());
+
+        assert_eq!(run(&mut session, &["multi"]).await?, RESP_OK.clone());
+        let queued = run(&mut session, &["set", "a", "1"]).await?;
+        assert_eq!(queued, SimpleString::new("QUEUED").into());
+        run(&mut session, &["get", "a"]).await?;
+        assert_eq!(backend.get("a"), Ok(None));
+
+        let ret = run(&mut session, &["exec"]).await?;
+        assert_eq!(
+            ret,
+            RespArray::new([RESP_OK.clone(), BulkString::from("1").into()]).into()
+        );
+
+        run(&mut session, &["multi"]).await?;
+        run(&mut session, &["set", "a", "2"]).await?;
+        assert_eq!(run(&mut session, &["discard"]).await?, RESP_OK.clone());
+        assert_eq!(backend.get("a"), Ok(Some(BulkString::from("1").into())));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_exec_should_fail_when_watched_key_changed() -> Result<()> {
+        let backend = Backend::new();
+        let mut session = Session::new(&backend, test_addr());
+        let mut other = Session::new(&backend, test_addr());
+
+        run(&mut session, &["watch", "a"]).await?;
+        run(&mut session, &["multi"]).await?;
+        run(&mut session, &["set", "a", "1"]).await?;
+        run(&mut other, &["set", "a", "2"]).await?;
+
+        let ret = run(&mut session, &["exec"]).await?;
+        assert_eq!(ret, RespFrame::NullArray(RespNullArray));
+        assert_eq!(backend.get("a"), Ok(Some(BulkString::from("2").into())));
+        assert!(backend.db().versions.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hello_should_switch_protocol() -> Result<()> {
+        let backend = Backend::new();
+        let mut session = Session::new(&backend, test_addr());
+        assert_eq!(
+            session.reply(RespFrame::Null(RespNull)),
+            NullBulkString.into()
+        );
+
+        let ret = run(&mut session, &["hello", "3", "setname", "cli"]).await?;
+        assert!(matches!(ret, RespFrame::Map(_)));
+        assert_eq!(session.protocol, 3);
+        assert_eq!(session.name.as_deref(), Some("cli"));
+        assert_eq!(session.reply(RespFrame::Null(RespNull)), RespNull.into());
+
+        let ret = run(&mut session, &["hello", "4"]).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+        let ret = run(&mut session, &["hello", "2", "auth", "bob", "pwd"]).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+        assert_eq!(session.protocol, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_command_should_abort_transaction() -> Result<()> {
+        let backend = Backend::new();
+        let mut session = Session::new(&backend, test_addr());
+
+        run(&mut session, &["multi"]).await?;
+        let ret = run(&mut session, &["get"]).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+        run(&mut session, &["set", "a", "1"]).await?;
+
+        let ret = run(&mut session, &["exec"]).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+        assert_eq!(backend.get("a"), Ok(None));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_acl_should_reject_commands() -> Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            requirepass: Some("pwd".to_string()),
+            users: vec![AclUser {
+                name: "app".to_string(),
+                password: Some("secret".to_string()),
+                commands: vec!["get".to_string(), "set".to_string()],
+                keys: vec!["app:*".to_string()],
+            }],
+            ..Default::default()
+        });
+        let mut session = Session::new(&backend, test_addr());
+
+        let ret = run(&mut session, &["get", "app:1"]).await?;
+        assert_eq!(
+            ret,
+            SimpleError::new("NOAUTH Authentication required.").into()
+        );
+        let ret = run(&mut session, &["auth", "wrong"]).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+        let ret = run(&mut session, &["auth", "app", "secret"]).await?;
+        assert_eq!(ret, RESP_OK.clone());
+
+        let ret = run(&mut session, &["set", "app:1", "v"]).await?;
+        assert_eq!(ret, RESP_OK.clone());
+        let ret = run(&mut session, &["set", "other", "v"]).await?;
+        assert_eq!(
+            ret,
+            SimpleError::new("NOPERM No permissions to access a key").into()
+        );
+        let ret = run(&mut session, &["hget", "app:1", "f"]).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+
+        let ret = run(&mut session, &["auth", "pwd"]).await?;
+        assert_eq!(ret, RESP_OK.clone());
+        let ret = run(&mut session, &["set", "other", "v"]).await?;
+        assert_eq!(ret, RESP_OK.clone());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_select_should_switch_database() -> Result<()> {
+        let backend = Backend::new();
+        let mut session = Session::new(&backend, test_addr());
+
+        run(&mut session, &["set", "a", "0"]).await?;
+        let ret = run(&mut session, &["select", "16"]).await?;
+        assert_eq!(ret, SimpleError::new("ERR DB index is out of range").into());
+
+        run(&mut session, &["multi"]).await?;
+        run(&mut session, &["select", "2"]).await?;
+        run(&mut session, &["set", "a", "2"]).await?;
+        run(&mut session, &["exec"]).await?;
+        assert_eq!(session.backend.db_index(), 2);
+        assert_eq!(
+            run(&mut session, &["get", "a"]).await?,
+            BulkString::from("2").into()
+        );
+        assert_eq!(backend.get("a"), Ok(Some(BulkString::from("0").into())));
+
+        let ret = run(&mut session, &["hget", "a", "f"]).await?;
+        assert_eq!(
+            ret,
+            SimpleError::new("WRONGTYPE Operation against a key holding the wrong kind of value")
+                .into()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replica_should_refuse_writes() -> Result<()> {
+        let backend = Backend::new();
+        let mut session = Session::new(&backend, test_addr());
+
+        backend.replicaof(Some(("127.0.0.1".to_string(), 1)));
+        let ret = run(&mut session, &["set", "a", "1"]).await?;
+        assert_eq!(
+            ret,
+            SimpleError::new("READONLY You can't write against a read only replica.").into()
+        );
+        run(&mut session, &["get", "a"]).await?;
+
+        backend.replicaof(None);
+        let ret = run(&mut session, &["set", "a", "1"]).await?;
+        assert_eq!(ret, RESP_OK.clone());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_commands() -> Result<()> {
+        let backend = Backend::new();
+        let mut session = Session::new(&backend, test_addr());
+        let mut other = Session::new(&backend, "127.0.0.1:50001".parse()?);
+
+        assert_eq!(
+            run(&mut session, &["client", "getname"]).await?,
+            RespFrame::Null(RespNull)
+        );
+        run(&mut session, &["client", "setname", "worker"]).await?;
+        run(&mut other, &["select", "3"]).await?;
+        let RespFrame::BulkString(list) = run(&mut session, &["client", "list"]).await? else {
+            panic!("CLIENT LIST should reply with a bulk string");
+        };
+        let list = String::from_utf8(list.0)?;
+        let lines = list.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("id=1 addr=127.0.0.1:50000 name=worker "));
+        assert!(lines[1].contains(" db=3 "));
+        assert!(lines[1].contains(" cmd=select "));
+
+        let ret = run(&mut session, &["client", "kill", "id", "1"]).await?;
+        assert_eq!(ret, RespFrame::Integer(0));
+        let ret = run(&mut session, &["client", "kill", "127.0.0.1:1"]).await?;
+        assert_eq!(ret, SimpleError::new("ERR No such client").into());
+        let ret = run(&mut session, &["client", "kill", "127.0.0.1:50001"]).await?;
+        assert_eq!(ret, RESP_OK.clone());
+        assert!(futures::FutureExt::now_or_never(other.kill.notified()).is_some());
+
+        drop(other);
+        assert_eq!(backend.clients().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slowlog_and_command_stats() -> Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            slowlog_log_slower_than: 0,
+            ..Default::default()
+        });
+        let mut session = Session::new(&backend, test_addr());
+
+        run(&mut session, &["set", "a", "1"]).await?;
+        run(&mut session, &["auth", "secret"]).await?;
+        run(&mut session, &["nope"]).await?;
+        let RespFrame::Array(entries) = run(&mut session, &["slowlog", "get"]).await? else {
+            panic!("SLOWLOG GET should reply with an array");
+        };
+        assert_eq!(entries.len(), 2);
+        let RespFrame::Array(entry) = &entries[0] else {
+            panic!("an entry should be an array");
+        };
+        assert_eq!(
+            entry[3],
+            RespArray::new([
+                BulkString::from("auth").into(),
+                BulkString::from("(redacted)").into()
+            ])
+            .into()
+        );
+
+        let calls = backend.command_stats("set").map(|stats| stats.calls);
+        assert_eq!(calls, Some(1));
+        assert_eq!(backend.command_stats("nope"), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cluster_should_redirect_other_slots() -> Result<()> {
+        // bar is in slot 5061 and foo in slot 12182, which moves to a
+        let cluster = serde_yaml::from_str(
+            "nodes:\n  - id: a\n    host: 127.0.0.1\n    port: 7000\n    slots: [0-8191]\n  - id: b\n    host: 127.0.0.1\n    port: 7001\n    slots: [8192-16383]\nmigrating:\n  - slots: 12000-12999\n    to: a\n",
+        )?;
+        let backend = Backend::with_config(ServerConfig {
+            port: 7000,
+            cluster: Some(cluster),
+            ..Default::default()
+        });
+        let mut session = Session::new(&backend, test_addr());
+
+        assert_eq!(
+            run(&mut session, &["set", "bar", "1"]).await?,
+            RESP_OK.clone()
+        );
+        let moved = SimpleError::new("MOVED 12182 127.0.0.1:7001").into();
+        assert_eq!(run(&mut session, &["get", "foo"]).await?, moved);
+        let ret = run(&mut session, &["mset", "bar", "1", "foo", "2"]).await?;
+        assert_eq!(
+            ret,
+            SimpleError::new("CROSSSLOT Keys in request don't hash to the same slot").into()
+        );
+
+        assert_eq!(run(&mut session, &["asking"]).await?, RESP_OK.clone());
+        let ret = run(&mut session, &["set", "foo", "2"]).await?;
+        assert_eq!(ret, RESP_OK.clone());
+        assert_eq!(run(&mut session, &["get", "foo"]).await?, moved);
+
+        let ret = run(&mut session, &["select", "1"]).await?;
+        assert!(matches!(ret, RespFrame::Error(_)));
+        assert_eq!(run(&mut session, &["dbsize"]).await?, RespFrame::Integer(2));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_monitor_should_stream_commands() -> Result<()> {
+        let backend = Backend::new();
+        let mut monitor = Session::new(&backend, test_addr());
+        let mut session = Session::new(&backend, "127.0.0.1:50001".parse()?);
+
+        assert_eq!(run(&mut monitor, &["monitor"]).await?, RESP_OK.clone());
+        run(&mut session, &["set", "a", "1"]).await?;
+        run(&mut session, &["get"]).await?;
+        let Ok(RespFrame::SimpleString(line)) = monitor.messages.try_recv() else {
+            panic!("the monitor should receive the command");
+        };
+        assert!(line.ends_with(r#" [0 127.0.0.1:50001] "set" "a" "1""#));
+        // rejected commands are not streamed
+        assert!(monitor.messages.try_recv().is_err());
+        Ok(())
+    }
+
+    /// Serve the backend on a random port, returns a connected client.
+    async fn connect(backend: &Backend) -> Result<Framed<TcpStream, RespFrameCodec>> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let backend = backend.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(stream_handle(stream, backend.clone()));
+            }
+        });
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Framed::new(stream, RespFrameCodec))
+    }
+
+    fn command(args: &[&str]) -> RespFrame {
+        RespArray::new(
+            args.iter()
+                .map(|arg| BulkString::from(*arg).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+        .into()
+    }
+
+    async fn settle() {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn test_closed_blocked_client_should_not_pop() -> Result<()> {
+        let backend = Backend::new();
+        let mut client = connect(&backend).await?;
+        client.send(command(&["blpop", "list", "0"])).await?;
+        settle().await;
+        drop(client);
+        settle().await;
+
+        backend.lpush("list".to_string(), vec![b"a".into()])?;
+        settle().await;
+        assert_eq!(backend.llen("list"), Ok(1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_killed_blocked_client_should_not_pop() -> Result<()> {
+        let backend = Backend::new();
+        let mut client = connect(&backend).await?;
+        client.send(command(&["brpop", "list", "0"])).await?;
+        settle().await;
+        assert_eq!(backend.kill_clients(|_| true), 1);
+        settle().await;
+
+        backend.lpush("list".to_string(), vec![b"a".into()])?;
+        settle().await;
+        assert_eq!(backend.llen("list"), Ok(1));
+        assert!(client.next().await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commands_pipelined_after_blocking_pop_should_wait() -> Result<()> {
+        let backend = Backend::new();
+        let mut client = connect(&backend).await?;
+        client.feed(command(&["blpop", "list", "0"])).await?;
+        client.feed(command(&["ping"])).await?;
+        client.flush().await?;
+        settle().await;
+
+        backend.lpush("list".to_string(), vec![b"a".into()])?;
+        let popped = RespArray::new([b"list".into(), b"a".into()]).into();
+        assert_eq!(client.next().await.transpose()?, Some(popped));
+        let pong = SimpleString::new("PONG").into();
+        assert_eq!(client.next().await.transpose()?, Some(pong));
+        Ok(())
+    }
 }
@@ -0,0 +1,290 @@
+use super::{bulk, command_frame, replay};
+use crate::cmd::{Command, SetExpire};
+use crate::{Backend, RespEncode, RespFrame};
+use anyhow::{Result, anyhow};
+use bytes::BytesMut;
+use serde::Deserialize;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+/// When the append only file is flushed to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FsyncPolicy {
+    /// fsync after every write, the safest and slowest policy
+    Always,
+    /// fsync once per second from a background task
+    #[default]
+    EverySec,
+    /// leave flushing to the operating system
+    No,
+}
+
+impl FromStr for FsyncPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "always" => Ok(FsyncPolicy::Always),
+            "everysec" => Ok(FsyncPolicy::EverySec),
+            "no" => Ok(FsyncPolicy::No),
+            _ => Err(anyhow!("invalid fsync policy: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for FsyncPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            FsyncPolicy::Always => "always",
+            FsyncPolicy::EverySec => "everysec",
+            FsyncPolicy::No => "no",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug)]
+pub struct Aof {
+    file: Mutex<File>,
+    fsync: FsyncPolicy,
+}
+
+impl Aof {
+    pub fn open(path: impl AsRef<Path>, fsync: FsyncPolicy) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            fsync,
+        })
+    }
+
+    pub fn fsync(&self) -> FsyncPolicy {
+        self.fsync
+    }
+
+    pub fn append(&self, frames: Vec<RespFrame>) -> io::Result<()> {
+        let buf = frames
+            .into_iter()
+            .flat_map(|frame| frame.encode())
+            .collect::<Vec<u8>>();
+
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(&buf)?;
+        if self.fsync == FsyncPolicy::Always {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Replace the whole content of the aof, e.g. after a full resync with
+    /// a master made the previous commands irrelevant.
+    pub fn rewrite(&self, frames: Vec<RespFrame>) -> io::Result<()> {
+        {
+            let file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+            file.set_len(0)?;
+        }
+        self.append(frames)
+    }
+
+    pub fn sync(&self) -> io::Result<()> {
+        let file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.sync_data()
+    }
+}
+
+/// How an executed command is written to the aof and sent to replicas.
+#[derive(Debug, PartialEq)]
+pub(crate) enum AofRecord {
+    Skip,
+    Verbatim,
+    WithTtl(String),
+    Pop {
+        left: bool,
+    },
+    /// XADD, the id of the entry is recorded instead of the requested one
+    StreamAdd {
+        fields: usize,
+    },
+    /// recorded only if it replied with entries
+    Served,
+    /// INCRBYFLOAT, recorded as a SET of the result so that replaying it
+    /// does not depend on float rounding
+    SetResult(String),
+}
+
+impl AofRecord {
+    /// The frames recording an executed command. Relative expire times are
+    /// followed by the resulting absolute PEXPIREAT so replaying later does
+    /// not extend them, a served blocking pop is recorded as the plain pop it
+    /// turned into and a stream entry keeps the id it was given.
+    pub(crate) fn frames(
+        self,
+        request: RespFrame,
+        response: &RespFrame,
+        backend: &Backend,
+    ) -> Vec<RespFrame> {
+        match self {
+            AofRecord::Skip => vec![],
+            AofRecord::Verbatim => vec![request],
+            AofRecord::WithTtl(key) => {
+                let mut frames = vec![request];
+                if let Some(at) = backend.db().expires.get(&key).map(|v| *v.value()) {
+                    frames.push(command_frame([
+                        bulk("pexpireat"),
+                        bulk(key),
+                        bulk(at.to_string()),
+                    ]));
+                }
+                frames
+            }
+            AofRecord::Pop { left } => match response {
+                RespFrame::Array(served) if served.len() == 2 => {
+                    let name = if left { "lpop" } else { "rpop" };
+                    vec![command_frame([bulk(name), served[0].clone()])]
+                }
+                _ => vec![],
+            },
+            AofRecord::StreamAdd { fields } => match (request, response) {
+                // the id comes right before the field value pairs
+                (RespFrame::Array(mut request), RespFrame::BulkString(id)) => {
+                    let index = request.len() - 2 * fields - 1;
+                    request.0[index] = id.clone().into();
+                    vec![request.into()]
+                }
+                _ => vec![],
+            },
+            AofRecord::Served => match response {
+                RespFrame::Array(_) => vec![request],
+                _ => vec![],
+            },
+            AofRecord::SetResult(key) => vec![command_frame([
+                bulk("set"),
+                bulk(key),
+                response.clone(),
+                bulk("keepttl"),
+            ])],
+        }
+    }
+}
+
+impl From<&Command> for AofRecord {
+    fn from(cmd: &Command) -> Self {
+        match cmd {
+            _ if !cmd.is_write() => AofRecord::Skip,
+            Command::Set(set) if matches!(set.expire, Some(SetExpire::After(_))) => {
+                AofRecord::WithTtl(set.key.clone())
+            }
+            Command::Expire(expire) => AofRecord::WithTtl(expire.key.clone()),
+            Command::BlockingPop(pop) => AofRecord::Pop { left: pop.left },
+            Command::XAdd(add) => AofRecord::StreamAdd {
+                fields: add.fields.len(),
+            },
+            Command::XReadGroup(_) => AofRecord::Served,
+            Command::IncrByFloat(incr) => AofRecord::SetResult(incr.key.clone()),
+            _ => AofRecord::Verbatim,
+        }
+    }
+}
+
+impl Backend {
+    /// Start appending write commands to the aof, returns false if it was
+    /// already turned on.
+    pub fn enable_aof(&self, aof: Aof) -> bool {
+        self.aof.set(aof).is_ok()
+    }
+
+    /// Flush the aof to disk periodically for the everysec policy.
+    pub async fn fsync_aof(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Some(aof) = self.aof()
+                && let Err(e) = aof.sync()
+            {
+                warn!("Failed to fsync append only file: {:?}", e);
+            }
+        }
+    }
+}
+
+pub(super) fn load_aof(backend: &Backend, path: &Path) -> Result<usize> {
+    let mut buf = BytesMut::from(fs::read(path)?.as_slice());
+    replay(backend, &mut buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{PersistenceConfig, load};
+    use crate::{BulkString, RespArray, RespNull, now_ms};
+
+    #[test]
+    fn test_fsync_policy_from_str() -> Result<()> {
+        assert_eq!("always".parse::<FsyncPolicy>()?, FsyncPolicy::Always);
+        assert_eq!("EverySec".parse::<FsyncPolicy>()?, FsyncPolicy::EverySec);
+        assert_eq!("no".parse::<FsyncPolicy>()?, FsyncPolicy::No);
+        assert!("sometimes".parse::<FsyncPolicy>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_aof_should_replay_at_load() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("simple-redis-aof-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let config = PersistenceConfig {
+            dir: dir.clone(),
+            appendonly: true,
+            appendfsync: FsyncPolicy::Always,
+            ..Default::default()
+        };
+
+        let backend = Backend::with_persistence(config.clone());
+        assert_eq!(load(&backend)?, 0);
+        let aof = backend.aof().expect("aof should be enabled");
+
+        let set = command_frame([bulk("set"), bulk("hello"), bulk("world")]);
+        aof.append(AofRecord::Verbatim.frames(set, &RespFrame::Null(RespNull), &backend))?;
+
+        backend.set("hello".to_string(), BulkString::from("world").into());
+        backend.expire_at("hello", now_ms() + 60_000);
+        let expire = command_frame([bulk("expire"), bulk("hello"), bulk("60")]);
+        aof.append(AofRecord::WithTtl("hello".to_string()).frames(
+            expire,
+            &RespFrame::Integer(1),
+            &backend,
+        ))?;
+
+        let served = RespArray::new([bulk("list"), bulk("a")]).into();
+        let blpop = command_frame([bulk("blpop"), bulk("list"), bulk("0")]);
+        aof.append(AofRecord::Pop { left: true }.frames(blpop, &served, &backend))?;
+
+        let xadd = command_frame([bulk("xadd"), bulk("s"), bulk("*"), bulk("f"), bulk("v")]);
+        let added = bulk("5-1");
+        aof.append(AofRecord::StreamAdd { fields: 1 }.frames(xadd, &added, &backend))?;
+
+        let incr = command_frame([bulk("incrbyfloat"), bulk("f"), bulk("0.1")]);
+        let record = AofRecord::SetResult("f".to_string());
+        aof.append(record.frames(incr, &bulk("1.1"), &backend))?;
+
+        let restored = Backend::with_persistence(config);
+        assert_eq!(load(&restored)?, 6);
+        assert_eq!(restored.get("f"), Ok(Some(BulkString::from("1.1").into())));
+        assert_eq!(restored.xlast_id("s"), Ok(Some(crate::StreamId::new(5, 1))));
+        assert_eq!(
+            restored.get("hello"),
+            Ok(Some(BulkString::from("world").into()))
+        );
+        let ttl = restored.pttl("hello");
+        assert!(ttl > 50_000 && ttl <= 60_000);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+mod aof;
+mod rdb;
+
+use crate::{
+    Backend, BulkString, RespArray, RespDecodeV2, RespError, RespFrame,
+    cmd::{Command, CommandExecutor},
+};
+use anyhow::{Result, anyhow};
+use bytes::BytesMut;
+use serde::Deserialize;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+pub(crate) use aof::AofRecord;
+pub use aof::{Aof, FsyncPolicy};
+pub use rdb::{dump, restore, save, save_snapshot, snapshot};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PersistenceConfig {
+    pub dir: PathBuf,
+    pub dbfilename: String,
+    pub appendonly: bool,
+    pub appendfilename: String,
+    pub appendfsync: FsyncPolicy,
+}
+
+impl PersistenceConfig {
+    pub fn snapshot_path(&self) -> PathBuf {
+        self.dir.join(&self.dbfilename)
+    }
+
+    pub fn aof_path(&self) -> PathBuf {
+        self.dir.join(&self.appendfilename)
+    }
+}
+
+impl Default for PersistenceConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("."),
+            dbfilename: "dump.rdb".to_string(),
+            appendonly: false,
+            appendfilename: "appendonly.aof".to_string(),
+            appendfsync: FsyncPolicy::default(),
+        }
+    }
+}
+
+/// Restore the backend at startup: the aof wins over the snapshot when it is
+/// turned on, as it is the more complete of the two. The aof is opened for
+/// writing once it has been replayed. Returns the number of loaded commands.
+pub fn load(backend: &Backend) -> Result<usize> {
+    let config = &backend.config.persistence;
+    let snapshot = config.snapshot_path();
+    let aof = config.aof_path();
+
+    let count = if config.appendonly && aof.exists() {
+        info!("Replaying append only file {:?}", aof);
+        aof::load_aof(backend, &aof)?
+    } else if snapshot.exists() {
+        info!("Loading snapshot {:?}", snapshot);
+        rdb::load_snapshot(backend, &snapshot)?
+    } else {
+        0
+    };
+
+    if config.appendonly {
+        backend.enable_aof(Aof::open(&aof, config.appendfsync)?);
+    }
+    Ok(count)
+}
+
+/// Execute every command frame in the buffer, a truncated frame at the end
+/// (e.g. a crash in the middle of an aof write) is skipped with a warning.
+/// SELECT switches the database the following commands run against.
+fn replay(backend: &Backend, buf: &mut BytesMut) -> Result<usize> {
+    let mut backend = backend.clone();
+    let mut count = 0;
+    while !buf.is_empty() {
+        let frame = match RespFrame::decode(buf) {
+            Ok(frame) => frame,
+            Err(RespError::NotComplete) => {
+                warn!("Ignoring {} bytes of truncated data", buf.len());
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        match Command::try_from(frame)? {
+            Command::Select(cmd) => {
+                backend = cmd
+                    .select(&backend)
+                    .map_err(|e| anyhow!("{}", e.as_str()))?
+            }
+            cmd => {
+                cmd.execute(&backend);
+            }
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn command_frame(args: impl IntoIterator<Item = RespFrame>) -> RespFrame {
+    RespArray::new(args.into_iter().collect::<Vec<_>>()).into()
+}
+
+fn bulk(s: impl Into<String>) -> RespFrame {
+    BulkString::from(s.into()).into()
+}
@@ -0,0 +1,228 @@
+use super::{bulk, command_frame, replay};
+use crate::{Backend, DATABASES, RespDecodeV2, RespEncode, RespFrame, Value};
+use anyhow::{Context, Result, bail};
+use bytes::BytesMut;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+const SNAPSHOT_HEADER: &str = "SIMPLE-REDIS-SNAPSHOT 1";
+
+/// Dump every key of the backend as the write commands which rebuild it,
+/// each non-empty database introduced by a SELECT. Expire times go last as
+/// absolute PEXPIREAT so they survive restarts.
+pub fn dump(backend: &Backend) -> Vec<RespFrame> {
+    let mut frames = Vec::new();
+    for index in 0..DATABASES {
+        let db = &backend.dbs[index];
+        if db.keys.is_empty() {
+            continue;
+        }
+        frames.push(command_frame([bulk("select"), bulk(index.to_string())]));
+
+        for v in db.keys.iter() {
+            let key = v.key();
+            match v.value() {
+                Value::String(value) => {
+                    frames.push(command_frame([bulk("set"), bulk(key), value.clone()]));
+                }
+                Value::Hash(hash) => {
+                    for (field, value) in hash {
+                        frames.push(command_frame([
+                            bulk("hset"),
+                            bulk(key),
+                            bulk(field),
+                            value.clone(),
+                        ]));
+                    }
+                }
+                Value::List(list) => {
+                    let args = [bulk("rpush"), bulk(key)];
+                    frames.push(command_frame(args.into_iter().chain(list.iter().cloned())));
+                }
+                Value::ZSet(zset) => {
+                    let args = [bulk("zadd"), bulk(key)];
+                    let members = zset
+                        .iter()
+                        .flat_map(|(member, score)| [bulk(score.to_string()), bulk(member)])
+                        .collect::<Vec<_>>();
+                    frames.push(command_frame(args.into_iter().chain(members)));
+                }
+                Value::Stream(stream) => {
+                    for (id, fields) in stream.iter() {
+                        let args = [bulk("xadd"), bulk(key), bulk(id.to_string())];
+                        let fields = fields
+                            .iter()
+                            .flat_map(|(field, value)| [bulk(field), value.clone()]);
+                        frames.push(command_frame(args.into_iter().chain(fields)));
+                    }
+                    for (group, state) in stream.groups() {
+                        frames.push(command_frame([
+                            bulk("xgroup"),
+                            bulk("create"),
+                            bulk(key),
+                            bulk(group),
+                            bulk(state.last_delivered().to_string()),
+                            bulk("mkstream"),
+                        ]));
+                        for (id, pending) in state.pending() {
+                            frames.push(command_frame([
+                                bulk("xclaim"),
+                                bulk(key),
+                                bulk(group),
+                                bulk(&pending.consumer),
+                                bulk("0"),
+                                bulk(id.to_string()),
+                                bulk("time"),
+                                bulk(pending.delivered_at.to_string()),
+                                bulk("retrycount"),
+                                bulk(pending.deliveries.to_string()),
+                                bulk("force"),
+                                bulk("justid"),
+                            ]));
+                        }
+                    }
+                    // the ids of trimmed entries must not be reused
+                    if !stream.is_empty() || stream.groups().next().is_some() {
+                        frames.push(command_frame([
+                            bulk("xsetid"),
+                            bulk(key),
+                            bulk(stream.last_id().to_string()),
+                        ]));
+                    }
+                }
+            }
+        }
+
+        for v in db.expires.iter() {
+            frames.push(command_frame([
+                bulk("pexpireat"),
+                bulk(v.key()),
+                bulk(v.value().to_string()),
+            ]));
+        }
+    }
+    frames
+}
+
+/// The snapshot of the backend as stored on disk and sent to replicas.
+pub fn snapshot(backend: &Backend) -> Vec<u8> {
+    let mut buf = RespFrame::from(SNAPSHOT_HEADER).encode();
+    for frame in dump(backend) {
+        buf.extend_from_slice(&frame.encode());
+    }
+    buf
+}
+
+/// Write a point in time snapshot, the file is replaced atomically so a
+/// crash while saving never leaves a half written snapshot behind. Writes
+/// must be kept out while it runs, see `save_snapshot` to save off the lock.
+pub fn save(backend: &Backend) -> Result<()> {
+    save_snapshot(backend, &snapshot(backend))
+}
+
+/// Write a snapshot taken earlier with `snapshot`, as `save` does.
+pub fn save_snapshot(backend: &Backend, snapshot: &[u8]) -> Result<()> {
+    let path = backend.config.persistence.snapshot_path();
+    let tmp = path.with_extension("tmp");
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(snapshot)?;
+    file.sync_all()?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Load a snapshot into the backend, returns the number of loaded commands.
+pub fn restore(backend: &Backend, buf: &mut BytesMut) -> Result<usize> {
+    match RespFrame::decode(buf)? {
+        RespFrame::SimpleString(header) if header.as_str() == SNAPSHOT_HEADER => {}
+        _ => bail!("not a simple-redis snapshot"),
+    }
+    replay(backend, buf)
+}
+
+pub(super) fn load_snapshot(backend: &Backend, path: &Path) -> Result<usize> {
+    let mut buf = BytesMut::from(fs::read(path)?.as_slice());
+    restore(backend, &mut buf).with_context(|| format!("failed to load {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{StreamTrim, XAddId};
+    use crate::persistence::PersistenceConfig;
+    use crate::{BulkString, StreamId, now_ms};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("simple-redis-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_save_and_load_snapshot() -> Result<()> {
+        let config = PersistenceConfig {
+            dir: temp_dir("rdb"),
+            ..Default::default()
+        };
+        let backend = Backend::with_persistence(config.clone());
+        backend.set("hello".to_string(), BulkString::from("world").into());
+        backend.set("empty".to_string(), BulkString::from("").into());
+        backend.hset("map".to_string(), "f".to_string(), RespFrame::Integer(-1))?;
+        backend.rpush("list".to_string(), vec![b"a".into(), b"b".into()])?;
+        backend.zadd(
+            "z".to_string(),
+            vec![(1.5, "a".to_string()), (f64::INFINITY, "b".to_string())],
+            None,
+            false,
+        )?;
+        backend.xadd(
+            "stream".to_string(),
+            XAddId::Explicit(StreamId::new(1, 0)),
+            vec![("f".to_string(), b"v".into())],
+            false,
+            None,
+        )?;
+        backend.xgroup_create("stream".to_string(), "g".to_string(), None, false)?;
+        backend.xadd(
+            "stream".to_string(),
+            XAddId::Explicit(StreamId::new(2, 0)),
+            vec![("f".to_string(), b"w".into())],
+            false,
+            Some(StreamTrim::MaxLen(1)),
+        )?;
+        backend.xreadgroup("stream", "g", "c", None, None, false)?;
+        backend.expire_at("hello", now_ms() + 60_000);
+        let other = backend.select(5).expect("db 5 should exist");
+        other.set("hello".to_string(), BulkString::from("db5").into());
+        save(&backend)?;
+
+        let restored = Backend::with_persistence(config.clone());
+        let count = load_snapshot(&restored, &config.snapshot_path())?;
+        assert_eq!(count, 13);
+        assert_eq!(
+            restored.get("hello"),
+            Ok(Some(BulkString::from("world").into()))
+        );
+        assert_eq!(restored.get("empty"), Ok(Some(BulkString::from("").into())));
+        assert_eq!(restored.hget("map", "f"), Ok(Some(RespFrame::Integer(-1))));
+        assert_eq!(
+            restored.lrange("list", 0, -1),
+            Ok(vec![b"a".into(), b"b".into()])
+        );
+        assert_eq!(restored.zscore("z", "b"), Ok(Some(f64::INFINITY)));
+        assert!(restored.pttl("hello") > 50_000);
+        assert_eq!(restored.xlen("stream"), Ok(1));
+        assert_eq!(restored.xlast_id("stream"), Ok(Some(StreamId::new(2, 0))));
+        let pending = restored.xpending_summary("stream", "g")?;
+        assert_eq!(pending.consumers, vec![("c".to_string(), 1)]);
+        let other = restored.select(5).expect("db 5 should exist");
+        assert_eq!(other.get("hello"), Ok(Some(BulkString::from("db5").into())));
+        assert_eq!(other.pttl("hello"), -1);
+
+        fs::remove_dir_all(&config.dir)?;
+        Ok(())
+    }
+}
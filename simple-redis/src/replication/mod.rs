@@ -0,0 +1,453 @@
+mod replica;
+
+use crate::{
+    Backend, BulkString, RespArray, RespEncode, RespFrame, SimpleString, Subscriber, now_ms,
+    persistence,
+};
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// How many bytes of write commands a replica may fall behind and still
+/// resume with a partial resync after reconnecting.
+const BACKLOG_SIZE: usize = 1024 * 1024;
+
+/// Replication state of the server: a master streams its write commands to
+/// the replicas, a replica follows its master and refuses writes from
+/// clients.
+#[derive(Debug)]
+pub struct Replication {
+    /// id of the history of the dataset, replicas take the one of their master
+    replid: Mutex<String>,
+    /// bytes of write commands produced as a master or applied as a replica
+    offset: AtomicU64,
+    /// only kept once a replica connected
+    backlog: Mutex<Option<Backlog>>,
+    /// the connected replicas by client id
+    replicas: DashMap<u64, ReplicaLink>,
+    /// set when following a master
+    master: Mutex<Option<MasterLink>>,
+    /// database of the last propagated command, None makes the next one
+    /// start with a SELECT
+    db: Mutex<Option<usize>>,
+}
+
+/// A replica connected to this server.
+#[derive(Debug)]
+pub struct ReplicaLink {
+    ip: String,
+    port: u16,
+    sender: Subscriber,
+    /// offset acknowledged by the replica and when (unix milliseconds)
+    ack_offset: u64,
+    ack_at: u64,
+}
+
+/// The master this server follows.
+#[derive(Debug)]
+struct MasterLink {
+    host: String,
+    port: u16,
+    task: JoinHandle<()>,
+    up: bool,
+    /// last time data was received from the master (unix milliseconds)
+    last_io: Option<u64>,
+}
+
+/// The latest write commands, with the offset each one ends at.
+#[derive(Debug, Default)]
+struct Backlog {
+    frames: VecDeque<(u64, usize, RespFrame)>,
+    size: usize,
+}
+
+impl Backlog {
+    fn push(&mut self, end: u64, len: usize, frame: RespFrame) {
+        self.frames.push_back((end, len, frame));
+        self.size += len;
+        while self.size > BACKLOG_SIZE
+            && let Some((_, len, _)) = self.frames.pop_front()
+        {
+            self.size -= len;
+        }
+    }
+
+    /// The commands following the offset, None if some of them are no
+    /// longer in the backlog.
+    fn since(&self, offset: u64, current: u64) -> Option<Vec<RespFrame>> {
+        let start = self
+            .frames
+            .front()
+            .map_or(current, |(end, len, _)| end - *len as u64);
+        if offset < start || offset > current {
+            return None;
+        }
+        let frames = self
+            .frames
+            .iter()
+            .filter(|(end, ..)| *end > offset)
+            .map(|(.., frame)| frame.clone())
+            .collect();
+        Some(frames)
+    }
+}
+
+impl ReplicaLink {
+    pub fn new(ip: String, port: u16, sender: Subscriber) -> Self {
+        Self {
+            ip,
+            port,
+            sender,
+            ack_offset: 0,
+            ack_at: now_ms(),
+        }
+    }
+}
+
+impl Replication {
+    pub fn replid(&self) -> String {
+        lock(&self.replid).clone()
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset.load(Ordering::SeqCst)
+    }
+
+    /// Count the frames in the offset and send them to the replicas.
+    fn feed(&self, frames: Vec<RespFrame>) {
+        let mut backlog = lock(&self.backlog);
+        for frame in frames {
+            let len = frame.clone().encode().len();
+            let end = self.offset.fetch_add(len as u64, Ordering::SeqCst) + len as u64;
+            for replica in self.replicas.iter() {
+                let _ = replica.sender.send(frame.clone());
+            }
+            if let Some(backlog) = backlog.as_mut() {
+                backlog.push(end, len, frame);
+            }
+        }
+    }
+
+    /// Take the history of the master after a full resync. The replicas of
+    /// this server no longer match it and are dropped.
+    fn reset(&self, replid: String, offset: u64) {
+        let mut backlog = lock(&self.backlog);
+        *lock(&self.db) = None;
+        *lock(&self.replid) = replid;
+        self.offset.store(offset, Ordering::SeqCst);
+        if backlog.is_some() {
+            *backlog = Some(Backlog::default());
+        }
+        self.replicas.clear();
+    }
+
+    fn update_link(&self, up: bool) {
+        if let Some(link) = lock(&self.master).as_mut() {
+            link.up = up;
+            if up {
+                link.last_io = Some(now_ms());
+            }
+        }
+    }
+}
+
+impl Default for Replication {
+    fn default() -> Self {
+        Self {
+            replid: Mutex::new(new_replid()),
+            offset: AtomicU64::new(0),
+            backlog: Mutex::new(None),
+            replicas: DashMap::new(),
+            master: Mutex::new(None),
+            db: Mutex::new(None),
+        }
+    }
+}
+
+impl Backend {
+    pub fn is_replica(&self) -> bool {
+        lock(&self.replication.master).is_some()
+    }
+
+    /// Follow the given master, or become a master again when None. Returns
+    /// false if the server already follows that master.
+    pub fn replicaof(&self, master: Option<(String, u16)>) -> bool {
+        let mut link = lock(&self.replication.master);
+        if let (Some(current), Some((host, port))) = (link.as_ref(), &master)
+            && current.host == *host
+            && current.port == *port
+        {
+            return false;
+        }
+
+        let following = match link.take() {
+            Some(old) => {
+                old.task.abort();
+                true
+            }
+            None => false,
+        };
+        match master {
+            Some((host, port)) => {
+                info!("Replicating {}:{}", host, port);
+                let task = tokio::spawn(replica::follow(self.clone(), host.clone(), port));
+                *link = Some(MasterLink {
+                    host,
+                    port,
+                    task,
+                    up: false,
+                    last_io: None,
+                });
+            }
+            // a new history starts, the old master cannot resume from it
+            None if following => {
+                info!("Promoted to master");
+                *lock(&self.replication.replid) = new_replid();
+            }
+            None => {}
+        }
+        true
+    }
+
+    /// Whether executed write commands have to be recorded, i.e. the aof is
+    /// turned on or a replica connected once.
+    pub(crate) fn propagates(&self) -> bool {
+        self.aof().is_some() || lock(&self.replication.backlog).is_some()
+    }
+
+    /// Append the frames recording write commands executed against the
+    /// selected database to the aof and send them to the replicas, preceded
+    /// by a SELECT when the database changed.
+    pub(crate) fn propagate(&self, mut frames: Vec<RespFrame>) {
+        if frames.is_empty() {
+            return;
+        }
+        let mut db = lock(&self.replication.db);
+        if *db != Some(self.db_index()) {
+            let select: [RespFrame; 2] = [
+                BulkString::from("select").into(),
+                BulkString::from(self.db_index().to_string()).into(),
+            ];
+            frames.insert(0, RespArray::new(select).into());
+            *db = Some(self.db_index());
+        }
+        self.propagate_verbatim(frames);
+    }
+
+    /// Propagate the frames as they are, for a replica passing on the stream
+    /// of its master, SELECT included.
+    pub(crate) fn propagate_verbatim(&self, frames: Vec<RespFrame>) {
+        if let Some(aof) = self.aof()
+            && let Err(e) = aof.append(frames.clone())
+        {
+            warn!("Failed to write append only file: {:?}", e);
+        }
+        self.replication.feed(frames);
+    }
+
+    /// Register a replica asking to sync from the given offset (the first
+    /// byte it is missing). It resumes from the backlog when possible,
+    /// otherwise it gets a snapshot. The frames to send the replica are
+    /// returned, the following writes go through its sender.
+    pub fn psync(&self, id: u64, link: ReplicaLink, replid: &str, offset: i64) -> Vec<RespFrame> {
+        // nothing is written while the snapshot is taken
+        let _guard = self.exclusive();
+        let replication = &self.replication;
+        let mut backlog = lock(&replication.backlog);
+        let backlog = backlog.get_or_insert_with(Backlog::default);
+        let current_replid = replication.replid();
+        let current = replication.offset();
+
+        let missed = (replid == current_replid && offset > 0)
+            .then(|| backlog.since(offset as u64 - 1, current))
+            .flatten();
+        let frames = match missed {
+            Some(missed) => {
+                info!("Partial resync of replica {}:{}", link.ip, link.port);
+                std::iter::once(SimpleString::new(format!("CONTINUE {}", current_replid)).into())
+                    .chain(missed)
+                    .collect()
+            }
+            None => {
+                info!("Full resync of replica {}:{}", link.ip, link.port);
+                // the replica starts over from the snapshot
+                *lock(&replication.db) = None;
+                vec![
+                    SimpleString::new(format!("FULLRESYNC {} {}", current_replid, current)).into(),
+                    BulkString::new(persistence::snapshot(self)).into(),
+                ]
+            }
+        };
+        replication.replicas.insert(id, link);
+        frames
+    }
+
+    pub fn replica_ack(&self, id: u64, offset: u64) {
+        if let Some(mut replica) = self.replication.replicas.get_mut(&id) {
+            replica.ack_offset = offset;
+            replica.ack_at = now_ms();
+        }
+    }
+
+    pub fn remove_replica(&self, id: u64) {
+        if self.replication.replicas.remove(&id).is_some() {
+            info!("Replica {} disconnected", id);
+        }
+    }
+
+    /// The replication section of INFO.
+    pub fn replication_info(&self) -> String {
+        let replication = &self.replication;
+        let now = now_ms();
+        let mut lines = vec![];
+        match lock(&replication.master).as_ref() {
+            Some(link) => {
+                let last_io = link
+                    .last_io
+                    .map_or(-1, |at| (now.saturating_sub(at) / 1000) as i64);
+                lines.push("role:slave".to_string());
+                lines.push(format!("master_host:{}", link.host));
+                lines.push(format!("master_port:{}", link.port));
+                lines.push(format!(
+                    "master_link_status:{}",
+                    if link.up { "up" } else { "down" }
+                ));
+                lines.push(format!("master_last_io_seconds_ago:{}", last_io));
+                lines.push(format!("slave_repl_offset:{}", replication.offset()));
+                lines.push("slave_read_only:1".to_string());
+            }
+            None => lines.push("role:master".to_string()),
+        }
+
+        lines.push(format!("connected_slaves:{}", replication.replicas.len()));
+        for (i, replica) in replication.replicas.iter().enumerate() {
+            lines.push(format!(
+                "slave{}:ip={},port={},state=online,offset={},lag={}",
+                i,
+                replica.ip,
+                replica.port,
+                replica.ack_offset,
+                now.saturating_sub(replica.ack_at) / 1000
+            ));
+        }
+        lines.push(format!("master_replid:{}", replication.replid()));
+        lines.push(format!("master_repl_offset:{}", replication.offset()));
+        let backlog = lock(&replication.backlog);
+        lines.push(format!("repl_backlog_active:{}", backlog.is_some() as u8));
+        lines.push(format!("repl_backlog_size:{}", BACKLOG_SIZE));
+        lines.push(format!(
+            "repl_backlog_histlen:{}",
+            backlog.as_ref().map_or(0, |b| b.size)
+        ));
+        lines.join("\r\n")
+    }
+}
+
+fn new_replid() -> String {
+    let state = RandomState::new();
+    let mut replid = (0..3)
+        .map(|i| format!("{:016x}", state.hash_one((i, now_ms()))))
+        .collect::<String>();
+    replid.truncate(40);
+    replid
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    fn command(args: &[&str]) -> RespFrame {
+        RespArray::new(
+            args.iter()
+                .map(|s| BulkString::from(*s).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+        .into()
+    }
+
+    fn set(key: &str, value: &str) -> RespFrame {
+        command(&["set", key, value])
+    }
+
+    #[test]
+    fn test_backlog_should_keep_latest_frames() {
+        let mut backlog = Backlog::default();
+        let frame = set("a", "1");
+        let len = frame.clone().encode().len();
+        backlog.push(len as u64, len, frame.clone());
+        backlog.push(2 * len as u64, len, frame.clone());
+
+        assert_eq!(backlog.since(0, 2 * len as u64).map(|v| v.len()), Some(2));
+        assert_eq!(
+            backlog.since(len as u64, 2 * len as u64).map(|v| v.len()),
+            Some(1)
+        );
+        assert_eq!(backlog.since(2 * len as u64, 2 * len as u64), Some(vec![]));
+        assert_eq!(backlog.since(3 * len as u64, 2 * len as u64), None);
+
+        let count = BACKLOG_SIZE / len + 1;
+        for i in 3..3 + count {
+            backlog.push((i * len) as u64, len, frame.clone());
+        }
+        assert!(backlog.size <= BACKLOG_SIZE);
+        assert_eq!(backlog.since(0, ((2 + count) * len) as u64), None);
+    }
+
+    #[test]
+    fn test_psync_should_resume_from_backlog() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::from("1").into());
+        assert!(!backend.propagates());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let link = ReplicaLink::new("127.0.0.1".to_string(), 6380, tx);
+        let frames = backend.psync(1, link, "?", -1);
+        let replid = backend.replication.replid();
+        assert_eq!(
+            frames[0],
+            SimpleString::new(format!("FULLRESYNC {} 0", replid)).into()
+        );
+        assert_eq!(frames.len(), 2);
+        assert!(backend.propagates());
+
+        backend.propagate(vec![set("b", "2")]);
+        assert_eq!(rx.try_recv().ok(), Some(command(&["select", "0"])));
+        assert_eq!(rx.try_recv().ok(), Some(set("b", "2")));
+        backend.propagate(vec![set("c", "3")]);
+        assert_eq!(rx.try_recv().ok(), Some(set("c", "3")));
+        let offset = backend.replication.offset();
+        let len = command(&["select", "0"]).encode().len()
+            + set("b", "2").encode().len()
+            + set("c", "3").encode().len();
+        assert_eq!(offset, len as u64);
+        backend.replica_ack(1, offset);
+        assert!(
+            backend
+                .replication_info()
+                .contains(&format!("offset={}", offset))
+        );
+
+        backend.remove_replica(1);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let link = ReplicaLink::new("127.0.0.1".to_string(), 6380, tx);
+        let frames = backend.psync(2, link, &replid, 1);
+        assert_eq!(
+            frames,
+            vec![
+                SimpleString::new(format!("CONTINUE {}", replid)).into(),
+                command(&["select", "0"]),
+                set("b", "2"),
+                set("c", "3")
+            ]
+        );
+    }
+}
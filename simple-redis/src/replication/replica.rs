@@ -0,0 +1,141 @@
+use crate::cmd::Command;
+use crate::network::{self, RespFrameCodec};
+use crate::{Backend, BulkString, RespArray, RespFrame, persistence};
+use anyhow::{Result, anyhow, bail};
+use bytes::BytesMut;
+use futures::SinkExt;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_stream::StreamExt;
+use tokio_util::codec::Framed;
+use tracing::{info, warn};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const ACK_PERIOD: Duration = Duration::from_secs(1);
+
+/// Connection to the master.
+struct MasterConnection(Framed<TcpStream, RespFrameCodec>);
+
+impl MasterConnection {
+    async fn send(&mut self, args: &[&str]) -> Result<()> {
+        let frame = RespArray::new(
+            args.iter()
+                .map(|arg| BulkString::from(*arg).into())
+                .collect::<Vec<RespFrame>>(),
+        );
+        self.0.send(frame.into()).await
+    }
+
+    async fn read(&mut self) -> Result<RespFrame> {
+        match self.0.next().await {
+            Some(frame) => frame,
+            None => bail!("connection closed by master"),
+        }
+    }
+
+    /// Send a handshake command, an error reply aborts the sync.
+    async fn request(&mut self, args: &[&str]) -> Result<RespFrame> {
+        self.send(args).await?;
+        match self.read().await? {
+            RespFrame::Error(e) => Err(anyhow!("{} failed: {}", args[0], e.as_str())),
+            frame => Ok(frame),
+        }
+    }
+}
+
+/// Keep the backend in sync with the master, reconnecting when the link
+/// breaks until the task is aborted by REPLICAOF.
+pub(super) async fn follow(backend: Backend, host: String, port: u16) {
+    // the database the streamed commands run against, switched by the
+    // SELECT commands of the master
+    let mut db = backend.clone();
+    loop {
+        if let Err(e) = sync(&backend, &mut db, &host, port).await {
+            warn!("Replication from {}:{} failed: {:?}", host, port, e);
+        }
+        backend.replication.update_link(false);
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn sync(backend: &Backend, db: &mut Backend, host: &str, port: u16) -> Result<()> {
+    let stream = TcpStream::connect((host, port)).await?;
+    let mut master = MasterConnection(Framed::new(stream, RespFrameCodec));
+
+    if let Some(password) = &backend.config.masterauth {
+        master.request(&["auth", password]).await?;
+    }
+    master.request(&["ping"]).await?;
+    let listening_port = backend.config.port.to_string();
+    master
+        .request(&["replconf", "listening-port", &listening_port])
+        .await?;
+    master.request(&["replconf", "capa", "psync2"]).await?;
+
+    let replication = &backend.replication;
+    let replid = replication.replid();
+    let offset = (replication.offset() + 1).to_string();
+    master.send(&["psync", &replid, &offset]).await?;
+    let reply = match master.read().await? {
+        RespFrame::SimpleString(reply) => reply,
+        frame => bail!("unexpected PSYNC reply: {:?}", frame),
+    };
+    let mut parts = reply.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("FULLRESYNC"), Some(replid), Some(offset)) => {
+            let offset = offset.parse()?;
+            let snapshot = match master.read().await? {
+                RespFrame::BulkString(snapshot) => snapshot,
+                frame => bail!("unexpected snapshot: {:?}", frame),
+            };
+            let count = load(backend, &snapshot, replid.to_string(), offset)?;
+            info!("Full resync with master: loaded {} commands", count);
+        }
+        (Some("CONTINUE"), ..) => info!("Partial resync with master"),
+        _ => bail!("unexpected PSYNC reply: {}", reply.as_str()),
+    }
+    replication.update_link(true);
+
+    let mut ack = tokio::time::interval(ACK_PERIOD);
+    loop {
+        tokio::select! {
+            frame = master.read() => {
+                apply(db, frame?);
+                replication.update_link(true);
+            }
+            _ = ack.tick() => {
+                let offset = replication.offset().to_string();
+                master.send(&["replconf", "ack", &offset]).await?;
+            }
+        }
+    }
+}
+
+/// Replace the dataset with the snapshot of the master.
+fn load(backend: &Backend, snapshot: &[u8], replid: String, offset: u64) -> Result<usize> {
+    let _guard = backend.exclusive();
+    backend.flushall();
+    let count = persistence::restore(backend, &mut BytesMut::from(snapshot))?;
+    backend.replication.reset(replid, offset);
+    if let Some(aof) = backend.aof() {
+        aof.rewrite(persistence::dump(backend))?;
+    }
+    Ok(count)
+}
+
+/// Run a write command streamed by the master. It is recorded verbatim so
+/// the offset and the replicas of this server follow the master exactly.
+fn apply(db: &mut Backend, frame: RespFrame) {
+    match Command::try_from(frame.clone()) {
+        Ok(Command::Select(cmd)) => match cmd.select(db) {
+            Ok(selected) => *db = selected,
+            Err(e) => warn!("Invalid SELECT from master: {}", e.as_str()),
+        },
+        Ok(cmd) => {
+            let _guard = db.shared();
+            network::execute(cmd, None, db);
+        }
+        Err(e) => warn!("Invalid command from master: {}", e),
+    }
+    db.propagate_verbatim(vec![frame]);
+}
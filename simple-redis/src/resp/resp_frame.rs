@@ -110,6 +110,42 @@ impl RespDecode for RespFrame {
     }
 }
 
+impl RespFrame {
+    /// Downgrade the RESP3 only types for a client speaking RESP2: maps are
+    /// flattened, sets become arrays, doubles become bulk strings, booleans
+    /// become integers and null becomes a null bulk string.
+    pub fn into_resp2(self) -> RespFrame {
+        match self {
+            RespFrame::Array(array) => RespArray::new(
+                array
+                    .0
+                    .into_iter()
+                    .map(RespFrame::into_resp2)
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Set(set) => RespArray::new(
+                set.0
+                    .into_iter()
+                    .map(RespFrame::into_resp2)
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Map(map) => RespArray::new(
+                map.0
+                    .into_iter()
+                    .flat_map(|(k, v)| [BulkString::from(k).into(), v.into_resp2()])
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Null(_) => NullBulkString.into(),
+            RespFrame::Boolean(b) => RespFrame::Integer(b as i64),
+            RespFrame::Double(d) => BulkString::from(d.to_string()).into(),
+            frame => frame,
+        }
+    }
+}
+
 impl From<&str> for RespFrame {
     fn from(value: &str) -> Self {
         SimpleString(value.to_string()).into()
@@ -127,3 +163,27 @@ impl<const N: usize> From<&[u8; N]> for RespFrame {
         BulkString(value.to_vec()).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_resp2() {
+        let mut map = RespMap::new();
+        map.insert("score".to_string(), RespFrame::Double(1.5));
+        map.insert(
+            "set".to_string(),
+            RespSet::new([RespFrame::Boolean(true), RespFrame::Null(RespNull)]).into(),
+        );
+        let frame: RespFrame = map.into();
+
+        let expected = RespArray::new([
+            BulkString::from("score").into(),
+            BulkString::from("1.5").into(),
+            BulkString::from("set").into(),
+            RespArray::new([RespFrame::Integer(1), NullBulkString.into()]).into(),
+        ]);
+        assert_eq!(frame.into_resp2(), expected.into());
+    }
+}
@@ -11,7 +11,7 @@ pub trait RespDecodeV2: Sized {
 
 impl RespDecodeV2 for RespFrame {
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let len = Self::expect_length(&buf)?;
+        let len = Self::expect_length(buf)?;
         let data = buf.split_to(len);
 
         parse_frame(&mut data.as_ref()).map_err(|e| RespError::InvalidFrame(e.to_string()))
@@ -91,6 +91,18 @@ mod test {
         assert_eq!(frame, RespFrame::BulkString("foobar".into()));
     }
 
+    #[test]
+    fn respv2_empty_bulk_string_should_work() {
+        let mut buf = BytesMut::from("$0\r\n\r\n+OK\r\n");
+        let len = RespFrame::expect_length(&buf).unwrap();
+        assert_eq!(len, 6);
+
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(frame, RespFrame::BulkString("".into()));
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(frame, RespFrame::SimpleString("OK".into()));
+    }
+
     #[test]
     fn respv2_null_bulk_string_length_should_work() {
         let buf = b"$-1\r\n";
@@ -90,12 +90,9 @@ fn null_bulk_string(input: &mut &[u8]) -> winnow::Result<NullBulkString> {
     "-1\r\n".value(NullBulkString).parse_next(input)
 }
 
-#[allow(clippy::comparison_chain)]
 fn bulk_string(input: &mut &[u8]) -> winnow::Result<BulkString> {
     let len = integer.parse_next(input)?;
-    if len == 0 {
-        return Ok(BulkString::new(vec![]));
-    } else if len < 0 {
+    if len < 0 {
         return Err(err_cut("bulk string length must be non-negative"));
     }
 
@@ -107,7 +104,7 @@ fn bulk_string(input: &mut &[u8]) -> winnow::Result<BulkString> {
 
 fn bulk_string_len(input: &mut &[u8]) -> winnow::Result<()> {
     let len = integer.parse_next(input)?;
-    if len == 0 || len == -1 {
+    if len == -1 {
         return Ok(());
     } else if len < -1 {
         return Err(err_cut("bulk String length must be non-megatice"));